@@ -0,0 +1,220 @@
+use crate::qcore::gates::Gate;
+use crate::utils::qasm::QasmError;
+use crate::zxcalc::extract::ExtractionError;
+use crate::zxcalc::qgraph::QGraphError;
+use std::fmt::Display;
+
+/// Errors returned by the fallible circuit building, conversion and simulation functions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuojoError {
+    /// The gate names a qubit the circuit does not have.
+    QubitOutOfRange {
+        gate: Gate,
+        qubit: usize,
+        width: usize,
+    },
+    /// The gate names a classical bit the circuit does not have.
+    ClbitOutOfRange {
+        gate: Gate,
+        clbit: usize,
+        num_clbits: usize,
+    },
+    /// The gate names the same qubit more than once, such as a control equal to its target.
+    DuplicateQubit {
+        gate: Gate,
+        qubit: usize,
+    },
+    /// The gate has no controlled form.
+    UnsupportedControl(Gate),
+    /// The gate was given a different number of qubits than it acts on.
+    ArityMismatch {
+        gate: Gate,
+        expected: usize,
+        found: usize,
+    },
+    /// The gate names its own qubits and was applied to other ones.
+    TargetMismatch {
+        gate: Gate,
+        found: Vec<usize>,
+    },
+    /// The gate has no ZX translation.
+    UnsupportedGate(Gate),
+    /// The gate cannot be written in the named file format.
+    UnsupportedExport {
+        gate: Gate,
+        format: String,
+    },
+    DuplicateRegister(String),
+    /// A custom gate's matrix is not 2^k × 2^k for its k ≥ 1 qubits.
+    MatrixShape {
+        label: String,
+        qubits: usize,
+        rows: usize,
+        columns: usize,
+    },
+    /// A custom gate's matrix is off from unitary by more than `UNITARY_TOLERANCE`.
+    NotUnitary {
+        label: String,
+        deviation: f64,
+    },
+    /// A circuit of `found` qubits was used where `expected` were required.
+    WidthMismatch {
+        expected: usize,
+        found: usize,
+    },
+    /// A state vector was asked for a basis state index that is not below 2^`num_qubits`.
+    BasisStateOutOfRange {
+        index: usize,
+        num_qubits: usize,
+    },
+    /// A measurement, reset or conditional gate was applied to a state vector.
+    NonUnitaryGate(Gate),
+    /// A ZX diagram or compiled scalar was given `found` values where it needs `expected`,
+    /// such as one bit per input. `kind` names the values, as in `"input bits"`.
+    CountMismatch {
+        kind: String,
+        expected: usize,
+        found: usize,
+    },
+    /// A diagram with inputs or outputs was used where a closed diagram was required.
+    OpenDiagram {
+        inputs: usize,
+        outputs: usize,
+    },
+    /// Phases still depend on these variables, which have to be bound before simulating.
+    UnboundVariables(Vec<String>),
+    Extraction(ExtractionError),
+    Qasm(QasmError),
+    QGraph(QGraphError),
+}
+
+impl Display for QuojoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuojoError::QubitOutOfRange { gate, qubit, width } => write!(
+                f,
+                "Gate {} uses qubit {} but the circuit width is {}",
+                gate, qubit, width
+            ),
+            QuojoError::ClbitOutOfRange {
+                gate,
+                clbit,
+                num_clbits,
+            } => write!(
+                f,
+                "Gate {} uses classical bit {} but the circuit has {}",
+                gate, clbit, num_clbits
+            ),
+            QuojoError::DuplicateQubit { gate, qubit } => {
+                write!(f, "Gate {} uses qubit {} more than once", gate, qubit)
+            }
+            QuojoError::UnsupportedControl(gate) => {
+                write!(f, "Unsupported controlled operation for gate {}", gate)
+            }
+            QuojoError::ArityMismatch {
+                gate,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Gate {} acts on {} qubits but was given {}",
+                gate, expected, found
+            ),
+            QuojoError::TargetMismatch { gate, found } => write!(
+                f,
+                "Gate {} acts on qubits {:?} but was given {:?}",
+                gate,
+                gate.involved_qubits(found[0]),
+                found
+            ),
+            QuojoError::UnsupportedGate(gate) => {
+                write!(f, "Gate {} has no ZX translation", gate)
+            }
+            QuojoError::UnsupportedExport { gate, format } => {
+                write!(f, "Gate {} cannot be written as {}", gate, format)
+            }
+            QuojoError::DuplicateRegister(name) => {
+                write!(f, "Register {} already exists", name)
+            }
+            QuojoError::MatrixShape {
+                label,
+                qubits,
+                rows,
+                columns,
+            } => write!(
+                f,
+                "Gate {} on {} qubits needs a 2^k × 2^k matrix with k ≥ 1 but got {} × {}",
+                label, qubits, rows, columns
+            ),
+            QuojoError::NotUnitary { label, deviation } => write!(
+                f,
+                "Matrix of gate {} is not unitary: U†U differs from I by {:e}",
+                label, deviation
+            ),
+            QuojoError::WidthMismatch { expected, found } => write!(
+                f,
+                "Circuit has {} qubits but the width is {}",
+                found, expected
+            ),
+            QuojoError::BasisStateOutOfRange { index, num_qubits } => write!(
+                f,
+                "Basis state {} does not exist on {} qubits",
+                index, num_qubits
+            ),
+            QuojoError::NonUnitaryGate(gate) => write!(
+                f,
+                "{} is not unitary; run the circuit with the sampler",
+                gate
+            ),
+            QuojoError::CountMismatch {
+                kind,
+                expected,
+                found,
+            } => write!(f, "Expected {} {} but got {}", expected, kind, found),
+            QuojoError::OpenDiagram { inputs, outputs } => write!(
+                f,
+                "Diagram has {} inputs and {} outputs but must be closed",
+                inputs, outputs
+            ),
+            QuojoError::UnboundVariables(variables) => {
+                write!(
+                    f,
+                    "Phase depends on {}; bind its variables first",
+                    variables.join(", ")
+                )
+            }
+            QuojoError::Extraction(error) => error.fmt(f),
+            QuojoError::Qasm(error) => error.fmt(f),
+            QuojoError::QGraph(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for QuojoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            QuojoError::Extraction(error) => Some(error),
+            QuojoError::Qasm(error) => Some(error),
+            QuojoError::QGraph(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<ExtractionError> for QuojoError {
+    fn from(error: ExtractionError) -> Self {
+        QuojoError::Extraction(error)
+    }
+}
+
+impl From<QasmError> for QuojoError {
+    fn from(error: QasmError) -> Self {
+        QuojoError::Qasm(error)
+    }
+}
+
+impl From<QGraphError> for QuojoError {
+    fn from(error: QGraphError) -> Self {
+        QuojoError::QGraph(error)
+    }
+}
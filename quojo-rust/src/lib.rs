@@ -1,6 +1,8 @@
 #![feature(portable_simd)]
 
+pub mod error;
 pub mod linalg;
 pub mod qcore;
+pub mod sim;
 pub mod utils;
 pub mod zxcalc;
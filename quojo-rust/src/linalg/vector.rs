@@ -2,13 +2,42 @@ use std::fmt::Display;
 use std::ops;
 use std::simd::prelude::*;
 
-const LANES: usize = 16;
+pub const LANES: usize = 16;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Complex {
     pub re: f64,
     pub im: f64,
 }
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Complex {
+        Complex { re, im }
+    }
+
+    /// Returns e^(i * phase).
+    pub fn from_phase(phase: f64) -> Complex {
+        Complex {
+            re: phase.cos(),
+            im: phase.sin(),
+        }
+    }
+
+    pub fn conj(self) -> Complex {
+        Complex {
+            re: self.re,
+            im: -self.im,
+        }
+    }
+
+    pub fn norm_sqr(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    pub fn abs(self) -> f64 {
+        self.norm_sqr().sqrt()
+    }
+}
+
 impl ops::Add for Complex {
     type Output = Complex;
 
@@ -141,6 +170,17 @@ impl ops::Sub<f64> for Complex {
     }
 }
 
+impl ops::Mul<f64> for Complex {
+    type Output = Complex;
+
+    fn mul(self, rhs: f64) -> Complex {
+        Complex {
+            re: self.re * rhs,
+            im: self.im * rhs,
+        }
+    }
+}
+
 impl ops::Sub<i32> for Complex {
     type Output = Complex;
 
@@ -152,6 +192,7 @@ impl ops::Sub<i32> for Complex {
     }
 }
 
+#[derive(Clone)]
 pub struct ComplexSIMD<const N: usize> {
     pub re: [f64; N],
     pub im: [f64; N],
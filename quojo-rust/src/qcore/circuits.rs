@@ -1,61 +1,545 @@
-use crate::qcore::gates::Gate;
+use crate::error::QuojoError;
+use crate::qcore;
+use crate::qcore::dag::{CircuitDag, Instruction};
+use crate::qcore::gates::{Condition, ConditionalGate, ControlledGate, Gate};
+use std::collections::{BTreeSet, HashMap};
 use std::fmt::Display;
+use std::ops::{Deref, DerefMut, Range};
 
 pub struct Controls {
     pub controls: Vec<usize>,
+    /// Qubits that must be |0⟩ for the gate to act.
+    pub open_controls: Vec<usize>,
+}
+
+impl Controls {
+    /// Adds controls that fire on |0⟩ instead of |1⟩.
+    pub fn with_open(mut self, open_controls: &[usize]) -> Self {
+        self.open_controls.extend(open_controls);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.controls.is_empty() && self.open_controls.is_empty()
+    }
 }
 
 pub struct Targets {
     pub targets: Vec<usize>,
 }
 
-#[derive(Debug)]
-pub struct CircuitRepr<const WIDTH: usize> {
-    pub storage: [Vec<Gate>; WIDTH],
+/// A named, contiguous block of qubits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantumRegister {
+    pub name: String,
+    pub qubits: Range<usize>,
 }
 
-impl<const WIDTH: usize> CircuitRepr<WIDTH> {
+/// A named, contiguous block of classical bits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassicalRegister {
+    pub name: String,
+    pub clbits: Range<usize>,
+}
+
+/// A circuit whose qubit count is only known at run time and can grow.
+#[derive(Debug, Clone, Default)]
+pub struct Circuit {
+    /// Every gate in the order it was applied.
+    pub dag: CircuitDag,
+    pub registers: Vec<QuantumRegister>,
+    pub classical_registers: Vec<ClassicalRegister>,
+}
+
+impl Circuit {
+    pub fn new(num_qubits: usize) -> Self {
+        Circuit {
+            dag: CircuitDag::new(num_qubits),
+            registers: Vec::new(),
+            classical_registers: Vec::new(),
+        }
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.dag.num_qubits()
+    }
+
+    pub fn num_clbits(&self) -> usize {
+        self.dag.num_clbits()
+    }
+
+    /// Appends `count` classical bits, all starting at 0, and returns their indices.
+    pub fn add_clbits(&mut self, count: usize) -> Range<usize> {
+        let start = self.num_clbits();
+        self.dag.add_clbits(count);
+        start..start + count
+    }
+
+    /// Appends `count` qubits and returns their indices.
+    pub fn add_qubits(&mut self, count: usize) -> Range<usize> {
+        let start = self.num_qubits();
+        self.dag.add_qubits(count);
+        start..start + count
+    }
+
+    /// Appends `size` qubits under `name` and returns their indices.
+    pub fn add_register(&mut self, name: &str, size: usize) -> Range<usize> {
+        self.try_add_register(name, size)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `add_register`, but fails instead of panicking when `name` is taken.
+    pub fn try_add_register(
+        &mut self,
+        name: &str,
+        size: usize,
+    ) -> Result<Range<usize>, QuojoError> {
+        if self.register(name).is_some() || self.classical_register(name).is_some() {
+            return Err(QuojoError::DuplicateRegister(name.to_string()));
+        }
+        let qubits = self.add_qubits(size);
+        self.registers.push(QuantumRegister {
+            name: name.to_string(),
+            qubits: qubits.clone(),
+        });
+        Ok(qubits)
+    }
+
+    pub fn register(&self, name: &str) -> Option<&QuantumRegister> {
+        self.registers.iter().find(|register| register.name == name)
+    }
+
+    /// Appends `size` classical bits under `name` and returns their indices. Quantum and
+    /// classical registers share one set of names.
+    pub fn add_classical_register(&mut self, name: &str, size: usize) -> Range<usize> {
+        self.try_add_classical_register(name, size)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `add_classical_register`, but fails instead of panicking when `name` is taken.
+    pub fn try_add_classical_register(
+        &mut self,
+        name: &str,
+        size: usize,
+    ) -> Result<Range<usize>, QuojoError> {
+        if self.register(name).is_some() || self.classical_register(name).is_some() {
+            return Err(QuojoError::DuplicateRegister(name.to_string()));
+        }
+        let clbits = self.add_clbits(size);
+        self.classical_registers.push(ClassicalRegister {
+            name: name.to_string(),
+            clbits: clbits.clone(),
+        });
+        Ok(clbits)
+    }
+
+    pub fn classical_register(&self, name: &str) -> Option<&ClassicalRegister> {
+        self.classical_registers
+            .iter()
+            .find(|register| register.name == name)
+    }
+
+    /// Applies a single-qubit gate to every target. A multi-qubit or custom unitary gate names
+    /// its own qubits and is added once; its targets must be those qubits, in any order.
     pub fn Apply(&mut self, gate: Gate, targets: Targets) {
-        for &target in &targets.targets {
-            if target >= WIDTH {
-                panic!(
-                    "Target qubit index {} exceeds circuit width {}",
-                    target, WIDTH
-                );
-            }
-            self.storage[target].push(gate.clone());
+        self.try_apply(gate, targets)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `Apply`, but returns an error and leaves the circuit unchanged when a qubit is
+    /// out of range or repeated, or the targets do not fit the gate.
+    pub fn try_apply(&mut self, gate: Gate, targets: Targets) -> Result<(), QuojoError> {
+        for instruction in self.checked_instructions(gate, targets)? {
+            self.dag.push(instruction);
+        }
+        Ok(())
+    }
+
+    /// The instructions `try_apply` adds for `gate` on `targets`, checked against the circuit.
+    fn checked_instructions(
+        &self,
+        gate: Gate,
+        targets: Targets,
+    ) -> Result<Vec<Instruction>, QuojoError> {
+        let Some(&wire) = targets.targets.first() else {
+            return Err(QuojoError::ArityMismatch {
+                gate,
+                expected: 1,
+                found: 0,
+            });
+        };
+
+        let first = Instruction::new(gate.clone(), wire);
+        // Custom unitaries and controlled gates name their qubits even when they act on one
+        if !gate.takes_wire() {
+            check_named_targets(&gate, &first.qubits, &targets.targets)?;
+            first.check(self.num_qubits())?;
+            first.check_clbits(self.num_clbits())?;
+            return Ok(vec![first]);
         }
+
+        // Checked as a whole first so that a repeated target adds nothing
+        let broadcast = Instruction {
+            gate,
+            qubits: targets.targets,
+        };
+        broadcast.check(self.num_qubits())?;
+        Ok(broadcast
+            .qubits
+            .iter()
+            .map(|&target| Instruction::new(broadcast.gate.clone(), target))
+            .collect())
     }
 
+    /// Applies `gate` to `target` controlled on `control`.
     pub fn ApplyControlled(&mut self, gate: Gate, control: usize, target: usize) {
-        if control >= WIDTH || target >= WIDTH {
-            panic!("Qubit index exceeds circuit width {}", WIDTH);
+        self.apply_controlled(gate, qcore::Controls(&[control]), qcore::Targets(&[target]))
+    }
+
+    /// Applies `gate` controlled on every qubit in `controls`. A single-qubit gate is applied
+    /// to every target, each time with the same controls; any other gate names its qubits
+    /// and needs as many targets as it has qubits.
+    ///
+    /// Controlled X, Z and SWAP with closed controls become CNOT, CZ, Toffoli and Fredkin
+    /// gates; everything else becomes a `Gate::Controlled`.
+    pub fn apply_controlled(&mut self, gate: Gate, controls: Controls, targets: Targets) {
+        self.try_apply_controlled(gate, controls, targets)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `apply_controlled`, but returns an error and leaves the circuit unchanged when a
+    /// qubit is out of range or used twice, or the targets do not fit the gate.
+    pub fn try_apply_controlled(
+        &mut self,
+        gate: Gate,
+        controls: Controls,
+        targets: Targets,
+    ) -> Result<(), QuojoError> {
+        if controls.is_empty() {
+            return self.try_apply(gate, targets);
         }
+        if !gate.is_unitary() {
+            return Err(QuojoError::UnsupportedControl(gate));
+        }
+        let Some(&wire) = targets.targets.first() else {
+            return Err(QuojoError::ArityMismatch {
+                gate,
+                expected: 1,
+                found: 0,
+            });
+        };
 
-        let controlled_gate = match gate {
-            Gate::X => Gate::CNOT { control, target },
-            Gate::Z => Gate::CZ { control, target },
-            _ => panic!("Unsupported controlled operation for gate {:?}", gate),
+        let instructions: Vec<Instruction> = if gate.takes_wire() {
+            targets
+                .targets
+                .iter()
+                .map(|&target| controlled_instruction(gate.clone(), target, &controls))
+                .collect()
+        } else {
+            check_named_targets(&gate, &gate.involved_qubits(wire), &targets.targets)?;
+            vec![controlled_instruction(gate, wire, &controls)]
         };
 
-        self.storage[control].push(controlled_gate.clone());
-        self.storage[target].push(controlled_gate);
+        // Broadcast targets must also differ from each other
+        let mut targets = targets.targets;
+        targets.sort_unstable();
+        if let Some(pair) = targets.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(QuojoError::DuplicateQubit {
+                gate: instructions[0].gate.clone(),
+                qubit: pair[0],
+            });
+        }
+        for instruction in &instructions {
+            instruction.check(self.num_qubits())?;
+        }
+        for instruction in instructions {
+            self.dag.push(instruction);
+        }
+        Ok(())
     }
 
     pub fn ApplySwap(&mut self, qubit1: usize, qubit2: usize) {
-        if qubit1 >= WIDTH || qubit2 >= WIDTH {
-            panic!("Qubit index exceeds circuit width {}", WIDTH);
+        self.try_apply_swap(qubit1, qubit2)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `ApplySwap`, but returns an error for qubits out of range or equal to each other.
+    pub fn try_apply_swap(&mut self, qubit1: usize, qubit2: usize) -> Result<(), QuojoError> {
+        self.push_checked(Instruction::new(Gate::SWAP { qubit1, qubit2 }, qubit1))
+    }
+
+    /// Measures `qubit` in the computational basis and stores the outcome in `clbit`.
+    pub fn Measure(&mut self, qubit: usize, clbit: usize) {
+        self.try_measure(qubit, clbit)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `Measure`, but returns an error for a qubit or classical bit out of range.
+    pub fn try_measure(&mut self, qubit: usize, clbit: usize) -> Result<(), QuojoError> {
+        self.push_checked(Instruction::new(Gate::Measure { qubit, clbit }, qubit))
+    }
+
+    /// Returns `qubit` to |0⟩, whatever its state.
+    pub fn Reset(&mut self, qubit: usize) {
+        self.try_reset(qubit)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `Reset`, but returns an error for a qubit out of range.
+    pub fn try_reset(&mut self, qubit: usize) -> Result<(), QuojoError> {
+        self.push_checked(Instruction::new(Gate::Reset, qubit))
+    }
+
+    /// Applies `gate` to `targets` as `Apply` does, each instruction taking effect only when
+    /// `condition` holds for the classical bits at that point.
+    pub fn apply_conditional(&mut self, gate: Gate, condition: Condition, targets: Targets) {
+        self.try_apply_conditional(gate, condition, targets)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `apply_conditional`, but returns an error and leaves the circuit unchanged when
+    /// `try_apply` would, or a condition bit is out of range.
+    pub fn try_apply_conditional(
+        &mut self,
+        gate: Gate,
+        condition: Condition,
+        targets: Targets,
+    ) -> Result<(), QuojoError> {
+        let instructions: Vec<Instruction> = self
+            .checked_instructions(gate, targets)?
+            .into_iter()
+            .map(|instruction| {
+                let target = instruction.qubits[0];
+                let conditional = ConditionalGate {
+                    condition: condition.clone(),
+                    gate: instruction.gate,
+                    target,
+                };
+                Instruction::new(Gate::Conditional(Box::new(conditional)), target)
+            })
+            .collect();
+        for instruction in &instructions {
+            instruction.check_clbits(self.num_clbits())?;
+        }
+        for instruction in instructions {
+            self.dag.push(instruction);
+        }
+        Ok(())
+    }
+
+    fn push_checked(&mut self, instruction: Instruction) -> Result<(), QuojoError> {
+        instruction.check(self.num_qubits())?;
+        instruction.check_clbits(self.num_clbits())?;
+        self.dag.push(instruction);
+        Ok(())
+    }
+
+    /// Checks every instruction, including those pushed onto `dag` directly.
+    pub fn validate(&self) -> Result<(), QuojoError> {
+        self.dag.iter().try_for_each(|instruction| {
+            instruction.check(self.num_qubits())?;
+            instruction.check_clbits(self.num_clbits())
+        })
+    }
+
+    /// Whether every instruction is unitary, with no measurement, reset or condition.
+    pub fn is_unitary(&self) -> bool {
+        self.dag
+            .iter()
+            .all(|instruction| instruction.gate.is_unitary())
+    }
+
+    /// The gates in order as `(wire, gate)` pairs, where `wire` is the first qubit of the gate.
+    pub fn ordered_gates(&self) -> Vec<(usize, Gate)> {
+        self.dag
+            .iter()
+            .map(|instruction| (instruction.qubits[0], instruction.gate.clone()))
+            .collect()
+    }
+
+    /// The per-wire view, with multi-qubit gates listed on each of their wires.
+    pub fn wires(&self) -> Vec<Vec<Gate>> {
+        self.dag.to_wires()
+    }
+
+    /// Builds a circuit from a per-wire view; see `CircuitDag::from_wires` for how the order
+    /// between wires is recovered.
+    pub fn from_wires(wires: &[Vec<Gate>]) -> Self {
+        Circuit {
+            dag: CircuitDag::from_wires(wires),
+            registers: Vec::new(),
+            classical_registers: Vec::new(),
         }
+    }
 
-        let swap_gate = Gate::SWAP { qubit1, qubit2 };
-        self.storage[qubit1].push(swap_gate.clone());
-        self.storage[qubit2].push(swap_gate);
+    /// Names of the symbolic variables used anywhere in the circuit.
+    pub fn variables(&self) -> BTreeSet<String> {
+        self.dag
+            .iter()
+            .flat_map(|instruction| instruction.gate.variables())
+            .collect()
+    }
+
+    /// A copy of the circuit with variable values, in radians, substituted into every gate.
+    pub fn bind(&self, values: &HashMap<String, f64>) -> Circuit {
+        let mut dag = CircuitDag::new(self.num_qubits());
+        dag.add_clbits(self.num_clbits());
+        for instruction in self.dag.iter() {
+            dag.push(Instruction {
+                gate: instruction.gate.bind(values),
+                qubits: instruction.qubits.clone(),
+            });
+        }
+        Circuit {
+            dag,
+            registers: self.registers.clone(),
+            classical_registers: self.classical_registers.clone(),
+        }
     }
 }
 
-impl<const WIDTH: usize> Display for CircuitRepr<WIDTH> {
+/// Checks that a gate naming its own `qubits` was given exactly those as targets, in any order.
+fn check_named_targets(gate: &Gate, qubits: &[usize], targets: &[usize]) -> Result<(), QuojoError> {
+    if targets.len() != qubits.len() {
+        return Err(QuojoError::ArityMismatch {
+            gate: gate.clone(),
+            expected: qubits.len(),
+            found: targets.len(),
+        });
+    }
+    let mut named = qubits.to_vec();
+    let mut given = targets.to_vec();
+    named.sort_unstable();
+    given.sort_unstable();
+    if named != given {
+        return Err(QuojoError::TargetMismatch {
+            gate: gate.clone(),
+            found: targets.to_vec(),
+        });
+    }
+    Ok(())
+}
+
+/// `gate` on `wire` under `controls`, with controls of an already controlled gate merged in
+/// and the named controlled gates used where they fit.
+fn controlled_instruction(gate: Gate, wire: usize, controls: &Controls) -> Instruction {
+    let mut closed = controls.controls.clone();
+    let mut open = controls.open_controls.clone();
+    let (mut gate, mut wire) = (gate, wire);
+
+    loop {
+        (gate, wire) = match gate {
+            Gate::CNOT { control, target } => {
+                closed.push(control);
+                (Gate::X, target)
+            }
+            Gate::CZ { control, target } => {
+                closed.push(control);
+                (Gate::Z, target)
+            }
+            Gate::CP {
+                control, target, ..
+            }
+            | Gate::CRx {
+                control, target, ..
+            }
+            | Gate::CRy {
+                control, target, ..
+            }
+            | Gate::CRz {
+                control, target, ..
+            } => {
+                closed.push(control);
+                (gate.controlled_base(), target)
+            }
+            Gate::Toffoli {
+                control1,
+                control2,
+                target,
+            } => {
+                closed.extend([control1, control2]);
+                (Gate::X, target)
+            }
+            Gate::Fredkin {
+                control,
+                target1,
+                target2,
+            } => {
+                closed.push(control);
+                let swap = Gate::SWAP {
+                    qubit1: target1,
+                    qubit2: target2,
+                };
+                (swap, target1)
+            }
+            Gate::Controlled(controlled) => {
+                closed.extend(&controlled.controls);
+                open.extend(&controlled.open_controls);
+                (controlled.gate, controlled.target)
+            }
+            other => {
+                gate = other;
+                break;
+            }
+        };
+    }
+
+    let named = match (&gate, closed.as_slice(), open.is_empty()) {
+        (Gate::X, &[control], true) => Some(Gate::CNOT {
+            control,
+            target: wire,
+        }),
+        (Gate::X, &[control1, control2], true) => Some(Gate::Toffoli {
+            control1,
+            control2,
+            target: wire,
+        }),
+        (Gate::Z, &[control], true) => Some(Gate::CZ {
+            control,
+            target: wire,
+        }),
+        (Gate::P(phase), &[control], true) => Some(Gate::CP {
+            control,
+            target: wire,
+            phase: phase.clone(),
+        }),
+        (Gate::Rx(theta), &[control], true) => Some(Gate::CRx {
+            control,
+            target: wire,
+            theta: theta.clone(),
+        }),
+        (Gate::Ry(theta), &[control], true) => Some(Gate::CRy {
+            control,
+            target: wire,
+            theta: theta.clone(),
+        }),
+        (Gate::Rz(theta), &[control], true) => Some(Gate::CRz {
+            control,
+            target: wire,
+            theta: theta.clone(),
+        }),
+        (&Gate::SWAP { qubit1, qubit2 }, &[control], true) => Some(Gate::Fredkin {
+            control,
+            target1: qubit1,
+            target2: qubit2,
+        }),
+        _ => None,
+    };
+
+    let gate = named.unwrap_or_else(|| {
+        Gate::Controlled(Box::new(ControlledGate {
+            controls: closed,
+            open_controls: open,
+            gate,
+            target: wire,
+        }))
+    });
+    Instruction::new(gate, wire)
+}
+
+impl Display for Circuit {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for wire in self.storage.iter() {
+        for wire in self.wires().iter() {
             for gate in wire.iter() {
                 write!(f, "{} ", gate)?;
             }
@@ -64,3 +548,84 @@ impl<const WIDTH: usize> Display for CircuitRepr<WIDTH> {
         Ok(())
     }
 }
+
+/// A `Circuit` whose width is fixed at compile time. Every `Circuit` method is available
+/// through `Deref`; adding qubits through it breaks the `WIDTH` guarantee.
+#[derive(Debug, Clone)]
+pub struct CircuitRepr<const WIDTH: usize> {
+    circuit: Circuit,
+}
+
+impl<const WIDTH: usize> CircuitRepr<WIDTH> {
+    pub fn new() -> Self {
+        CircuitRepr {
+            circuit: Circuit::new(WIDTH),
+        }
+    }
+
+    /// Wraps a circuit of exactly `WIDTH` qubits.
+    pub fn from_circuit(circuit: Circuit) -> Self {
+        Self::try_from_circuit(circuit).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_from_circuit(circuit: Circuit) -> Result<Self, QuojoError> {
+        if circuit.num_qubits() != WIDTH {
+            return Err(QuojoError::WidthMismatch {
+                expected: WIDTH,
+                found: circuit.num_qubits(),
+            });
+        }
+        Ok(CircuitRepr { circuit })
+    }
+
+    /// The per-wire view, with multi-qubit gates listed on each of their wires.
+    pub fn wires(&self) -> [Vec<Gate>; WIDTH] {
+        let mut wires = self.circuit.wires().into_iter();
+        std::array::from_fn(|_| wires.next().unwrap_or_default())
+    }
+
+    /// Builds a circuit from a per-wire view; see `CircuitDag::from_wires` for how the order
+    /// between wires is recovered.
+    pub fn from_wires(wires: &[Vec<Gate>; WIDTH]) -> Self {
+        CircuitRepr {
+            circuit: Circuit::from_wires(wires),
+        }
+    }
+
+    /// A copy of the circuit with variable values, in radians, substituted into every gate.
+    pub fn bind(&self, values: &HashMap<String, f64>) -> CircuitRepr<WIDTH> {
+        CircuitRepr {
+            circuit: self.circuit.bind(values),
+        }
+    }
+
+    pub fn into_circuit(self) -> Circuit {
+        self.circuit
+    }
+}
+
+impl<const WIDTH: usize> Default for CircuitRepr<WIDTH> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const WIDTH: usize> Deref for CircuitRepr<WIDTH> {
+    type Target = Circuit;
+
+    fn deref(&self) -> &Circuit {
+        &self.circuit
+    }
+}
+
+impl<const WIDTH: usize> DerefMut for CircuitRepr<WIDTH> {
+    fn deref_mut(&mut self) -> &mut Circuit {
+        &mut self.circuit
+    }
+}
+
+impl<const WIDTH: usize> Display for CircuitRepr<WIDTH> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.circuit.fmt(f)
+    }
+}
@@ -0,0 +1,259 @@
+use crate::error::QuojoError;
+use crate::qcore::gates::Gate;
+
+/// A gate together with every qubit it acts on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub gate: Gate,
+    pub qubits: Vec<usize>,
+}
+
+impl Instruction {
+    /// The gate as stored on `wire`; multi-qubit gates name their own qubits.
+    pub fn new(gate: Gate, wire: usize) -> Self {
+        let qubits = gate.involved_qubits(wire);
+        Instruction { gate, qubits }
+    }
+
+    /// Checks that every qubit is below `width` and listed once.
+    pub fn check(&self, width: usize) -> Result<(), QuojoError> {
+        for (i, &qubit) in self.qubits.iter().enumerate() {
+            if qubit >= width {
+                return Err(QuojoError::QubitOutOfRange {
+                    gate: self.gate.clone(),
+                    qubit,
+                    width,
+                });
+            }
+            if self.qubits[..i].contains(&qubit) {
+                return Err(QuojoError::DuplicateQubit {
+                    gate: self.gate.clone(),
+                    qubit,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every classical bit of the gate is below `num_clbits`.
+    pub fn check_clbits(&self, num_clbits: usize) -> Result<(), QuojoError> {
+        match self
+            .gate
+            .involved_clbits()
+            .into_iter()
+            .find(|&clbit| clbit >= num_clbits)
+        {
+            Some(clbit) => Err(QuojoError::ClbitOutOfRange {
+                gate: self.gate.clone(),
+                clbit,
+                num_clbits,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A circuit as one ordered list of instructions, with the dependency DAG derived from it.
+///
+/// Instruction j succeeds instruction i when j is the next instruction after i on one of the
+/// qubits or classical bits of i. The list order is always a topological order of the DAG.
+#[derive(Debug, Clone, Default)]
+pub struct CircuitDag {
+    num_qubits: usize,
+    num_clbits: usize,
+    instructions: Vec<Instruction>,
+    predecessors: Vec<Vec<usize>>,
+    successors: Vec<Vec<usize>>,
+    /// The latest instruction on each qubit.
+    last_on_qubit: Vec<Option<usize>>,
+    /// The latest instruction reading or writing each classical bit.
+    last_on_clbit: Vec<Option<usize>>,
+}
+
+impl CircuitDag {
+    pub fn new(num_qubits: usize) -> Self {
+        CircuitDag {
+            num_qubits,
+            last_on_qubit: vec![None; num_qubits],
+            ..Default::default()
+        }
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    pub fn num_clbits(&self) -> usize {
+        self.num_clbits
+    }
+
+    /// Adds `count` classical bits, numbered after the existing ones.
+    pub fn add_clbits(&mut self, count: usize) {
+        self.num_clbits += count;
+        if self.last_on_clbit.len() < self.num_clbits {
+            self.last_on_clbit.resize(self.num_clbits, None);
+        }
+    }
+
+    /// Widens the circuit by `count` qubits, numbered after the existing ones.
+    pub fn add_qubits(&mut self, count: usize) {
+        self.num_qubits += count;
+        if self.last_on_qubit.len() < self.num_qubits {
+            self.last_on_qubit.resize(self.num_qubits, None);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_empty()
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Instructions in a topological order, which is the order they were added in.
+    pub fn iter(&self) -> std::slice::Iter<'_, Instruction> {
+        self.instructions.iter()
+    }
+
+    /// Appends an instruction after everything already on its qubits and classical bits and
+    /// returns its index. Neither is checked against the circuit's size; see
+    /// `Instruction::check` and `Instruction::check_clbits`.
+    pub fn push(&mut self, instruction: Instruction) -> usize {
+        let index = self.instructions.len();
+        let mut predecessors = Vec::new();
+
+        let clbits = instruction.gate.involved_clbits();
+        for (last_on, bits) in [
+            (&mut self.last_on_qubit, &instruction.qubits),
+            (&mut self.last_on_clbit, &clbits),
+        ] {
+            for &bit in bits {
+                if bit >= last_on.len() {
+                    last_on.resize(bit + 1, None);
+                }
+                if let Some(previous) = last_on[bit].replace(index) {
+                    // A bit listed twice would otherwise find the instruction itself
+                    if previous != index && !predecessors.contains(&previous) {
+                        predecessors.push(previous);
+                        self.successors[previous].push(index);
+                    }
+                }
+            }
+        }
+
+        predecessors.sort_unstable();
+        self.instructions.push(instruction);
+        self.predecessors.push(predecessors);
+        self.successors.push(Vec::new());
+        index
+    }
+
+    /// Instructions that must run directly before instruction `index`, in ascending order.
+    pub fn predecessors(&self, index: usize) -> &[usize] {
+        &self.predecessors[index]
+    }
+
+    /// Instructions that must run directly after instruction `index`.
+    pub fn successors(&self, index: usize) -> &[usize] {
+        &self.successors[index]
+    }
+
+    /// Groups instruction indices into layers of instructions on disjoint qubits. Every
+    /// instruction is placed as early as possible, one layer after its latest predecessor.
+    pub fn layers(&self) -> Vec<Vec<usize>> {
+        let mut layer_of = vec![0; self.instructions.len()];
+        let mut layers: Vec<Vec<usize>> = Vec::new();
+
+        for index in 0..self.instructions.len() {
+            let layer = self.predecessors[index]
+                .iter()
+                .map(|&p| layer_of[p] + 1)
+                .max()
+                .unwrap_or(0);
+            layer_of[index] = layer;
+            if layer == layers.len() {
+                layers.push(Vec::new());
+            }
+            layers[layer].push(index);
+        }
+
+        layers
+    }
+
+    pub fn depth(&self) -> usize {
+        self.layers().len()
+    }
+
+    /// The per-wire view: every wire lists the gates touching it, multi-qubit gates included
+    /// once on each of their wires.
+    pub fn to_wires(&self) -> Vec<Vec<Gate>> {
+        let mut wires = vec![Vec::new(); self.num_qubits];
+        for instruction in &self.instructions {
+            for (i, &qubit) in instruction.qubits.iter().enumerate() {
+                if qubit < self.num_qubits && !instruction.qubits[..i].contains(&qubit) {
+                    wires[qubit].push(instruction.gate.clone());
+                }
+            }
+        }
+        wires
+    }
+
+    /// Rebuilds the global order from a per-wire view. A multi-qubit gate is taken once it
+    /// sits at the head of every wire it touches, so the order between independent wires is
+    /// a guess; a gate missing from some of its wires is taken when nothing else can move.
+    pub fn from_wires(wires: &[Vec<Gate>]) -> Self {
+        let width = wires.len();
+        let mut dag = CircuitDag::new(width);
+        let clbits = wires.iter().flatten().flat_map(Gate::involved_clbits).max();
+        dag.add_clbits(clbits.map_or(0, |clbit| clbit + 1));
+        let mut heads = vec![0; width];
+
+        loop {
+            let mut progressed = false;
+
+            for wire in 0..width {
+                while let Some(gate) = wires[wire].get(heads[wire]) {
+                    let instruction = Instruction::new(gate.clone(), wire);
+                    let ready = instruction
+                        .qubits
+                        .iter()
+                        .all(|&q| q == wire || (q < width && wires[q].get(heads[q]) == Some(gate)));
+                    if !ready {
+                        break;
+                    }
+
+                    advance_heads(wires, &mut heads, wire, &instruction);
+                    dag.push(instruction);
+                    progressed = true;
+                }
+            }
+
+            if !progressed {
+                match (0..width).find(|&w| heads[w] < wires[w].len()) {
+                    Some(wire) => {
+                        let instruction = Instruction::new(wires[wire][heads[wire]].clone(), wire);
+                        advance_heads(wires, &mut heads, wire, &instruction);
+                        dag.push(instruction);
+                    }
+                    None => break,
+                }
+            }
+        }
+
+        dag
+    }
+}
+
+fn advance_heads(wires: &[Vec<Gate>], heads: &mut [usize], wire: usize, instruction: &Instruction) {
+    heads[wire] += 1;
+    for &q in &instruction.qubits {
+        if q != wire && q < wires.len() && wires[q].get(heads[q]) == Some(&instruction.gate) {
+            heads[q] += 1;
+        }
+    }
+}
@@ -1,19 +1,47 @@
-// TODO: custom unitary gates
-
+use crate::error::QuojoError;
+use crate::linalg::vector::Complex;
+use crate::qcore::phase::Phase;
+use crate::utils::synthesis::expand;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt::Display;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Gate {
     X,
     Y,
     Z,
     H,
-    P(f64),
+    P(Phase),
+    S,
+    Sdg, // S†
+    T,
+    Tdg, // T†
+    SX,  // √X
+    Rx(Phase),
+    Ry(Phase),
+    Rz(Phase),
+    U3 { theta: Phase, phi: Phase, lambda: Phase },
+    GlobalPhase(Phase),
     CNOT { control: usize, target: usize },
     CZ { control: usize, target: usize },
+    CP { control: usize, target: usize, phase: Phase },
+    CRx { control: usize, target: usize, theta: Phase },
+    CRy { control: usize, target: usize, theta: Phase },
+    CRz { control: usize, target: usize, theta: Phase },
     SWAP { qubit1: usize, qubit2: usize },
+    RXX { qubit1: usize, qubit2: usize, theta: Phase }, // e^(-iθ/2·X⊗X)
+    RYY { qubit1: usize, qubit2: usize, theta: Phase }, // e^(-iθ/2·Y⊗Y)
+    RZZ { qubit1: usize, qubit2: usize, theta: Phase }, // e^(-iθ/2·Z⊗Z)
+    ISWAP { qubit1: usize, qubit2: usize },
+    SqrtISWAP { qubit1: usize, qubit2: usize },
+    ECR { qubit1: usize, qubit2: usize }, // (X⊗I − Y⊗X)/√2, qubit1 on the left
     Toffoli { control1: usize, control2: usize, target: usize }, // CCX
     Fredkin { control: usize, target1: usize, target2: usize },  // CSWAP
+    Unitary(UnitaryGate),
+    Controlled(Box<ControlledGate>),
+    Measure { qubit: usize, clbit: usize },
+    Reset, // To |0⟩
+    Conditional(Box<ConditionalGate>),
 }
 
 impl Display for Gate {
@@ -24,17 +52,306 @@ impl Display for Gate {
             Gate::Z => write!(f, "Z"),
             Gate::H => write!(f, "H"),
             Gate::P(phase) => write!(f, "P({})", phase),
+            Gate::S => write!(f, "S"),
+            Gate::Sdg => write!(f, "S†"),
+            Gate::T => write!(f, "T"),
+            Gate::Tdg => write!(f, "T†"),
+            Gate::SX => write!(f, "√X"),
+            Gate::Rx(theta) => write!(f, "Rx({})", theta),
+            Gate::Ry(theta) => write!(f, "Ry({})", theta),
+            Gate::Rz(theta) => write!(f, "Rz({})", theta),
+            Gate::U3 { theta, phi, lambda } => write!(f, "U3({},{},{})", theta, phi, lambda),
+            Gate::GlobalPhase(phase) => write!(f, "GlobalPhase({})", phase),
             Gate::CNOT { control, target } => write!(f, "CNOT({},{})", control, target),
             Gate::CZ { control, target } => write!(f, "CZ({},{})", control, target),
+            Gate::CP { control, target, phase } => write!(f, "CP({},{},{})", control, target, phase),
+            Gate::CRx { control, target, theta } => write!(f, "CRx({},{},{})", control, target, theta),
+            Gate::CRy { control, target, theta } => write!(f, "CRy({},{},{})", control, target, theta),
+            Gate::CRz { control, target, theta } => write!(f, "CRz({},{},{})", control, target, theta),
             Gate::SWAP { qubit1, qubit2 } => write!(f, "SWAP({},{})", qubit1, qubit2),
+            Gate::RXX { qubit1, qubit2, theta } => write!(f, "RXX({},{},{})", qubit1, qubit2, theta),
+            Gate::RYY { qubit1, qubit2, theta } => write!(f, "RYY({},{},{})", qubit1, qubit2, theta),
+            Gate::RZZ { qubit1, qubit2, theta } => write!(f, "RZZ({},{},{})", qubit1, qubit2, theta),
+            Gate::ISWAP { qubit1, qubit2 } => write!(f, "iSWAP({},{})", qubit1, qubit2),
+            Gate::SqrtISWAP { qubit1, qubit2 } => write!(f, "√iSWAP({},{})", qubit1, qubit2),
+            Gate::ECR { qubit1, qubit2 } => write!(f, "ECR({},{})", qubit1, qubit2),
             Gate::Toffoli { control1, control2, target } => 
                 write!(f, "Toffoli({},{},{})", control1, control2, target),
             Gate::Fredkin { control, target1, target2 } => 
                 write!(f, "Fredkin({},{},{})", control, target1, target2),
+            Gate::Unitary(unitary) => {
+                let qubits: Vec<String> = unitary.qubits.iter().map(|q| q.to_string()).collect();
+                write!(f, "{}({})", unitary.label, qubits.join(","))
+            }
+            Gate::Controlled(controlled) => {
+                let controls: Vec<String> = controlled
+                    .controls
+                    .iter()
+                    .map(|q| q.to_string())
+                    .chain(controlled.open_controls.iter().map(|q| format!("!{}", q)))
+                    .collect();
+                write!(f, "C({}){}", controls.join(","), controlled.gate)?;
+                if controlled.gate.takes_wire() {
+                    write!(f, "({})", controlled.target)?;
+                }
+                Ok(())
+            }
+            Gate::Measure { qubit, clbit } => write!(f, "Measure({},c{})", qubit, clbit),
+            Gate::Reset => write!(f, "Reset"),
+            Gate::Conditional(conditional) => {
+                let clbits: Vec<String> = conditional.condition.clbits.iter().map(|c| format!("c{}", c)).collect();
+                write!(f, "If({}={}){}", clbits.join(","), conditional.condition.value, conditional.gate)?;
+                if conditional.gate.takes_wire() {
+                    write!(f, "({})", conditional.target)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Gate {
+    /// A custom gate applying `matrix` to `qubits`; see `UnitaryGate::new`.
+    pub fn unitary(
+        label: &str,
+        qubits: &[usize],
+        matrix: Vec<Vec<Complex>>,
+    ) -> Result<Gate, QuojoError> {
+        UnitaryGate::new(label, qubits, matrix).map(Gate::Unitary)
+    }
+
+    /// Qubits touched by this gate when it is stored on `wire`.
+    pub fn involved_qubits(&self, wire: usize) -> Vec<usize> {
+        match self {
+            Gate::X | Gate::Y | Gate::Z | Gate::H | Gate::P(_)
+            | Gate::S | Gate::Sdg | Gate::T | Gate::Tdg | Gate::SX
+            | Gate::Rx(_) | Gate::Ry(_) | Gate::Rz(_) | Gate::U3 { .. } | Gate::GlobalPhase(_) | Gate::Reset => vec![wire],
+            Gate::CNOT { control, target } => vec![*control, *target],
+            Gate::CZ { control, target } => vec![*control, *target],
+            Gate::CP { control, target, .. } | Gate::CRx { control, target, .. }
+            | Gate::CRy { control, target, .. } | Gate::CRz { control, target, .. } => vec![*control, *target],
+            Gate::SWAP { qubit1, qubit2 } => vec![*qubit1, *qubit2],
+            Gate::RXX { qubit1, qubit2, .. } | Gate::RYY { qubit1, qubit2, .. } | Gate::RZZ { qubit1, qubit2, .. }
+            | Gate::ISWAP { qubit1, qubit2 } | Gate::SqrtISWAP { qubit1, qubit2 } | Gate::ECR { qubit1, qubit2 } => vec![*qubit1, *qubit2],
+            Gate::Toffoli {
+                control1,
+                control2,
+                target,
+            } => vec![*control1, *control2, *target],
+            Gate::Fredkin {
+                control,
+                target1,
+                target2,
+            } => vec![*control, *target1, *target2],
+            Gate::Unitary(unitary) => unitary.qubits.clone(),
+            Gate::Controlled(controlled) => {
+                let mut qubits = controlled.controls.clone();
+                qubits.extend(&controlled.open_controls);
+                qubits.extend(controlled.gate.involved_qubits(controlled.target));
+                qubits
+            }
+            Gate::Measure { qubit, .. } => vec![*qubit],
+            Gate::Conditional(conditional) => conditional.gate.involved_qubits(conditional.target),
+        }
+    }
+
+    /// Classical bits read or written by this gate.
+    pub fn involved_clbits(&self) -> Vec<usize> {
+        match self {
+            Gate::Measure { clbit, .. } => vec![*clbit],
+            Gate::Conditional(conditional) => {
+                let mut clbits = conditional.condition.clbits.clone();
+                clbits.extend(conditional.gate.involved_clbits());
+                clbits
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether the gate is a unitary operation. Measurements, resets and classically
+    /// conditioned gates are not, so they cannot be controlled or converted to ZX.
+    pub fn is_unitary(&self) -> bool {
+        !matches!(self, Gate::Measure { .. } | Gate::Reset | Gate::Conditional(_))
+    }
+
+    /// Whether the gate acts on the wire it is stored on, rather than naming its qubits.
+    pub fn takes_wire(&self) -> bool {
+        matches!(
+            self,
+            Gate::X | Gate::Y | Gate::Z | Gate::H | Gate::P(_)
+                | Gate::S | Gate::Sdg | Gate::T | Gate::Tdg | Gate::SX
+                | Gate::Rx(_) | Gate::Ry(_) | Gate::Rz(_) | Gate::U3 { .. } | Gate::GlobalPhase(_)
+                | Gate::Reset
+        )
+    }
+
+    /// Names of the symbolic variables in this gate's phases.
+    pub fn variables(&self) -> BTreeSet<String> {
+        match self {
+            Gate::P(phase) | Gate::Rx(phase) | Gate::Ry(phase) | Gate::Rz(phase)
+            | Gate::GlobalPhase(phase) => phase.variables(),
+            Gate::CP { phase, .. } | Gate::CRx { theta: phase, .. } | Gate::CRy { theta: phase, .. }
+            | Gate::CRz { theta: phase, .. } | Gate::RXX { theta: phase, .. } | Gate::RYY { theta: phase, .. }
+            | Gate::RZZ { theta: phase, .. } => phase.variables(),
+            Gate::U3 { theta, phi, lambda } => {
+                [theta, phi, lambda].iter().flat_map(|phase| phase.variables()).collect()
+            }
+            Gate::Controlled(controlled) => controlled.gate.variables(),
+            Gate::Conditional(conditional) => conditional.gate.variables(),
+            _ => BTreeSet::new(),
+        }
+    }
+
+    /// Substitutes variable values, in radians, into this gate's phases.
+    pub fn bind(&self, values: &HashMap<String, f64>) -> Gate {
+        match self {
+            Gate::P(phase) => Gate::P(phase.bind(values)),
+            Gate::Rx(theta) => Gate::Rx(theta.bind(values)),
+            Gate::Ry(theta) => Gate::Ry(theta.bind(values)),
+            Gate::Rz(theta) => Gate::Rz(theta.bind(values)),
+            Gate::U3 { theta, phi, lambda } => Gate::U3 {
+                theta: theta.bind(values),
+                phi: phi.bind(values),
+                lambda: lambda.bind(values),
+            },
+            Gate::GlobalPhase(phase) => Gate::GlobalPhase(phase.bind(values)),
+            Gate::CP { control, target, phase } => Gate::CP { control: *control, target: *target, phase: phase.bind(values) },
+            Gate::CRx { control, target, theta } => Gate::CRx { control: *control, target: *target, theta: theta.bind(values) },
+            Gate::CRy { control, target, theta } => Gate::CRy { control: *control, target: *target, theta: theta.bind(values) },
+            Gate::CRz { control, target, theta } => Gate::CRz { control: *control, target: *target, theta: theta.bind(values) },
+            Gate::RXX { qubit1, qubit2, theta } => Gate::RXX { qubit1: *qubit1, qubit2: *qubit2, theta: theta.bind(values) },
+            Gate::RYY { qubit1, qubit2, theta } => Gate::RYY { qubit1: *qubit1, qubit2: *qubit2, theta: theta.bind(values) },
+            Gate::RZZ { qubit1, qubit2, theta } => Gate::RZZ { qubit1: *qubit1, qubit2: *qubit2, theta: theta.bind(values) },
+            Gate::Controlled(controlled) => Gate::Controlled(Box::new(ControlledGate {
+                gate: controlled.gate.bind(values),
+                ..(**controlled).clone()
+            })),
+            Gate::Conditional(conditional) => Gate::Conditional(Box::new(ConditionalGate {
+                gate: conditional.gate.bind(values),
+                ..(**conditional).clone()
+            })),
+            gate => gate.clone(),
+        }
+    }
+
+    /// The single-qubit gate a singly controlled gate applies to its target: X for CNOT,
+    /// Z for CZ, P for CP and the rotation for CRx, CRy and CRz. Panics for other gates.
+    pub fn controlled_base(&self) -> Gate {
+        match self {
+            Gate::CNOT { .. } => Gate::X,
+            Gate::CZ { .. } => Gate::Z,
+            Gate::CP { phase, .. } => Gate::P(phase.clone()),
+            Gate::CRx { theta, .. } => Gate::Rx(theta.clone()),
+            Gate::CRy { theta, .. } => Gate::Ry(theta.clone()),
+            Gate::CRz { theta, .. } => Gate::Rz(theta.clone()),
+            _ => panic!("{} is not a singly controlled gate", self),
         }
     }
 }
 
+/// `gate` applied only when every qubit in `controls` is |1⟩ and every qubit in
+/// `open_controls` is |0⟩.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlledGate {
+    pub controls: Vec<usize>,
+    pub open_controls: Vec<usize>,
+    pub gate: Gate,
+    /// The wire of `gate` when it is a single-qubit gate; other gates name their qubits.
+    pub target: usize,
+}
+
+/// Holds when the classical bits `clbits` read `value`, with bit i of `value` the state of
+/// `clbits[i]`, like OpenQASM's `if (c == value)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub clbits: Vec<usize>,
+    pub value: u64,
+}
+
+impl Condition {
+    /// Whether the condition holds for the classical bits `bits`, indexed by clbit.
+    pub fn holds(&self, bits: &[bool]) -> bool {
+        self.clbits
+            .iter()
+            .enumerate()
+            .all(|(i, &clbit)| bits[clbit] == (self.value.checked_shr(i as u32).unwrap_or(0) & 1 == 1))
+            && self.value.checked_shr(self.clbits.len() as u32).unwrap_or(0) == 0
+    }
+}
+
+/// `gate` applied only when `condition` holds for the classical bits at that point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConditionalGate {
+    pub condition: Condition,
+    pub gate: Gate,
+    /// The wire of `gate` when it is a single-qubit gate; other gates name their qubits.
+    pub target: usize,
+}
+
+/// Largest entry of U†U − I accepted for a custom unitary.
+pub const UNITARY_TOLERANCE: f64 = 1e-8;
+
+/// A gate given by its matrix. Bit i of a row or column index is the state of `qubits[i]`,
+/// the same order the state-vector simulator uses for qubit indices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitaryGate {
+    pub label: String,
+    pub qubits: Vec<usize>,
+    matrix: Vec<Vec<Complex>>,
+}
+
+impl UnitaryGate {
+    /// Checks that `matrix` is 2^k × 2^k for k = `qubits.len()` ≥ 1 and unitary within
+    /// `UNITARY_TOLERANCE`.
+    pub fn new(
+        label: &str,
+        qubits: &[usize],
+        matrix: Vec<Vec<Complex>>,
+    ) -> Result<Self, QuojoError> {
+        let dimension = 1usize << qubits.len();
+        let columns = matrix
+            .iter()
+            .map(|row| row.len())
+            .find(|&len| len != dimension)
+            .unwrap_or(dimension);
+        if qubits.is_empty() || matrix.len() != dimension || columns != dimension {
+            return Err(QuojoError::MatrixShape {
+                label: label.to_string(),
+                qubits: qubits.len(),
+                rows: matrix.len(),
+                columns,
+            });
+        }
+
+        let mut deviation: f64 = 0.0;
+        for i in 0..dimension {
+            for j in 0..dimension {
+                let mut entry = Complex::new(if i == j { -1.0 } else { 0.0 }, 0.0);
+                for row in &matrix {
+                    entry = entry + row[i].conj() * row[j];
+                }
+                deviation = deviation.max(entry.abs());
+            }
+        }
+        if deviation > UNITARY_TOLERANCE {
+            return Err(QuojoError::NotUnitary {
+                label: label.to_string(),
+                deviation,
+            });
+        }
+
+        Ok(UnitaryGate {
+            label: label.to_string(),
+            qubits: qubits.to_vec(),
+            matrix,
+        })
+    }
+
+    pub fn matrix(&self) -> &[Vec<Complex>] {
+        &self.matrix
+    }
+}
+
 pub trait GateDecomposition {
     fn decompose(&self) -> Vec<DecomposedGate>;
 }
@@ -45,62 +362,52 @@ pub struct DecomposedGate {
     pub qubits: Vec<usize>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum PrimitiveGate {
     X,
     Z,
     H,
-    P(f64),
+    P(Phase),
     Connect,
 }
 
+/// Gates decompose through `synthesis::expand` until only primitives are left, so every
+/// unitary gate has a decomposition. Global phases cannot be expressed by primitives and
+/// are dropped, which makes rotations, U3, Y, the Ising couplings, √iSWAP and ECR exact only
+/// up to a global phase. Single-qubit gates act on qubit 0. Measurements, resets and
+/// conditional gates are not unitary and decompose to nothing.
 impl GateDecomposition for Gate {
     fn decompose(&self) -> Vec<DecomposedGate> {
-        match self {
-            Gate::X => vec![DecomposedGate { 
-                gate: PrimitiveGate::X, 
-                qubits: vec![0] 
-            }],
-            Gate::Z => vec![DecomposedGate { 
-                gate: PrimitiveGate::Z, 
-                qubits: vec![0] 
-            }],
-            Gate::H => vec![DecomposedGate { 
-                gate: PrimitiveGate::H, 
-                qubits: vec![0] 
-            }],
-            Gate::P(phase) => vec![DecomposedGate { 
-                gate: PrimitiveGate::P(*phase), 
-                qubits: vec![0] 
-            }],
-            Gate::CNOT { control, target } => vec![
-                DecomposedGate { gate: PrimitiveGate::H, qubits: vec![*target] },
-                DecomposedGate { gate: PrimitiveGate::Z, qubits: vec![*control, *target] },
-                DecomposedGate { gate: PrimitiveGate::H, qubits: vec![*target] },
-                DecomposedGate { gate: PrimitiveGate::Connect, qubits: vec![*control, *target] },
-            ],
-            Gate::CZ { control, target } => vec![
-                DecomposedGate { gate: PrimitiveGate::Z, qubits: vec![*control, *target] },
-                DecomposedGate { gate: PrimitiveGate::Connect, qubits: vec![*control, *target] },
-            ],
-            Gate::SWAP { qubit1, qubit2 } => vec![
-                // SWAP = 3 consecutive CNOTs
-                // First CNOT
-                DecomposedGate { gate: PrimitiveGate::H, qubits: vec![*qubit2] },
-                DecomposedGate { gate: PrimitiveGate::Z, qubits: vec![*qubit1, *qubit2] },
-                DecomposedGate { gate: PrimitiveGate::H, qubits: vec![*qubit2] },
-                // Second CNOT
-                DecomposedGate { gate: PrimitiveGate::H, qubits: vec![*qubit1] },
-                DecomposedGate { gate: PrimitiveGate::Z, qubits: vec![*qubit2, *qubit1] },
-                DecomposedGate { gate: PrimitiveGate::H, qubits: vec![*qubit1] },
-                // Third CNOT
-                DecomposedGate { gate: PrimitiveGate::H, qubits: vec![*qubit2] },
-                DecomposedGate { gate: PrimitiveGate::Z, qubits: vec![*qubit1, *qubit2] },
-                DecomposedGate { gate: PrimitiveGate::H, qubits: vec![*qubit2] },
-                DecomposedGate { gate: PrimitiveGate::Connect, qubits: vec![*qubit1, *qubit2] },
-            ],
-            // TODO: Additional decompositions for Toffoli and Fredkin WILL GO HERE
-            _ => vec![],
+        if !self.is_unitary() {
+            return vec![];
+        }
+        let mut primitives = primitives(self, 0);
+        let qubits = self.involved_qubits(0);
+        if qubits.len() > 1 {
+            primitives.push(DecomposedGate { gate: PrimitiveGate::Connect, qubits });
         }
+        primitives
+    }
+}
+
+/// The primitives of `gate` stored on `wire`, without the closing `Connect`.
+fn primitives(gate: &Gate, wire: usize) -> Vec<DecomposedGate> {
+    let on = |gate: PrimitiveGate, qubits: Vec<usize>| vec![DecomposedGate { gate, qubits }];
+    match gate {
+        Gate::X => on(PrimitiveGate::X, vec![wire]),
+        Gate::Z => on(PrimitiveGate::Z, vec![wire]),
+        Gate::H => on(PrimitiveGate::H, vec![wire]),
+        Gate::P(phase) => on(PrimitiveGate::P(phase.clone()), vec![wire]),
+        Gate::GlobalPhase(_) => vec![],
+        // CNOT = H·CZ·H on the target
+        Gate::CNOT { control, target } => [
+            on(PrimitiveGate::H, vec![*target]),
+            on(PrimitiveGate::Z, vec![*control, *target]),
+            on(PrimitiveGate::H, vec![*target]),
+        ].concat(),
+        Gate::CZ { control, target } => on(PrimitiveGate::Z, vec![*control, *target]),
+        _ => expand(gate, wire).instructions.iter()
+            .flat_map(|instruction| primitives(&instruction.gate, instruction.qubits[0]))
+            .collect(),
     }
 }
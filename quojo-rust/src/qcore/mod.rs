@@ -1,5 +1,8 @@
 pub mod circuits;
+pub mod dag;
 pub mod gates;
+pub mod phase;
+pub mod svg;
 pub mod tikz;
 
 pub fn Targets(targets: &[usize]) -> circuits::Targets {
@@ -11,19 +14,30 @@ pub fn Targets(targets: &[usize]) -> circuits::Targets {
 pub fn Controls(controls: &[usize]) -> circuits::Controls {
     circuits::Controls {
         controls: controls.to_vec(),
+        open_controls: Vec::new(),
     }
 }
 
-pub fn CircuitRepr<const WIDTH: usize>() -> circuits::CircuitRepr<WIDTH> {
-    circuits::CircuitRepr {
-        storage: std::array::from_fn(|_| Vec::new()),
+/// A condition on classical bits; see `gates::Condition`.
+pub fn Condition(clbits: &[usize], value: u64) -> gates::Condition {
+    gates::Condition {
+        clbits: clbits.to_vec(),
+        value,
     }
 }
 
+pub fn CircuitRepr<const WIDTH: usize>() -> circuits::CircuitRepr<WIDTH> {
+    circuits::CircuitRepr::new()
+}
+
 pub fn TikzConfig() -> tikz::TikzQConfig {
     tikz::TikzQConfig::default()
 }
 
+pub fn SvgConfig() -> svg::SvgQConfig {
+    svg::SvgQConfig::default()
+}
+
 pub fn Qubits(qubits: &[usize]) -> Vec<usize> {
     qubits.to_vec()
 }
@@ -0,0 +1,443 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::f64::consts::{PI, TAU};
+use std::fmt::Display;
+use std::ops;
+
+/// Largest denominator recognised when converting radians back into a multiple of π.
+const MAX_DENOMINATOR: i64 = 64;
+const TOLERANCE: f64 = 1e-9;
+
+/// An angle, kept as an exact multiple of π whenever possible.
+///
+/// Constant phases compare equal when they are the same angle mod 2π: exactly when both are
+/// rational, and to within 1e-9 radians otherwise. Symbolic phases compare equal when they
+/// have the same terms and constant part.
+#[derive(Debug, Clone)]
+pub struct Phase(Repr);
+
+/// Only built by `Phase`'s constructors and arithmetic, which keep rational phases in lowest
+/// terms with 0 ≤ numerator < 2·denominator.
+#[derive(Debug, Clone)]
+enum Repr {
+    /// (numerator / denominator)·π.
+    Rational(i64, i64),
+    /// Radians in [0, 2π) with no small rational form.
+    Float(f64),
+    /// A phase depending on named variables, fixed later with `bind`.
+    Symbolic(Box<Expression>),
+}
+
+/// Σ coefficient·variable + constant, with variables measured in radians.
+///
+/// Always has at least one term; expressions whose terms cancel collapse back to the constant.
+#[derive(Debug, Clone, PartialEq)]
+struct Expression {
+    terms: BTreeMap<String, f64>,
+    /// Never symbolic.
+    constant: Phase,
+}
+
+impl Phase {
+    pub const ZERO: Phase = Phase(Repr::Rational(0, 1));
+    pub const PI: Phase = Phase(Repr::Rational(1, 1));
+
+    /// The phase (numerator / denominator)·π, normalised mod 2π. Panics if `denominator` is 0;
+    /// `try_new` returns `None` instead.
+    pub fn new(numerator: i64, denominator: i64) -> Self {
+        Self::try_new(numerator, denominator).expect("Phase denominator must be non-zero")
+    }
+
+    /// Like `new`, but `None` if `denominator` is 0.
+    pub fn try_new(numerator: i64, denominator: i64) -> Option<Self> {
+        (denominator != 0).then(|| Self::reduce(numerator as i128, denominator as i128))
+    }
+
+    /// Converts radians, recognising multiples of π/d for small d exactly.
+    pub fn from_radians(radians: f64) -> Self {
+        let radians = radians.rem_euclid(TAU);
+        for denominator in 1..=MAX_DENOMINATOR {
+            let multiple = radians / PI * denominator as f64;
+            let rounded = multiple.round();
+            if ((multiple - rounded) * PI / denominator as f64).abs() < TOLERANCE {
+                return Phase::new(rounded as i64, denominator);
+            }
+        }
+        Phase(Repr::Float(radians))
+    }
+
+    /// A single named variable with coefficient 1.
+    pub fn variable(name: impl Into<String>) -> Self {
+        Phase(Repr::Symbolic(Box::new(Expression {
+            terms: BTreeMap::from([(name.into(), 1.0)]),
+            constant: Phase::ZERO,
+        })))
+    }
+
+    /// Numerator and denominator of a rational phase, in lowest terms with
+    /// 0 ≤ numerator < 2·denominator.
+    pub fn as_rational(&self) -> Option<(i64, i64)> {
+        match self.0 {
+            Repr::Rational(numerator, denominator) => Some((numerator, denominator)),
+            _ => None,
+        }
+    }
+
+    /// Radians in [0, 2π) of a constant phase with no small rational form.
+    pub fn as_float(&self) -> Option<f64> {
+        match self.0 {
+            Repr::Float(radians) => Some(radians),
+            _ => None,
+        }
+    }
+
+    /// The variables and their coefficients, in name order. Empty for constant phases.
+    pub fn terms(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.expression().into_iter().flat_map(|expression| {
+            expression
+                .terms
+                .iter()
+                .map(|(name, &coefficient)| (name.as_str(), coefficient))
+        })
+    }
+
+    /// The part without variables, which is the phase itself when it is constant.
+    pub fn constant(&self) -> Phase {
+        match self.expression() {
+            Some(expression) => expression.constant.clone(),
+            None => self.clone(),
+        }
+    }
+
+    fn expression(&self) -> Option<&Expression> {
+        match &self.0 {
+            Repr::Symbolic(expression) => Some(expression),
+            _ => None,
+        }
+    }
+
+    /// Panics on symbolic phases, which have to be bound first.
+    pub fn to_radians(&self) -> f64 {
+        match &self.0 {
+            Repr::Rational(numerator, denominator) => *numerator as f64 * PI / *denominator as f64,
+            Repr::Float(radians) => *radians,
+            Repr::Symbolic(expression) => panic!(
+                "Phase {} has unbound variables {:?}",
+                self,
+                expression.terms.keys().collect::<Vec<_>>()
+            ),
+        }
+    }
+
+    pub fn is_symbolic(&self) -> bool {
+        matches!(self.0, Repr::Symbolic(_))
+    }
+
+    /// Names of the variables this phase depends on.
+    pub fn variables(&self) -> BTreeSet<String> {
+        self.terms().map(|(name, _)| name.to_string()).collect()
+    }
+
+    /// Substitutes variable values in radians. Variables missing from `values` stay symbolic.
+    ///
+    /// Each value is taken at its representative in (−π, π], like a constant angle is by
+    /// `half`, so halving a variable and then binding it gives the same phase as binding it
+    /// and then halving.
+    pub fn bind(&self, values: &HashMap<String, f64>) -> Phase {
+        let Some(expression) = self.expression() else {
+            return self.clone();
+        };
+        let mut radians = 0.0;
+        let mut terms = BTreeMap::new();
+        for (name, &coefficient) in &expression.terms {
+            match values.get(name) {
+                Some(&value) => radians += coefficient * representative(value),
+                None => {
+                    terms.insert(name.clone(), coefficient);
+                }
+            }
+        }
+        Phase::symbolic(
+            terms,
+            expression.constant.clone() + Phase::from_radians(radians),
+        )
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Phase::ZERO
+    }
+
+    /// Half of the representative in (−π, π], so that rotation gates, whose θ/2 is only fixed
+    /// up to π by θ mod 2π, treat small negative angles as expected. Variable coefficients are
+    /// halved exactly and the constant part is halved the same way, which `bind` matches as
+    /// long as the bound sum stays in (−π, π].
+    pub fn half(&self) -> Phase {
+        match &self.0 {
+            Repr::Rational(numerator, denominator) if numerator > denominator => Phase::reduce(
+                *numerator as i128 - 2 * *denominator as i128,
+                2 * *denominator as i128,
+            ),
+            Repr::Rational(numerator, denominator) => {
+                Phase::reduce(*numerator as i128, 2 * *denominator as i128)
+            }
+            Repr::Float(radians) => Phase::from_radians(representative(*radians) / 2.0),
+            Repr::Symbolic(expression) => {
+                let terms = expression
+                    .terms
+                    .iter()
+                    .map(|(name, coefficient)| (name.clone(), coefficient / 2.0))
+                    .collect();
+                Phase::symbolic(terms, expression.constant.half())
+            }
+        }
+    }
+
+    /// 0 or π.
+    pub fn is_pauli(&self) -> bool {
+        matches!(self.as_rational(), Some((_, 1)))
+    }
+
+    /// A multiple of π/2.
+    pub fn is_clifford(&self) -> bool {
+        matches!(self.as_rational(), Some((_, 1 | 2)))
+    }
+
+    /// ±π/2, the Clifford phases that are not Pauli.
+    pub fn is_proper_clifford(&self) -> bool {
+        matches!(self.as_rational(), Some((_, 2)))
+    }
+
+    /// LaTeX math-mode source, with multiples of π as fractions.
+    pub fn to_latex(&self) -> String {
+        match &self.0 {
+            Repr::Rational(0, _) => "0".to_string(),
+            Repr::Rational(1, 1) => "\\pi".to_string(),
+            Repr::Rational(numerator, 1) => format!("{}\\pi", numerator),
+            Repr::Rational(1, denominator) => format!("\\frac{{\\pi}}{{{}}}", denominator),
+            Repr::Rational(numerator, denominator) => {
+                format!("\\frac{{{}\\pi}}{{{}}}", numerator, denominator)
+            }
+            Repr::Float(radians) => format!("{:.2}", radians),
+            Repr::Symbolic(expression) => {
+                let mut latex = String::new();
+                for (name, &coefficient) in &expression.terms {
+                    if !latex.is_empty() || coefficient < 0.0 {
+                        latex.push_str(if coefficient < 0.0 { "-" } else { "+" });
+                    }
+                    if coefficient.abs() != 1.0 {
+                        latex.push_str(&format!("{}\\,", coefficient.abs()));
+                    }
+                    latex.push_str(name);
+                }
+                if !expression.constant.is_zero() {
+                    latex.push('+');
+                    latex.push_str(&expression.constant.to_latex());
+                }
+                latex
+            }
+        }
+    }
+
+    /// Builds an expression, dropping cancelled terms and collapsing to a constant if none are left.
+    fn symbolic(mut terms: BTreeMap<String, f64>, constant: Phase) -> Phase {
+        terms.retain(|_, coefficient| coefficient.abs() > TOLERANCE);
+        if terms.is_empty() {
+            constant
+        } else {
+            Phase(Repr::Symbolic(Box::new(Expression { terms, constant })))
+        }
+    }
+
+    /// Splits a phase into its variable terms and constant part.
+    fn into_parts(self) -> (BTreeMap<String, f64>, Phase) {
+        match self.0 {
+            Repr::Symbolic(expression) => (expression.terms, expression.constant),
+            _ => (BTreeMap::new(), self),
+        }
+    }
+
+    fn reduce(numerator: i128, denominator: i128) -> Self {
+        let sign = denominator.signum();
+        let (numerator, denominator) = (numerator * sign, denominator * sign);
+        let numerator = numerator.rem_euclid(2 * denominator);
+        let divisor = gcd(numerator, denominator);
+        let (numerator, denominator) = (numerator / divisor, denominator / divisor);
+
+        match (i64::try_from(numerator), i64::try_from(denominator)) {
+            (Ok(numerator), Ok(denominator)) => Phase(Repr::Rational(numerator, denominator)),
+            _ => Phase(Repr::Float(numerator as f64 * PI / denominator as f64)),
+        }
+    }
+}
+
+impl From<f64> for Phase {
+    fn from(radians: f64) -> Self {
+        Phase::from_radians(radians)
+    }
+}
+
+impl ops::Add for Phase {
+    type Output = Phase;
+
+    fn add(self, rhs: Phase) -> Phase {
+        match (&self.0, &rhs.0) {
+            (&Repr::Rational(n1, d1), &Repr::Rational(n2, d2)) => Phase::reduce(
+                n1 as i128 * d2 as i128 + n2 as i128 * d1 as i128,
+                d1 as i128 * d2 as i128,
+            ),
+            _ if self.is_symbolic() || rhs.is_symbolic() => {
+                let (mut terms, lhs_constant) = self.into_parts();
+                let (rhs_terms, rhs_constant) = rhs.into_parts();
+                for (name, coefficient) in rhs_terms {
+                    *terms.entry(name).or_insert(0.0) += coefficient;
+                }
+                Phase::symbolic(terms, lhs_constant + rhs_constant)
+            }
+            _ => Phase::from_radians(self.to_radians() + rhs.to_radians()),
+        }
+    }
+}
+
+impl ops::AddAssign for Phase {
+    fn add_assign(&mut self, rhs: Phase) {
+        *self = std::mem::replace(self, Phase::ZERO) + rhs;
+    }
+}
+
+impl ops::Neg for Phase {
+    type Output = Phase;
+
+    fn neg(self) -> Phase {
+        match self.0 {
+            Repr::Rational(numerator, denominator) => {
+                Phase::reduce(-(numerator as i128), denominator as i128)
+            }
+            Repr::Float(radians) => Phase::from_radians(-radians),
+            Repr::Symbolic(_) => self * -1.0,
+        }
+    }
+}
+
+impl ops::Sub for Phase {
+    type Output = Phase;
+
+    fn sub(self, rhs: Phase) -> Phase {
+        self + -rhs
+    }
+}
+
+impl ops::Mul<i64> for Phase {
+    type Output = Phase;
+
+    fn mul(self, rhs: i64) -> Phase {
+        match self.0 {
+            Repr::Rational(numerator, denominator) => {
+                Phase::reduce(numerator as i128 * rhs as i128, denominator as i128)
+            }
+            Repr::Float(radians) => Phase::from_radians(radians * rhs as f64),
+            Repr::Symbolic(_) => self * rhs as f64,
+        }
+    }
+}
+
+/// Scales by a real factor. Constant phases are taken mod 2π first, so only integer factors
+/// are well defined for them; variable coefficients are scaled exactly.
+impl ops::Mul<f64> for Phase {
+    type Output = Phase;
+
+    fn mul(self, rhs: f64) -> Phase {
+        match self.0 {
+            Repr::Symbolic(expression) => {
+                let Expression {
+                    mut terms,
+                    constant,
+                } = *expression;
+                for coefficient in terms.values_mut() {
+                    *coefficient *= rhs;
+                }
+                Phase::symbolic(terms, constant * rhs)
+            }
+            _ => Phase::from_radians(self.to_radians() * rhs),
+        }
+    }
+}
+
+/// Divides the representative in [0, 2π), exactly for rational and variable phases. Only
+/// sums of n such shares are well defined mod 2π, as in phase polynomials. Panics if `rhs`
+/// is 0, like integer division.
+impl ops::Div<i64> for Phase {
+    type Output = Phase;
+
+    fn div(self, rhs: i64) -> Phase {
+        if rhs == 0 {
+            panic!("Phase {} cannot be divided by zero", self);
+        }
+        match self.0 {
+            Repr::Rational(numerator, denominator) => {
+                Phase::reduce(numerator as i128, denominator as i128 * rhs as i128)
+            }
+            Repr::Float(radians) => Phase::from_radians(radians / rhs as f64),
+            Repr::Symbolic(_) => self * (1.0 / rhs as f64),
+        }
+    }
+}
+
+impl PartialEq for Phase {
+    fn eq(&self, other: &Phase) -> bool {
+        match (&self.0, &other.0) {
+            (Repr::Rational(n1, d1), Repr::Rational(n2, d2)) => (n1, d1) == (n2, d2),
+            (Repr::Symbolic(e1), Repr::Symbolic(e2)) => e1 == e2,
+            (Repr::Symbolic(_), _) | (_, Repr::Symbolic(_)) => false,
+            _ => representative(self.to_radians() - other.to_radians()).abs() < TOLERANCE,
+        }
+    }
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Repr::Rational(0, _) => write!(f, "0"),
+            Repr::Rational(1, 1) => write!(f, "π"),
+            Repr::Rational(1, denominator) => write!(f, "π/{}", denominator),
+            Repr::Rational(numerator, 1) => write!(f, "{}π", numerator),
+            Repr::Rational(numerator, denominator) => {
+                write!(f, "{}π/{}", numerator, denominator)
+            }
+            Repr::Float(radians) => write!(f, "{}", radians),
+            Repr::Symbolic(expression) => {
+                for (i, (name, &coefficient)) in expression.terms.iter().enumerate() {
+                    let sign = if coefficient < 0.0 { "-" } else { "+" };
+                    match i {
+                        0 if coefficient < 0.0 => write!(f, "-")?,
+                        0 => {}
+                        _ => write!(f, " {} ", sign)?,
+                    }
+                    if coefficient.abs() != 1.0 {
+                        write!(f, "{}·", coefficient.abs())?;
+                    }
+                    write!(f, "{}", name)?;
+                }
+                if !expression.constant.is_zero() {
+                    write!(f, " + {}", expression.constant)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The angle equal to `radians` mod 2π in (−π, π].
+fn representative(radians: f64) -> f64 {
+    if -PI < radians && radians <= PI {
+        radians
+    } else {
+        PI - (PI - radians).rem_euclid(TAU)
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs().max(1)
+    } else {
+        gcd(b, a % b)
+    }
+}
@@ -0,0 +1,451 @@
+//! SVG drawings of circuits, laid out like the TikZ ones, for viewing without LaTeX.
+
+use super::circuits::Circuit;
+use super::tikz::{GateRenderInfo, GateType, process_gates_and_determine_time_steps};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+/// The knobs of `TikzQConfig`, with styles given as CSS declarations and lengths in the
+/// same units as TikZ, drawn `scale` pixels to the unit.
+pub struct SvgQConfig {
+    pub gate_spacing: f64,
+    pub wire_spacing: f64,
+    pub show_labels: bool,
+    /// Font size of labels, in pixels.
+    pub font_size: f64,
+    /// Style of gate boxes, meters, control dots and targets.
+    pub qubit_style: String,
+    pub wire_style: String,
+    /// Style of the lines joining controls to targets.
+    pub connection_style: String,
+    pub scale: f64,
+}
+
+impl Default for SvgQConfig {
+    fn default() -> Self {
+        Self {
+            gate_spacing: 1.2,
+            wire_spacing: 0.6,
+            show_labels: true,
+            font_size: 12.0,
+            qubit_style: "stroke:black;stroke-width:1".to_string(),
+            wire_style: "stroke:black;stroke-width:1.5".to_string(),
+            connection_style: "stroke:black;stroke-width:1.5".to_string(),
+            scale: 50.0,
+        }
+    }
+}
+
+/// Side of a gate box, and the other sizes of the TikZ drawing, in TikZ units.
+const BOX: f64 = 0.5;
+const DOT_RADIUS: f64 = 0.075;
+const TARGET_RADIUS: f64 = 0.15;
+/// Room left of the wires for labels, and around the drawing.
+const LABEL_MARGIN: f64 = 0.7;
+const MARGIN: f64 = 0.4;
+/// Pixels between the two lines of a classical wire.
+const DOUBLE_GAP: f64 = 3.0;
+
+pub fn generate_svg_circuit(circuit: &Circuit, config: &SvgQConfig) -> String {
+    let (time_steps, max_time) = process_gates_and_determine_time_steps(circuit);
+    let rows = circuit.num_qubits() + circuit.num_clbits();
+    let total_width = (max_time + 1) as f64 * config.gate_spacing + 0.5;
+    let left = if config.show_labels {
+        LABEL_MARGIN
+    } else {
+        MARGIN
+    };
+
+    let scale = config.scale;
+    let x = |x: f64| (x + left) * scale;
+    let y = |row: usize| (row as f64 * config.wire_spacing + MARGIN) * scale;
+    let width = (total_width + left + MARGIN) * scale;
+    let height = (rows.saturating_sub(1) as f64 * config.wire_spacing + 2.0 * MARGIN) * scale;
+
+    let mut output = String::with_capacity(1024);
+    writeln!(
+        output,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.2}\" height=\"{:.2}\" viewBox=\"0 0 {:.2} {:.2}\">",
+        width, height, width, height
+    )
+    .unwrap();
+    writeln!(
+        output,
+        "<style>.wire {{ {} }} .connection {{ {} }} .gate {{ fill:white; {} }} .dot {{ fill:black; {} }} .mark {{ fill:none; {} }} text {{ font-family:serif; font-size:{}px; }}</style>",
+        config.wire_style, config.connection_style, config.qubit_style, config.qubit_style, config.qubit_style, config.font_size
+    )
+    .unwrap();
+    writeln!(
+        output,
+        "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>"
+    )
+    .unwrap();
+
+    for row in 0..rows {
+        let classical = row >= circuit.num_qubits();
+        if classical {
+            double_line(
+                &mut output,
+                "wire",
+                (x(0.0), y(row)),
+                (x(total_width), y(row)),
+            );
+        } else {
+            line(
+                &mut output,
+                "wire",
+                (x(0.0), y(row)),
+                (x(total_width), y(row)),
+            );
+        }
+        if config.show_labels {
+            let (name, index) = match classical {
+                false => ('q', row),
+                true => ('c', row - circuit.num_qubits()),
+            };
+            writeln!(
+                output,
+                "<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"end\" dominant-baseline=\"middle\">{}<tspan baseline-shift=\"sub\" font-size=\"70%\">{}</tspan></text>",
+                x(-0.2),
+                y(row),
+                name,
+                index
+            )
+            .unwrap();
+        }
+    }
+
+    let mut by_time: BTreeMap<usize, Vec<(usize, &GateRenderInfo)>> = BTreeMap::new();
+    for (&(row, time), info) in &time_steps {
+        by_time.entry(time).or_default().push((row, info));
+    }
+    for (time, mut gates) in by_time {
+        gates.sort_by_key(|&(row, _)| row);
+        let column = x((time as f64 + 1.0) * config.gate_spacing);
+
+        // Connections first, so that the gates cover their ends. Both ends of a
+        // two-qubit gate name each other, so each segment is drawn once.
+        let mut drawn = HashSet::new();
+        for &(row, info) in &gates {
+            for &other in &info.connected_to {
+                let classical = matches!(
+                    info.gate_type,
+                    GateType::Meter
+                        | GateType::ClassicalControlPoint
+                        | GateType::OpenClassicalControlPoint
+                );
+                if !drawn.insert((row.min(other), row.max(other), classical)) {
+                    continue;
+                }
+                let (from, to) = ((column, y(row)), (column, y(other)));
+                if classical {
+                    double_line(&mut output, "connection", from, to);
+                } else {
+                    line(&mut output, "connection", from, to);
+                }
+            }
+        }
+        for (row, info) in gates {
+            draw_gate(&mut output, info, (column, y(row)), config);
+        }
+    }
+
+    output.push_str("</svg>\n");
+    output
+}
+
+fn draw_gate(output: &mut String, info: &GateRenderInfo, (x, y): (f64, f64), config: &SvgQConfig) {
+    let scale = config.scale;
+    let circle = |output: &mut String, class: &str, radius: f64| {
+        writeln!(
+            output,
+            "<circle class=\"{}\" cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\"/>",
+            class,
+            x,
+            y,
+            radius * scale
+        )
+        .unwrap();
+    };
+
+    match info.gate_type {
+        GateType::X | GateType::CNOT_Target => {
+            let r = TARGET_RADIUS * scale;
+            circle(output, "gate", TARGET_RADIUS);
+            line(output, "mark", (x - r, y), (x + r, y));
+            line(output, "mark", (x, y - r), (x, y + r));
+        }
+        GateType::Y => labelled_box(output, "Y", (x, y), BOX, config),
+        GateType::Z => labelled_box(output, "Z", (x, y), BOX, config),
+        GateType::H => labelled_box(output, "H", (x, y), BOX, config),
+        GateType::P => {
+            let phase = info
+                .params
+                .as_ref()
+                .map_or("\\phi".to_string(), |p| p.to_latex());
+            labelled_box(output, &format!("P({})", phase), (x, y), BOX, config);
+        }
+        GateType::ControlPoint | GateType::CZ_Target | GateType::ClassicalControlPoint => {
+            circle(output, "dot", DOT_RADIUS)
+        }
+        GateType::OpenControlPoint | GateType::OpenClassicalControlPoint => {
+            circle(output, "gate", DOT_RADIUS)
+        }
+        GateType::SWAP_Point => {
+            let d = 0.1 * scale;
+            line(output, "mark", (x - d, y - d), (x + d, y + d));
+            line(output, "mark", (x - d, y + d), (x + d, y - d));
+        }
+        GateType::Meter => {
+            let (half, r) = (BOX / 2.0 * scale, 0.15 * scale);
+            writeln!(
+                output,
+                "<rect class=\"gate\" x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\"/>",
+                x - half,
+                y - half,
+                2.0 * half,
+                2.0 * half
+            )
+            .unwrap();
+            let base = y + 0.1 * scale;
+            writeln!(
+                output,
+                "<path class=\"mark\" d=\"M {:.2} {:.2} A {:.2} {:.2} 0 0 1 {:.2} {:.2}\"/>",
+                x - r,
+                base,
+                r,
+                r,
+                x + r,
+                base
+            )
+            .unwrap();
+            line(output, "mark", (x, base), (x + r, y - r));
+        }
+        GateType::Reset => labelled_box(output, "|0\\rangle", (x, y), BOX, config),
+        GateType::Unitary => {
+            let extent = (info.span - 1) as f64 * config.wire_spacing;
+            let label = info.label.as_deref().unwrap_or("U");
+            labelled_box(
+                output,
+                label,
+                (x, y + extent / 2.0 * scale),
+                extent + BOX,
+                config,
+            );
+        }
+    }
+}
+
+/// A white box centred on `centre`, `height` units tall and wide enough for `label`.
+fn labelled_box(
+    output: &mut String,
+    label: &str,
+    (x, y): (f64, f64),
+    height: f64,
+    config: &SvgQConfig,
+) {
+    let (text, characters) = svg_text(label);
+    let width = (BOX * config.scale).max(characters * config.font_size * 0.6 + 8.0);
+    let height = height * config.scale;
+    writeln!(
+        output,
+        "<rect class=\"gate\" x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\"/>",
+        x - width / 2.0,
+        y - height / 2.0,
+        width,
+        height
+    )
+    .unwrap();
+    writeln!(
+        output,
+        "<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+        x, y, text
+    )
+    .unwrap();
+}
+
+fn line(output: &mut String, class: &str, (x1, y1): (f64, f64), (x2, y2): (f64, f64)) {
+    writeln!(
+        output,
+        "<line class=\"{}\" x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\"/>",
+        class, x1, y1, x2, y2
+    )
+    .unwrap();
+}
+
+/// Two parallel lines, for classical information.
+fn double_line(output: &mut String, class: &str, from: (f64, f64), to: (f64, f64)) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = (dx * dx + dy * dy).sqrt().max(f64::EPSILON);
+    let (nx, ny) = (
+        -dy / length * DOUBLE_GAP / 2.0,
+        dx / length * DOUBLE_GAP / 2.0,
+    );
+    for side in [-1.0, 1.0] {
+        let offset = |(x, y): (f64, f64)| (x + side * nx, y + side * ny);
+        line(output, class, offset(from), offset(to));
+    }
+}
+
+/// SVG text content for a LaTeX label, as `generate_tikz_circuit` and the ZX drawings
+/// produce them, with the number of characters it shows. `\frac{a}{b}` becomes `a/b`,
+/// Greek letters and `\dagger` their symbols, `^` and `_` raised and lowered text, and
+/// other commands their names.
+pub(crate) fn svg_text(latex: &str) -> (String, f64) {
+    let chars: Vec<char> = latex.chars().collect();
+    let mut position = 0;
+    let mut characters = 0.0;
+    let text = latex_group(&chars, &mut position, false, &mut characters, 1.0);
+    (text, characters)
+}
+
+/// Converts LaTeX up to the end of the input, or to the closing brace of a group.
+fn latex_group(
+    chars: &[char],
+    position: &mut usize,
+    in_braces: bool,
+    characters: &mut f64,
+    size: f64,
+) -> String {
+    let mut text = String::new();
+    while *position < chars.len() {
+        let c = chars[*position];
+        *position += 1;
+        match c {
+            '}' if in_braces => return text,
+            '{' => text += &latex_group(chars, position, true, characters, size),
+            '^' | '_' => {
+                let shift = if c == '^' { "super" } else { "sub" };
+                let inner = latex_argument(chars, position, characters, size * 0.7);
+                write!(
+                    text,
+                    "<tspan baseline-shift=\"{}\" font-size=\"70%\">{}</tspan>",
+                    shift, inner
+                )
+                .unwrap();
+            }
+            '\\' => {
+                let start = *position;
+                while *position < chars.len() && chars[*position].is_ascii_alphabetic() {
+                    *position += 1;
+                }
+                let command: String = chars[start..*position].iter().collect();
+                match command.as_str() {
+                    // A spacing or symbol command like `\,` or `\{`
+                    "" => {
+                        let symbol = chars.get(*position).copied().unwrap_or(' ');
+                        *position += 1;
+                        if symbol == ',' || symbol == ' ' {
+                            text.push(' ');
+                        } else {
+                            text += &escape(&symbol.to_string());
+                        }
+                        *characters += size;
+                    }
+                    "frac" => {
+                        let numerator = latex_argument(chars, position, characters, size);
+                        *characters += size;
+                        let denominator = latex_argument(chars, position, characters, size);
+                        text += &format!("{}/{}", numerator, denominator);
+                    }
+                    "sqrt" => {
+                        *characters += size;
+                        text.push('√');
+                        text += &latex_argument(chars, position, characters, size);
+                    }
+                    "mathrm" | "text" | "mathit" | "mathbf" => {
+                        text += &latex_argument(chars, position, characters, size);
+                    }
+                    "left" | "right" => {}
+                    command => {
+                        let symbol = match command {
+                            "pi" => "π",
+                            "phi" => "φ",
+                            "theta" => "θ",
+                            "lambda" => "λ",
+                            "alpha" => "α",
+                            "beta" => "β",
+                            "gamma" => "γ",
+                            "dagger" => "†",
+                            "oplus" => "⊕",
+                            "otimes" => "⊗",
+                            "times" => "×",
+                            "cdot" => "·",
+                            "rangle" => "⟩",
+                            "langle" => "⟨",
+                            other => other,
+                        };
+                        *characters += symbol.chars().count() as f64 * size;
+                        text += symbol;
+                    }
+                }
+            }
+            '$' => {}
+            c => {
+                *characters += size;
+                text += &escape(&c.to_string());
+            }
+        }
+    }
+    text
+}
+
+/// The next braced group or single character, converted.
+fn latex_argument(chars: &[char], position: &mut usize, characters: &mut f64, size: f64) -> String {
+    match chars.get(*position) {
+        Some('{') => {
+            *position += 1;
+            latex_group(chars, position, true, characters, size)
+        }
+        Some('\\') => {
+            // A single command, such as the `\dagger` of `S^\dagger`
+            let start = *position;
+            let mut end = start + 1;
+            while end < chars.len() && chars[end].is_ascii_alphabetic() {
+                end += 1;
+            }
+            let command: Vec<char> = chars[start..end.max(start + 2).min(chars.len())].to_vec();
+            *position = start + command.len();
+            latex_group(&command, &mut 0, false, characters, size)
+        }
+        Some(&c) => {
+            *position += 1;
+            *characters += size;
+            escape(&c.to_string())
+        }
+        None => String::new(),
+    }
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn save_circuit_svg_to_file(
+    circuit: &Circuit,
+    config: &SvgQConfig,
+    filepath: &str,
+) -> std::io::Result<()> {
+    std::fs::write(filepath, generate_svg_circuit(circuit, config))?;
+    println!("Quantum circuit SVG diagram saved to: {}", filepath);
+    Ok(())
+}
+
+pub trait SvgCircuit {
+    fn to_svg(&self, config: &SvgQConfig) -> String;
+
+    fn save_svg(&self, config: &SvgQConfig, filepath: &str) -> std::io::Result<()>;
+}
+
+impl SvgCircuit for Circuit {
+    fn to_svg(&self, config: &SvgQConfig) -> String {
+        generate_svg_circuit(self, config)
+    }
+
+    fn save_svg(&self, config: &SvgQConfig, filepath: &str) -> std::io::Result<()> {
+        save_circuit_svg_to_file(self, config, filepath)
+    }
+}
@@ -1,6 +1,7 @@
-use super::circuits::CircuitRepr;
+use super::circuits::Circuit;
 use super::gates::Gate;
-use std::collections::{HashMap, HashSet};
+use super::phase::Phase;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
@@ -29,7 +30,7 @@ impl Default for TikzQConfig {
     }
 }
 
-pub fn generate_tikz_circuit<const WIDTH: usize>(circuit: &CircuitRepr<WIDTH>, config: &TikzQConfig) -> String {
+pub fn generate_tikz_circuit(circuit: &Circuit, config: &TikzQConfig) -> String {
     let mut output = String::with_capacity(1024);
     
     output.push_str("\\begin{tikzpicture}[\n");
@@ -39,7 +40,7 @@ pub fn generate_tikz_circuit<const WIDTH: usize>(circuit: &CircuitRepr<WIDTH>, c
     
     let total_width = (max_time + 1) as f64 * config.gate_spacing + 0.5;
     
-    for q in 0..WIDTH {
+    for q in 0..circuit.num_qubits() {
         let y_pos = q as f64 * config.wire_spacing;
         
         output.push_str(&format!("  \\draw[wire] (0,{y:.2}) -- ({max_x:.2},{y:.2});\n", 
@@ -55,167 +56,331 @@ pub fn generate_tikz_circuit<const WIDTH: usize>(circuit: &CircuitRepr<WIDTH>, c
         }
     }
     
-    draw_gates(WIDTH, &time_steps, config, &mut output);
+    // Classical bits below the qubits, as double wires
+    for c in 0..circuit.num_clbits() {
+        let y_pos = (circuit.num_qubits() + c) as f64 * config.wire_spacing;
+        
+        output.push_str(&format!("  \\draw[double] (0,{y:.2}) -- ({max_x:.2},{y:.2});\n", 
+            y = -y_pos, 
+            max_x = total_width
+        ));
+        
+        if config.show_labels {
+            output.push_str(&format!(
+                "  \\node[font={}, anchor=east] at (-0.2,{:.2}) {{$c_{{{}}}$}};\n", 
+                config.font_size, -y_pos, c
+            ));
+        }
+    }
+    
+    draw_gates(circuit.num_qubits(), &time_steps, config, &mut output);
     
     output.push_str("\\end{tikzpicture}");
     output
 }
 
-fn process_gates_and_determine_time_steps<const WIDTH: usize>(
-    circuit: &CircuitRepr<WIDTH>
+/// Places every gate in the first column after the last gate on any wire it spans, so gates
+/// whose wires (and the wires between them) are disjoint share a column. Classical bit `c`
+/// is wire `num_qubits + c`.
+pub(crate) fn process_gates_and_determine_time_steps(
+    circuit: &Circuit
 ) -> (HashMap<(usize, usize), GateRenderInfo>, usize) {
     let mut time_steps = HashMap::new();
-    let mut max_time = 0;
+    let num_qubits = circuit.num_qubits();
+    let mut next_free = vec![0; num_qubits + circuit.num_clbits()];
     
-    let mut processed_multi_qubit = HashSet::new();
-    
-    let mut last_used_time = vec![0; WIDTH];
-    
-    for qubit in 0..WIDTH {
-        let mut time = 0;
+    for instruction in circuit.dag.iter() {
+        let clbit_wires = instruction.gate.involved_clbits().into_iter().map(|clbit| num_qubits + clbit);
+        let wires: Vec<usize> = instruction.qubits.iter().copied().chain(clbit_wires).collect();
+        let low = wires.iter().copied().min().unwrap_or(0);
+        let high = wires.iter().copied().max().unwrap_or(0);
+        if high >= next_free.len() {
+            continue;
+        }
+        let time = next_free[low..=high].iter().copied().max().unwrap_or(0);
+        next_free[low..=high].fill(time + 1);
         
-        for gate in &circuit.storage[qubit] {
-            match gate {
-                Gate::CNOT { control, target } => {
-                    if !processed_multi_qubit.contains(&(*control, *target, GateType::CNOT)) {
-                        processed_multi_qubit.insert((*control, *target, GateType::CNOT));
-                        
-                        time = std::cmp::max(time, last_used_time[*control]);
-                        time = std::cmp::max(time, last_used_time[*target]);
-                        
-                        time_steps.insert(
-                            (*control, time), 
-                            GateRenderInfo { 
-                                gate_type: GateType::ControlPoint,
-                                connected_to: vec![*target],
-                                params: None
-                            }
-                        );
-                        
-                        time_steps.insert(
-                            (*target, time), 
-                            GateRenderInfo { 
-                                gate_type: GateType::CNOT_Target, 
-                                connected_to: vec![*control],
-                                params: None
-                            }
-                        );
-                        
-                        last_used_time[*control] = time + 1;
-                        last_used_time[*target] = time + 1;
-                        
-                        time = time + 1;
-                        max_time = max_time.max(time);
+        let (gate_type, partner_type, qubits, params) = match &instruction.gate {
+            Gate::CNOT { control, target } => 
+                (GateType::ControlPoint, Some(GateType::CNOT_Target), (*control, *target), None),
+            Gate::CZ { control, target } => 
+                (GateType::ControlPoint, Some(GateType::CZ_Target), (*control, *target), None),
+            Gate::SWAP { qubit1, qubit2 } => 
+                (GateType::SWAP_Point, Some(GateType::SWAP_Point), (*qubit1, *qubit2), None),
+            Gate::X => (GateType::X, None, (instruction.qubits[0], 0), None),
+            Gate::Y => (GateType::Y, None, (instruction.qubits[0], 0), None),
+            Gate::Z => (GateType::Z, None, (instruction.qubits[0], 0), None),
+            Gate::H => (GateType::H, None, (instruction.qubits[0], 0), None),
+            Gate::P(phase) => 
+                (GateType::P, None, (instruction.qubits[0], 0), Some(phase.clone())),
+            Gate::S | Gate::Sdg | Gate::T | Gate::Tdg | Gate::SX
+            | Gate::Rx(_) | Gate::Ry(_) | Gate::Rz(_) | Gate::U3 { .. } | Gate::GlobalPhase(_) => {
+                time_steps.insert(
+                    (low, time),
+                    GateRenderInfo {
+                        gate_type: GateType::Unitary,
+                        connected_to: vec![],
+                        params: None,
+                        label: gate_label(&instruction.gate),
+                        span: 1,
                     }
-                },
-                Gate::CZ { control, target } => {
-                    if !processed_multi_qubit.contains(&(*control, *target, GateType::CZ)) {
-                        processed_multi_qubit.insert((*control, *target, GateType::CZ));
-                        
-                        time = std::cmp::max(time, last_used_time[*control]);
-                        time = std::cmp::max(time, last_used_time[*target]);
-                        
-                        time_steps.insert(
-                            (*control, time), 
-                            GateRenderInfo { 
-                                gate_type: GateType::ControlPoint,
-                                connected_to: vec![*target],
-                                params: None
-                            }
-                        );
-                        
-                        time_steps.insert(
-                            (*target, time), 
-                            GateRenderInfo { 
-                                gate_type: GateType::CZ_Target,
-                                connected_to: vec![*control],
-                                params: None
-                            }
-                        );
-                        
-                        last_used_time[*control] = time + 1;
-                        last_used_time[*target] = time + 1;
-                        
-                        time = time + 1;
-                        max_time = max_time.max(time);
+                );
+                continue;
+            }
+            Gate::Unitary(unitary) => {
+                // One box from the lowest to the highest of its qubits
+                time_steps.insert(
+                    (low, time),
+                    GateRenderInfo {
+                        gate_type: GateType::Unitary,
+                        connected_to: vec![],
+                        params: None,
+                        label: Some(unitary.label.clone()),
+                        span: high - low + 1,
                     }
-                },
-                Gate::SWAP { qubit1, qubit2 } => {
-                    if !processed_multi_qubit.contains(&(*qubit1, *qubit2, GateType::SWAP)) {
-                        processed_multi_qubit.insert((*qubit1, *qubit2, GateType::SWAP));
-                        
-                        time = std::cmp::max(time, last_used_time[*qubit1]);
-                        time = std::cmp::max(time, last_used_time[*qubit2]);
-                        
-                        time_steps.insert(
-                            (*qubit1, time), 
-                            GateRenderInfo { 
-                                gate_type: GateType::SWAP_Point,
-                                connected_to: vec![*qubit2],
-                                params: None
-                            }
-                        );
-                        
+                );
+                continue;
+            }
+            Gate::CP { control, target, .. } | Gate::CRx { control, target, .. }
+            | Gate::CRy { control, target, .. } | Gate::CRz { control, target, .. } => {
+                insert_controlled(&mut time_steps, time, &[*control], &[], &instruction.gate.controlled_base(), *target);
+                continue;
+            }
+            Gate::RXX { .. } | Gate::RYY { .. } | Gate::RZZ { .. }
+            | Gate::ISWAP { .. } | Gate::SqrtISWAP { .. } | Gate::ECR { .. } => {
+                // One box over both qubits, like a two-qubit unitary
+                time_steps.insert(
+                    (low, time),
+                    GateRenderInfo {
+                        gate_type: GateType::Unitary,
+                        connected_to: vec![],
+                        params: None,
+                        label: gate_label(&instruction.gate),
+                        span: high - low + 1,
+                    }
+                );
+                continue;
+            }
+            Gate::Toffoli { control1, control2, target } => {
+                insert_controlled(&mut time_steps, time, &[*control1, *control2], &[], &Gate::X, *target);
+                continue;
+            }
+            Gate::Fredkin { control, target1, target2 } => {
+                let swap = Gate::SWAP { qubit1: *target1, qubit2: *target2 };
+                insert_controlled(&mut time_steps, time, &[*control], &[], &swap, *target1);
+                continue;
+            }
+            Gate::Controlled(controlled) => {
+                insert_controlled(
+                    &mut time_steps,
+                    time,
+                    &controlled.controls,
+                    &controlled.open_controls,
+                    &controlled.gate,
+                    controlled.target,
+                );
+                continue;
+            }
+            Gate::Measure { qubit, clbit } => {
+                insert_measure(&mut time_steps, time, *qubit, num_qubits + clbit);
+                continue;
+            }
+            Gate::Reset => (GateType::Reset, None, (instruction.qubits[0], 0), None),
+            Gate::Conditional(conditional) => {
+                let anchor = match &conditional.gate {
+                    Gate::Measure { qubit, clbit } => {
+                        insert_measure(&mut time_steps, time, *qubit, num_qubits + clbit);
+                        Some(*qubit)
+                    }
+                    gate => insert_controlled(&mut time_steps, time, &[], &[], gate, conditional.target),
+                };
+                // A filled dot on each classical bit that must read 1, an open one for 0
+                if let Some(anchor) = anchor {
+                    let condition = &conditional.condition;
+                    for (i, &clbit) in condition.clbits.iter().enumerate() {
+                        let gate_type = if condition.value.checked_shr(i as u32).unwrap_or(0) & 1 == 1 {
+                            GateType::ClassicalControlPoint
+                        } else {
+                            GateType::OpenClassicalControlPoint
+                        };
                         time_steps.insert(
-                            (*qubit2, time), 
-                            GateRenderInfo { 
-                                gate_type: GateType::SWAP_Point,
-                                connected_to: vec![*qubit1],
-                                params: None
-                            }
+                            (num_qubits + clbit, time),
+                            GateRenderInfo { gate_type, connected_to: vec![anchor], params: None, label: None, span: 1 }
                         );
-                        
-                        last_used_time[*qubit1] = time + 1;
-                        last_used_time[*qubit2] = time + 1;
-                        
-                        time = time + 1;
-                        max_time = max_time.max(time);
                     }
-                },
-                Gate::X | Gate::Y | Gate::Z | Gate::H | Gate::P(_) => {
-                    time = std::cmp::max(time, last_used_time[qubit]);
-                    
-                    let gate_type = match gate {
-                        Gate::X => GateType::X,
-                        Gate::Y => GateType::Y,
-                        Gate::Z => GateType::Z,
-                        Gate::H => GateType::H,
-                        Gate::P(phase) => {
-                            time_steps.insert(
-                                (qubit, time), 
-                                GateRenderInfo { 
-                                    gate_type: GateType::P,
-                                    connected_to: vec![],
-                                    params: Some(*phase)
-                                }
-                            );
-                            last_used_time[qubit] = time + 1;
-                            time = time + 1;
-                            max_time = max_time.max(time);
-                            continue;
-                        },
-                        _ => continue,
-                    };
-                    
-                    time_steps.insert(
-                        (qubit, time), 
-                        GateRenderInfo { 
-                            gate_type,
-                            connected_to: vec![],
-                            params: None
-                        }
-                    );
-                    
-                    last_used_time[qubit] = time + 1;
-                    time = time + 1;
-                    max_time = max_time.max(time);
-                },
-                _ => {}, 
+                }
+                continue;
             }
+        };
+        
+        let (first, second) = qubits;
+        match partner_type {
+            Some(partner_type) => {
+                time_steps.insert(
+                    (first, time), 
+                    GateRenderInfo { 
+                        gate_type,
+                        connected_to: vec![second],
+                        params: None,
+                        label: None,
+                        span: 1,
+                    }
+                );
+                time_steps.insert(
+                    (second, time), 
+                    GateRenderInfo { 
+                        gate_type: partner_type,
+                        connected_to: vec![first],
+                        params: None,
+                        label: None,
+                        span: 1,
+                    }
+                );
+            },
+            None => {
+                time_steps.insert(
+                    (first, time), 
+                    GateRenderInfo { 
+                        gate_type,
+                        connected_to: vec![],
+                        params,
+                        label: None,
+                        span: 1,
+                    }
+                );
+            },
         }
     }
     
-    (time_steps, max_time)
+    (time_steps, next_free.into_iter().max().unwrap_or(0))
+}
+
+/// LaTeX for the gates drawn as a labelled box like a custom unitary.
+fn gate_label(gate: &Gate) -> Option<String> {
+    let label = match gate {
+        Gate::S => "S".to_string(),
+        Gate::Sdg => "S^\\dagger".to_string(),
+        Gate::T => "T".to_string(),
+        Gate::Tdg => "T^\\dagger".to_string(),
+        Gate::SX => "\\sqrt{X}".to_string(),
+        Gate::Rx(theta) => format!("R_x({})", theta.to_latex()),
+        Gate::Ry(theta) => format!("R_y({})", theta.to_latex()),
+        Gate::Rz(theta) => format!("R_z({})", theta.to_latex()),
+        Gate::U3 { theta, phi, lambda } => {
+            format!("U_3({}, {}, {})", theta.to_latex(), phi.to_latex(), lambda.to_latex())
+        }
+        Gate::GlobalPhase(phase) => format!("e^{{i{}}}", phase.to_latex()),
+        Gate::RXX { theta, .. } => format!("R_{{XX}}({})", theta.to_latex()),
+        Gate::RYY { theta, .. } => format!("R_{{YY}}({})", theta.to_latex()),
+        Gate::RZZ { theta, .. } => format!("R_{{ZZ}}({})", theta.to_latex()),
+        Gate::ISWAP { .. } => "i\\mathrm{SWAP}".to_string(),
+        Gate::SqrtISWAP { .. } => "\\sqrt{i\\mathrm{SWAP}}".to_string(),
+        Gate::ECR { .. } => "\\mathrm{ECR}".to_string(),
+        _ => return None,
+    };
+    Some(label)
+}
+
+/// A meter on `qubit` joined by a double line to a dot on the classical wire `clbit_wire`.
+fn insert_measure(
+    time_steps: &mut HashMap<(usize, usize), GateRenderInfo>,
+    time: usize,
+    qubit: usize,
+    clbit_wire: usize,
+) {
+    time_steps.insert((qubit, time), GateRenderInfo { gate_type: GateType::Meter, connected_to: vec![clbit_wire], params: None, label: None, span: 1 });
+    time_steps.insert((clbit_wire, time), GateRenderInfo { gate_type: GateType::ClassicalControlPoint, connected_to: vec![], params: None, label: None, span: 1 });
+}
+
+/// Adds a dot for each control, an open circle for each open control and the gate itself,
+/// all joined by a line to the gate's first wire, which is returned. Controls of a controlled
+/// gate are drawn alongside the given ones. Nothing is drawn for a gate without a symbol.
+fn insert_controlled(
+    time_steps: &mut HashMap<(usize, usize), GateRenderInfo>,
+    time: usize,
+    controls: &[usize],
+    open_controls: &[usize],
+    gate: &Gate,
+    target: usize,
+) -> Option<usize> {
+    let marker = |gate_type, connected_to| GateRenderInfo {
+        gate_type,
+        connected_to,
+        params: None,
+        label: None,
+        span: 1,
+    };
+
+    let mut targets = match gate {
+        Gate::X => vec![(target, marker(GateType::CNOT_Target, vec![]))],
+        Gate::Y => vec![(target, marker(GateType::Y, vec![]))],
+        Gate::Z => vec![(target, marker(GateType::CZ_Target, vec![]))],
+        Gate::H => vec![(target, marker(GateType::H, vec![]))],
+        Gate::P(phase) => vec![(target, GateRenderInfo {
+            params: Some(phase.clone()),
+            ..marker(GateType::P, vec![])
+        })],
+        Gate::S | Gate::Sdg | Gate::T | Gate::Tdg | Gate::SX
+        | Gate::Rx(_) | Gate::Ry(_) | Gate::Rz(_) | Gate::U3 { .. } | Gate::GlobalPhase(_) => vec![(target, GateRenderInfo {
+            label: gate_label(gate),
+            ..marker(GateType::Unitary, vec![])
+        })],
+        Gate::SWAP { qubit1, qubit2 } => vec![
+            (*qubit1, marker(GateType::SWAP_Point, vec![])),
+            (*qubit2, marker(GateType::SWAP_Point, vec![*qubit1])),
+        ],
+        Gate::RXX { qubit1, qubit2, .. } | Gate::RYY { qubit1, qubit2, .. } | Gate::RZZ { qubit1, qubit2, .. }
+        | Gate::ISWAP { qubit1, qubit2 } | Gate::SqrtISWAP { qubit1, qubit2 } | Gate::ECR { qubit1, qubit2 } => {
+            vec![(*qubit1.min(qubit2), GateRenderInfo {
+                label: gate_label(gate),
+                span: qubit1.abs_diff(*qubit2) + 1,
+                ..marker(GateType::Unitary, vec![])
+            })]
+        }
+        Gate::Unitary(unitary) => {
+            let low = unitary.qubits.iter().copied().min().unwrap_or(target);
+            let high = unitary.qubits.iter().copied().max().unwrap_or(target);
+            vec![(low, GateRenderInfo {
+                label: Some(unitary.label.clone()),
+                span: high - low + 1,
+                ..marker(GateType::Unitary, vec![])
+            })]
+        }
+        Gate::Reset => vec![(target, marker(GateType::Reset, vec![]))],
+        Gate::CNOT { control, target } | Gate::CZ { control, target }
+        | Gate::CP { control, target, .. } | Gate::CRx { control, target, .. }
+        | Gate::CRy { control, target, .. } | Gate::CRz { control, target, .. } => {
+            let controls = [controls, &[*control]].concat();
+            return insert_controlled(time_steps, time, &controls, open_controls, &gate.controlled_base(), *target);
+        }
+        Gate::Toffoli { control1, control2, target } => {
+            let controls = [controls, &[*control1, *control2]].concat();
+            return insert_controlled(time_steps, time, &controls, open_controls, &Gate::X, *target);
+        }
+        Gate::Fredkin { control, target1, target2 } => {
+            let controls = [controls, &[*control]].concat();
+            let swap = Gate::SWAP { qubit1: *target1, qubit2: *target2 };
+            return insert_controlled(time_steps, time, &controls, open_controls, &swap, *target1);
+        }
+        Gate::Controlled(controlled) => {
+            let controls = [controls, &controlled.controls].concat();
+            let open_controls = [open_controls, &controlled.open_controls].concat();
+            return insert_controlled(time_steps, time, &controls, &open_controls, &controlled.gate, controlled.target);
+        }
+        _ => return None,
+    };
+
+    let anchor = targets[0].0;
+    for &control in controls {
+        targets.push((control, marker(GateType::ControlPoint, vec![anchor])));
+    }
+    for &control in open_controls {
+        targets.push((control, marker(GateType::OpenControlPoint, vec![anchor])));
+    }
+    for (qubit, info) in targets {
+        time_steps.insert((qubit, time), info);
+    }
+    Some(anchor)
 }
 
 fn draw_gates(
@@ -240,12 +405,18 @@ fn draw_gates(
                     let x_pos = (time as f64 + 1.0) * config.gate_spacing;
                     let y_pos = -(qubit as f64 * config.wire_spacing);
                     
+                    // Classical information travels on double lines
+                    let style = match gate_info.gate_type {
+                        GateType::Meter | GateType::ClassicalControlPoint | GateType::OpenClassicalControlPoint => "double",
+                        _ => "thick",
+                    };
+                    
                     for &connected_qubit in &gate_info.connected_to {
                         let connected_y = -(connected_qubit as f64 * config.wire_spacing);
                         
                         output.push_str(&format!(
-                            "  \\draw[thick] ({:.2},{:.2}) -- ({:.2},{:.2});\n",
-                            x_pos, y_pos, x_pos, connected_y
+                            "  \\draw[{}] ({:.2},{:.2}) -- ({:.2},{:.2});\n",
+                            style, x_pos, y_pos, x_pos, connected_y
                         ));
                     }
                 }
@@ -285,14 +456,8 @@ fn draw_gates(
                         ));
                     },
                     GateType::P => {
-                        let phase_str = if let Some(phase) = gate_info.params {
-                            if phase == std::f64::consts::PI {
-                                "\\pi".to_string()
-                            } else if phase == std::f64::consts::PI / 2.0 {
-                                "\\pi/2".to_string()
-                            } else {
-                                format!("{:.2}", phase)
-                            }
+                        let phase_str = if let Some(phase) = &gate_info.params {
+                            phase.to_latex()
                         } else {
                             "\\phi".to_string()
                         };
@@ -308,6 +473,12 @@ fn draw_gates(
                             x_pos, y_pos
                         ));
                     },
+                    GateType::OpenControlPoint => {
+                        output.push_str(&format!(
+                            "  \\node[circle, draw=black, fill=white, minimum size=0.15cm, inner sep=0pt] at ({:.2},{:.2}) {{}};\n",
+                            x_pos, y_pos
+                        ));
+                    },
                     GateType::CNOT_Target => {
                         output.push_str(&format!(
                             "  \\node[circle, fill=white, minimum size=3.0mm, inner sep=0pt] at ({:.2},{:.2}) {{}};\n",
@@ -330,7 +501,49 @@ fn draw_gates(
                             x_pos, y_pos
                         ));
                     },
-                    _ => {}
+                    GateType::Meter => {
+                        output.push_str(&format!(
+                            "  \\node[draw, minimum size=0.5cm, fill=white] at ({:.2},{:.2}) {{}};\n",
+                            x_pos, y_pos
+                        ));
+                        output.push_str(&format!(
+                            "  \\draw ({:.2},{:.2}) arc (180:0:0.15);\n",
+                            x_pos - 0.15, y_pos - 0.1
+                        ));
+                        output.push_str(&format!(
+                            "  \\draw[->] ({:.2},{:.2}) -- ({:.2},{:.2});\n",
+                            x_pos, y_pos - 0.1, x_pos + 0.15, y_pos + 0.15
+                        ));
+                    },
+                    GateType::Reset => {
+                        output.push_str(&format!(
+                            "  \\node[draw, minimum size=0.5cm, fill=white, font={}] at ({:.2},{:.2}) {{$|0\\rangle$}};\n",
+                            config.font_size, x_pos, y_pos
+                        ));
+                    },
+                    GateType::ClassicalControlPoint => {
+                        output.push_str(&format!(
+                            "  \\node[circle, fill=black, minimum size=0.15cm, inner sep=0pt] at ({:.2},{:.2}) {{}};\n",
+                            x_pos, y_pos
+                        ));
+                    },
+                    GateType::OpenClassicalControlPoint => {
+                        output.push_str(&format!(
+                            "  \\node[circle, draw=black, fill=white, minimum size=0.15cm, inner sep=0pt] at ({:.2},{:.2}) {{}};\n",
+                            x_pos, y_pos
+                        ));
+                    },
+                    GateType::Unitary => {
+                        let extent = (gate_info.span - 1) as f64 * config.wire_spacing;
+                        output.push_str(&format!(
+                            "  \\node[draw, minimum width=0.5cm, minimum height={:.2}cm, fill=white, font={}] at ({:.2},{:.2}) {{${}$}};\n",
+                            extent + 0.5,
+                            config.font_size,
+                            x_pos,
+                            y_pos - extent / 2.0,
+                            gate_info.label.as_deref().unwrap_or("U")
+                        ));
+                    },
                 }
             }
         }
@@ -338,29 +551,36 @@ fn draw_gates(
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-enum GateType {
+pub(crate) enum GateType {
     X,
     Y,
     Z,
     H,
     P,
     ControlPoint,
+    OpenControlPoint,
     CNOT_Target,
     CZ_Target,
     SWAP_Point,
-    CNOT,
-    CZ,
-    SWAP,
+    Unitary,
+    Meter,
+    Reset,
+    ClassicalControlPoint,
+    OpenClassicalControlPoint,
 }
 
-struct GateRenderInfo {
-    gate_type: GateType,
-    connected_to: Vec<usize>, 
-    params: Option<f64>, 
+pub(crate) struct GateRenderInfo {
+    pub gate_type: GateType,
+    pub connected_to: Vec<usize>, 
+    pub params: Option<Phase>, 
+    /// LaTeX for a labelled box.
+    pub label: Option<String>,
+    /// Number of wires covered, downwards from this one.
+    pub span: usize,
 }
 
-pub fn save_circuit_to_file<const WIDTH: usize>(
-    circuit: &CircuitRepr<WIDTH>, 
+pub fn save_circuit_to_file(
+    circuit: &Circuit, 
     config: &TikzQConfig, 
     filepath: &str
 ) -> std::io::Result<()> {
@@ -378,13 +598,13 @@ pub fn save_circuit_to_file<const WIDTH: usize>(
     Ok(())
 }
 
-pub trait TikzCircuit<const WIDTH: usize> {
+pub trait TikzCircuit {
     fn to_tikz(&self, config: &TikzQConfig) -> String;
     
     fn save_tikz(&self, config: &TikzQConfig, filepath: &str) -> std::io::Result<()>;
 }
 
-impl<const WIDTH: usize> TikzCircuit<WIDTH> for CircuitRepr<WIDTH> {
+impl TikzCircuit for Circuit {
     fn to_tikz(&self, config: &TikzQConfig) -> String {
         generate_tikz_circuit(self, config)
     }
@@ -0,0 +1,138 @@
+use crate::qcore::circuits::Circuit;
+use crate::qcore::gates::Gate;
+use crate::sim::statevector::StateVector;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+
+/// Number of shots for each outcome, keyed by `bitstring`.
+pub type Counts = BTreeMap<String, usize>;
+
+/// Runs circuits with measurements, resets and classically conditioned gates, drawing
+/// every measurement outcome at random.
+pub struct Sampler {
+    rng: StdRng,
+}
+
+impl Sampler {
+    /// A sampler seeded from the operating system.
+    pub fn new() -> Self {
+        Sampler {
+            rng: StdRng::from_os_rng(),
+        }
+    }
+
+    /// A sampler whose outcomes are reproducible from `seed`.
+    pub fn seeded(seed: u64) -> Self {
+        Sampler {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Runs `circuit` once on |0...0⟩ and returns the final state and classical bits.
+    /// Classical bits start at 0.
+    pub fn run(&mut self, circuit: &Circuit) -> (StateVector, Vec<bool>) {
+        let mut state = StateVector::new(circuit.num_qubits());
+        let mut clbits = vec![false; circuit.num_clbits()];
+        for (wire, gate) in circuit.ordered_gates() {
+            self.apply(&mut state, &mut clbits, wire, &gate);
+        }
+        (state, clbits)
+    }
+
+    /// Runs `circuit` `shots` times and counts the classical outcomes.
+    ///
+    /// When every measurement is the last operation on its qubit and nothing is reset or
+    /// conditioned, the state is simulated once and each shot is drawn from its final
+    /// distribution. Otherwise every shot runs the whole circuit.
+    pub fn sample(&mut self, circuit: &Circuit, shots: usize) -> Counts {
+        let mut counts = Counts::new();
+        let Some(measurements) = terminal_measurements(circuit) else {
+            for _ in 0..shots {
+                let (_, clbits) = self.run(circuit);
+                *counts.entry(bitstring(&clbits)).or_default() += 1;
+            }
+            return counts;
+        };
+
+        let mut state = StateVector::new(circuit.num_qubits());
+        for (wire, gate) in circuit.ordered_gates() {
+            if !matches!(gate, Gate::Measure { .. }) {
+                state.apply_gate(wire, &gate);
+            }
+        }
+        let cumulative: Vec<f64> = state
+            .probabilities()
+            .into_iter()
+            .scan(0.0, |total, probability| {
+                *total += probability;
+                Some(*total)
+            })
+            .collect();
+        let total = cumulative.last().copied().unwrap_or(0.0);
+
+        for _ in 0..shots {
+            // States of probability zero add nothing to the running total, so none is drawn
+            let draw = self.rng.random::<f64>() * total;
+            let index = cumulative
+                .partition_point(|&bound| bound <= draw)
+                .min(cumulative.len() - 1);
+            let mut clbits = vec![false; circuit.num_clbits()];
+            for &(qubit, clbit) in &measurements {
+                clbits[clbit] = index >> qubit & 1 == 1;
+            }
+            *counts.entry(bitstring(&clbits)).or_default() += 1;
+        }
+        counts
+    }
+
+    fn apply(&mut self, state: &mut StateVector, clbits: &mut [bool], wire: usize, gate: &Gate) {
+        match gate {
+            Gate::Measure { qubit, clbit } => clbits[*clbit] = state.measure(*qubit, &mut self.rng),
+            Gate::Reset => state.reset(wire, &mut self.rng),
+            Gate::Conditional(conditional) => {
+                if conditional.condition.holds(clbits) {
+                    self.apply(state, clbits, conditional.target, &conditional.gate);
+                }
+            }
+            _ => state.apply_gate(wire, gate),
+        }
+    }
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Classical bits as a binary string with the highest bit first, the order `StateVector`
+/// prints basis states in.
+pub fn bitstring(clbits: &[bool]) -> String {
+    clbits
+        .iter()
+        .rev()
+        .map(|&bit| if bit { '1' } else { '0' })
+        .collect()
+}
+
+/// The (qubit, clbit) pairs of every measurement in order, if each is the last operation on
+/// its qubit and the circuit has no resets or conditions.
+fn terminal_measurements(circuit: &Circuit) -> Option<Vec<(usize, usize)>> {
+    let mut measured = vec![false; circuit.num_qubits()];
+    let mut measurements = Vec::new();
+    for instruction in circuit.dag.iter() {
+        if instruction.qubits.iter().any(|&qubit| measured[qubit]) {
+            return None;
+        }
+        match instruction.gate {
+            Gate::Measure { qubit, clbit } => {
+                measured[qubit] = true;
+                measurements.push((qubit, clbit));
+            }
+            Gate::Reset | Gate::Conditional(_) => return None,
+            _ => {}
+        }
+    }
+    Some(measurements)
+}
@@ -0,0 +1,709 @@
+use crate::error::QuojoError;
+use crate::linalg::vector::{Complex, ComplexSIMD, LANES};
+use crate::qcore::circuits::Circuit;
+use crate::qcore::dag::Instruction;
+use crate::qcore::gates::Gate;
+use rand::Rng;
+use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_4};
+use std::fmt::Display;
+use std::simd::prelude::*;
+
+/// Number of qubits addressed inside a single SIMD block.
+const LANE_BITS: usize = LANES.trailing_zeros() as usize;
+
+const STRIDE_1: [usize; LANES] = partner_lanes(1);
+const STRIDE_2: [usize; LANES] = partner_lanes(2);
+const STRIDE_4: [usize; LANES] = partner_lanes(4);
+const STRIDE_8: [usize; LANES] = partner_lanes(8);
+
+const fn partner_lanes(stride: usize) -> [usize; LANES] {
+    let mut lanes = [0; LANES];
+    let mut i = 0;
+    while i < LANES {
+        lanes[i] = i ^ stride;
+        i += 1;
+    }
+    lanes
+}
+
+const ZERO: Complex = Complex { re: 0.0, im: 0.0 };
+const ONE: Complex = Complex { re: 1.0, im: 0.0 };
+
+pub type Matrix2 = [[Complex; 2]; 2];
+
+/// Dense state vector over `num_qubits` qubits.
+///
+/// Amplitudes are stored in blocks of `LANES` so that gate kernels run on `f64x16`.
+/// Qubit `q` corresponds to bit `q` of the basis state index (qubit 0 is the least
+/// significant bit).
+pub struct StateVector {
+    num_qubits: usize,
+    blocks: Vec<ComplexSIMD<LANES>>,
+}
+
+impl StateVector {
+    /// Creates the all-zero state |0...0⟩.
+    pub fn new(num_qubits: usize) -> Self {
+        Self::from_basis_state(num_qubits, 0)
+    }
+
+    pub fn from_basis_state(num_qubits: usize, index: usize) -> Self {
+        Self::try_from_basis_state(num_qubits, index).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `from_basis_state`, but returns an error when `index` is not below 2^num_qubits.
+    pub fn try_from_basis_state(num_qubits: usize, index: usize) -> Result<Self, QuojoError> {
+        let dimension = 1usize << num_qubits;
+        if index >= dimension {
+            return Err(QuojoError::BasisStateOutOfRange { index, num_qubits });
+        }
+
+        let block_count = dimension.div_ceil(LANES);
+        let mut blocks = Vec::with_capacity(block_count);
+        for _ in 0..block_count {
+            blocks.push(ComplexSIMD::new());
+        }
+        blocks[index / LANES].re[index % LANES] = 1.0;
+
+        Ok(StateVector { num_qubits, blocks })
+    }
+
+    /// Runs a circuit on |0...0⟩ and returns the final state.
+    pub fn from_circuit(circuit: &Circuit) -> Self {
+        let mut state = Self::new(circuit.num_qubits());
+        state.run(circuit);
+        state
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    pub fn dimension(&self) -> usize {
+        1 << self.num_qubits
+    }
+
+    pub fn amplitude(&self, index: usize) -> Complex {
+        self.try_amplitude(index)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `amplitude`, but returns an error when `index` is not a basis state.
+    pub fn try_amplitude(&self, index: usize) -> Result<Complex, QuojoError> {
+        if index >= self.dimension() {
+            return Err(QuojoError::BasisStateOutOfRange {
+                index,
+                num_qubits: self.num_qubits,
+            });
+        }
+        let block = &self.blocks[index / LANES];
+        Ok(Complex {
+            re: block.re[index % LANES],
+            im: block.im[index % LANES],
+        })
+    }
+
+    pub fn amplitudes(&self) -> Vec<Complex> {
+        (0..self.dimension()).map(|i| self.amplitude(i)).collect()
+    }
+
+    pub fn probabilities(&self) -> Vec<f64> {
+        self.amplitudes()
+            .iter()
+            .map(|a| a.re * a.re + a.im * a.im)
+            .collect()
+    }
+
+    pub fn norm_squared(&self) -> f64 {
+        let mut sum = f64x16::splat(0.0);
+        for block in &self.blocks {
+            let re = f64x16::from_array(block.re);
+            let im = f64x16::from_array(block.im);
+            sum += re * re + im * im;
+        }
+        sum.reduce_sum()
+    }
+
+    /// Probability that measuring `qubit` gives 1.
+    pub fn probability_of_one(&self, qubit: usize) -> f64 {
+        self.outcome_probabilities(qubit)[1]
+    }
+
+    /// Measures `qubit` in the computational basis, collapsing the state, and returns the
+    /// outcome.
+    pub fn measure(&mut self, qubit: usize, rng: &mut impl Rng) -> bool {
+        let [zero, one] = self.outcome_probabilities(qubit);
+        // Scaled by the total so that an outcome of probability zero is never drawn
+        let outcome = rng.random::<f64>() * (zero + one) < one;
+        self.collapse(qubit, outcome);
+        outcome
+    }
+
+    /// Projects `qubit` onto |0⟩ or |1⟩ and renormalises. Panics if the outcome has
+    /// probability zero.
+    pub fn collapse(&mut self, qubit: usize, outcome: bool) {
+        let probability = self.outcome_probabilities(qubit)[outcome as usize];
+        if probability <= 0.0 {
+            panic!("Qubit {} cannot be measured as {}", qubit, outcome as usize);
+        }
+
+        let scale = probability.sqrt().recip();
+        for index in 0..self.dimension() {
+            let scale = if (index >> qubit & 1 == 1) == outcome {
+                scale
+            } else {
+                0.0
+            };
+            let block = &mut self.blocks[index / LANES];
+            block.re[index % LANES] *= scale;
+            block.im[index % LANES] *= scale;
+        }
+    }
+
+    /// Measures `qubit` and flips it back to |0⟩ if the outcome was 1.
+    pub fn reset(&mut self, qubit: usize, rng: &mut impl Rng) {
+        if self.measure(qubit, rng) {
+            self.apply_controlled_matrix(&[], qubit, single_qubit_matrix(&Gate::X));
+        }
+    }
+
+    /// Probabilities of measuring 0 and 1 on `qubit`.
+    fn outcome_probabilities(&self, qubit: usize) -> [f64; 2] {
+        self.check_qubit(qubit);
+        let mut probabilities = [0.0; 2];
+        for index in 0..self.dimension() {
+            probabilities[index >> qubit & 1] += self.amplitude(index).norm_sqr();
+        }
+        probabilities
+    }
+
+    /// Applies every gate of `circuit` in order.
+    pub fn run(&mut self, circuit: &Circuit) {
+        self.try_run(circuit)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `run`, but returns an error and leaves the state unchanged when the circuit is
+    /// wider than the state, or a gate cannot be applied by `try_apply_gate`.
+    pub fn try_run(&mut self, circuit: &Circuit) -> Result<(), QuojoError> {
+        if circuit.num_qubits() > self.num_qubits {
+            return Err(QuojoError::WidthMismatch {
+                expected: self.num_qubits,
+                found: circuit.num_qubits(),
+            });
+        }
+
+        let gates = circuit.ordered_gates();
+        for (wire, gate) in &gates {
+            self.check_gate(&[], *wire, gate)?;
+        }
+        for (wire, gate) in &gates {
+            self.apply_unchecked(&[], *wire, gate);
+        }
+        Ok(())
+    }
+
+    /// Applies `gate` as stored on `wire`. Multi-qubit gates carry their own qubit
+    /// indices, so `wire` only matters for single-qubit gates. Panics for measurements,
+    /// resets and conditional gates, which need the `sampler`.
+    pub fn apply_gate(&mut self, wire: usize, gate: &Gate) {
+        self.apply_controlled_gate(&[], wire, gate);
+    }
+
+    /// Like `apply_gate`, but returns an error and leaves the state unchanged when a qubit is
+    /// out of range or repeated, the gate is not unitary or a phase has unbound variables.
+    pub fn try_apply_gate(&mut self, wire: usize, gate: &Gate) -> Result<(), QuojoError> {
+        self.try_apply_controlled_gate(&[], wire, gate)
+    }
+
+    /// Applies `gate` as stored on `wire` on the subspace where every control is |1⟩.
+    /// Controlled gates add their controls to `controls`, so nesting costs nothing extra.
+    pub fn apply_controlled_gate(&mut self, controls: &[usize], wire: usize, gate: &Gate) {
+        self.try_apply_controlled_gate(controls, wire, gate)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `apply_controlled_gate`, but returns an error instead of panicking, as
+    /// `try_apply_gate` does. The controls count as qubits of the gate.
+    pub fn try_apply_controlled_gate(
+        &mut self,
+        controls: &[usize],
+        wire: usize,
+        gate: &Gate,
+    ) -> Result<(), QuojoError> {
+        self.check_gate(controls, wire, gate)?;
+        self.apply_unchecked(controls, wire, gate);
+        Ok(())
+    }
+
+    /// Checks everything `apply_unchecked` would otherwise panic on.
+    fn check_gate(&self, controls: &[usize], wire: usize, gate: &Gate) -> Result<(), QuojoError> {
+        if !gate.is_unitary() {
+            return Err(QuojoError::NonUnitaryGate(gate.clone()));
+        }
+        let mut instruction = Instruction::new(gate.clone(), wire);
+        instruction.qubits.splice(0..0, controls.iter().copied());
+        instruction.check(self.num_qubits)?;
+
+        let variables = gate.variables();
+        if !variables.is_empty() {
+            return Err(QuojoError::UnboundVariables(
+                variables.into_iter().collect(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Applies a gate that `check_gate` accepted.
+    fn apply_unchecked(&mut self, controls: &[usize], wire: usize, gate: &Gate) {
+        let x = single_qubit_matrix(&Gate::X);
+        let with = |extra: &[usize]| [controls, extra].concat();
+
+        match gate {
+            Gate::X
+            | Gate::Y
+            | Gate::Z
+            | Gate::H
+            | Gate::P(_)
+            | Gate::S
+            | Gate::Sdg
+            | Gate::T
+            | Gate::Tdg
+            | Gate::SX
+            | Gate::Rx(_)
+            | Gate::Ry(_)
+            | Gate::Rz(_)
+            | Gate::U3 { .. }
+            | Gate::GlobalPhase(_) => {
+                self.apply_controlled_matrix(controls, wire, single_qubit_matrix(gate));
+            }
+            Gate::CNOT { control, target } => {
+                self.apply_controlled_matrix(&with(&[*control]), *target, x);
+            }
+            Gate::CZ { control, target } => {
+                let z = single_qubit_matrix(&Gate::Z);
+                self.apply_controlled_matrix(&with(&[*control]), *target, z);
+            }
+            Gate::CP {
+                control, target, ..
+            }
+            | Gate::CRx {
+                control, target, ..
+            }
+            | Gate::CRy {
+                control, target, ..
+            }
+            | Gate::CRz {
+                control, target, ..
+            } => {
+                self.apply_unchecked(&with(&[*control]), *target, &gate.controlled_base());
+            }
+            Gate::SWAP { qubit1, qubit2 } => {
+                self.apply_controlled_swap(controls, *qubit1, *qubit2);
+            }
+            Gate::RXX { qubit1, qubit2, .. }
+            | Gate::RYY { qubit1, qubit2, .. }
+            | Gate::RZZ { qubit1, qubit2, .. }
+            | Gate::ISWAP { qubit1, qubit2 }
+            | Gate::SqrtISWAP { qubit1, qubit2 }
+            | Gate::ECR { qubit1, qubit2 } => {
+                let matrix = two_qubit_matrix(gate);
+                self.apply_controlled_unitary(controls, &[*qubit1, *qubit2], &matrix);
+            }
+            Gate::Toffoli {
+                control1,
+                control2,
+                target,
+            } => {
+                self.apply_controlled_matrix(&with(&[*control1, *control2]), *target, x);
+            }
+            Gate::Fredkin {
+                control,
+                target1,
+                target2,
+            } => {
+                self.apply_controlled_swap(&with(&[*control]), *target1, *target2);
+            }
+            Gate::Unitary(unitary) => {
+                self.apply_controlled_unitary(controls, &unitary.qubits, unitary.matrix());
+            }
+            Gate::Controlled(controlled) => {
+                // Open controls are flipped to |1⟩ around the gate and back
+                for &qubit in &controlled.open_controls {
+                    self.apply_controlled_matrix(&[], qubit, x);
+                }
+                let all = [controls, &controlled.controls, &controlled.open_controls].concat();
+                self.apply_unchecked(&all, controlled.target, &controlled.gate);
+                for &qubit in &controlled.open_controls {
+                    self.apply_controlled_matrix(&[], qubit, x);
+                }
+            }
+            Gate::Measure { .. } | Gate::Reset | Gate::Conditional(_) => {
+                panic!("{} is not unitary; run the circuit with the sampler", gate)
+            }
+        }
+    }
+
+    /// Exchanges `qubit1` and `qubit2` on the subspace where every control is |1⟩.
+    pub fn apply_controlled_swap(&mut self, controls: &[usize], qubit1: usize, qubit2: usize) {
+        let x = single_qubit_matrix(&Gate::X);
+        let mut inner_controls = controls.to_vec();
+        inner_controls.push(qubit1);
+
+        self.apply_controlled_matrix(&[qubit2], qubit1, x);
+        self.apply_controlled_matrix(&inner_controls, qubit2, x);
+        self.apply_controlled_matrix(&[qubit2], qubit1, x);
+    }
+
+    /// Applies a 2^k × 2^k `matrix` to `qubits`, where bit i of a matrix index is the state
+    /// of `qubits[i]`.
+    pub fn apply_matrix(&mut self, qubits: &[usize], matrix: &[Vec<Complex>]) {
+        self.apply_controlled_unitary(&[], qubits, matrix);
+    }
+
+    /// Like `apply_matrix`, on the subspace where every control is |1⟩. A single target
+    /// goes through the SIMD kernels; larger matrices are applied one 2^k-amplitude group
+    /// at a time. Panics if a qubit is out of range or repeated; `try_apply_controlled_gate`
+    /// with a `Gate::Unitary` returns an error instead.
+    pub fn apply_controlled_unitary(
+        &mut self,
+        controls: &[usize],
+        qubits: &[usize],
+        matrix: &[Vec<Complex>],
+    ) {
+        if let ([qubit], [row0, row1]) = (qubits, matrix) {
+            let u = [[row0[0], row0[1]], [row1[0], row1[1]]];
+            self.apply_controlled_matrix(controls, *qubit, u);
+            return;
+        }
+
+        let mut qubit_mask = 0usize;
+        for &qubit in qubits.iter().chain(controls) {
+            self.check_qubit(qubit);
+            if qubit_mask & (1 << qubit) != 0 {
+                panic!("Qubit {} is used more than once", qubit);
+            }
+            qubit_mask |= 1 << qubit;
+        }
+        let control_mask: usize = controls.iter().map(|&control| 1 << control).sum();
+        let target_mask = qubit_mask & !control_mask;
+
+        // Position of each matrix index within a group
+        let offsets: Vec<usize> = (0..matrix.len())
+            .map(|local| {
+                qubits
+                    .iter()
+                    .enumerate()
+                    .filter(|&(bit, _)| local >> bit & 1 == 1)
+                    .map(|(_, &qubit)| 1 << qubit)
+                    .sum()
+            })
+            .collect();
+        let mut inputs = vec![ZERO; matrix.len()];
+
+        for base in (0..self.dimension())
+            .filter(|index| index & target_mask == 0 && index & control_mask == control_mask)
+        {
+            for (input, &offset) in inputs.iter_mut().zip(&offsets) {
+                *input = self.amplitude(base | offset);
+            }
+            for (row, &offset) in matrix.iter().zip(&offsets) {
+                let mut sum = ZERO;
+                for (&entry, &input) in row.iter().zip(&inputs) {
+                    sum = sum + entry * input;
+                }
+                let index = base | offset;
+                let block = &mut self.blocks[index / LANES];
+                block.re[index % LANES] = sum.re;
+                block.im[index % LANES] = sum.im;
+            }
+        }
+    }
+
+    /// Applies the 2x2 matrix `u` to `target` on the subspace where every control is |1⟩.
+    /// Panics if a qubit is out of range or a control is the target.
+    pub fn apply_controlled_matrix(&mut self, controls: &[usize], target: usize, u: Matrix2) {
+        self.check_qubit(target);
+        let mut control_mask = 0usize;
+        for &control in controls {
+            self.check_qubit(control);
+            if control == target {
+                panic!("Qubit {} is used as both control and target", target);
+            }
+            control_mask |= 1 << control;
+        }
+
+        let lane_controls = control_mask & (LANES - 1);
+        let block_controls = control_mask >> LANE_BITS;
+        let lane_mask = mask64x16::from_array(std::array::from_fn(|lane| {
+            lane & lane_controls == lane_controls
+        }));
+
+        if target < LANE_BITS {
+            self.apply_lane_kernel(target, block_controls, lane_mask, u);
+        } else {
+            self.apply_block_kernel(target - LANE_BITS, block_controls, lane_mask, u);
+        }
+    }
+
+    /// Target bit lives inside a block: pair each lane with its partner via a swizzle.
+    fn apply_lane_kernel(
+        &mut self,
+        target: usize,
+        block_controls: usize,
+        lane_mask: mask64x16,
+        u: Matrix2,
+    ) {
+        let stride = 1 << target;
+        let diag: [Complex; LANES] =
+            std::array::from_fn(|lane| if lane & stride == 0 { u[0][0] } else { u[1][1] });
+        let off: [Complex; LANES] =
+            std::array::from_fn(|lane| if lane & stride == 0 { u[0][1] } else { u[1][0] });
+
+        let diag_re = f64x16::from_array(diag.map(|c| c.re));
+        let diag_im = f64x16::from_array(diag.map(|c| c.im));
+        let off_re = f64x16::from_array(off.map(|c| c.re));
+        let off_im = f64x16::from_array(off.map(|c| c.im));
+
+        for (b, block) in self.blocks.iter_mut().enumerate() {
+            if b & block_controls != block_controls {
+                continue;
+            }
+
+            let re = f64x16::from_array(block.re);
+            let im = f64x16::from_array(block.im);
+            let (partner_re, partner_im) = match stride {
+                1 => (simd_swizzle!(re, STRIDE_1), simd_swizzle!(im, STRIDE_1)),
+                2 => (simd_swizzle!(re, STRIDE_2), simd_swizzle!(im, STRIDE_2)),
+                4 => (simd_swizzle!(re, STRIDE_4), simd_swizzle!(im, STRIDE_4)),
+                _ => (simd_swizzle!(re, STRIDE_8), simd_swizzle!(im, STRIDE_8)),
+            };
+
+            let new_re = diag_re * re - diag_im * im + off_re * partner_re - off_im * partner_im;
+            let new_im = diag_re * im + diag_im * re + off_re * partner_im + off_im * partner_re;
+
+            block.re = lane_mask.select(new_re, re).to_array();
+            block.im = lane_mask.select(new_im, im).to_array();
+        }
+    }
+
+    /// Target bit selects between blocks: combine whole block pairs lane by lane.
+    fn apply_block_kernel(
+        &mut self,
+        block_target: usize,
+        block_controls: usize,
+        lane_mask: mask64x16,
+        u: Matrix2,
+    ) {
+        let stride = 1 << block_target;
+        let [[u00, u01], [u10, u11]] = u;
+        let (u00_re, u00_im) = (f64x16::splat(u00.re), f64x16::splat(u00.im));
+        let (u01_re, u01_im) = (f64x16::splat(u01.re), f64x16::splat(u01.im));
+        let (u10_re, u10_im) = (f64x16::splat(u10.re), f64x16::splat(u10.im));
+        let (u11_re, u11_im) = (f64x16::splat(u11.re), f64x16::splat(u11.im));
+
+        for b in 0..self.blocks.len() {
+            if b & stride != 0 || b & block_controls != block_controls {
+                continue;
+            }
+
+            let (head, tail) = self.blocks.split_at_mut(b + stride);
+            let low = &mut head[b];
+            let high = &mut tail[0];
+
+            let lo_re = f64x16::from_array(low.re);
+            let lo_im = f64x16::from_array(low.im);
+            let hi_re = f64x16::from_array(high.re);
+            let hi_im = f64x16::from_array(high.im);
+
+            let new_lo_re = u00_re * lo_re - u00_im * lo_im + u01_re * hi_re - u01_im * hi_im;
+            let new_lo_im = u00_re * lo_im + u00_im * lo_re + u01_re * hi_im + u01_im * hi_re;
+            let new_hi_re = u10_re * lo_re - u10_im * lo_im + u11_re * hi_re - u11_im * hi_im;
+            let new_hi_im = u10_re * lo_im + u10_im * lo_re + u11_re * hi_im + u11_im * hi_re;
+
+            low.re = lane_mask.select(new_lo_re, lo_re).to_array();
+            low.im = lane_mask.select(new_lo_im, lo_im).to_array();
+            high.re = lane_mask.select(new_hi_re, hi_re).to_array();
+            high.im = lane_mask.select(new_hi_im, hi_im).to_array();
+        }
+    }
+
+    fn check_qubit(&self, qubit: usize) {
+        if qubit >= self.num_qubits {
+            panic!(
+                "Qubit index {} exceeds state vector width {}",
+                qubit, self.num_qubits
+            );
+        }
+    }
+}
+
+impl Display for StateVector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, amplitude) in self.amplitudes().iter().enumerate() {
+            if amplitude.re.abs() < 1e-12 && amplitude.im.abs() < 1e-12 {
+                continue;
+            }
+            let sign = if amplitude.im >= 0.0 { '+' } else { '-' };
+            writeln!(
+                f,
+                "|{:0width$b}⟩: {:.6} {} {:.6}i",
+                index,
+                amplitude.re,
+                sign,
+                amplitude.im.abs(),
+                width = self.num_qubits.max(1)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Matrix of a single-qubit gate. Panics for multi-qubit gates.
+pub fn single_qubit_matrix(gate: &Gate) -> Matrix2 {
+    let i = Complex { re: 0.0, im: 1.0 };
+    let minus_i = Complex { re: 0.0, im: -1.0 };
+    let minus_one = Complex { re: -1.0, im: 0.0 };
+    let h = Complex {
+        re: FRAC_1_SQRT_2,
+        im: 0.0,
+    };
+    let minus_h = Complex {
+        re: -FRAC_1_SQRT_2,
+        im: 0.0,
+    };
+
+    match gate {
+        Gate::X => [[ZERO, ONE], [ONE, ZERO]],
+        Gate::Y => [[ZERO, minus_i], [i, ZERO]],
+        Gate::Z => [[ONE, ZERO], [ZERO, minus_one]],
+        Gate::H => [[h, h], [h, minus_h]],
+        Gate::P(phase) => [[ONE, ZERO], [ZERO, Complex::from_phase(phase.to_radians())]],
+        Gate::S => [[ONE, ZERO], [ZERO, i]],
+        Gate::Sdg => [[ONE, ZERO], [ZERO, minus_i]],
+        Gate::T => [[ONE, ZERO], [ZERO, Complex::from_phase(FRAC_PI_4)]],
+        Gate::Tdg => [[ONE, ZERO], [ZERO, Complex::from_phase(-FRAC_PI_4)]],
+        Gate::SX => {
+            let (plus, minus) = (Complex::new(0.5, 0.5), Complex::new(0.5, -0.5));
+            [[plus, minus], [minus, plus]]
+        }
+        Gate::Rx(theta) => {
+            let (sin, cos) = theta.half().to_radians().sin_cos();
+            let (cos, minus_i_sin) = (Complex::new(cos, 0.0), Complex::new(0.0, -sin));
+            [[cos, minus_i_sin], [minus_i_sin, cos]]
+        }
+        Gate::Ry(theta) => {
+            let (sin, cos) = theta.half().to_radians().sin_cos();
+            let (sin, cos) = (Complex::new(sin, 0.0), Complex::new(cos, 0.0));
+            [[cos, sin * -1.0], [sin, cos]]
+        }
+        Gate::Rz(theta) => {
+            let half = theta.half().to_radians();
+            [
+                [Complex::from_phase(-half), ZERO],
+                [ZERO, Complex::from_phase(half)],
+            ]
+        }
+        Gate::U3 { theta, phi, lambda } => {
+            let (sin, cos) = theta.half().to_radians().sin_cos();
+            let (phi, lambda) = (phi.to_radians(), lambda.to_radians());
+            [
+                [Complex::new(cos, 0.0), Complex::from_phase(lambda) * -sin],
+                [
+                    Complex::from_phase(phi) * sin,
+                    Complex::from_phase(phi + lambda) * cos,
+                ],
+            ]
+        }
+        Gate::GlobalPhase(phase) => {
+            let phase = Complex::from_phase(phase.to_radians());
+            [[phase, ZERO], [ZERO, phase]]
+        }
+        _ => panic!("{} is not a single-qubit gate", gate),
+    }
+}
+
+/// Matrix of a two-qubit gate. Bit 0 of an index is the state of the gate's first qubit
+/// (the control of a controlled gate) and bit 1 that of its second. Panics for other gates.
+pub fn two_qubit_matrix(gate: &Gate) -> Vec<Vec<Complex>> {
+    let i = Complex { re: 0.0, im: 1.0 };
+    let mut matrix = vec![vec![ZERO; 4]; 4];
+
+    match gate {
+        Gate::CNOT { .. }
+        | Gate::CZ { .. }
+        | Gate::CP { .. }
+        | Gate::CRx { .. }
+        | Gate::CRy { .. }
+        | Gate::CRz { .. } => {
+            // The base gate acts on the indices where the control bit is set
+            let u = single_qubit_matrix(&gate.controlled_base());
+            matrix[0][0] = ONE;
+            matrix[2][2] = ONE;
+            for (row, entries) in u.iter().enumerate() {
+                for (column, &entry) in entries.iter().enumerate() {
+                    matrix[1 + 2 * row][1 + 2 * column] = entry;
+                }
+            }
+        }
+        Gate::SWAP { .. } => {
+            matrix[0][0] = ONE;
+            matrix[1][2] = ONE;
+            matrix[2][1] = ONE;
+            matrix[3][3] = ONE;
+        }
+        Gate::RXX { theta, .. } | Gate::RYY { theta, .. } => {
+            let (sin, cos) = theta.half().to_radians().sin_cos();
+            // −i·sin times the entries of X⊗X or Y⊗Y
+            let (outer, inner) = match gate {
+                Gate::RXX { .. } => (-sin, -sin),
+                _ => (sin, -sin),
+            };
+            for (k, row) in matrix.iter_mut().enumerate() {
+                row[k] = Complex::new(cos, 0.0);
+                row[3 - k] = i * if k == 0 || k == 3 { outer } else { inner };
+            }
+        }
+        Gate::RZZ { theta, .. } => {
+            let half = theta.half().to_radians();
+            for (k, row) in matrix.iter_mut().enumerate() {
+                // Z⊗Z is −1 on the indices of odd parity
+                let parity = if k == 1 || k == 2 { -1.0 } else { 1.0 };
+                row[k] = Complex::from_phase(-half * parity);
+            }
+        }
+        Gate::ISWAP { .. } => {
+            matrix[0][0] = ONE;
+            matrix[1][2] = i;
+            matrix[2][1] = i;
+            matrix[3][3] = ONE;
+        }
+        Gate::SqrtISWAP { .. } => {
+            let (h, i_h) = (Complex::new(FRAC_1_SQRT_2, 0.0), i * FRAC_1_SQRT_2);
+            matrix[0][0] = ONE;
+            matrix[1][1] = h;
+            matrix[1][2] = i_h;
+            matrix[2][1] = i_h;
+            matrix[2][2] = h;
+            matrix[3][3] = ONE;
+        }
+        Gate::ECR { .. } => {
+            let (h, i_h) = (Complex::new(FRAC_1_SQRT_2, 0.0), i * FRAC_1_SQRT_2);
+            matrix[0][1] = h;
+            matrix[0][3] = i_h;
+            matrix[1][0] = h;
+            matrix[1][2] = i_h * -1.0;
+            matrix[2][1] = i_h;
+            matrix[2][3] = h;
+            matrix[3][0] = i_h * -1.0;
+            matrix[3][2] = h;
+        }
+        _ => panic!("{} is not a two-qubit gate", gate),
+    }
+    matrix
+}
+
+/// Runs a circuit on |0...0⟩ and returns the final amplitudes.
+pub fn simulate(circuit: &Circuit) -> Vec<Complex> {
+    StateVector::from_circuit(circuit).amplitudes()
+}
@@ -1 +1,4 @@
+pub(crate) mod json;
+pub mod qasm;
+pub mod synthesis;
 pub mod to_zx;
@@ -0,0 +1,1745 @@
+use crate::error::QuojoError;
+use crate::qcore::Targets;
+use crate::qcore::circuits::Circuit;
+use crate::qcore::dag::Instruction;
+use crate::qcore::gates::{Condition, ControlledGate, Gate, UnitaryGate};
+use crate::qcore::phase::Phase;
+use crate::utils::synthesis::{synthesize_controlled_gate, synthesize_unitary};
+use std::collections::{BTreeSet, HashMap};
+use std::f64::consts::PI;
+use std::fmt::Display;
+use std::ops::Range;
+
+/// A problem in OpenQASM source, at the 1-based line and column where it was found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QasmError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: QasmErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QasmErrorKind {
+    UnexpectedCharacter(char),
+    /// `expected` describes what the grammar allows at this point.
+    UnexpectedToken {
+        expected: String,
+        found: String,
+    },
+    UnsupportedVersion(String),
+    /// Only `qelib1.inc` is built in; no other file is read.
+    UnsupportedInclude(String),
+    UnknownGate(String),
+    UnknownRegister(String),
+    UnknownParameter(String),
+    UnknownFunction(String),
+    /// A gate definition uses a qubit it does not declare.
+    UnknownArgument(String),
+    /// A gate, register, parameter or argument name is used twice.
+    DuplicateDefinition(String),
+    IndexOutOfRange {
+        register: String,
+        index: u64,
+        size: usize,
+    },
+    ParameterCount {
+        gate: String,
+        expected: usize,
+        found: usize,
+    },
+    QubitCount {
+        gate: String,
+        expected: usize,
+        found: usize,
+    },
+    /// Whole registers of different sizes in one statement.
+    RegisterSizeMismatch {
+        expected: usize,
+        found: usize,
+    },
+    RepeatedQubit {
+        gate: String,
+        qubit: String,
+    },
+    /// The gate was declared `opaque`, so there is nothing to apply.
+    OpaqueGate(String),
+    /// The circuit rejected an operation.
+    Circuit(Box<QuojoError>),
+}
+
+impl Display for QasmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.kind
+        )
+    }
+}
+
+impl Display for QasmErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QasmErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected character {:?}", c),
+            QasmErrorKind::UnexpectedToken { expected, found } => {
+                write!(f, "expected {} but found {}", expected, found)
+            }
+            QasmErrorKind::UnsupportedVersion(version) => {
+                write!(f, "OpenQASM {} is not supported; only 2.0 is", version)
+            }
+            QasmErrorKind::UnsupportedInclude(file) => {
+                write!(
+                    f,
+                    "cannot include \"{}\"; only qelib1.inc is built in",
+                    file
+                )
+            }
+            QasmErrorKind::UnknownGate(name) => write!(f, "unknown gate {}", name),
+            QasmErrorKind::UnknownRegister(name) => write!(f, "unknown register {}", name),
+            QasmErrorKind::UnknownParameter(name) => write!(f, "unknown parameter {}", name),
+            QasmErrorKind::UnknownFunction(name) => write!(f, "unknown function {}", name),
+            QasmErrorKind::UnknownArgument(name) => write!(f, "unknown qubit argument {}", name),
+            QasmErrorKind::DuplicateDefinition(name) => write!(f, "{} is already defined", name),
+            QasmErrorKind::IndexOutOfRange {
+                register,
+                index,
+                size,
+            } => write!(
+                f,
+                "index {} is out of range for register {} of size {}",
+                index, register, size
+            ),
+            QasmErrorKind::ParameterCount {
+                gate,
+                expected,
+                found,
+            } => write!(
+                f,
+                "gate {} takes {} parameters but was given {}",
+                gate, expected, found
+            ),
+            QasmErrorKind::QubitCount {
+                gate,
+                expected,
+                found,
+            } => write!(
+                f,
+                "gate {} acts on {} qubits but was given {}",
+                gate, expected, found
+            ),
+            QasmErrorKind::RegisterSizeMismatch { expected, found } => write!(
+                f,
+                "registers of sizes {} and {} cannot be used together",
+                expected, found
+            ),
+            QasmErrorKind::RepeatedQubit { gate, qubit } => {
+                write!(f, "gate {} is given qubit {} more than once", gate, qubit)
+            }
+            QasmErrorKind::OpaqueGate(name) => {
+                write!(f, "opaque gate {} has no definition to apply", name)
+            }
+            QasmErrorKind::Circuit(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for QasmError {}
+
+/// Reads an OpenQASM 2.0 program into a circuit.
+///
+/// Quantum and classical registers become named registers in declaration order, user `gate`
+/// definitions are inlined, and `qelib1.inc` gates map to the matching `Gate` variants.
+/// `barrier` is checked and dropped, since circuits keep no scheduling hints.
+pub fn parse_qasm2(source: &str) -> Result<Circuit, QasmError> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        circuit: Circuit::new(0),
+        gates: BUILTIN
+            .into_iter()
+            .map(|(name, parameters, qubits)| {
+                (name.to_string(), GateKind::Native { parameters, qubits })
+            })
+            .collect(),
+    };
+    parser.parse_program()?;
+    Ok(parser.circuit)
+}
+
+/// The gates every program has, with their parameter and qubit counts.
+const BUILTIN: [(&str, usize, usize); 2] = [("U", 3, 1), ("CX", 0, 2)];
+
+/// Gates declared by `qelib1.inc`, with their parameter and qubit counts.
+const QELIB1: [(&str, usize, usize); 42] = [
+    ("u3", 3, 1),
+    ("u2", 2, 1),
+    ("u1", 1, 1),
+    ("u", 3, 1),
+    ("p", 1, 1),
+    ("u0", 1, 1),
+    ("id", 0, 1),
+    ("x", 0, 1),
+    ("y", 0, 1),
+    ("z", 0, 1),
+    ("h", 0, 1),
+    ("s", 0, 1),
+    ("sdg", 0, 1),
+    ("t", 0, 1),
+    ("tdg", 0, 1),
+    ("sx", 0, 1),
+    ("sxdg", 0, 1),
+    ("rx", 1, 1),
+    ("ry", 1, 1),
+    ("rz", 1, 1),
+    ("cx", 0, 2),
+    ("cy", 0, 2),
+    ("cz", 0, 2),
+    ("ch", 0, 2),
+    ("swap", 0, 2),
+    ("crx", 1, 2),
+    ("cry", 1, 2),
+    ("crz", 1, 2),
+    ("cu1", 1, 2),
+    ("cp", 1, 2),
+    ("cu3", 3, 2),
+    ("cu", 4, 2),
+    ("csx", 0, 2),
+    ("rxx", 1, 2),
+    ("rzz", 1, 2),
+    ("ccx", 0, 3),
+    ("cswap", 0, 3),
+    ("rccx", 0, 3),
+    ("rc3x", 0, 4),
+    ("c3x", 0, 4),
+    ("c3sqrtx", 0, 4),
+    ("c4x", 0, 5),
+];
+
+const FUNCTIONS: [&str; 6] = ["sin", "cos", "tan", "exp", "ln", "sqrt"];
+
+/// Longer symbols first, so that `->` is not read as `-`.
+const SYMBOLS: [&str; 15] = [
+    "->", "==", ";", ",", "(", ")", "[", "]", "{", "}", "+", "-", "*", "/", "^",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Identifier(String),
+    Integer(u64),
+    Real(f64),
+    String(String),
+    Symbol(&'static str),
+    End,
+}
+
+impl Display for TokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenKind::Identifier(name) => write!(f, "`{}`", name),
+            TokenKind::Integer(value) => write!(f, "`{}`", value),
+            TokenKind::Real(value) => write!(f, "`{}`", value),
+            TokenKind::String(text) => write!(f, "\"{}\"", text),
+            TokenKind::Symbol(symbol) => write!(f, "`{}`", symbol),
+            TokenKind::End => write!(f, "end of input"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    line: usize,
+    column: usize,
+}
+
+impl Token {
+    fn error(&self, kind: QasmErrorKind) -> QasmError {
+        QasmError {
+            line: self.line,
+            column: self.column,
+            kind,
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> QasmError {
+        self.error(QasmErrorKind::UnexpectedToken {
+            expected: expected.to_string(),
+            found: self.kind.to_string(),
+        })
+    }
+}
+
+/// Splits the source into tokens, ending with `TokenKind::End`. Comments run from `//` to the
+/// end of the line.
+fn tokenize(source: &str) -> Result<Vec<Token>, QasmError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let (mut i, mut line, mut column) = (0, 1, 1);
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let next = chars.get(i + 1).copied();
+        let token = |kind| Token { kind, line, column };
+
+        if c == '\n' {
+            i += 1;
+            line += 1;
+            column = 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            column += 1;
+            continue;
+        }
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+
+        let kind = if c.is_ascii_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            TokenKind::Identifier(chars[start..i].iter().collect())
+        } else if c.is_ascii_digit() || (c == '.' && next.is_some_and(|n| n.is_ascii_digit())) {
+            let digits = |i: &mut usize| {
+                while *i < chars.len() && chars[*i].is_ascii_digit() {
+                    *i += 1;
+                }
+            };
+            digits(&mut i);
+            let mut real = false;
+            if chars.get(i) == Some(&'.') {
+                real = true;
+                i += 1;
+                digits(&mut i);
+            }
+            if matches!(chars.get(i), Some('e' | 'E')) {
+                let sign = usize::from(matches!(chars.get(i + 1), Some('+' | '-')));
+                if chars.get(i + 1 + sign).is_some_and(|d| d.is_ascii_digit()) {
+                    real = true;
+                    i += 1 + sign;
+                    digits(&mut i);
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            match text.parse() {
+                Ok(value) if !real => TokenKind::Integer(value),
+                // Integers too large for u64 are only useful as angles
+                _ => TokenKind::Real(text.parse().expect("digits form a valid float")),
+            }
+        } else if c == '"' {
+            i += 1;
+            while i < chars.len() && chars[i] != '"' && chars[i] != '\n' {
+                i += 1;
+            }
+            if chars.get(i) != Some(&'"') {
+                return Err(token(TokenKind::End).unexpected("a closing `\"`"));
+            }
+            i += 1;
+            TokenKind::String(chars[start + 1..i - 1].iter().collect())
+        } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| {
+            symbol
+                .chars()
+                .enumerate()
+                .all(|(offset, s)| chars.get(i + offset) == Some(&s))
+        }) {
+            i += symbol.len();
+            TokenKind::Symbol(symbol)
+        } else {
+            return Err(token(TokenKind::End).error(QasmErrorKind::UnexpectedCharacter(c)));
+        };
+
+        tokens.push(token(kind));
+        column += i - start;
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::End,
+        line,
+        column,
+    });
+    Ok(tokens)
+}
+
+/// A parameter expression, evaluated in radians once the gate's parameters are known.
+#[derive(Debug, Clone)]
+enum Expression {
+    Number(f64),
+    Parameter(String),
+    Negate(Box<Expression>),
+    Binary(&'static str, Box<Expression>, Box<Expression>),
+    Function(String, Box<Expression>),
+}
+
+impl Expression {
+    /// Every parameter has been checked against the enclosing definition while parsing.
+    fn evaluate(&self, values: &HashMap<String, f64>) -> f64 {
+        match self {
+            Expression::Number(value) => *value,
+            Expression::Parameter(name) => values[name],
+            Expression::Negate(operand) => -operand.evaluate(values),
+            Expression::Binary(operator, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.evaluate(values), rhs.evaluate(values));
+                match *operator {
+                    "+" => lhs + rhs,
+                    "-" => lhs - rhs,
+                    "*" => lhs * rhs,
+                    "/" => lhs / rhs,
+                    _ => lhs.powf(rhs),
+                }
+            }
+            Expression::Function(name, argument) => {
+                let argument = argument.evaluate(values);
+                match name.as_str() {
+                    "sin" => argument.sin(),
+                    "cos" => argument.cos(),
+                    "tan" => argument.tan(),
+                    "exp" => argument.exp(),
+                    "ln" => argument.ln(),
+                    _ => argument.sqrt(),
+                }
+            }
+        }
+    }
+}
+
+enum GateKind {
+    /// `U`, `CX` or a qelib1.inc gate, applied by `native_gate`.
+    Native {
+        parameters: usize,
+        qubits: usize,
+    },
+    Defined(GateDefinition),
+    Opaque {
+        parameters: usize,
+        qubits: usize,
+    },
+}
+
+impl GateKind {
+    /// Number of parameters and qubits.
+    fn arity(&self) -> (usize, usize) {
+        match self {
+            GateKind::Native { parameters, qubits } | GateKind::Opaque { parameters, qubits } => {
+                (*parameters, *qubits)
+            }
+            GateKind::Defined(definition) => (definition.parameters.len(), definition.qubits.len()),
+        }
+    }
+}
+
+struct GateDefinition {
+    parameters: Vec<String>,
+    qubits: Vec<String>,
+    body: Vec<GateCall>,
+}
+
+/// A gate applied inside a definition, to the definition's qubits by position.
+struct GateCall {
+    name: String,
+    parameters: Vec<Expression>,
+    qubits: Vec<usize>,
+}
+
+/// A register or one of its bits as written in a statement.
+struct Argument {
+    register: String,
+    /// Position in the register of the first bit.
+    offset: usize,
+    bits: Vec<usize>,
+    whole: bool,
+    token: Token,
+}
+
+impl Argument {
+    /// The bit used in the `i`th application of a broadcast statement, as written in QASM.
+    fn label(&self, i: usize) -> String {
+        let i = if self.whole { i } else { 0 };
+        format!("{}[{}]", self.register, self.offset + i)
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    circuit: Circuit,
+    gates: HashMap<String, GateKind>,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position]
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.position].clone();
+        if token.kind != TokenKind::End {
+            self.position += 1;
+        }
+        token
+    }
+
+    /// Consumes `symbol` if it comes next.
+    fn accept(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek().kind, TokenKind::Symbol(s) if s == symbol);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<Token, QasmError> {
+        let token = self.next();
+        match token.kind {
+            TokenKind::Symbol(s) if s == symbol => Ok(token),
+            _ => Err(token.unexpected(&format!("`{}`", symbol))),
+        }
+    }
+
+    fn identifier(&mut self) -> Result<(String, Token), QasmError> {
+        let token = self.next();
+        match &token.kind {
+            TokenKind::Identifier(name) => Ok((name.clone(), token)),
+            _ => Err(token.unexpected("a name")),
+        }
+    }
+
+    fn integer(&mut self) -> Result<(u64, Token), QasmError> {
+        let token = self.next();
+        match token.kind {
+            TokenKind::Integer(value) => Ok((value, token)),
+            _ => Err(token.unexpected("an integer")),
+        }
+    }
+
+    /// Names separated by commas, each new in `taken`, which they are added to.
+    fn names(&mut self, taken: &mut Vec<String>) -> Result<(), QasmError> {
+        loop {
+            let (name, token) = self.identifier()?;
+            if taken.contains(&name) {
+                return Err(token.error(QasmErrorKind::DuplicateDefinition(name)));
+            }
+            taken.push(name);
+            if !self.accept(",") {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_program(&mut self) -> Result<(), QasmError> {
+        let (keyword, token) = self.identifier()?;
+        if keyword != "OPENQASM" {
+            return Err(token.unexpected("`OPENQASM`"));
+        }
+        let token = self.next();
+        match token.kind {
+            TokenKind::Real(2.0) | TokenKind::Integer(2) => {}
+            TokenKind::Real(_) | TokenKind::Integer(_) => {
+                let version = token.kind.to_string().replace('`', "");
+                return Err(token.error(QasmErrorKind::UnsupportedVersion(version)));
+            }
+            _ => return Err(token.unexpected("a version number")),
+        }
+        self.expect(";")?;
+
+        while self.peek().kind != TokenKind::End {
+            self.parse_statement()?;
+        }
+        Ok(())
+    }
+
+    fn parse_statement(&mut self) -> Result<(), QasmError> {
+        let token = self.peek().clone();
+        let TokenKind::Identifier(keyword) = &token.kind else {
+            return Err(token.unexpected("a statement"));
+        };
+        match keyword.as_str() {
+            "include" => self.parse_include(),
+            "qreg" | "creg" => self.parse_register(),
+            "gate" => self.parse_gate_definition(),
+            "opaque" => self.parse_opaque(),
+            "barrier" => {
+                self.next();
+                self.arguments(false)?;
+                self.expect(";")?;
+                Ok(())
+            }
+            "if" => self.parse_if(),
+            _ => self.parse_operation(None),
+        }
+    }
+
+    fn parse_include(&mut self) -> Result<(), QasmError> {
+        self.next();
+        let token = self.next();
+        let TokenKind::String(file) = token.kind.clone() else {
+            return Err(token.unexpected("a file name"));
+        };
+        self.expect(";")?;
+        if file != "qelib1.inc" {
+            return Err(token.error(QasmErrorKind::UnsupportedInclude(file)));
+        }
+        for (name, parameters, qubits) in QELIB1 {
+            self.gates
+                .entry(name.to_string())
+                .or_insert(GateKind::Native { parameters, qubits });
+        }
+        Ok(())
+    }
+
+    fn parse_register(&mut self) -> Result<(), QasmError> {
+        let (keyword, _) = self.identifier()?;
+        let (name, token) = self.identifier()?;
+        self.expect("[")?;
+        let (size, _) = self.integer()?;
+        self.expect("]")?;
+        self.expect(";")?;
+
+        let size = size as usize;
+        let added = if keyword == "qreg" {
+            self.circuit.try_add_register(&name, size).map(|_| ())
+        } else {
+            self.circuit
+                .try_add_classical_register(&name, size)
+                .map(|_| ())
+        };
+        added.map_err(|_| token.error(QasmErrorKind::DuplicateDefinition(name)))
+    }
+
+    fn parse_gate_definition(&mut self) -> Result<(), QasmError> {
+        self.next();
+        let (name, token) = self.identifier()?;
+        if self.gates.contains_key(&name) {
+            return Err(token.error(QasmErrorKind::DuplicateDefinition(name)));
+        }
+        let mut parameters = Vec::new();
+        if self.accept("(") && !self.accept(")") {
+            self.names(&mut parameters)?;
+            self.expect(")")?;
+        }
+        let mut qubits = Vec::new();
+        self.names(&mut qubits)?;
+        self.expect("{")?;
+
+        let mut body = Vec::new();
+        while !self.accept("}") {
+            let (callee, token) = self.identifier()?;
+            let call_parameters = if callee == "barrier" {
+                Vec::new()
+            } else {
+                self.call_parameters(&parameters)?
+            };
+            let mut call_qubits = Vec::new();
+            loop {
+                let (argument, token) = self.identifier()?;
+                let Some(index) = qubits.iter().position(|qubit| *qubit == argument) else {
+                    return Err(token.error(QasmErrorKind::UnknownArgument(argument)));
+                };
+                call_qubits.push(index);
+                if !self.accept(",") {
+                    break;
+                }
+            }
+            self.expect(";")?;
+            if callee == "barrier" {
+                continue;
+            }
+
+            self.check_call(&callee, call_parameters.len(), call_qubits.len(), &token)?;
+            if let Some(&repeated) = call_qubits
+                .iter()
+                .enumerate()
+                .find_map(|(i, q)| call_qubits[..i].contains(q).then_some(q))
+            {
+                return Err(token.error(QasmErrorKind::RepeatedQubit {
+                    gate: callee,
+                    qubit: qubits[repeated].clone(),
+                }));
+            }
+            body.push(GateCall {
+                name: callee,
+                parameters: call_parameters,
+                qubits: call_qubits,
+            });
+        }
+
+        let definition = GateDefinition {
+            parameters,
+            qubits,
+            body,
+        };
+        self.gates.insert(name, GateKind::Defined(definition));
+        Ok(())
+    }
+
+    fn parse_opaque(&mut self) -> Result<(), QasmError> {
+        self.next();
+        let (name, token) = self.identifier()?;
+        if self.gates.contains_key(&name) {
+            return Err(token.error(QasmErrorKind::DuplicateDefinition(name)));
+        }
+        let mut parameters = Vec::new();
+        if self.accept("(") && !self.accept(")") {
+            self.names(&mut parameters)?;
+            self.expect(")")?;
+        }
+        let mut qubits = Vec::new();
+        self.names(&mut qubits)?;
+        self.expect(";")?;
+
+        let kind = GateKind::Opaque {
+            parameters: parameters.len(),
+            qubits: qubits.len(),
+        };
+        self.gates.insert(name, kind);
+        Ok(())
+    }
+
+    /// `if (creg == value)` followed by a gate, measurement or reset.
+    fn parse_if(&mut self) -> Result<(), QasmError> {
+        self.next();
+        self.expect("(")?;
+        let (name, token) = self.identifier()?;
+        let Some(register) = self.circuit.classical_register(&name) else {
+            return Err(token.error(QasmErrorKind::UnknownRegister(name)));
+        };
+        let clbits: Vec<usize> = register.clbits.clone().collect();
+        self.expect("==")?;
+        let (value, _) = self.integer()?;
+        self.expect(")")?;
+        self.parse_operation(Some(Condition { clbits, value }))
+    }
+
+    /// A gate call, `measure` or `reset`, applied once per bit of any whole registers given.
+    fn parse_operation(&mut self, condition: Option<Condition>) -> Result<(), QasmError> {
+        let (name, token) = self.identifier()?;
+        let mut instructions = Vec::new();
+
+        match name.as_str() {
+            "measure" => {
+                let mut arguments = vec![self.argument(false)?];
+                self.expect("->")?;
+                arguments.push(self.argument(true)?);
+                self.expect(";")?;
+                for bits in broadcast(&arguments)? {
+                    let gate = Gate::Measure {
+                        qubit: bits[0],
+                        clbit: bits[1],
+                    };
+                    instructions.push(Instruction::new(gate, bits[0]));
+                }
+            }
+            "reset" => {
+                let arguments = vec![self.argument(false)?];
+                self.expect(";")?;
+                for bits in broadcast(&arguments)? {
+                    instructions.push(Instruction::new(Gate::Reset, bits[0]));
+                }
+            }
+            _ => {
+                let parameters = self.call_parameters(&[])?;
+                let arguments = self.arguments(false)?;
+                self.expect(";")?;
+                self.check_call(&name, parameters.len(), arguments.len(), &token)?;
+
+                let values = HashMap::new();
+                let parameters: Vec<f64> = parameters
+                    .iter()
+                    .map(|parameter| parameter.evaluate(&values))
+                    .collect();
+                for (i, qubits) in broadcast(&arguments)?.into_iter().enumerate() {
+                    if let Some(repeated) =
+                        (0..qubits.len()).find(|&j| qubits[..j].contains(&qubits[j]))
+                    {
+                        return Err(arguments[repeated].token.error(
+                            QasmErrorKind::RepeatedQubit {
+                                gate: name,
+                                qubit: arguments[repeated].label(i),
+                            },
+                        ));
+                    }
+                    self.expand(&name, &parameters, &qubits, &mut instructions);
+                }
+            }
+        }
+
+        for instruction in instructions {
+            let targets = Targets(&instruction.qubits);
+            match &condition {
+                Some(condition) => {
+                    self.circuit
+                        .try_apply_conditional(instruction.gate, condition.clone(), targets)
+                }
+                None => self.circuit.try_apply(instruction.gate, targets),
+            }
+            .map_err(|error| token.error(QasmErrorKind::Circuit(Box::new(error))))?;
+        }
+        Ok(())
+    }
+
+    /// Checks that `name` is a gate that can be applied with these many parameters and qubits.
+    fn check_call(
+        &self,
+        name: &str,
+        parameters: usize,
+        qubits: usize,
+        token: &Token,
+    ) -> Result<(), QasmError> {
+        let Some(kind) = self.gates.get(name) else {
+            return Err(token.error(QasmErrorKind::UnknownGate(name.to_string())));
+        };
+        if let GateKind::Opaque { .. } = kind {
+            return Err(token.error(QasmErrorKind::OpaqueGate(name.to_string())));
+        }
+        let (expected_parameters, expected_qubits) = kind.arity();
+        if parameters != expected_parameters {
+            return Err(token.error(QasmErrorKind::ParameterCount {
+                gate: name.to_string(),
+                expected: expected_parameters,
+                found: parameters,
+            }));
+        }
+        if qubits != expected_qubits {
+            return Err(token.error(QasmErrorKind::QubitCount {
+                gate: name.to_string(),
+                expected: expected_qubits,
+                found: qubits,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Appends the native gates `name` stands for on `qubits`, inlining definitions.
+    fn expand(
+        &self,
+        name: &str,
+        parameters: &[f64],
+        qubits: &[usize],
+        instructions: &mut Vec<Instruction>,
+    ) {
+        match &self.gates[name] {
+            GateKind::Native { .. } => instructions.extend(native_gate(name, parameters, qubits)),
+            GateKind::Defined(definition) => {
+                let values: HashMap<String, f64> = definition
+                    .parameters
+                    .iter()
+                    .cloned()
+                    .zip(parameters.iter().copied())
+                    .collect();
+                for call in &definition.body {
+                    let parameters: Vec<f64> = call
+                        .parameters
+                        .iter()
+                        .map(|parameter| parameter.evaluate(&values))
+                        .collect();
+                    let qubits: Vec<usize> = call.qubits.iter().map(|&i| qubits[i]).collect();
+                    self.expand(&call.name, &parameters, &qubits, instructions);
+                }
+            }
+            GateKind::Opaque { .. } => unreachable!("check_call rejects opaque gate {}", name),
+        }
+    }
+
+    /// Optional parenthesised expressions, which may use `scope`'s parameters.
+    fn call_parameters(&mut self, scope: &[String]) -> Result<Vec<Expression>, QasmError> {
+        let mut parameters = Vec::new();
+        if self.accept("(") && !self.accept(")") {
+            loop {
+                parameters.push(self.expression(scope)?);
+                if !self.accept(",") {
+                    break;
+                }
+            }
+            self.expect(")")?;
+        }
+        Ok(parameters)
+    }
+
+    fn arguments(&mut self, classical: bool) -> Result<Vec<Argument>, QasmError> {
+        let mut arguments = vec![self.argument(classical)?];
+        while self.accept(",") {
+            arguments.push(self.argument(classical)?);
+        }
+        Ok(arguments)
+    }
+
+    /// A whole register or one indexed bit of it.
+    fn argument(&mut self, classical: bool) -> Result<Argument, QasmError> {
+        let (register, token) = self.identifier()?;
+        let bits = if classical {
+            let found = self.circuit.classical_register(&register);
+            found.map(|register| register.clbits.clone())
+        } else {
+            let found = self.circuit.register(&register);
+            found.map(|register| register.qubits.clone())
+        };
+        let Some(bits) = bits else {
+            return Err(token.error(QasmErrorKind::UnknownRegister(register)));
+        };
+
+        if !self.accept("[") {
+            return Ok(Argument {
+                register,
+                offset: 0,
+                bits: bits.collect(),
+                whole: true,
+                token,
+            });
+        }
+        let (index, index_token) = self.integer()?;
+        self.expect("]")?;
+        if index >= bits.len() as u64 {
+            return Err(index_token.error(QasmErrorKind::IndexOutOfRange {
+                register,
+                index,
+                size: bits.len(),
+            }));
+        }
+        Ok(Argument {
+            register,
+            offset: index as usize,
+            bits: vec![bits.start + index as usize],
+            whole: false,
+            token,
+        })
+    }
+
+    fn expression(&mut self, scope: &[String]) -> Result<Expression, QasmError> {
+        let mut lhs = self.term(scope)?;
+        loop {
+            let operator = if self.accept("+") {
+                "+"
+            } else if self.accept("-") {
+                "-"
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expression::Binary(operator, Box::new(lhs), Box::new(self.term(scope)?));
+        }
+    }
+
+    fn term(&mut self, scope: &[String]) -> Result<Expression, QasmError> {
+        let mut lhs = self.unary(scope)?;
+        loop {
+            let operator = if self.accept("*") {
+                "*"
+            } else if self.accept("/") {
+                "/"
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expression::Binary(operator, Box::new(lhs), Box::new(self.unary(scope)?));
+        }
+    }
+
+    /// Signs bind looser than `^`, so `-2^2` is −4.
+    fn unary(&mut self, scope: &[String]) -> Result<Expression, QasmError> {
+        if self.accept("-") {
+            return Ok(Expression::Negate(Box::new(self.unary(scope)?)));
+        }
+        if self.accept("+") {
+            return self.unary(scope);
+        }
+        let base = self.primary(scope)?;
+        if self.accept("^") {
+            let exponent = self.unary(scope)?;
+            return Ok(Expression::Binary("^", Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self, scope: &[String]) -> Result<Expression, QasmError> {
+        let token = self.next();
+        match &token.kind {
+            TokenKind::Integer(value) => Ok(Expression::Number(*value as f64)),
+            TokenKind::Real(value) => Ok(Expression::Number(*value)),
+            TokenKind::Identifier(name) if name == "pi" => Ok(Expression::Number(PI)),
+            TokenKind::Identifier(name) if scope.contains(name) => {
+                Ok(Expression::Parameter(name.clone()))
+            }
+            TokenKind::Identifier(name) if self.accept("(") => {
+                if !FUNCTIONS.contains(&name.as_str()) {
+                    return Err(token.error(QasmErrorKind::UnknownFunction(name.clone())));
+                }
+                let argument = self.expression(scope)?;
+                self.expect(")")?;
+                Ok(Expression::Function(name.clone(), Box::new(argument)))
+            }
+            TokenKind::Identifier(name) => {
+                Err(token.error(QasmErrorKind::UnknownParameter(name.clone())))
+            }
+            TokenKind::Symbol("(") => {
+                let expression = self.expression(scope)?;
+                self.expect(")")?;
+                Ok(expression)
+            }
+            _ => Err(token.unexpected("an expression")),
+        }
+    }
+}
+
+/// The bits for each application of a statement: whole registers, which must be the same
+/// size, contribute one bit each time and indexed bits are repeated.
+fn broadcast(arguments: &[Argument]) -> Result<Vec<Vec<usize>>, QasmError> {
+    let mut size = None;
+    for argument in arguments.iter().filter(|argument| argument.whole) {
+        match size {
+            None => size = Some(argument.bits.len()),
+            Some(expected) if expected != argument.bits.len() => {
+                return Err(argument.token.error(QasmErrorKind::RegisterSizeMismatch {
+                    expected,
+                    found: argument.bits.len(),
+                }));
+            }
+            Some(_) => {}
+        }
+    }
+    Ok((0..size.unwrap_or(1))
+        .map(|i| {
+            arguments
+                .iter()
+                .map(|argument| argument.bits[if argument.whole { i } else { 0 }])
+                .collect()
+        })
+        .collect())
+}
+
+/// The instructions for a built-in or qelib1.inc gate. Gates without a `Gate` variant become
+/// controlled gates or, for `sxdg`, `rccx` and `rc3x`, the sequences qelib1.inc defines.
+fn native_gate(name: &str, parameters: &[f64], qubits: &[usize]) -> Vec<Instruction> {
+    let angle = |i: usize| Phase::from_radians(parameters[i]);
+    let target = qubits[qubits.len() - 1];
+    let on = |gate: Gate| Instruction::new(gate, target);
+    let cx = |control: usize| Instruction::new(Gate::CNOT { control, target }, control);
+    let controlled = |gate: Gate| {
+        let controlled = ControlledGate {
+            controls: qubits[..qubits.len() - 1].to_vec(),
+            open_controls: Vec::new(),
+            gate,
+            target,
+        };
+        Instruction::new(Gate::Controlled(Box::new(controlled)), target)
+    };
+    let u3 = || Gate::U3 {
+        theta: angle(0),
+        phi: angle(1),
+        lambda: angle(2),
+    };
+    let (control, qubit1, qubit2) = (qubits[0], qubits[0], qubits.get(1).copied().unwrap_or(0));
+
+    let gate = match name {
+        "U" | "u3" | "u" => u3(),
+        "u2" => Gate::U3 {
+            theta: Phase::new(1, 2),
+            phi: angle(0),
+            lambda: angle(1),
+        },
+        "u1" | "p" => Gate::P(angle(0)),
+        "u0" | "id" => return Vec::new(),
+        "x" => Gate::X,
+        "y" => Gate::Y,
+        "z" => Gate::Z,
+        "h" => Gate::H,
+        "s" => Gate::S,
+        "sdg" => Gate::Sdg,
+        "t" => Gate::T,
+        "tdg" => Gate::Tdg,
+        "sx" => Gate::SX,
+        "sxdg" => return vec![on(Gate::S), on(Gate::H), on(Gate::S)],
+        "rx" => Gate::Rx(angle(0)),
+        "ry" => Gate::Ry(angle(0)),
+        "rz" => Gate::Rz(angle(0)),
+        "CX" | "cx" => Gate::CNOT { control, target },
+        "cz" => Gate::CZ { control, target },
+        "cy" => return vec![controlled(Gate::Y)],
+        "ch" => return vec![controlled(Gate::H)],
+        "swap" => Gate::SWAP { qubit1, qubit2 },
+        "crx" => Gate::CRx {
+            control,
+            target,
+            theta: angle(0),
+        },
+        "cry" => Gate::CRy {
+            control,
+            target,
+            theta: angle(0),
+        },
+        "crz" => Gate::CRz {
+            control,
+            target,
+            theta: angle(0),
+        },
+        "cu1" | "cp" => Gate::CP {
+            control,
+            target,
+            phase: angle(0),
+        },
+        "cu3" => return vec![controlled(u3())],
+        // The fourth parameter is a phase on the controlled block, a P on the control
+        "cu" => {
+            return vec![
+                controlled(u3()),
+                Instruction::new(Gate::P(angle(3)), control),
+            ];
+        }
+        "csx" | "c3sqrtx" => return vec![controlled(Gate::SX)],
+        "c3x" | "c4x" => return vec![controlled(Gate::X)],
+        "rxx" => Gate::RXX {
+            qubit1,
+            qubit2,
+            theta: angle(0),
+        },
+        "rzz" => Gate::RZZ {
+            qubit1,
+            qubit2,
+            theta: angle(0),
+        },
+        "ccx" => Gate::Toffoli {
+            control1: qubits[0],
+            control2: qubits[1],
+            target,
+        },
+        "cswap" => Gate::Fredkin {
+            control,
+            target1: qubits[1],
+            target2: qubits[2],
+        },
+        "rccx" => {
+            let [a, b, _] = qubits else {
+                unreachable!("rccx acts on 3 qubits")
+            };
+            return vec![
+                on(Gate::H),
+                on(Gate::T),
+                cx(*b),
+                on(Gate::Tdg),
+                cx(*a),
+                on(Gate::T),
+                cx(*b),
+                on(Gate::Tdg),
+                on(Gate::H),
+            ];
+        }
+        "rc3x" => {
+            let [a, b, c, _] = qubits else {
+                unreachable!("rc3x acts on 4 qubits")
+            };
+            return vec![
+                on(Gate::H),
+                on(Gate::T),
+                cx(*c),
+                on(Gate::Tdg),
+                on(Gate::H),
+                cx(*a),
+                on(Gate::T),
+                cx(*b),
+                on(Gate::Tdg),
+                cx(*a),
+                on(Gate::T),
+                cx(*b),
+                on(Gate::Tdg),
+                on(Gate::H),
+                on(Gate::T),
+                cx(*c),
+                on(Gate::Tdg),
+                on(Gate::H),
+            ];
+        }
+        _ => unreachable!("{} is not a native gate", name),
+    };
+    vec![Instruction::new(gate, qubits[0])]
+}
+
+/// OpenQASM output for circuits, which `parse_qasm2` reads back.
+pub trait QasmCircuit {
+    /// An OpenQASM 2.0 program using `qelib1.inc`. Fails on symbolic phases, which OpenQASM
+    /// 2.0 cannot express, and on conditions that are not on a whole classical register.
+    fn to_qasm2(&self) -> Result<String, QuojoError>;
+
+    /// An OpenQASM 3.0 program using `stdgates.inc`, with controlled gates written with
+    /// `ctrl @` and `negctrl @` and each phase variable as an `input float[64]`.
+    fn to_qasm3(&self) -> String;
+}
+
+impl QasmCircuit for Circuit {
+    fn to_qasm2(&self) -> Result<String, QuojoError> {
+        QasmWriter::new(self, Dialect::Qasm2).write()
+    }
+
+    fn to_qasm3(&self) -> String {
+        QasmWriter::new(self, Dialect::Qasm3)
+            .write()
+            .expect("every circuit can be written as OpenQASM 3.0")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dialect {
+    Qasm2,
+    Qasm3,
+}
+
+/// Definitions written ahead of the program for gates without a standard name, in an order
+/// where each only uses those before it. `rxx` and `rzz` are in qelib1.inc but not in
+/// stdgates.inc, and OpenQASM 3.0 has `gphase` built in.
+const DEFINITIONS: [(&str, &str); 7] = [
+    (
+        "gphase",
+        "gate gphase(theta) a { u1(theta) a; x a; u1(theta) a; x a; }",
+    ),
+    (
+        "rxx",
+        "gate rxx(theta) a, b { h a; h b; cx a, b; rz(theta) b; cx a, b; h a; h b; }",
+    ),
+    (
+        "ryy",
+        "gate ryy(theta) a, b { rx(pi/2) a; rx(pi/2) b; cx a, b; rz(theta) b; cx a, b; rx(-pi/2) a; rx(-pi/2) b; }",
+    ),
+    (
+        "rzz",
+        "gate rzz(theta) a, b { cx a, b; rz(theta) b; cx a, b; }",
+    ),
+    (
+        "iswap",
+        "gate iswap a, b { s a; s b; h a; cx a, b; cx b, a; h b; }",
+    ),
+    (
+        "siswap",
+        "gate siswap a, b { rxx(-pi/4) a, b; ryy(-pi/4) a, b; }",
+    ),
+    (
+        "ecr",
+        "gate ecr a, b { h b; cx a, b; rz(pi/4) b; cx a, b; x a; cx a, b; rz(-pi/4) b; cx a, b; h b; }",
+    ),
+];
+
+/// A gate applied to some qubits, as in `name(parameters) qubits;`.
+struct Call {
+    name: String,
+    parameters: Vec<Phase>,
+    qubits: Vec<usize>,
+}
+
+impl Call {
+    fn new(name: &str, parameters: &[&Phase], qubits: &[usize]) -> Self {
+        Call {
+            name: name.to_string(),
+            parameters: parameters.iter().map(|&phase| phase.clone()).collect(),
+            qubits: qubits.to_vec(),
+        }
+    }
+
+    fn render(&self, names: &[String]) -> String {
+        let mut text = self.name.clone();
+        if !self.parameters.is_empty() {
+            let parameters: Vec<String> = self.parameters.iter().map(angle).collect();
+            text += &format!("({})", parameters.join(", "));
+        }
+        if !self.qubits.is_empty() {
+            let qubits: Vec<&str> = self.qubits.iter().map(|&q| names[q].as_str()).collect();
+            text += &format!(" {}", qubits.join(", "));
+        }
+        text + ";"
+    }
+}
+
+struct QasmWriter<'a> {
+    circuit: &'a Circuit,
+    dialect: Dialect,
+    quantum_registers: Vec<(String, Range<usize>)>,
+    classical_registers: Vec<(String, Range<usize>)>,
+    qubit_names: Vec<String>,
+    clbit_names: Vec<String>,
+    /// Names from `DEFINITIONS` the program uses.
+    required: BTreeSet<&'static str>,
+    /// Custom unitaries with the name and definition written for each.
+    unitaries: Vec<(UnitaryGate, String, String)>,
+}
+
+impl<'a> QasmWriter<'a> {
+    fn new(circuit: &'a Circuit, dialect: Dialect) -> Self {
+        let mut taken: BTreeSet<String> = circuit
+            .registers
+            .iter()
+            .map(|register| register.name.clone())
+            .chain(
+                circuit
+                    .classical_registers
+                    .iter()
+                    .map(|register| register.name.clone()),
+            )
+            .collect();
+        let quantum = circuit
+            .registers
+            .iter()
+            .map(|register| (register.name.clone(), register.qubits.clone()));
+        let quantum_registers = layout(quantum, circuit.num_qubits(), "q", &mut taken);
+        let classical = circuit
+            .classical_registers
+            .iter()
+            .map(|register| (register.name.clone(), register.clbits.clone()));
+        let classical_registers = layout(classical, circuit.num_clbits(), "c", &mut taken);
+
+        let names = |registers: &[(String, Range<usize>)]| -> Vec<String> {
+            registers
+                .iter()
+                .flat_map(|(name, bits)| (0..bits.len()).map(move |i| format!("{}[{}]", name, i)))
+                .collect()
+        };
+        QasmWriter {
+            circuit,
+            dialect,
+            qubit_names: names(&quantum_registers),
+            clbit_names: names(&classical_registers),
+            quantum_registers,
+            classical_registers,
+            required: BTreeSet::new(),
+            unitaries: Vec::new(),
+        }
+    }
+
+    fn write(mut self) -> Result<String, QuojoError> {
+        let mut body = Vec::new();
+        for (wire, gate) in self.circuit.ordered_gates() {
+            body.extend(self.statements(&gate, wire)?);
+        }
+
+        let mut lines = match self.dialect {
+            Dialect::Qasm2 => vec!["OPENQASM 2.0;", "include \"qelib1.inc\";"],
+            Dialect::Qasm3 => vec!["OPENQASM 3.0;", "include \"stdgates.inc\";"],
+        }
+        .into_iter()
+        .map(String::from)
+        .collect::<Vec<_>>();
+        for variable in self.circuit.variables() {
+            lines.push(format!("input float[64] {};", variable));
+        }
+        for (name, definition) in DEFINITIONS {
+            if self.required.contains(name) {
+                lines.push(definition.to_string());
+            }
+        }
+        for (unitary, _, definition) in &self.unitaries {
+            lines.push(format!("// {}", unitary.label));
+            lines.push(definition.clone());
+        }
+        for (name, qubits) in &self.quantum_registers {
+            lines.push(match self.dialect {
+                Dialect::Qasm2 => format!("qreg {}[{}];", name, qubits.len()),
+                Dialect::Qasm3 => format!("qubit[{}] {};", qubits.len(), name),
+            });
+        }
+        for (name, clbits) in &self.classical_registers {
+            lines.push(match self.dialect {
+                Dialect::Qasm2 => format!("creg {}[{}];", name, clbits.len()),
+                Dialect::Qasm3 => format!("bit[{}] {};", clbits.len(), name),
+            });
+        }
+        lines.extend(body);
+        Ok(lines.join("\n") + "\n")
+    }
+
+    /// The statements for one instruction of the circuit.
+    fn statements(&mut self, gate: &Gate, wire: usize) -> Result<Vec<String>, QuojoError> {
+        let statement = match (gate, self.dialect) {
+            (Gate::Measure { qubit, clbit }, Dialect::Qasm2) => format!(
+                "measure {} -> {};",
+                self.qubit_names[*qubit], self.clbit_names[*clbit]
+            ),
+            (Gate::Measure { qubit, clbit }, Dialect::Qasm3) => format!(
+                "{} = measure {};",
+                self.clbit_names[*clbit], self.qubit_names[*qubit]
+            ),
+            (Gate::Reset, _) => format!("reset {};", self.qubit_names[wire]),
+            (Gate::Conditional(conditional), _) => {
+                let Some(condition) = self.condition(&conditional.condition, gate)? else {
+                    return Ok(Vec::new());
+                };
+                let statements = self.statements(&conditional.gate, conditional.target)?;
+                return Ok(statements
+                    .into_iter()
+                    .map(|statement| format!("if ({}) {}", condition, statement))
+                    .collect());
+            }
+            _ => {
+                if self.dialect == Dialect::Qasm2 && !gate.variables().is_empty() {
+                    return Err(self.unsupported(gate));
+                }
+                let calls = self.calls(gate, wire);
+                return Ok(calls
+                    .iter()
+                    .map(|call| call.render(&self.qubit_names))
+                    .collect());
+            }
+        };
+        Ok(vec![statement])
+    }
+
+    /// The test of an `if`, or `None` for a condition that never holds. OpenQASM 2.0 can
+    /// only compare a whole classical register.
+    fn condition(&self, condition: &Condition, gate: &Gate) -> Result<Option<String>, QuojoError> {
+        let register = self
+            .classical_registers
+            .iter()
+            .find(|(_, clbits)| clbits.clone().eq(condition.clbits.iter().copied()));
+        if let Some((name, _)) = register {
+            return Ok(Some(format!("{} == {}", name, condition.value)));
+        }
+        if self.dialect == Dialect::Qasm2 {
+            return Err(self.unsupported(gate));
+        }
+        if condition
+            .value
+            .checked_shr(condition.clbits.len() as u32)
+            .unwrap_or(0)
+            != 0
+        {
+            return Ok(None);
+        }
+        let tests: Vec<String> = condition
+            .clbits
+            .iter()
+            .enumerate()
+            .map(|(i, &clbit)| {
+                let bit = condition.value.checked_shr(i as u32).unwrap_or(0) & 1;
+                format!("{} == {}", self.clbit_names[clbit], bit)
+            })
+            .collect();
+        Ok(Some(tests.join(" && ")))
+    }
+
+    fn unsupported(&self, gate: &Gate) -> QuojoError {
+        QuojoError::UnsupportedExport {
+            gate: gate.clone(),
+            format: "OpenQASM 2.0".to_string(),
+        }
+    }
+
+    /// The gate calls for a unitary gate, using a definition where there is no standard name.
+    fn calls(&mut self, gate: &Gate, wire: usize) -> Vec<Call> {
+        let qasm3 = self.dialect == Dialect::Qasm3;
+        let single = |name: &str| vec![Call::new(name, &[], &[wire])];
+        let rotation = |name: &str, theta: &Phase| vec![Call::new(name, &[theta], &[wire])];
+        let pair =
+            |name: &str, first: usize, second: usize| vec![Call::new(name, &[], &[first, second])];
+        let controlled = |name: &str, theta: &Phase, control: usize, target: usize| {
+            vec![Call::new(name, &[theta], &[control, target])]
+        };
+
+        match gate {
+            Gate::X => single("x"),
+            Gate::Y => single("y"),
+            Gate::Z => single("z"),
+            Gate::H => single("h"),
+            Gate::S => single("s"),
+            Gate::Sdg => single("sdg"),
+            Gate::T => single("t"),
+            Gate::Tdg => single("tdg"),
+            Gate::SX => single("sx"),
+            Gate::P(phase) => rotation(if qasm3 { "p" } else { "u1" }, phase),
+            Gate::Rx(theta) => rotation("rx", theta),
+            Gate::Ry(theta) => rotation("ry", theta),
+            Gate::Rz(theta) => rotation("rz", theta),
+            // OpenQASM 3.0's built-in U is exactly U3, unlike stdgates.inc's u3
+            Gate::U3 { theta, phi, lambda } => {
+                let name = if qasm3 { "U" } else { "u3" };
+                vec![Call::new(name, &[theta, phi, lambda], &[wire])]
+            }
+            Gate::GlobalPhase(phase) if qasm3 => vec![Call::new("gphase", &[phase], &[])],
+            Gate::GlobalPhase(phase) => {
+                self.required.insert("gphase");
+                rotation("gphase", phase)
+            }
+            Gate::CNOT { control, target } => pair("cx", *control, *target),
+            Gate::CZ { control, target } => pair("cz", *control, *target),
+            Gate::CP {
+                control,
+                target,
+                phase,
+            } => controlled(if qasm3 { "cp" } else { "cu1" }, phase, *control, *target),
+            Gate::CRx {
+                control,
+                target,
+                theta,
+            } => controlled("crx", theta, *control, *target),
+            Gate::CRy {
+                control,
+                target,
+                theta,
+            } => controlled("cry", theta, *control, *target),
+            Gate::CRz {
+                control,
+                target,
+                theta,
+            } => controlled("crz", theta, *control, *target),
+            Gate::SWAP { qubit1, qubit2 } => pair("swap", *qubit1, *qubit2),
+            Gate::RXX {
+                qubit1,
+                qubit2,
+                theta,
+            }
+            | Gate::RYY {
+                qubit1,
+                qubit2,
+                theta,
+            }
+            | Gate::RZZ {
+                qubit1,
+                qubit2,
+                theta,
+            } => {
+                let name = match gate {
+                    Gate::RXX { .. } => "rxx",
+                    Gate::RYY { .. } => "ryy",
+                    _ => "rzz",
+                };
+                if qasm3 || name == "ryy" {
+                    self.required.insert(name);
+                }
+                vec![Call::new(name, &[theta], &[*qubit1, *qubit2])]
+            }
+            Gate::ISWAP { qubit1, qubit2 } => {
+                self.required.insert("iswap");
+                pair("iswap", *qubit1, *qubit2)
+            }
+            Gate::SqrtISWAP { qubit1, qubit2 } => {
+                self.required.extend(["rxx", "ryy", "siswap"]);
+                if !qasm3 {
+                    self.required.remove("rxx");
+                }
+                pair("siswap", *qubit1, *qubit2)
+            }
+            Gate::ECR { qubit1, qubit2 } => {
+                self.required.insert("ecr");
+                pair("ecr", *qubit1, *qubit2)
+            }
+            Gate::Toffoli {
+                control1,
+                control2,
+                target,
+            } => vec![Call::new("ccx", &[], &[*control1, *control2, *target])],
+            Gate::Fredkin {
+                control,
+                target1,
+                target2,
+            } => vec![Call::new("cswap", &[], &[*control, *target1, *target2])],
+            Gate::Unitary(unitary) => {
+                let name = self.unitary_definition(unitary);
+                vec![Call::new(&name, &[], &unitary.qubits)]
+            }
+            Gate::Controlled(controlled) if qasm3 => vec![self.modified(controlled)],
+            Gate::Controlled(controlled) => self.controlled(controlled),
+            Gate::Measure { .. } | Gate::Reset | Gate::Conditional(_) => {
+                unreachable!("{} is not unitary", gate)
+            }
+        }
+    }
+
+    /// An OpenQASM 3.0 controlled gate, as the inner gate with `ctrl @` and `negctrl @`.
+    fn modified(&mut self, controlled: &ControlledGate) -> Call {
+        let calls = self.calls(&controlled.gate, controlled.target);
+        let [inner] = calls.as_slice() else {
+            unreachable!("OpenQASM 3.0 writes every unitary gate as one call")
+        };
+        let modifier = |name: &str, count: usize| match count {
+            0 => String::new(),
+            1 => format!("{} @ ", name),
+            _ => format!("{}({}) @ ", name, count),
+        };
+        Call {
+            name: modifier("ctrl", controlled.controls.len())
+                + &modifier("negctrl", controlled.open_controls.len())
+                + &inner.name,
+            parameters: inner.parameters.clone(),
+            qubits: [
+                &controlled.controls[..],
+                &controlled.open_controls,
+                &inner.qubits,
+            ]
+            .concat(),
+        }
+    }
+
+    /// An OpenQASM 2.0 controlled gate, with open controls flipped by X around it. The
+    /// qelib1.inc gate is used where there is one, and a synthesis from simpler gates
+    /// otherwise.
+    fn controlled(&mut self, controlled: &ControlledGate) -> Vec<Call> {
+        let flips = || {
+            controlled
+                .open_controls
+                .iter()
+                .map(|&qubit| Call::new("x", &[], &[qubit]))
+        };
+        let controls = [&controlled.controls[..], &controlled.open_controls].concat();
+        let gate = &controlled.gate;
+        let target = controlled.target;
+
+        let (name, parameters): (&str, Vec<&Phase>) = match (gate, controls.len()) {
+            (Gate::X, 1) => ("cx", vec![]),
+            (Gate::X, 2) => ("ccx", vec![]),
+            (Gate::X, 3) => ("c3x", vec![]),
+            (Gate::X, 4) => ("c4x", vec![]),
+            (Gate::Y, 1) => ("cy", vec![]),
+            (Gate::Z, 1) => ("cz", vec![]),
+            (Gate::H, 1) => ("ch", vec![]),
+            (Gate::SX, 1) => ("csx", vec![]),
+            (Gate::SX, 3) => ("c3sqrtx", vec![]),
+            (Gate::P(phase), 1) => ("cu1", vec![phase]),
+            (Gate::Rx(theta), 1) => ("crx", vec![theta]),
+            (Gate::Ry(theta), 1) => ("cry", vec![theta]),
+            (Gate::Rz(theta), 1) => ("crz", vec![theta]),
+            (Gate::U3 { theta, phi, lambda }, 1) => ("cu3", vec![theta, phi, lambda]),
+            (Gate::SWAP { .. }, 1) => ("cswap", vec![]),
+            _ => ("", vec![]),
+        };
+
+        let mut calls: Vec<Call> = flips().collect();
+        if name.is_empty() {
+            let synthesis = synthesize_controlled_gate(&controls, target, gate);
+            for instruction in &synthesis.instructions {
+                calls.extend(self.calls(&instruction.gate, instruction.qubits[0]));
+            }
+            let phase = Phase::from_radians(synthesis.phase);
+            if !phase.is_zero() {
+                calls.extend(self.calls(&Gate::GlobalPhase(phase), target));
+            }
+        } else {
+            let qubits = [controls, gate.involved_qubits(target)].concat();
+            calls.push(Call::new(name, &parameters, &qubits));
+        }
+        calls.extend(flips());
+        calls
+    }
+
+    /// The name of a gate defined by the synthesis of `unitary`, added on first use.
+    fn unitary_definition(&mut self, unitary: &UnitaryGate) -> String {
+        let same = |(known, _, _): &&(UnitaryGate, String, String)| {
+            known.label == unitary.label && known.matrix() == unitary.matrix()
+        };
+        if let Some((_, name, _)) = self.unitaries.iter().find(same) {
+            return name.clone();
+        }
+
+        let width = unitary.qubits.len();
+        let local: Vec<usize> = (0..width).collect();
+        let names: Vec<String> = (0..width).map(|i| format!("q{}", i)).collect();
+        let synthesis = synthesize_unitary(unitary.matrix(), &local);
+        let mut calls = Vec::new();
+        for instruction in &synthesis.instructions {
+            calls.extend(self.calls(&instruction.gate, instruction.qubits[0]));
+        }
+        let phase = Phase::from_radians(synthesis.phase);
+        if !phase.is_zero() {
+            calls.extend(self.calls(&Gate::GlobalPhase(phase), 0));
+        }
+
+        let name = format!("unitary{}", self.unitaries.len());
+        let body: Vec<String> = calls.iter().map(|call| call.render(&names)).collect();
+        let definition = format!(
+            "gate {} {} {{ {} }}",
+            name,
+            names.join(", "),
+            body.join(" ")
+        );
+        self.unitaries
+            .push((unitary.clone(), name.clone(), definition));
+        name
+    }
+}
+
+/// Registers covering bits `0..count` in order: the named ones, and fresh names from
+/// `prefix` for any bits between them.
+fn layout(
+    named: impl Iterator<Item = (String, Range<usize>)>,
+    count: usize,
+    prefix: &str,
+    taken: &mut BTreeSet<String>,
+) -> Vec<(String, Range<usize>)> {
+    let mut named: Vec<(String, Range<usize>)> = named.collect();
+    named.sort_by_key(|(_, bits)| bits.start);
+    let mut fresh = |bits: Range<usize>| {
+        let name = (0..)
+            .map(|i| match i {
+                0 => prefix.to_string(),
+                _ => format!("{}{}", prefix, i),
+            })
+            .find(|name| !taken.contains(name))
+            .expect("some name is free");
+        taken.insert(name.clone());
+        (name, bits)
+    };
+
+    let mut registers = Vec::new();
+    let mut next = 0;
+    for (name, bits) in named {
+        if bits.start > next {
+            registers.push(fresh(next..bits.start));
+        }
+        next = bits.end;
+        registers.push((name, bits));
+    }
+    if count > next {
+        registers.push(fresh(next..count));
+    }
+    registers
+}
+
+/// An angle in radians, written as a fraction of π when it is one. Constant angles use
+/// their representative in (−π, π].
+fn angle(phase: &Phase) -> String {
+    match (phase.as_rational(), phase.as_float()) {
+        (Some((numerator, denominator)), _) => {
+            let numerator = if numerator > denominator {
+                numerator - 2 * denominator
+            } else {
+                numerator
+            };
+            let pi = match numerator.abs() {
+                0 => return "0".to_string(),
+                1 => "pi".to_string(),
+                multiple => format!("{}*pi", multiple),
+            };
+            let sign = if numerator < 0 { "-" } else { "" };
+            match denominator {
+                1 => format!("{}{}", sign, pi),
+                _ => format!("{}{}/{}", sign, pi, denominator),
+            }
+        }
+        (_, Some(radians)) if radians > PI => format!("{}", radians - 2.0 * PI),
+        (_, Some(radians)) => format!("{}", radians),
+        (None, None) => {
+            let mut text = String::new();
+            for (name, coefficient) in phase.terms() {
+                let (sign, magnitude) = if coefficient < 0.0 {
+                    ("-", -coefficient)
+                } else {
+                    ("+", coefficient)
+                };
+                match (text.is_empty(), sign) {
+                    (true, "+") => {}
+                    (true, _) => text.push('-'),
+                    (false, _) => text += &format!(" {} ", sign),
+                }
+                if magnitude != 1.0 {
+                    text += &format!("{}*", magnitude);
+                }
+                text += name;
+            }
+            let constant = phase.constant();
+            if !constant.is_zero() {
+                let constant = angle(&constant);
+                match constant.strip_prefix('-') {
+                    Some(magnitude) => text += &format!(" - {}", magnitude),
+                    None => text += &format!(" + {}", constant),
+                }
+            }
+            text
+        }
+    }
+}
@@ -0,0 +1,750 @@
+use crate::linalg::vector::Complex;
+use crate::qcore::dag::Instruction;
+use crate::qcore::gates::Gate;
+use crate::qcore::phase::Phase;
+use crate::sim::statevector::{Matrix2, single_qubit_matrix};
+use std::f64::consts::FRAC_PI_2;
+
+/// Entries below this are treated as zero when choosing rotations.
+const EPSILON: f64 = 1e-12;
+
+/// Gates implementing a unitary up to a global phase: the gates times e^(i·phase) equal
+/// the unitary.
+#[derive(Debug, Clone, Default)]
+pub struct Synthesis {
+    pub instructions: Vec<Instruction>,
+    /// Global phase in radians.
+    pub phase: f64,
+}
+
+impl Synthesis {
+    fn push(&mut self, gate: Gate, wire: usize) {
+        self.instructions.push(Instruction::new(gate, wire));
+    }
+
+    /// Appends a P gate unless its angle is a multiple of 2π.
+    fn push_phase(&mut self, radians: f64, wire: usize) {
+        let phase = Phase::from(radians);
+        if !phase.is_zero() {
+            self.push(Gate::P(phase), wire);
+        }
+    }
+
+    fn extend(&mut self, other: Synthesis) {
+        self.instructions.extend(other.instructions);
+        self.phase += other.phase;
+    }
+}
+
+/// Synthesises a 2^k × 2^k unitary on `qubits` from H, P, CNOT, CZ and Toffoli gates. Bit i
+/// of a matrix index is the state of `qubits[i]`.
+///
+/// A single qubit takes at most three P gates. Larger unitaries are reduced to the identity
+/// by two-level Givens rotations, each implemented as a Gray-code walk of multi-controlled
+/// X gates around one fully controlled single-qubit gate, so the gate count grows as
+/// 4^k times the cost of a (k-1)-controlled gate.
+pub fn synthesize_unitary(matrix: &[Vec<Complex>], qubits: &[usize]) -> Synthesis {
+    if let ([qubit], [row0, row1]) = (qubits, matrix) {
+        return synthesize_single(&[[row0[0], row0[1]], [row1[0], row1[1]]], *qubit);
+    }
+
+    let mut remaining = matrix.to_vec();
+    let mut rotations = Vec::new();
+
+    for column in 0..remaining.len() {
+        for row in column + 1..remaining.len() {
+            let b = remaining[row][column];
+            if b.abs() < EPSILON {
+                continue;
+            }
+            let a = remaining[column][column];
+            let norm = (a.norm_sqr() + b.norm_sqr()).sqrt();
+            let rotation = [
+                [a.conj() * (1.0 / norm), b.conj() * (1.0 / norm)],
+                [b * (-1.0 / norm), a * (1.0 / norm)],
+            ];
+            apply_two_level(&mut remaining, column, row, &rotation);
+            rotations.push((column, row, rotation));
+        }
+
+        // The diagonal entry is now a phase, which only a rotation with no other entries
+        // to clear leaves behind
+        let angle = arg(remaining[column][column]);
+        if angle.abs() > EPSILON {
+            let correction = [[Complex::from_phase(-angle), ZERO], [ZERO, ONE]];
+            apply_two_level(&mut remaining, column, column ^ 1, &correction);
+            rotations.push((column, column ^ 1, correction));
+        }
+    }
+
+    // The rotations take U to I, so U is their inverses in reverse order
+    let mut synthesis = Synthesis::default();
+    for (s, t, rotation) in rotations.into_iter().rev() {
+        synthesis.extend(two_level(qubits, s, t, &dagger(&rotation)));
+    }
+    synthesis
+}
+
+/// Synthesises `u` on `target` controlled on every qubit in `controls` being |1⟩.
+///
+/// Diagonal gates become controlled phases. Otherwise U = e^(iα)·A·X·B·X·C with A·B·C = I,
+/// so C^n(U) is C, C^n(X), B, C^n(X), A and a phase of α on the controls.
+pub fn synthesize_controlled(controls: &[usize], target: usize, u: &Matrix2) -> Synthesis {
+    if controls.is_empty() {
+        return synthesize_single(u, target);
+    }
+    if is_close(u, &single_qubit_matrix(&Gate::X)) {
+        return controlled_x(controls, target);
+    }
+
+    let mut synthesis = Synthesis::default();
+    let (alpha, beta, gamma, delta) = euler_angles(u);
+
+    if u[0][1].abs() < EPSILON && u[1][0].abs() < EPSILON {
+        let (low, high) = (arg(u[0][0]), arg(u[1][1]));
+        synthesis.extend(controlled_phase(controls, &Phase::from(low)));
+        let with_target = [controls, &[target]].concat();
+        synthesis.extend(controlled_phase(&with_target, &Phase::from(high - low)));
+        return synthesis;
+    }
+
+    let a = multiply(&rz(beta), &ry(gamma / 2.0));
+    let b = multiply(&ry(-gamma / 2.0), &rz(-(delta + beta) / 2.0));
+    let c = rz((delta - beta) / 2.0);
+
+    synthesis.extend(synthesize_single(&c, target));
+    synthesis.extend(controlled_x(controls, target));
+    synthesis.extend(synthesize_single(&b, target));
+    synthesis.extend(controlled_x(controls, target));
+    synthesis.extend(synthesize_single(&a, target));
+    synthesis.extend(controlled_phase(controls, &Phase::from(alpha)));
+    synthesis
+}
+
+/// Synthesises `gate`, as stored on `wire`, controlled on every qubit in `controls` being
+/// |1⟩. Single-qubit gates and global phases without controls are returned as they are;
+/// everything else uses only H, P, X, CNOT, CZ and Toffoli gates, so it has a ZX form.
+/// Panics for measurements, resets and conditional gates.
+pub fn synthesize_controlled_gate(controls: &[usize], wire: usize, gate: &Gate) -> Synthesis {
+    let with = |extra: &[usize]| [controls, extra].concat();
+    let mut synthesis = Synthesis::default();
+    if controls.is_empty() && gate.takes_wire() && gate.is_unitary() {
+        synthesis.push(gate.clone(), wire);
+        return synthesis;
+    }
+
+    match gate {
+        Gate::X => return controlled_x(controls, wire),
+        Gate::Z => return controlled_phase(&with(&[wire]), &Phase::PI),
+        Gate::P(phase) => return controlled_phase(&with(&[wire]), phase),
+        Gate::GlobalPhase(phase) => return controlled_phase(controls, phase),
+        Gate::Y | Gate::H => {
+            return synthesize_controlled(controls, wire, &single_qubit_matrix(gate));
+        }
+        Gate::CNOT { control, target } => return controlled_x(&with(&[*control]), *target),
+        Gate::CZ { control, target } => {
+            return controlled_phase(&with(&[*control, *target]), &Phase::PI);
+        }
+        Gate::CP {
+            control,
+            target,
+            phase,
+        } => {
+            return controlled_phase(&with(&[*control, *target]), phase);
+        }
+        Gate::Toffoli {
+            control1,
+            control2,
+            target,
+        } => return controlled_x(&with(&[*control1, *control2]), *target),
+        Gate::SWAP { qubit1, qubit2 } => {
+            return controlled_swap(controls, *qubit1, *qubit2);
+        }
+        Gate::Fredkin {
+            control,
+            target1,
+            target2,
+        } => return controlled_swap(&with(&[*control]), *target1, *target2),
+        Gate::Unitary(unitary) => {
+            let inner = synthesize_unitary(unitary.matrix(), &unitary.qubits);
+            for instruction in &inner.instructions {
+                synthesis.extend(synthesize_controlled_gate(
+                    controls,
+                    instruction.qubits[0],
+                    &instruction.gate,
+                ));
+            }
+            synthesis.extend(controlled_phase(controls, &Phase::from(inner.phase)));
+        }
+        Gate::Controlled(controlled) => {
+            for &qubit in &controlled.open_controls {
+                synthesis.push(Gate::X, qubit);
+            }
+            let all = [controls, &controlled.controls, &controlled.open_controls].concat();
+            synthesis.extend(synthesize_controlled_gate(
+                &all,
+                controlled.target,
+                &controlled.gate,
+            ));
+            for &qubit in &controlled.open_controls {
+                synthesis.push(Gate::X, qubit);
+            }
+        }
+        Gate::Measure { .. } | Gate::Reset | Gate::Conditional(_) => {
+            panic!("{} is not unitary", gate)
+        }
+        _ => {
+            let Some(factored) = factor(gate, wire) else {
+                unreachable!("{} has no factored form", gate)
+            };
+            synthesis.instructions.extend(factored.before);
+            for instruction in &factored.core {
+                synthesis.extend(synthesize_controlled_gate(
+                    controls,
+                    instruction.qubits[0],
+                    &instruction.gate,
+                ));
+            }
+            synthesis.instructions.extend(factored.after);
+        }
+    }
+
+    synthesis
+}
+
+/// `gate`, as stored on `wire`, one step closer to X, Z, H, P, CNOT and CZ gates, with its
+/// global phase kept as a `GlobalPhase` gate. Rotations become P and H gates, the two-qubit
+/// gates CNOT, CZ, CP, SWAP and RZZ gates, and Toffoli a phase polynomial. Gates with
+/// nothing simpler are returned as they are, and the rest are synthesised without
+/// controls. Panics for measurements, resets and conditional gates.
+pub fn expand(gate: &Gate, wire: usize) -> Synthesis {
+    let Some(factored) = factor(gate, wire) else {
+        return synthesize_controlled_gate(&[], wire, gate);
+    };
+    let mut synthesis = Synthesis::default();
+    synthesis.instructions.extend(factored.before);
+    synthesis.instructions.extend(factored.core);
+    synthesis.instructions.extend(factored.after);
+    synthesis
+}
+
+/// A gate as `before`, `core` and `after`, where `after` undoes `before`. Controlling the
+/// gate only needs controls on `core`, as `before` and `after` cancel when a control is off.
+struct Factored {
+    before: Vec<Instruction>,
+    core: Vec<Instruction>,
+    after: Vec<Instruction>,
+}
+
+/// The decompositions shared by `expand` and `synthesize_controlled_gate`.
+fn factor(gate: &Gate, wire: usize) -> Option<Factored> {
+    let on = Instruction::new;
+    let s = |sign: i64| Gate::P(Phase::new(sign, 2));
+    let (before, core, after) = match gate {
+        // Y = i·X·Z
+        Gate::Y => (
+            vec![],
+            vec![
+                on(Gate::Z, wire),
+                on(Gate::X, wire),
+                on(Gate::GlobalPhase(Phase::new(1, 2)), wire),
+            ],
+            vec![],
+        ),
+        Gate::S => (vec![], vec![on(s(1), wire)], vec![]),
+        Gate::Sdg => (vec![], vec![on(s(-1), wire)], vec![]),
+        Gate::T => (vec![], vec![on(Gate::P(Phase::new(1, 4)), wire)], vec![]),
+        Gate::Tdg => (vec![], vec![on(Gate::P(Phase::new(-1, 4)), wire)], vec![]),
+        // √X = H·S·H exactly
+        Gate::SX => (
+            vec![on(Gate::H, wire)],
+            vec![on(s(1), wire)],
+            vec![on(Gate::H, wire)],
+        ),
+        // Rz(θ) = e^(-iθ/2)·P(θ)
+        Gate::Rz(theta) => (
+            vec![],
+            vec![
+                on(Gate::GlobalPhase(-theta.half()), wire),
+                on(Gate::P(theta.clone()), wire),
+            ],
+            vec![],
+        ),
+        // Rx(θ) = H·Rz(θ)·H
+        Gate::Rx(theta) => (
+            vec![on(Gate::H, wire)],
+            vec![on(Gate::Rz(theta.clone()), wire)],
+            vec![on(Gate::H, wire)],
+        ),
+        // Ry(θ) = S·Rx(θ)·S†
+        Gate::Ry(theta) => (
+            vec![on(s(-1), wire)],
+            vec![on(Gate::Rx(theta.clone()), wire)],
+            vec![on(s(1), wire)],
+        ),
+        // U3(θ, φ, λ) = P(φ)·Ry(θ)·P(λ)
+        Gate::U3 { theta, phi, lambda } => (
+            vec![],
+            vec![
+                on(Gate::P(lambda.clone()), wire),
+                on(Gate::Ry(theta.clone()), wire),
+                on(Gate::P(phi.clone()), wire),
+            ],
+            vec![],
+        ),
+        Gate::CP {
+            control,
+            target,
+            phase,
+        } => (
+            vec![],
+            phase_polynomial(&[*control, *target], phase).instructions,
+            vec![],
+        ),
+        // CRz(θ) = P(-θ/2) on the control · CP(θ); CRx and CRy change the target's basis
+        Gate::CRz {
+            control,
+            target,
+            theta,
+        } => (
+            vec![],
+            vec![
+                on(Gate::P(-theta.half()), *control),
+                on(
+                    Gate::CP {
+                        control: *control,
+                        target: *target,
+                        phase: theta.clone(),
+                    },
+                    *control,
+                ),
+            ],
+            vec![],
+        ),
+        Gate::CRx {
+            control,
+            target,
+            theta,
+        } => (
+            vec![on(Gate::H, *target)],
+            vec![on(
+                Gate::CRz {
+                    control: *control,
+                    target: *target,
+                    theta: theta.clone(),
+                },
+                *control,
+            )],
+            vec![on(Gate::H, *target)],
+        ),
+        Gate::CRy {
+            control,
+            target,
+            theta,
+        } => (
+            vec![on(s(-1), *target)],
+            vec![on(
+                Gate::CRx {
+                    control: *control,
+                    target: *target,
+                    theta: theta.clone(),
+                },
+                *control,
+            )],
+            vec![on(s(1), *target)],
+        ),
+        // SWAP = 3 consecutive CNOTs
+        Gate::SWAP { qubit1, qubit2 } => {
+            let cnot = |control: usize, target: usize| on(Gate::CNOT { control, target }, control);
+            (
+                vec![],
+                vec![
+                    cnot(*qubit1, *qubit2),
+                    cnot(*qubit2, *qubit1),
+                    cnot(*qubit1, *qubit2),
+                ],
+                vec![],
+            )
+        }
+        // RZZ(θ) = e^(-iθ/2)·CNOT·P(θ)·CNOT, which puts the phase on the parity
+        Gate::RZZ {
+            qubit1,
+            qubit2,
+            theta,
+        } => {
+            let cnot = on(
+                Gate::CNOT {
+                    control: *qubit1,
+                    target: *qubit2,
+                },
+                *qubit1,
+            );
+            (
+                vec![cnot.clone()],
+                vec![
+                    on(Gate::GlobalPhase(-theta.half()), *qubit1),
+                    on(Gate::P(theta.clone()), *qubit2),
+                ],
+                vec![cnot],
+            )
+        }
+        // RXX is RZZ in the X basis, and RYY is RXX with S† before and S after
+        Gate::RXX {
+            qubit1,
+            qubit2,
+            theta,
+        }
+        | Gate::RYY {
+            qubit1,
+            qubit2,
+            theta,
+        } => {
+            let (basis, undo, inner) = match gate {
+                Gate::RXX { .. } => (
+                    Gate::H,
+                    Gate::H,
+                    Gate::RZZ {
+                        qubit1: *qubit1,
+                        qubit2: *qubit2,
+                        theta: theta.clone(),
+                    },
+                ),
+                _ => (
+                    s(-1),
+                    s(1),
+                    Gate::RXX {
+                        qubit1: *qubit1,
+                        qubit2: *qubit2,
+                        theta: theta.clone(),
+                    },
+                ),
+            };
+            (
+                vec![on(basis.clone(), *qubit1), on(basis, *qubit2)],
+                vec![on(inner, *qubit1)],
+                vec![on(undo.clone(), *qubit1), on(undo, *qubit2)],
+            )
+        }
+        // iSWAP = SWAP·CZ·(S ⊗ S)
+        Gate::ISWAP { qubit1, qubit2 } => (
+            vec![],
+            vec![
+                on(s(1), *qubit1),
+                on(s(1), *qubit2),
+                on(
+                    Gate::CZ {
+                        control: *qubit1,
+                        target: *qubit2,
+                    },
+                    *qubit1,
+                ),
+                on(
+                    Gate::SWAP {
+                        qubit1: *qubit1,
+                        qubit2: *qubit2,
+                    },
+                    *qubit1,
+                ),
+            ],
+            vec![],
+        ),
+        // √iSWAP = e^(iπ/8·(X⊗X + Y⊗Y)) = RXX(-π/4)·RYY(-π/4), which commute
+        Gate::SqrtISWAP { qubit1, qubit2 } => (
+            vec![],
+            vec![
+                on(
+                    Gate::RXX {
+                        qubit1: *qubit1,
+                        qubit2: *qubit2,
+                        theta: Phase::new(-1, 4),
+                    },
+                    *qubit1,
+                ),
+                on(
+                    Gate::RYY {
+                        qubit1: *qubit1,
+                        qubit2: *qubit2,
+                        theta: Phase::new(-1, 4),
+                    },
+                    *qubit1,
+                ),
+            ],
+            vec![],
+        ),
+        // ECR = RZX(-π/2)·(X ⊗ I), with RZX the RZZ conjugated by H on qubit2
+        Gate::ECR { qubit1, qubit2 } => (
+            vec![on(Gate::H, *qubit2)],
+            vec![
+                on(Gate::X, *qubit1),
+                on(
+                    Gate::RZZ {
+                        qubit1: *qubit1,
+                        qubit2: *qubit2,
+                        theta: Phase::new(-1, 2),
+                    },
+                    *qubit1,
+                ),
+            ],
+            vec![on(Gate::H, *qubit2)],
+        ),
+        // CCX = H·CCZ·H on the target, with CCZ the phase polynomial of 7 T gates
+        Gate::Toffoli {
+            control1,
+            control2,
+            target,
+        } => (
+            vec![on(Gate::H, *target)],
+            phase_polynomial(&[*control1, *control2, *target], &Phase::PI).instructions,
+            vec![on(Gate::H, *target)],
+        ),
+        _ => return None,
+    };
+    Some(Factored {
+        before,
+        core,
+        after,
+    })
+}
+
+/// X on `target` controlled on `controls`. Beyond two controls this is H·C^n(Z)·H.
+fn controlled_x(controls: &[usize], target: usize) -> Synthesis {
+    let mut synthesis = Synthesis::default();
+    match *controls {
+        [] => synthesis.push(Gate::X, target),
+        [control] => synthesis.push(Gate::CNOT { control, target }, control),
+        [control1, control2] => synthesis.push(
+            Gate::Toffoli {
+                control1,
+                control2,
+                target,
+            },
+            control1,
+        ),
+        _ => {
+            synthesis.push(Gate::H, target);
+            let with_target = [controls, &[target]].concat();
+            synthesis.extend(controlled_phase(&with_target, &Phase::PI));
+            synthesis.push(Gate::H, target);
+        }
+    }
+    synthesis
+}
+
+/// SWAP of `qubit1` and `qubit2` controlled on `controls`, as CNOT · C^(n+1)(X) · CNOT.
+fn controlled_swap(controls: &[usize], qubit1: usize, qubit2: usize) -> Synthesis {
+    let mut synthesis = Synthesis::default();
+    if controls.is_empty() {
+        synthesis.push(Gate::SWAP { qubit1, qubit2 }, qubit1);
+        return synthesis;
+    }
+
+    let cnot = Gate::CNOT {
+        control: qubit2,
+        target: qubit1,
+    };
+    synthesis.push(cnot.clone(), qubit2);
+    synthesis.extend(controlled_x(&[controls, &[qubit1]].concat(), qubit2));
+    synthesis.push(cnot, qubit2);
+    synthesis
+}
+
+/// The phase e^(iθ) on the state where every qubit in `qubits` is |1⟩: a P gate on one
+/// qubit, a CZ for θ = π on two and a phase polynomial otherwise. With no qubits this is a
+/// global phase.
+fn controlled_phase(qubits: &[usize], theta: &Phase) -> Synthesis {
+    let mut synthesis = Synthesis::default();
+    match qubits {
+        _ if theta.is_zero() => {}
+        [] => synthesis.phase = theta.to_radians(),
+        [qubit] => synthesis.push(Gate::P(theta.clone()), *qubit),
+        [control, target] if *theta == Phase::PI => synthesis.push(
+            Gate::CZ {
+                control: *control,
+                target: *target,
+            },
+            *control,
+        ),
+        _ => return phase_polynomial(qubits, theta),
+    }
+    synthesis
+}
+
+/// The phase e^(iθ) on the state where every qubit in `qubits` is |1⟩, from P and CNOT gates
+/// without ancillas.
+///
+/// x₁·…·xₙ is Σ_S (−1)^(|S|+1)·parity(S) / 2^(n−1) over the non-empty subsets S, so each
+/// subset gets P(±θ/2^(n−1)) on its parity. The subsets whose last member is qubit k are
+/// visited in Gray-code order of the qubits before it, so each step is one CNOT into k and
+/// one more CNOT restores it: 2^n − 1 phases and about 2^n CNOTs.
+fn phase_polynomial(qubits: &[usize], theta: &Phase) -> Synthesis {
+    let mut synthesis = Synthesis::default();
+    let share = theta.clone() / (1i64 << qubits.len().saturating_sub(1));
+    let phase = |members: u32| {
+        let sign = if members % 2 == 1 { 1 } else { -1 };
+        Gate::P(share.clone() * sign)
+    };
+    let cnot = |control: usize, target: usize| Gate::CNOT { control, target };
+
+    for (k, &last) in qubits.iter().enumerate() {
+        synthesis.push(phase(1), last);
+        for step in 1usize..1 << k {
+            let (gray, previous) = (step ^ step >> 1, (step - 1) ^ (step - 1) >> 1);
+            let flipped = (gray ^ previous).trailing_zeros() as usize;
+            synthesis.push(cnot(qubits[flipped], last), qubits[flipped]);
+            synthesis.push(phase(gray.count_ones() + 1), last);
+        }
+        if k > 0 {
+            // The last Gray code has only bit k − 1 set
+            synthesis.push(cnot(qubits[k - 1], last), qubits[k - 1]);
+        }
+    }
+    synthesis
+}
+
+/// Angles (α, β, γ, δ) with U = e^(iα)·Rz(β)·Ry(γ)·Rz(δ).
+pub fn euler_angles(u: &Matrix2) -> (f64, f64, f64, f64) {
+    let determinant = u[0][0] * u[1][1] - u[0][1] * u[1][0];
+    let alpha = arg(determinant) / 2.0;
+    // e^(-iα)·U is in SU(2), so it is [[a, -b*], [b, a*]]
+    let unphase = Complex::from_phase(-alpha);
+    let (a, b) = (u[0][0] * unphase, u[1][0] * unphase);
+
+    let gamma = 2.0 * b.abs().atan2(a.abs());
+    let (beta, delta) = if b.abs() < EPSILON {
+        (-2.0 * arg(a), 0.0)
+    } else if a.abs() < EPSILON {
+        (2.0 * arg(b), 0.0)
+    } else {
+        let (sum, difference) = (-2.0 * arg(a), 2.0 * arg(b));
+        ((sum + difference) / 2.0, (sum - difference) / 2.0)
+    };
+
+    (alpha, beta, gamma, delta)
+}
+
+/// U = e^(iα)·Rz(β)·S·H·Rz(γ)·H·S†·Rz(δ), and Rz(θ) is P(θ) up to phase, so U is at most
+/// P, H, P, H, P.
+fn synthesize_single(u: &Matrix2, target: usize) -> Synthesis {
+    let (alpha, beta, gamma, delta) = euler_angles(u);
+    let mut synthesis = Synthesis::default();
+
+    if (gamma / 2.0).sin().abs() < EPSILON {
+        synthesis.push_phase(beta + delta, target);
+        synthesis.phase = alpha - (beta + delta) / 2.0;
+    } else {
+        synthesis.push_phase(delta - FRAC_PI_2, target);
+        synthesis.push(Gate::H, target);
+        synthesis.push_phase(gamma, target);
+        synthesis.push(Gate::H, target);
+        synthesis.push_phase(beta + FRAC_PI_2, target);
+        synthesis.phase = alpha - (beta + gamma + delta) / 2.0;
+    }
+
+    synthesis
+}
+
+/// Implements `u` on the span of basis states `s` and `t`, with |s⟩ as its first basis
+/// vector. A Gray-code walk from `s` brings |s⟩ next to |t⟩, `u` is applied on the one bit
+/// where they differ with every other bit as a control, and the walk is undone.
+fn two_level(qubits: &[usize], s: usize, t: usize, u: &Matrix2) -> Synthesis {
+    let mut path = vec![s];
+    for bit in 0..qubits.len() {
+        if (s ^ t) >> bit & 1 == 1 {
+            path.push(path[path.len() - 1] ^ 1 << bit);
+        }
+    }
+
+    let x = single_qubit_matrix(&Gate::X);
+    let steps: Vec<Synthesis> = path
+        .windows(2)
+        .take(path.len() - 2)
+        .map(|pair| controlled_on(qubits, pair[0], pair[0] ^ pair[1], &x))
+        .collect();
+
+    let mut synthesis = Synthesis::default();
+    for step in &steps {
+        synthesis.extend(step.clone());
+    }
+
+    let (before, last) = (path[path.len() - 2], path[path.len() - 1]);
+    let u = if before & (before ^ last) == 0 {
+        *u
+    } else {
+        multiply(&x, &multiply(u, &x))
+    };
+    synthesis.extend(controlled_on(qubits, before, before ^ last, &u));
+
+    for step in steps.into_iter().rev() {
+        synthesis.extend(step);
+    }
+    synthesis
+}
+
+/// Applies `u` to the qubit of the single bit in `target_mask`, controlled on every other
+/// qubit matching its bit in `state`. Controls on |0⟩ are conjugated with X.
+fn controlled_on(qubits: &[usize], state: usize, target_mask: usize, u: &Matrix2) -> Synthesis {
+    let target_bit = target_mask.trailing_zeros() as usize;
+    let controls: Vec<usize> = (0..qubits.len())
+        .filter(|&bit| bit != target_bit)
+        .map(|bit| qubits[bit])
+        .collect();
+    let negated: Vec<usize> = (0..qubits.len())
+        .filter(|&bit| bit != target_bit && state >> bit & 1 == 0)
+        .map(|bit| qubits[bit])
+        .collect();
+
+    let mut synthesis = Synthesis::default();
+    for &qubit in &negated {
+        synthesis.push(Gate::X, qubit);
+    }
+    synthesis.extend(synthesize_controlled(&controls, qubits[target_bit], u));
+    for &qubit in &negated {
+        synthesis.push(Gate::X, qubit);
+    }
+    synthesis
+}
+
+/// Replaces rows `s` and `t` of `matrix` by `u` applied to them.
+fn apply_two_level(matrix: &mut [Vec<Complex>], s: usize, t: usize, u: &Matrix2) {
+    let (row_s, row_t) = (matrix[s].clone(), matrix[t].clone());
+    let rows = row_s.iter().zip(&row_t);
+    matrix[s] = rows
+        .clone()
+        .map(|(&ms, &mt)| u[0][0] * ms + u[0][1] * mt)
+        .collect();
+    matrix[t] = rows.map(|(&ms, &mt)| u[1][0] * ms + u[1][1] * mt).collect();
+}
+
+fn rz(theta: f64) -> Matrix2 {
+    [
+        [Complex::from_phase(-theta / 2.0), ZERO],
+        [ZERO, Complex::from_phase(theta / 2.0)],
+    ]
+}
+
+fn ry(theta: f64) -> Matrix2 {
+    let (sin, cos) = (theta / 2.0).sin_cos();
+    [
+        [Complex::new(cos, 0.0), Complex::new(-sin, 0.0)],
+        [Complex::new(sin, 0.0), Complex::new(cos, 0.0)],
+    ]
+}
+
+fn multiply(a: &Matrix2, b: &Matrix2) -> Matrix2 {
+    std::array::from_fn(|i| std::array::from_fn(|j| a[i][0] * b[0][j] + a[i][1] * b[1][j]))
+}
+
+fn dagger(u: &Matrix2) -> Matrix2 {
+    std::array::from_fn(|i| std::array::from_fn(|j| u[j][i].conj()))
+}
+
+fn is_close(a: &Matrix2, b: &Matrix2) -> bool {
+    (0..2).all(|i| (0..2).all(|j| (a[i][j] - b[i][j]).abs() < EPSILON))
+}
+
+fn arg(z: Complex) -> f64 {
+    z.im.atan2(z.re)
+}
+
+const ZERO: Complex = Complex { re: 0.0, im: 0.0 };
+const ONE: Complex = Complex { re: 1.0, im: 0.0 };
@@ -1,141 +1,231 @@
-use crate::qcore::circuits::CircuitRepr;
+use crate::error::QuojoError;
+use crate::qcore::circuits::Circuit;
 use crate::qcore::gates::Gate;
+use crate::qcore::phase::Phase;
+use crate::utils::synthesis::{Synthesis, expand};
 use crate::zxcalc::graph::{EdgeType, NodeIndex, SpiderType, ZXGraph};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+
+/// Conversion fails with the same errors as building the circuit.
+pub type ConversionError = QuojoError;
 
 pub struct CircuitConverter {
     qubit_nodes: HashMap<usize, NodeIndex>,
+    /// Edge type of the next connection on each wire. Hadamard gates toggle it.
+    pending_edges: HashMap<usize, EdgeType>,
 }
 
 impl CircuitConverter {
     pub fn new() -> Self {
         CircuitConverter {
             qubit_nodes: HashMap::new(),
+            pending_edges: HashMap::new(),
         }
     }
 
-    pub fn convert<const WIDTH: usize>(circuit: &CircuitRepr<WIDTH>) -> ZXGraph {
+    /// Translates a circuit into a ZX graph with the same linear map, global scalar
+    /// included.
+    pub fn convert(circuit: &Circuit) -> Result<ZXGraph, ConversionError> {
+        let width = circuit.num_qubits();
         let mut converter = Self::new();
         let mut graph = ZXGraph::new();
 
-        for qubit in 0..WIDTH {
-            let input_node = graph.add_input_node(SpiderType::Z, 0.0);
+        for qubit in 0..width {
+            let input_node = graph.add_input_node(SpiderType::Boundary, Phase::ZERO);
             converter.qubit_nodes.insert(qubit, input_node);
+            converter.pending_edges.insert(qubit, EdgeType::Regular);
         }
 
-        let time_slices = converter.extract_time_slices(circuit);
-
-        for time_slice in time_slices {
-            for (qubit, gate) in time_slice {
-                converter.apply_gate(&mut graph, qubit, &gate);
+        for instruction in circuit.dag.iter() {
+            instruction.check(width)?;
+            if !instruction.gate.is_unitary() {
+                return Err(QuojoError::UnsupportedGate(instruction.gate.clone()));
             }
         }
+        for (wire, gate) in circuit.ordered_gates() {
+            converter.apply_gate(&mut graph, wire, &gate);
+        }
 
-        for qubit in 0..WIDTH {
-            if let Some(&last_node) = converter.qubit_nodes.get(&qubit) {
-                let output_node = graph.add_output_node(SpiderType::Z, 0.0);
-                graph.add_edge(last_node, output_node, EdgeType::Regular);
-            }
+        for qubit in 0..width {
+            let output_node = graph.add_output_node(SpiderType::Boundary, Phase::ZERO);
+            converter.connect(&mut graph, qubit, output_node);
         }
 
-        graph
+        Ok(graph)
     }
 
+    /// Gates with a ZX form of their own are drawn directly and the rest are expanded into
+    /// simpler gates by `synthesis::expand`.
     fn apply_gate(&mut self, graph: &mut ZXGraph, qubit: usize, gate: &Gate) {
         match gate {
             Gate::X => self.apply_x_gate(graph, qubit),
             Gate::Z => self.apply_z_gate(graph, qubit),
-            // Gate::Y => self.apply_y_gate(graph, qubit),
-            // Gate::H => self.apply_h_gate(graph, qubit),
-            // Gate::P(phase) => self.apply_phase_gate(graph, qubit, *phase),
-            // Gate::CNOT { control, target } => self.apply_cnot_gate(graph, *control, *target),
-            // Gate::CZ { control, target } => self.apply_cz_gate(graph, *control, *target),
-            // Gate::SWAP { qubit1, qubit2 } => self.apply_swap_gate(graph, *qubit1, *qubit2),
-            _ => {} // Other gates can be added as needed
+            Gate::H => self.apply_h_gate(qubit),
+            Gate::P(phase) => self.apply_phase_gate(graph, qubit, phase.clone()),
+            Gate::SX => {
+                // √X is exactly an X-spider with phase π/2
+                self.add_spider(graph, qubit, SpiderType::X, Phase::new(1, 2));
+            }
+            Gate::Rx(theta) => {
+                // An X-spider with phase θ is e^(iθ/2)·Rx(θ)
+                self.add_spider(graph, qubit, SpiderType::X, theta.clone());
+                self.apply_global_phase(graph, -theta.half());
+            }
+            Gate::U3 { theta, phi, lambda } => {
+                // The S and S† of Ry(θ) merge into the phases on either side
+                self.apply_phase_gate(graph, qubit, lambda.clone() - Phase::new(1, 2));
+                self.add_spider(graph, qubit, SpiderType::X, theta.clone());
+                self.apply_phase_gate(graph, qubit, phi.clone() + Phase::new(1, 2));
+                self.apply_global_phase(graph, -theta.half());
+            }
+            Gate::GlobalPhase(phase) => self.apply_global_phase(graph, phase.clone()),
+            Gate::CNOT { control, target } => self.apply_cnot_gate(graph, *control, *target),
+            Gate::CZ { control, target } => self.apply_cz_gate(graph, *control, *target),
+            Gate::CP {
+                control,
+                target,
+                phase,
+            } => self.apply_cp_gate(graph, *control, *target, phase.clone()),
+            Gate::SWAP { qubit1, qubit2 } => self.apply_swap_gate(*qubit1, *qubit2),
+            Gate::RZZ {
+                qubit1,
+                qubit2,
+                theta,
+            } => self.apply_rzz_gate(graph, *qubit1, *qubit2, theta.clone()),
+            Gate::Measure { .. } | Gate::Reset | Gate::Conditional(_) => {
+                unreachable!("convert rejects {}", gate)
+            }
+            _ => self.apply_synthesis(graph, &expand(gate, qubit)),
         }
     }
 
-    fn apply_x_gate(&mut self, graph: &mut ZXGraph, qubit: usize) {
-        if let Some(&prev_node) = self.qubit_nodes.get(&qubit) {
-            // X gate is represented by an X-spider with phase = 0
-            let x_node = graph.add_node(SpiderType::X, 0.0);
-            graph.add_edge(prev_node, x_node, EdgeType::Regular);
-            self.qubit_nodes.insert(qubit, x_node);
+    /// Applies synthesised gates and their global phase.
+    fn apply_synthesis(&mut self, graph: &mut ZXGraph, synthesis: &Synthesis) {
+        for instruction in &synthesis.instructions {
+            self.apply_gate(graph, instruction.qubits[0], &instruction.gate);
         }
+        graph.scalar.multiply_phase(synthesis.phase);
     }
 
-    fn apply_z_gate(&mut self, graph: &mut ZXGraph, qubit: usize) {
+    /// Connects `node` to the end of the wire and makes it the new end.
+    fn connect(&mut self, graph: &mut ZXGraph, qubit: usize, node: NodeIndex) {
         if let Some(&prev_node) = self.qubit_nodes.get(&qubit) {
-            // Z gate is represented by a Z-spider with phase = π
-            let z_node = graph.add_node(SpiderType::Z, std::f64::consts::PI);
-            graph.add_edge(prev_node, z_node, EdgeType::Regular);
-            self.qubit_nodes.insert(qubit, z_node);
+            let edge_type = self
+                .pending_edges
+                .insert(qubit, EdgeType::Regular)
+                .unwrap_or(EdgeType::Regular);
+            graph.add_edge(prev_node, node, edge_type);
+            self.qubit_nodes.insert(qubit, node);
         }
     }
 
-    fn extract_time_slices<const WIDTH: usize>(
-        &self,
-        circuit: &CircuitRepr<WIDTH>,
-    ) -> Vec<Vec<(usize, Gate)>> {
-        let mut qubit_times = vec![0; WIDTH];
+    fn add_spider(
+        &mut self,
+        graph: &mut ZXGraph,
+        qubit: usize,
+        spider_type: SpiderType,
+        phase: Phase,
+    ) -> NodeIndex {
+        let node = graph.add_node(spider_type, phase);
+        self.connect(graph, qubit, node);
+        node
+    }
 
-        let mut gate_timings: HashMap<(usize, usize), usize> = HashMap::new();
+    /// Multiplies the scalar by e^(i·phase). The scalar has no variables, so a symbolic phase
+    /// becomes a Z(phase) spider joined to an X(π) spider, which is √2·e^(i·phase).
+    fn apply_global_phase(&mut self, graph: &mut ZXGraph, phase: Phase) {
+        if !phase.is_symbolic() {
+            graph.scalar.multiply_phase(phase);
+            return;
+        }
+        let z = graph.add_node(SpiderType::Z, phase);
+        let x = graph.add_node(SpiderType::X, Phase::PI);
+        graph.add_edge(z, x, EdgeType::Regular);
+        graph.scalar.multiply_sqrt2_power(-1);
+    }
 
-        for qubit in 0..WIDTH {
-            for (gate_idx, gate) in circuit.storage[qubit].iter().enumerate() {
-                let mut start_time = qubit_times[qubit];
+    fn apply_x_gate(&mut self, graph: &mut ZXGraph, qubit: usize) {
+        // X gate is represented by an X-spider with phase = π
+        self.add_spider(graph, qubit, SpiderType::X, Phase::PI);
+    }
 
-                let involved_qubits = get_involved_qubits(qubit, gate);
+    fn apply_z_gate(&mut self, graph: &mut ZXGraph, qubit: usize) {
+        // Z gate is represented by a Z-spider with phase = π
+        self.add_spider(graph, qubit, SpiderType::Z, Phase::PI);
+    }
 
-                for &involved_qubit in &involved_qubits {
-                    start_time = start_time.max(qubit_times[involved_qubit]);
-                }
+    fn apply_h_gate(&mut self, qubit: usize) {
+        // H gate is a Hadamard edge on the wire, so two in a row cancel
+        let pending = self.pending_edges.entry(qubit).or_insert(EdgeType::Regular);
+        *pending = match pending {
+            EdgeType::Regular => EdgeType::Hadamard,
+            EdgeType::Hadamard => EdgeType::Regular,
+        };
+    }
 
-                gate_timings.insert((qubit, gate_idx), start_time);
+    fn apply_phase_gate(&mut self, graph: &mut ZXGraph, qubit: usize, phase: Phase) {
+        self.add_spider(graph, qubit, SpiderType::Z, phase);
+    }
 
-                for &involved_qubit in &involved_qubits {
-                    qubit_times[involved_qubit] = start_time + 1;
-                }
-            }
-        }
+    fn apply_cnot_gate(&mut self, graph: &mut ZXGraph, control: usize, target: usize) {
+        // Z-spider on the control joined to an X-spider on the target, times √2
+        let control_node = self.add_spider(graph, control, SpiderType::Z, Phase::ZERO);
+        let target_node = self.add_spider(graph, target, SpiderType::X, Phase::ZERO);
+        graph.add_edge(control_node, target_node, EdgeType::Regular);
+        graph.scalar.multiply_sqrt2_power(1);
+    }
+
+    fn apply_cz_gate(&mut self, graph: &mut ZXGraph, control: usize, target: usize) {
+        // Two Z-spiders joined by a Hadamard edge, times √2
+        let control_node = self.add_spider(graph, control, SpiderType::Z, Phase::ZERO);
+        let target_node = self.add_spider(graph, target, SpiderType::Z, Phase::ZERO);
+        graph.add_edge(control_node, target_node, EdgeType::Hadamard);
+        graph.scalar.multiply_sqrt2_power(1);
+    }
 
-        let max_time = *gate_timings.values().max().unwrap_or(&0);
+    fn apply_cp_gate(&mut self, graph: &mut ZXGraph, control: usize, target: usize, phase: Phase) {
+        // x·y = (x + y − x⊕y)/2, so CP(φ) is P(φ/2) on each qubit and a gadget of -φ/2
+        let half = phase / 2;
+        self.apply_phase_gate(graph, control, half.clone());
+        self.apply_phase_gate(graph, target, half.clone());
+        self.apply_phase_gadget(graph, &[control, target], -half);
+    }
 
-        let mut time_slices = vec![Vec::new(); max_time + 1];
+    fn apply_rzz_gate(&mut self, graph: &mut ZXGraph, qubit1: usize, qubit2: usize, theta: Phase) {
+        // Z⊗Z = 1 − 2·(x⊕y), so RZZ(θ) = e^(-iθ/2)·e^(iθ·(x⊕y))
+        self.apply_phase_gadget(graph, &[qubit1, qubit2], theta.clone());
+        self.apply_global_phase(graph, -theta.half());
+    }
 
-        for qubit in 0..WIDTH {
-            for (gate_idx, gate) in circuit.storage[qubit].iter().enumerate() {
-                if let Some(&time) = gate_timings.get(&(qubit, gate_idx)) {
-                    let involved_qubits = get_involved_qubits(qubit, gate);
-                    if involved_qubits.is_empty() || involved_qubits[0] == qubit {
-                        time_slices[time].push((qubit, gate.clone()));
-                    }
-                }
-            }
+    /// Multiplies each basis state by e^(i·phase) when an odd number of `qubits` are |1⟩.
+    /// A Z-spider on each wire copies its value to an X-spider, which computes the parity,
+    /// and a Z(phase) spider on the X-spider's last leg applies the phase. The X-spider
+    /// scales by 2^((1-n)/2), so the scalar is multiplied back.
+    fn apply_phase_gadget(&mut self, graph: &mut ZXGraph, qubits: &[usize], phase: Phase) {
+        let hub = graph.add_node(SpiderType::X, Phase::ZERO);
+        for &qubit in qubits {
+            let node = self.add_spider(graph, qubit, SpiderType::Z, Phase::ZERO);
+            graph.add_edge(node, hub, EdgeType::Regular);
         }
-
-        time_slices
-            .into_iter()
-            .filter(|slice| !slice.is_empty())
-            .collect()
+        let head = graph.add_node(SpiderType::Z, phase);
+        graph.add_edge(hub, head, EdgeType::Regular);
+        graph.scalar.multiply_sqrt2_power(qubits.len() as i32 - 1);
     }
-}
 
-fn get_involved_qubits(current_qubit: usize, gate: &Gate) -> Vec<usize> {
-    match gate {
-        Gate::X | Gate::Y | Gate::Z | Gate::H | Gate::P(_) => vec![current_qubit],
-        Gate::CNOT { control, target } => vec![*control, *target],
-        Gate::CZ { control, target } => vec![*control, *target],
-        Gate::SWAP { qubit1, qubit2 } => vec![*qubit1, *qubit2],
-        Gate::Toffoli {
-            control1,
-            control2,
-            target,
-        } => vec![*control1, *control2, *target],
-        Gate::Fredkin {
-            control,
-            target1,
-            target2,
-        } => vec![*control, *target1, *target2],
+    fn apply_swap_gate(&mut self, qubit1: usize, qubit2: usize) {
+        // SWAP only crosses the wires, so exchange their open ends
+        let node1 = self.qubit_nodes.remove(&qubit1);
+        let node2 = self.qubit_nodes.remove(&qubit2);
+        if let (Some(node1), Some(node2)) = (node1, node2) {
+            self.qubit_nodes.insert(qubit1, node2);
+            self.qubit_nodes.insert(qubit2, node1);
+        }
+
+        let edge1 = self.pending_edges.remove(&qubit1);
+        let edge2 = self.pending_edges.remove(&qubit2);
+        if let (Some(edge1), Some(edge2)) = (edge1, edge2) {
+            self.pending_edges.insert(qubit1, edge2);
+            self.pending_edges.insert(qubit2, edge1);
+        }
     }
 }
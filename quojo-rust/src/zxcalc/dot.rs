@@ -0,0 +1,134 @@
+//! Graphviz DOT output for ZX-graphs, for viewing diagrams without LaTeX.
+
+use super::graph::*;
+use super::parametric::Parity;
+use crate::qcore::phase::Phase;
+use std::fmt::Write;
+
+/// Fill and outline colours of Z spiders, matching the TikZ `z_style`.
+pub(crate) const Z_COLORS: (&str, &str) = ("#ccffcc", "#00a000");
+/// Fill and outline colours of X spiders, matching the TikZ `x_style`.
+pub(crate) const X_COLORS: (&str, &str) = ("#ffcccc", "#d00000");
+/// Colour of Hadamard edges.
+pub(crate) const HADAMARD_COLOR: &str = "#1f5fd0";
+
+/// Plain-text label of a spider: its phase, and any boolean parameters as `π(b0⊕b1)`.
+/// Empty for boundaries and for phase-free spiders.
+pub(crate) fn spider_label(node: &Node) -> String {
+    format_spider_label(
+        node,
+        |phase| match phase.as_float() {
+            Some(radians) => format!("{:.2}", radians),
+            None => phase.to_string(),
+        },
+        |params| format!("π({})", params),
+    )
+}
+
+/// The label of a spider in any output format: its nonzero phase, written by `phase`, then
+/// its boolean parameters, written by `params`, joined with `+`. Empty for boundaries.
+pub(crate) fn format_spider_label(
+    node: &Node,
+    phase: impl Fn(&Phase) -> String,
+    params: impl Fn(&Parity) -> String,
+) -> String {
+    if node.spider_type == SpiderType::Boundary {
+        return String::new();
+    }
+    let mut label = if node.phase.is_zero() {
+        String::new()
+    } else {
+        phase(&node.phase)
+    };
+    if !node.params.is_empty() {
+        if !label.is_empty() {
+            label.push('+');
+        }
+        label.push_str(&params(&node.params));
+    }
+    label
+}
+
+/// `in k` or `out k` for the k-th input or output, in the order of `ordered_inputs` and
+/// `ordered_outputs`.
+pub(crate) fn terminal_label(
+    inputs: &[NodeIndex],
+    outputs: &[NodeIndex],
+    node: NodeIndex,
+) -> Option<String> {
+    if let Some(k) = inputs.iter().position(|&n| n == node) {
+        return Some(format!("in {}", k));
+    }
+    outputs
+        .iter()
+        .position(|&n| n == node)
+        .map(|k| format!("out {}", k))
+}
+
+impl ZXGraph {
+    /// The diagram as an undirected Graphviz graph, laid out left to right with the inputs
+    /// in the first rank and the outputs in the last, each in qubit order. Z spiders are
+    /// green, X spiders red, and Hadamard edges blue and dashed.
+    pub fn to_dot(&self) -> String {
+        let (inputs, outputs) = (self.ordered_inputs(), self.ordered_outputs());
+        let mut output = String::from("graph zx {\n");
+        output.push_str("  rankdir=LR;\n");
+        output.push_str("  node [fontname=\"Helvetica\", fontsize=10];\n");
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            let Some(node) = node else { continue };
+            let attributes = match node.spider_type {
+                SpiderType::Boundary => {
+                    let label = terminal_label(&inputs, &outputs, NodeIndex(i)).unwrap_or_default();
+                    format!("shape=point, width=0.08, xlabel=\"{}\"", label)
+                }
+                SpiderType::Z | SpiderType::X => {
+                    let (fill, outline) = match node.spider_type {
+                        SpiderType::Z => Z_COLORS,
+                        _ => X_COLORS,
+                    };
+                    format!(
+                        "shape=circle, style=filled, fillcolor=\"{}\", color=\"{}\", label=\"{}\"",
+                        fill,
+                        outline,
+                        escape(&spider_label(node))
+                    )
+                }
+            };
+            writeln!(output, "  n{} [{}];", i, attributes).unwrap();
+        }
+
+        for edge in self.edges.iter().flatten() {
+            let (a, b) = edge.endpoints;
+            match edge.edge_type {
+                EdgeType::Regular => writeln!(output, "  n{} -- n{};", a.0, b.0),
+                EdgeType::Hadamard => writeln!(
+                    output,
+                    "  n{} -- n{} [color=\"{}\", style=dashed];",
+                    a.0, b.0, HADAMARD_COLOR
+                ),
+            }
+            .unwrap();
+        }
+
+        // Invisible edges inside each rank keep the terminals in qubit order
+        for (rank, terminals) in [("source", &inputs), ("sink", &outputs)] {
+            if terminals.is_empty() {
+                continue;
+            }
+            let names: Vec<String> = terminals.iter().map(|n| format!("n{}", n.0)).collect();
+            writeln!(output, "  {{ rank={}; {}; }}", rank, names.join("; ")).unwrap();
+            if names.len() > 1 {
+                writeln!(output, "  {} [style=invis];", names.join(" -- ")).unwrap();
+            }
+        }
+
+        output.push_str("}\n");
+        output
+    }
+}
+
+/// Escapes a string for a double-quoted DOT attribute.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
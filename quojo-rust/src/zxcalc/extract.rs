@@ -0,0 +1,414 @@
+use super::graph::*;
+use super::rules::{HadamardEdges, toggle};
+use crate::qcore::Targets;
+use crate::qcore::circuits::{Circuit, CircuitRepr};
+use crate::qcore::gates::Gate;
+use crate::qcore::phase::Phase;
+use std::fmt::Display;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtractionError {
+    /// The diagram's inputs and outputs do not match the requested circuit width, or each
+    /// other when the width is taken from the inputs.
+    WidthMismatch {
+        inputs: usize,
+        outputs: usize,
+        width: usize,
+    },
+    /// The spider carries boolean parameters, which have no gate equivalent.
+    ParametricSpider(NodeIndex),
+    /// No spider could be moved onto the frontier, so the diagram has no gflow.
+    NoGflow,
+}
+
+impl Display for ExtractionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtractionError::WidthMismatch {
+                inputs,
+                outputs,
+                width,
+            } => write!(
+                f,
+                "Diagram has {} inputs and {} outputs but the circuit width is {}",
+                inputs, outputs, width
+            ),
+            ExtractionError::ParametricSpider(node) => {
+                write!(f, "Spider {} has boolean parameters", node.0)
+            }
+            ExtractionError::NoGflow => write!(f, "Diagram has no gflow"),
+        }
+    }
+}
+
+impl std::error::Error for ExtractionError {}
+
+impl ZXGraph {
+    /// Like `extract`, for a circuit width known at compile time.
+    pub fn extract_circuit<const WIDTH: usize>(
+        &self,
+    ) -> Result<CircuitRepr<WIDTH>, ExtractionError> {
+        let (inputs, outputs) = (self.input_nodes.len(), self.output_nodes.len());
+        if inputs != WIDTH || outputs != WIDTH {
+            return Err(ExtractionError::WidthMismatch {
+                inputs,
+                outputs,
+                width: WIDTH,
+            });
+        }
+        self.extract().map(CircuitRepr::from_circuit)
+    }
+
+    /// Extracts a circuit of CNOT, CZ, H and P gates implementing the same map as this
+    /// diagram, up to a global scalar, with one qubit per input. The diagram is made
+    /// graph-like first and must have gflow, which holds for circuits simplified with
+    /// `clifford_simp` or `full_reduce`.
+    pub fn extract(&self) -> Result<Circuit, ExtractionError> {
+        let (inputs, outputs) = (self.ordered_inputs(), self.ordered_outputs());
+        let width = inputs.len();
+        if outputs.len() != width {
+            return Err(ExtractionError::WidthMismatch {
+                inputs: width,
+                outputs: outputs.len(),
+                width,
+            });
+        }
+        if let Some(i) = self
+            .nodes
+            .iter()
+            .position(|node| node.as_ref().is_some_and(|n| !n.params.is_empty()))
+        {
+            return Err(ExtractionError::ParametricSpider(NodeIndex(i)));
+        }
+
+        let mut graph = self.clone();
+        graph.to_graph_like();
+
+        let mut frontier = Vec::with_capacity(width);
+        for &output in &outputs {
+            match graph.incident_edges(output).as_slice() {
+                [(_, spider, _)] if graph.spider_type(*spider) == Some(SpiderType::Z) => {
+                    frontier.push(*spider)
+                }
+                _ => return Err(ExtractionError::NoGflow),
+            }
+        }
+
+        let mut extractor = Extractor {
+            graph,
+            inputs,
+            outputs,
+            frontier,
+            gates: Vec::new(),
+        };
+        extractor.run()?;
+
+        let mut circuit = Circuit::new(width);
+        for (wire, gate) in extractor.gates.into_iter().rev() {
+            match gate {
+                Gate::CNOT { control, target } => circuit.ApplyControlled(Gate::X, control, target),
+                Gate::CZ { control, target } => circuit.ApplyControlled(Gate::Z, control, target),
+                _ => circuit.Apply(gate, Targets(&[wire])),
+            }
+        }
+        Ok(circuit)
+    }
+}
+
+/// Walks a graph-like diagram from its outputs back to its inputs. `frontier[q]` is the
+/// spider on output `q`, and `gates` holds `(wire, gate)` pairs in reverse circuit order.
+struct Extractor {
+    graph: ZXGraph,
+    inputs: Vec<NodeIndex>,
+    outputs: Vec<NodeIndex>,
+    frontier: Vec<NodeIndex>,
+    gates: Vec<(usize, Gate)>,
+}
+
+impl Extractor {
+    fn run(&mut self) -> Result<(), ExtractionError> {
+        loop {
+            self.clean_frontier();
+            self.detach_inputs();
+
+            let neighbors = self.frontier_neighbors();
+            if neighbors.is_empty() {
+                break;
+            }
+            if self.remove_gadget(&neighbors) {
+                continue;
+            }
+            if !self.extract_neighbors(&neighbors) {
+                return Err(ExtractionError::NoGflow);
+            }
+        }
+
+        self.extract_permutation()
+    }
+
+    /// Moves Hadamards on the output wires, frontier phases and Hadamard edges between
+    /// frontier spiders into the circuit as H, P and CZ gates.
+    fn clean_frontier(&mut self) {
+        for q in 0..self.frontier.len() {
+            self.clean_output_edge(q);
+
+            let spider = self.frontier[q];
+            let phase = self
+                .graph
+                .node_data(spider)
+                .map_or(Phase::ZERO, |d| d.1.clone());
+            if !phase.is_zero() {
+                self.graph.add_phase(spider, -phase.clone());
+                self.gates.push((q, Gate::P(phase)));
+            }
+        }
+
+        for q in 0..self.frontier.len() {
+            for r in q + 1..self.frontier.len() {
+                for (edge_idx, other, _) in self.graph.incident_edges(self.frontier[q]) {
+                    if other == self.frontier[r] {
+                        self.graph.remove_edge(edge_idx);
+                        self.gates.push((
+                            q,
+                            Gate::CZ {
+                                control: q,
+                                target: r,
+                            },
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Turns a Hadamard edge between output `q` and its spider into an H gate.
+    fn clean_output_edge(&mut self, q: usize) {
+        let (output, spider) = (self.outputs[q], self.frontier[q]);
+        if let Some((edge_idx, _, EdgeType::Hadamard)) =
+            self.graph.incident_edges(output).into_iter().next()
+        {
+            self.graph.remove_edge(edge_idx);
+            self.graph.add_edge(spider, output, EdgeType::Regular);
+            self.gates.push((q, Gate::H));
+        }
+    }
+
+    /// A frontier spider that still has interior neighbours cannot also hold its input,
+    /// so the input is moved behind a new identity spider: v –H– w –(H·t)– b.
+    fn detach_inputs(&mut self) {
+        for &spider in &self.frontier {
+            let incident = self.graph.incident_edges(spider);
+            let has_interior = incident
+                .iter()
+                .any(|(_, other, _)| self.graph.spider_type(*other) == Some(SpiderType::Z));
+            if !has_interior {
+                continue;
+            }
+
+            for (edge_idx, other, edge_type) in incident {
+                if self.graph.is_input_node(other) {
+                    self.graph.remove_edge(edge_idx);
+                    let w = self.graph.add_node(SpiderType::Z, Phase::ZERO);
+                    self.graph.add_edge(spider, w, EdgeType::Hadamard);
+                    self.graph.add_edge(w, other, toggle(&edge_type));
+                }
+            }
+        }
+    }
+
+    /// Spiders adjacent to the frontier, in index order.
+    fn frontier_neighbors(&self) -> Vec<NodeIndex> {
+        let mut neighbors: Vec<NodeIndex> = self
+            .frontier
+            .iter()
+            .flat_map(|&spider| self.graph.neighbors(spider))
+            .filter(|&n| self.graph.spider_type(n) == Some(SpiderType::Z))
+            .collect();
+        neighbors.sort_by_key(|n| n.0);
+        neighbors.dedup();
+        neighbors
+    }
+
+    /// Pivots a frontier spider with the axis of an adjacent phase gadget. The gadget is
+    /// not covered by gflow, and after the pivot its leaf is an ordinary spider.
+    fn remove_gadget(&mut self, neighbors: &[NodeIndex]) -> bool {
+        let axis = neighbors.iter().copied().find(|&n| {
+            self.graph.is_interior_pauli(n)
+                && self
+                    .graph
+                    .neighbors(n)
+                    .iter()
+                    .any(|&leaf| self.graph.neighbors(leaf).len() == 1)
+        });
+        let Some(axis) = axis else {
+            return false;
+        };
+        let Some(q) = (0..self.frontier.len())
+            .find(|&q| self.graph.neighbors(self.frontier[q]).contains(&axis))
+        else {
+            return false;
+        };
+
+        // Unfuse the output wire so the frontier spider is interior: v – o becomes
+        // v –H– v' –H– o, and v' takes over the output.
+        let (output, spider) = (self.outputs[q], self.frontier[q]);
+        let Some((edge_idx, _, _)) = self.graph.incident_edges(output).into_iter().next() else {
+            return false;
+        };
+        self.graph.remove_edge(edge_idx);
+        let replacement = self.graph.add_node(SpiderType::Z, Phase::ZERO);
+        self.graph.add_edge(spider, replacement, EdgeType::Hadamard);
+        self.graph.add_edge(replacement, output, EdgeType::Hadamard);
+        self.frontier[q] = replacement;
+
+        self.graph.apply_pivot(spider, axis);
+        true
+    }
+
+    /// Row reduces the biadjacency matrix between the frontier and its neighbours over
+    /// GF(2), emitting a CNOT per row operation, then moves every neighbour that is left
+    /// with a single frontier spider onto the frontier behind an H gate. Returns whether
+    /// any neighbour was extracted.
+    fn extract_neighbors(&mut self, neighbors: &[NodeIndex]) -> bool {
+        let mut matrix: Vec<Vec<bool>> = self
+            .frontier
+            .iter()
+            .map(|&spider| {
+                let adjacent = self.graph.neighbors(spider);
+                neighbors.iter().map(|n| adjacent.contains(n)).collect()
+            })
+            .collect();
+
+        if !matrix.iter().any(|row| single_entry(row).is_some()) {
+            // Adding row `added` to row `row` is a CNOT controlled on `row`
+            for (row, added) in gaussian_elimination(&mut matrix) {
+                self.gates.push((
+                    row,
+                    Gate::CNOT {
+                        control: row,
+                        target: added,
+                    },
+                ));
+            }
+
+            for (q, row) in matrix.iter().enumerate() {
+                let spider = self.frontier[q];
+                let adjacent = self.graph.neighbors(spider);
+                for (&n, &entry) in neighbors.iter().zip(row) {
+                    if entry != adjacent.contains(&n) {
+                        self.graph.toggle_hadamard_edge(spider, n);
+                    }
+                }
+            }
+        }
+
+        let mut extracted = Vec::new();
+        for (q, row) in matrix.iter().enumerate() {
+            let Some(column) = single_entry(row) else {
+                continue;
+            };
+            if extracted.contains(&column) {
+                continue;
+            }
+            extracted.push(column);
+
+            // v(0) between the output and a single Hadamard edge is just an H gate
+            self.graph.remove_node(self.frontier[q]);
+            self.graph
+                .add_edge(neighbors[column], self.outputs[q], EdgeType::Regular);
+            self.frontier[q] = neighbors[column];
+            self.gates.push((q, Gate::H));
+        }
+
+        !extracted.is_empty()
+    }
+
+    /// Once every frontier spider is a plain wire to an input, the rest of the diagram is
+    /// Hadamards on those wires followed by a permutation, built from CNOT swaps.
+    fn extract_permutation(&mut self) -> Result<(), ExtractionError> {
+        let mut sources = Vec::with_capacity(self.frontier.len());
+
+        for q in 0..self.frontier.len() {
+            let incident = self.graph.incident_edges(self.frontier[q]);
+            let [first, second] = incident.as_slice() else {
+                return Err(ExtractionError::NoGflow);
+            };
+            let (_, input, edge_type) = if first.1 == self.outputs[q] {
+                second
+            } else {
+                first
+            };
+            let Some(source) = self.inputs.iter().position(|i| i == input) else {
+                return Err(ExtractionError::NoGflow);
+            };
+            if sources.contains(&source) {
+                return Err(ExtractionError::NoGflow);
+            }
+
+            if *edge_type == EdgeType::Hadamard {
+                self.gates.push((q, Gate::H));
+            }
+            sources.push(source);
+        }
+
+        // Output q reads input sources[q]. Sort the wires into place, in circuit order.
+        let mut current: Vec<usize> = (0..sources.len()).collect();
+        let mut swaps = Vec::new();
+        for (q, &source) in sources.iter().enumerate() {
+            let k = (q..current.len())
+                .find(|&k| current[k] == source)
+                .unwrap_or(q);
+            if k != q {
+                current.swap(q, k);
+                swaps.push((q, k));
+            }
+        }
+
+        for (a, b) in swaps.into_iter().rev() {
+            for (control, target) in [(a, b), (b, a), (a, b)] {
+                self.gates.push((control, Gate::CNOT { control, target }));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The column of the only set entry in the row, if there is exactly one.
+fn single_entry(row: &[bool]) -> Option<usize> {
+    let mut columns = row.iter().enumerate().filter(|(_, entry)| **entry);
+    match (columns.next(), columns.next()) {
+        (Some((column, _)), None) => Some(column),
+        _ => None,
+    }
+}
+
+/// Reduces the matrix over GF(2) until every pivot column holds a single set entry,
+/// using row additions only. Returns the operations as `(row, added)` pairs, meaning
+/// row `added` was added to row `row`.
+fn gaussian_elimination(matrix: &mut [Vec<bool>]) -> Vec<(usize, usize)> {
+    let mut operations = Vec::new();
+    let mut pivot_rows: Vec<usize> = Vec::new();
+    let columns = matrix.first().map_or(0, |row| row.len());
+
+    for column in 0..columns {
+        let Some(pivot) =
+            (0..matrix.len()).find(|&r| matrix[r][column] && !pivot_rows.contains(&r))
+        else {
+            continue;
+        };
+        pivot_rows.push(pivot);
+
+        for row in 0..matrix.len() {
+            if row != pivot && matrix[row][column] {
+                let added = matrix[pivot].clone();
+                for (entry, value) in matrix[row].iter_mut().zip(added) {
+                    *entry ^= value;
+                }
+                operations.push((row, pivot));
+            }
+        }
+    }
+
+    operations
+}
@@ -1,4 +1,7 @@
-use std::collections::{HashMap, HashSet};
+use super::parametric::Parity;
+use super::scalar::Scalar;
+use crate::qcore::phase::Phase;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct NodeIndex(pub usize);
@@ -10,6 +13,8 @@ pub struct EdgeIndex(pub usize);
 pub enum SpiderType {
     Z,
     X,
+    /// An input or output wire end. Boundaries carry no phase and are never fused.
+    Boundary,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -18,7 +23,7 @@ pub enum EdgeType {
     Hadamard,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ZXGraph {
     pub nodes: Vec<Option<Node>>,
     pub edges: Vec<Option<Edge>>,
@@ -26,16 +31,20 @@ pub struct ZXGraph {
     pub free_edges: Vec<usize>,
     pub input_nodes: HashSet<NodeIndex>,
     pub output_nodes: HashSet<NodeIndex>,
+    /// Global factor multiplying the whole diagram.
+    pub scalar: Scalar,
 }
 
 #[derive(Debug, Clone)]
 pub struct Node {
     pub spider_type: SpiderType,
-    pub phase: f64,
+    pub phase: Phase,
+    /// Boolean parameters adding π·(b_i ⊕ b_j ⊕ ...) on top of `phase`.
+    pub params: Parity,
     pub edges: HashSet<EdgeIndex>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Edge {
     pub endpoints: (NodeIndex, NodeIndex),
     pub edge_type: EdgeType,
@@ -50,14 +59,27 @@ impl ZXGraph {
             free_edges: Vec::new(),
             input_nodes: HashSet::new(),
             output_nodes: HashSet::new(),
+            scalar: Scalar::one(),
         }
     }
 
-    pub fn add_node(&mut self, spider_type: SpiderType, phase: f64) -> NodeIndex {
+    pub fn add_node(&mut self, spider_type: SpiderType, phase: impl Into<Phase>) -> NodeIndex {
+        self.add_parametric_node(spider_type, phase, Parity::new())
+    }
+
+    /// Adds a spider with phase `phase + π·params`.
+    pub fn add_parametric_node(
+        &mut self,
+        spider_type: SpiderType,
+        phase: impl Into<Phase>,
+        params: Parity,
+    ) -> NodeIndex {
+        let phase = phase.into();
         let idx = if let Some(idx) = self.free_nodes.pop() {
             self.nodes[idx] = Some(Node {
                 spider_type,
                 phase,
+                params,
                 edges: HashSet::new(),
             });
             idx
@@ -65,6 +87,7 @@ impl ZXGraph {
             self.nodes.push(Some(Node {
                 spider_type,
                 phase,
+                params,
                 edges: HashSet::new(),
             }));
             self.nodes.len() - 1
@@ -72,7 +95,11 @@ impl ZXGraph {
         NodeIndex(idx)
     }
 
-    pub fn add_input_node(&mut self, spider_type: SpiderType, phase: f64) -> NodeIndex {
+    pub fn add_input_node(
+        &mut self,
+        spider_type: SpiderType,
+        phase: impl Into<Phase>,
+    ) -> NodeIndex {
         let node_idx = self.add_node(spider_type, phase);
         self.input_nodes.insert(node_idx);
         node_idx
@@ -92,7 +119,11 @@ impl ZXGraph {
         self.input_nodes.contains(&node_idx)
     }
 
-    pub fn add_output_node(&mut self, spider_type: SpiderType, phase: f64) -> NodeIndex {
+    pub fn add_output_node(
+        &mut self,
+        spider_type: SpiderType,
+        phase: impl Into<Phase>,
+    ) -> NodeIndex {
         let node_idx = self.add_node(spider_type, phase);
         self.output_nodes.insert(node_idx);
         node_idx
@@ -178,12 +209,44 @@ impl ZXGraph {
         neighbors
     }
 
-    pub fn node_data(&self, idx: NodeIndex) -> Option<(&SpiderType, &f64)> {
+    pub fn node_data(&self, idx: NodeIndex) -> Option<(&SpiderType, &Phase)> {
         self.nodes[idx.0]
             .as_ref()
             .map(|n| (&n.spider_type, &n.phase))
     }
 
+    pub fn node_params(&self, idx: NodeIndex) -> Option<&Parity> {
+        self.nodes[idx.0].as_ref().map(|n| &n.params)
+    }
+
+    /// Number of boolean parameters referenced by the spiders of this graph.
+    pub fn num_parameters(&self) -> usize {
+        self.nodes
+            .iter()
+            .flatten()
+            .map(|n| n.params.param_count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Names of the symbolic variables appearing in spider phases.
+    pub fn variables(&self) -> BTreeSet<String> {
+        self.nodes
+            .iter()
+            .flatten()
+            .flat_map(|n| n.phase.variables())
+            .collect()
+    }
+
+    /// A copy of the graph with variable values, in radians, substituted into every phase.
+    pub fn bind(&self, values: &HashMap<String, f64>) -> ZXGraph {
+        let mut bound = self.clone();
+        for node in bound.nodes.iter_mut().flatten() {
+            node.phase = node.phase.bind(values);
+        }
+        bound
+    }
+
     pub fn edge_data(&self, idx: EdgeIndex) -> Option<&EdgeType> {
         self.edges[idx.0].as_ref().map(|e| &e.edge_type)
     }
@@ -220,7 +283,7 @@ impl ZXGraph {
                     if let (Some((type1, _)), Some((type2, _))) =
                         (self.node_data(node1), self.node_data(node2))
                     {
-                        if type1 == type2 {
+                        if type1 == type2 && *type1 != SpiderType::Boundary {
                             return Some((node1, node2, EdgeIndex(i)));
                         }
                     }
@@ -231,13 +294,17 @@ impl ZXGraph {
         None
     }
 
-    fn fuse_spider_pair(&mut self, node1: NodeIndex, node2: NodeIndex, edge_idx: EdgeIndex) {
+    pub(crate) fn fuse_spider_pair(
+        &mut self,
+        node1: NodeIndex,
+        node2: NodeIndex,
+        edge_idx: EdgeIndex,
+    ) {
         let mut node1_data = self.nodes[node1.0].clone().unwrap();
         let node2_data = self.nodes[node2.0].clone().unwrap();
 
-        let new_phase = normalize_phase(node1_data.phase + node2_data.phase);
-
-        node1_data.phase = new_phase;
+        node1_data.phase += node2_data.phase;
+        node1_data.params.xor_assign(&node2_data.params);
         self.nodes[node1.0] = Some(node1_data.clone());
 
         let mut edges_to_reconnect = Vec::new();
@@ -245,10 +312,10 @@ impl ZXGraph {
         for &e_idx in &node2_data.edges {
             if e_idx != edge_idx {
                 if let Some(edge) = &self.edges[e_idx.0] {
-                    let other_end = if edge.endpoints.0 == node2 {
-                        edge.endpoints.1
-                    } else {
-                        edge.endpoints.0
+                    let other_end = match edge.endpoints {
+                        (a, b) if a == b => node1,
+                        (a, b) if a == node2 => b,
+                        (a, _) => a,
                     };
 
                     edges_to_reconnect.push((e_idx, other_end, edge.edge_type.clone()));
@@ -261,7 +328,8 @@ impl ZXGraph {
         for (old_edge_idx, other_node, edge_type) in edges_to_reconnect {
             self.remove_edge(old_edge_idx);
 
-            if other_node != node1 {
+            // A regular self-loop on a spider is the identity, but a Hadamard one is not
+            if other_node != node1 || edge_type == EdgeType::Hadamard {
                 self.add_edge(node1, other_node, edge_type);
             }
         }
@@ -269,12 +337,3 @@ impl ZXGraph {
         self.remove_node(node2);
     }
 }
-
-fn normalize_phase(phase: f64) -> f64 {
-    const TWO_PI: f64 = std::f64::consts::PI * 2.0;
-    let mut normalized = phase % TWO_PI;
-    if normalized < 0.0 {
-        normalized += TWO_PI;
-    }
-    normalized
-}
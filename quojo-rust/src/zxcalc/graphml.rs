@@ -0,0 +1,124 @@
+//! GraphML output for ZX-graphs, for graph tools such as yEd, Gephi or NetworkX.
+
+use super::dot::{HADAMARD_COLOR, X_COLORS, Z_COLORS, spider_label, terminal_label};
+use super::graph::*;
+use super::tikz::{TikzConfig, calculate_professional_layout};
+use std::fmt::Write;
+
+/// Attributes declared for every file, as (id, element, name, type).
+const KEYS: [(&str, &str, &str, &str); 9] = [
+    ("type", "node", "type", "string"),
+    ("phase", "node", "phase", "string"),
+    ("color", "node", "color", "string"),
+    ("input", "node", "input", "int"),
+    ("output", "node", "output", "int"),
+    ("x", "node", "x", "double"),
+    ("y", "node", "y", "double"),
+    ("edge_type", "edge", "type", "string"),
+    ("edge_color", "edge", "color", "string"),
+];
+
+impl ZXGraph {
+    /// The diagram as an undirected GraphML graph. Each node records its spider type
+    /// (`Z`, `X` or `Boundary`), phase label, fill colour, its `input` or `output` number
+    /// for boundaries, and an `x`/`y` position from the TikZ layout, with inputs lined up on
+    /// the left, outputs on the right and y growing downwards. Each edge records whether it is
+    /// `regular` or `hadamard`, and Hadamard edges are coloured blue.
+    pub fn to_graphml(&self) -> String {
+        let (inputs, outputs) = (self.ordered_inputs(), self.ordered_outputs());
+        let mut positions = calculate_professional_layout(self, &TikzConfig::default());
+        // The layout ranks terminals by distance, so line them up at the edges
+        let xs = positions.values().map(|&(x, _)| x);
+        let (left, right) = (
+            xs.clone().fold(f64::MAX, f64::min),
+            xs.fold(f64::MIN, f64::max),
+        );
+        for (terminals, x) in [(&inputs, left), (&outputs, right)] {
+            for node in terminals {
+                if let Some(position) = positions.get_mut(node) {
+                    position.0 = x;
+                }
+            }
+        }
+
+        let mut output = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        output.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        for (id, element, name, kind) in KEYS {
+            writeln!(
+                output,
+                "  <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>",
+                id, element, name, kind
+            )
+            .unwrap();
+        }
+        output.push_str("  <graph id=\"zx\" edgedefault=\"undirected\">\n");
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            let Some(node) = node else { continue };
+            let index = NodeIndex(i);
+            let (kind, label, color) = match node.spider_type {
+                SpiderType::Z => ("Z", spider_label(node), Z_COLORS.0),
+                SpiderType::X => ("X", spider_label(node), X_COLORS.0),
+                SpiderType::Boundary => (
+                    "Boundary",
+                    terminal_label(&inputs, &outputs, index).unwrap_or_default(),
+                    "#000000",
+                ),
+            };
+            let (x, y) = positions.get(&index).copied().unwrap_or_default();
+
+            writeln!(output, "    <node id=\"n{}\">", i).unwrap();
+            let mut data = vec![
+                ("type", kind.to_string()),
+                ("phase", label),
+                ("color", color.to_string()),
+            ];
+            if let Some(k) = inputs.iter().position(|&n| n == index) {
+                data.push(("input", k.to_string()));
+            }
+            if let Some(k) = outputs.iter().position(|&n| n == index) {
+                data.push(("output", k.to_string()));
+            }
+            data.push(("x", format!("{:.2}", x)));
+            data.push(("y", format!("{:.2}", -y)));
+            for (key, value) in data {
+                writeln!(
+                    output,
+                    "      <data key=\"{}\">{}</data>",
+                    key,
+                    escape(&value)
+                )
+                .unwrap();
+            }
+            output.push_str("    </node>\n");
+        }
+
+        for (i, edge) in self.edges.iter().enumerate() {
+            let Some(edge) = edge else { continue };
+            let (a, b) = edge.endpoints;
+            let (kind, color) = match edge.edge_type {
+                EdgeType::Regular => ("regular", "#000000"),
+                EdgeType::Hadamard => ("hadamard", HADAMARD_COLOR),
+            };
+            writeln!(
+                output,
+                "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\">",
+                i, a.0, b.0
+            )
+            .unwrap();
+            writeln!(output, "      <data key=\"edge_type\">{}</data>", kind).unwrap();
+            writeln!(output, "      <data key=\"edge_color\">{}</data>", color).unwrap();
+            output.push_str("    </edge>\n");
+        }
+
+        output.push_str("  </graph>\n</graphml>\n");
+        output
+    }
+}
+
+/// Escapes text for XML element content.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
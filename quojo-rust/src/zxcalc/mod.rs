@@ -1,2 +1,12 @@
+pub mod dot;
+pub mod extract;
 pub mod graph;
+pub mod graphml;
+pub mod parametric;
+pub mod qgraph;
+pub mod rules;
+pub mod scalar;
+pub mod simplify;
+pub mod simulate;
+pub mod svg;
 pub mod tikz;
@@ -0,0 +1,219 @@
+use crate::error::QuojoError;
+use crate::linalg::vector::Complex;
+use std::collections::BTreeSet;
+use std::f64::consts::SQRT_2;
+use std::fmt::Display;
+
+/// An XOR of boolean parameters b_i ⊕ b_j ⊕ ...
+///
+/// A spider carrying a parity has the affine phase α + π·(b_i ⊕ b_j ⊕ ...), which is how
+/// basis states, measurement outcomes and other boolean inputs enter a diagram without
+/// fixing their values.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Parity {
+    params: BTreeSet<usize>,
+}
+
+impl Parity {
+    pub fn new() -> Self {
+        Parity::default()
+    }
+
+    pub fn single(param: usize) -> Self {
+        Parity {
+            params: BTreeSet::from([param]),
+        }
+    }
+
+    pub fn from_params(params: &[usize]) -> Self {
+        let mut parity = Parity::new();
+        for &param in params {
+            parity.toggle(param);
+        }
+        parity
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    pub fn params(&self) -> impl Iterator<Item = usize> + '_ {
+        self.params.iter().copied()
+    }
+
+    /// Largest parameter index plus one, or 0 for the empty parity.
+    pub fn param_count(&self) -> usize {
+        self.params.last().map_or(0, |&p| p + 1)
+    }
+
+    pub fn toggle(&mut self, param: usize) {
+        if !self.params.remove(&param) {
+            self.params.insert(param);
+        }
+    }
+
+    pub fn xor(&self, other: &Parity) -> Parity {
+        Parity {
+            params: self
+                .params
+                .symmetric_difference(&other.params)
+                .copied()
+                .collect(),
+        }
+    }
+
+    pub fn xor_assign(&mut self, other: &Parity) {
+        for &param in &other.params {
+            self.toggle(param);
+        }
+    }
+
+    pub fn evaluate(&self, assignment: &[bool]) -> bool {
+        self.params
+            .iter()
+            .fold(false, |acc, &param| acc ^ assignment[param])
+    }
+}
+
+impl Display for Parity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, param) in self.params.iter().enumerate() {
+            if i > 0 {
+                write!(f, "⊕")?;
+            }
+            write!(f, "b{}", param)?;
+        }
+        Ok(())
+    }
+}
+
+/// A parameter-dependent factor of a scalar, where p is the value of `parity`.
+#[derive(Debug, Clone)]
+pub enum Factor {
+    /// e^(i·angle·p)
+    Phase { angle: f64, parity: Parity },
+    /// 1 + e^(i·(phase + π·p))
+    OnePlusPhase { phase: f64, parity: Parity },
+    /// (-1)^((a ⊕ p)·(b ⊕ q))
+    SignProduct {
+        a: bool,
+        p: Parity,
+        b: bool,
+        q: Parity,
+    },
+}
+
+impl Factor {
+    pub fn evaluate(&self, assignment: &[bool]) -> Complex {
+        match self {
+            Factor::Phase { angle, parity } => {
+                if parity.evaluate(assignment) {
+                    Complex::from_phase(*angle)
+                } else {
+                    Complex::new(1.0, 0.0)
+                }
+            }
+            Factor::OnePlusPhase { phase, parity } => {
+                let shift = if parity.evaluate(assignment) {
+                    std::f64::consts::PI
+                } else {
+                    0.0
+                };
+                Complex::from_phase(phase + shift) + 1.0
+            }
+            Factor::SignProduct { a, p, b, q } => {
+                if (a ^ p.evaluate(assignment)) && (b ^ q.evaluate(assignment)) {
+                    Complex::new(-1.0, 0.0)
+                } else {
+                    Complex::new(1.0, 0.0)
+                }
+            }
+        }
+    }
+}
+
+/// One product term √2^sqrt2_power · value · Π factors.
+#[derive(Debug, Clone)]
+pub struct Term {
+    pub sqrt2_power: i32,
+    pub value: Complex,
+    pub factors: Vec<Factor>,
+}
+
+impl Term {
+    pub fn one() -> Self {
+        Term {
+            sqrt2_power: 0,
+            value: Complex::new(1.0, 0.0),
+            factors: Vec::new(),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.value.re == 0.0 && self.value.im == 0.0
+    }
+
+    pub fn multiply_phase(&mut self, phase: f64) {
+        self.value = self.value * Complex::from_phase(phase);
+    }
+
+    /// Multiplies by a factor, folding it into `value` when it does not depend on any
+    /// parameter.
+    pub fn multiply(&mut self, factor: Factor) {
+        let constant = match &factor {
+            Factor::Phase { parity, .. } | Factor::OnePlusPhase { parity, .. } => parity.is_empty(),
+            Factor::SignProduct { p, q, .. } => p.is_empty() && q.is_empty(),
+        };
+
+        if constant {
+            self.value = self.value * factor.evaluate(&[]);
+        } else {
+            self.factors.push(factor);
+        }
+    }
+
+    pub fn evaluate(&self, assignment: &[bool]) -> Complex {
+        let mut value = self.value * SQRT_2.powi(self.sqrt2_power);
+        for factor in &self.factors {
+            value = value * factor.evaluate(assignment);
+        }
+        value
+    }
+}
+
+/// A diagram simplified once into a sum of parameter-dependent terms.
+///
+/// Evaluating it for a parameter assignment only multiplies out each term, so many
+/// bitstrings can be evaluated without rewriting the diagram again.
+#[derive(Debug, Clone)]
+pub struct ParametricScalar {
+    pub num_params: usize,
+    pub terms: Vec<Term>,
+}
+
+impl ParametricScalar {
+    pub fn evaluate(&self, assignment: &[bool]) -> Complex {
+        self.try_evaluate(assignment)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `evaluate`, but returns an error when `assignment` has fewer than `num_params`
+    /// values.
+    pub fn try_evaluate(&self, assignment: &[bool]) -> Result<Complex, QuojoError> {
+        if assignment.len() < self.num_params {
+            return Err(QuojoError::CountMismatch {
+                kind: "parameter values".to_string(),
+                expected: self.num_params,
+                found: assignment.len(),
+            });
+        }
+
+        Ok(self.terms.iter().fold(Complex::new(0.0, 0.0), |acc, term| {
+            acc + term.evaluate(assignment)
+        }))
+    }
+
+    pub fn num_terms(&self) -> usize {
+        self.terms.len()
+    }
+}
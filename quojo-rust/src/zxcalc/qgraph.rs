@@ -0,0 +1,635 @@
+//! The `.qgraph` JSON format PyZX and ZXLive use to save diagrams.
+
+use super::graph::*;
+use super::scalar::Scalar;
+use super::tikz::{TikzConfig, calculate_professional_layout};
+use crate::linalg::vector::Complex;
+use crate::qcore::phase::Phase;
+use crate::utils::json::{Json, JsonError};
+use std::collections::{HashMap, HashSet};
+use std::f64::consts::PI;
+use std::fmt::Display;
+
+/// Vertex coordinates as `.qgraph` stores them: one unit per layer across and per row
+/// down, with y growing upwards.
+pub type Positions = HashMap<NodeIndex, (f64, f64)>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QGraphError {
+    /// The file is not valid JSON.
+    Json {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// A field is missing or has the wrong type. `path` names it from the top of the file,
+    /// as in `node_vertices.v0.annotation.coord`.
+    InvalidField {
+        path: String,
+        expected: &'static str,
+    },
+    UnknownVertexType {
+        vertex: String,
+        kind: String,
+    },
+    InvalidPhase {
+        vertex: String,
+        value: String,
+    },
+    /// An edge names a vertex the file does not define.
+    UnknownVertex(String),
+    DuplicateVertex(String),
+    /// Two wire vertices are the same input or the same output.
+    DuplicateBoundary {
+        vertex: String,
+        index: usize,
+    },
+    /// A Hadamard vertex needs exactly two edges to stand for a Hadamard edge.
+    HadamardDegree {
+        vertex: String,
+        degree: usize,
+    },
+    InvalidScalar(String),
+    /// The spider's phase has variables, which `.qgraph` files cannot hold.
+    SymbolicPhase(NodeIndex),
+    /// The spider carries boolean parameters, which `.qgraph` files cannot hold.
+    ParametricSpider(NodeIndex),
+    /// An input or output that is not a boundary; `.qgraph` files only mark wire vertices
+    /// as inputs and outputs.
+    NonBoundaryTerminal(NodeIndex),
+}
+
+impl Display for QGraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QGraphError::Json {
+                line,
+                column,
+                message,
+            } => write!(f, "line {}, column {}: {}", line, column, message),
+            QGraphError::InvalidField { path, expected } => {
+                write!(f, "Field {} should be {}", path, expected)
+            }
+            QGraphError::UnknownVertexType { vertex, kind } => {
+                write!(f, "Vertex {} has unknown type {}", vertex, kind)
+            }
+            QGraphError::InvalidPhase { vertex, value } => {
+                write!(f, "Vertex {} has invalid phase '{}'", vertex, value)
+            }
+            QGraphError::UnknownVertex(name) => write!(f, "Edge names unknown vertex {}", name),
+            QGraphError::DuplicateVertex(name) => write!(f, "Vertex {} is defined twice", name),
+            QGraphError::DuplicateBoundary { vertex, index } => {
+                write!(f, "Vertex {} repeats boundary index {}", vertex, index)
+            }
+            QGraphError::HadamardDegree { vertex, degree } => write!(
+                f,
+                "Hadamard vertex {} has {} edges instead of 2",
+                vertex, degree
+            ),
+            QGraphError::InvalidScalar(scalar) => write!(f, "Invalid scalar {}", scalar),
+            QGraphError::SymbolicPhase(node) => {
+                write!(f, "Spider {} has a symbolic phase", node.0)
+            }
+            QGraphError::ParametricSpider(node) => {
+                write!(f, "Spider {} has boolean parameters", node.0)
+            }
+            QGraphError::NonBoundaryTerminal(node) => {
+                write!(f, "Input or output {} is not a boundary", node.0)
+            }
+        }
+    }
+}
+
+impl std::error::Error for QGraphError {}
+
+impl From<JsonError> for QGraphError {
+    fn from(error: JsonError) -> Self {
+        QGraphError::Json {
+            line: error.line,
+            column: error.column,
+            message: error.message,
+        }
+    }
+}
+
+impl ZXGraph {
+    /// Writes the diagram as a `.qgraph` file, placed as `generate_tikz` would draw it.
+    pub fn to_qgraph(&self) -> Result<String, QGraphError> {
+        self.to_qgraph_with_positions(&HashMap::new())
+    }
+
+    /// Writes the diagram as a `.qgraph` file, with vertices at `positions` where given and
+    /// as `generate_tikz` would draw them otherwise.
+    ///
+    /// Boundaries become wire vertices, numbered as inputs and outputs in the order of
+    /// `ordered_inputs` and `ordered_outputs`, and each Hadamard edge becomes a Hadamard
+    /// vertex halfway along it, as PyZX writes them.
+    pub fn to_qgraph_with_positions(&self, positions: &Positions) -> Result<String, QGraphError> {
+        let config = TikzConfig::default();
+        let layout = calculate_professional_layout(self, &config);
+        let position = |node: NodeIndex| {
+            positions.get(&node).copied().unwrap_or_else(|| {
+                let (x, y) = layout.get(&node).copied().unwrap_or_default();
+                (x / config.layer_spacing, y / config.node_spacing)
+            })
+        };
+        let coord = |(x, y): (f64, f64)| Json::Array(vec![Json::Number(x), Json::Number(y)]);
+        let (inputs, outputs) = (self.ordered_inputs(), self.ordered_outputs());
+
+        let mut names = HashMap::new();
+        let mut wire_vertices = Vec::new();
+        let mut node_vertices = Vec::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let Some(node) = node else { continue };
+            let index = NodeIndex(i);
+            if !node.params.is_empty() {
+                return Err(QGraphError::ParametricSpider(index));
+            }
+            let mut annotation = vec![("coord", coord(position(index)))];
+
+            if node.spider_type == SpiderType::Boundary {
+                let name = format!("b{}", wire_vertices.len());
+                annotation.insert(0, ("boundary", Json::Bool(true)));
+                if let Some(k) = inputs.iter().position(|&n| n == index) {
+                    annotation.push(("input", Json::Number(k as f64)));
+                }
+                if let Some(k) = outputs.iter().position(|&n| n == index) {
+                    annotation.push(("output", Json::Number(k as f64)));
+                }
+                let vertex = Json::object(vec![("annotation", Json::object(annotation))]);
+                wire_vertices.push((name.clone(), vertex));
+                names.insert(index, name);
+                continue;
+            }
+
+            if self.is_input_node(index) || self.is_output_node(index) {
+                return Err(QGraphError::NonBoundaryTerminal(index));
+            }
+            let kind = if node.spider_type == SpiderType::Z {
+                "Z"
+            } else {
+                "X"
+            };
+            let mut data = vec![("type", Json::String(kind.to_string()))];
+            match phase_value(&node.phase) {
+                None => return Err(QGraphError::SymbolicPhase(index)),
+                Some(value) if value.is_empty() => {}
+                Some(value) => data.push(("value", Json::String(value))),
+            }
+            let name = format!("v{}", node_vertices.len());
+            let vertex = Json::object(vec![
+                ("annotation", Json::object(annotation)),
+                ("data", Json::object(data)),
+            ]);
+            node_vertices.push((name.clone(), vertex));
+            names.insert(index, name);
+        }
+
+        let mut edges = Vec::new();
+        let mut add_edge = |source: &str, target: &str| {
+            let edge = Json::object(vec![
+                ("src", Json::String(source.to_string())),
+                ("tgt", Json::String(target.to_string())),
+            ]);
+            edges.push((format!("e{}", edges.len()), edge));
+        };
+        for edge in self.edges.iter().flatten() {
+            let (a, b) = edge.endpoints;
+            if edge.edge_type == EdgeType::Regular {
+                add_edge(&names[&a], &names[&b]);
+                continue;
+            }
+            let ((x1, y1), (x2, y2)) = (position(a), position(b));
+            let name = format!("v{}", node_vertices.len());
+            let vertex = Json::object(vec![
+                (
+                    "annotation",
+                    Json::object(vec![("coord", coord(((x1 + x2) / 2.0, (y1 + y2) / 2.0)))]),
+                ),
+                (
+                    "data",
+                    Json::object(vec![
+                        ("type", Json::String("hadamard".to_string())),
+                        ("is_edge", Json::String("true".to_string())),
+                    ]),
+                ),
+            ]);
+            node_vertices.push((name.clone(), vertex));
+            add_edge(&names[&a], &name);
+            add_edge(&name, &names[&b]);
+        }
+
+        let file = Json::Object(vec![
+            ("wire_vertices".to_string(), Json::Object(wire_vertices)),
+            ("node_vertices".to_string(), Json::Object(node_vertices)),
+            ("undir_edges".to_string(), Json::Object(edges)),
+            (
+                "scalar".to_string(),
+                Json::String(scalar_json(&self.scalar).compact()),
+            ),
+        ]);
+        Ok(file.pretty() + "\n")
+    }
+
+    /// Reads a `.qgraph` file as written by PyZX, ZXLive or `to_qgraph`, with the vertex
+    /// coordinates it gives.
+    ///
+    /// Inputs and outputs get node indices in the order of their `input` and `output`
+    /// numbers, so `ordered_inputs` and `ordered_outputs` list them as the file does. A
+    /// Hadamard vertex with two edges, or a chain of them, becomes a single edge: a Hadamard
+    /// edge for an odd number of them and a regular edge for an even number.
+    pub fn from_qgraph(source: &str) -> Result<(ZXGraph, Positions), QGraphError> {
+        let file = Json::parse(source)?;
+        if file.as_object().is_none() {
+            return Err(invalid("the file", "an object"));
+        }
+        let section = |key: &str| match file.get(key) {
+            None => Ok(&[][..]),
+            Some(value) => value.as_object().ok_or_else(|| invalid(key, "an object")),
+        };
+
+        let mut vertices: Vec<Vertex> = Vec::new();
+        for (name, attributes) in section("wire_vertices")? {
+            let path = format!("wire_vertices.{}", name);
+            let annotation = attributes.get("annotation");
+            let index = |key: &str| -> Result<Option<usize>, QGraphError> {
+                match annotation.and_then(|a| a.get(key)) {
+                    None => Ok(None),
+                    Some(value) => value
+                        .as_f64()
+                        .filter(|index| index.fract() == 0.0 && *index >= 0.0)
+                        .map(|index| Some(index as usize))
+                        .ok_or_else(|| {
+                            invalid(&format!("{}.annotation.{}", path, key), "an index")
+                        }),
+                }
+            };
+            let (input, output) = (index("input")?, index("output")?);
+            vertices.push(Vertex {
+                name: name.clone(),
+                kind: VertexKind::Spider(SpiderType::Boundary, Phase::ZERO),
+                coord: coordinates(attributes, &path)?,
+                input,
+                output,
+            });
+        }
+        for (name, attributes) in section("node_vertices")? {
+            let path = format!("node_vertices.{}", name);
+            let data = attributes.get("data");
+            let field = |key: &str| -> Result<Option<&str>, QGraphError> {
+                match data.and_then(|d| d.get(key)) {
+                    None => Ok(None),
+                    Some(Json::Bool(value)) => Ok(Some(if *value { "true" } else { "false" })),
+                    Some(value) => value
+                        .as_str()
+                        .map(Some)
+                        .ok_or_else(|| invalid(&format!("{}.data.{}", path, key), "a string")),
+                }
+            };
+            let value = field("value")?.unwrap_or("");
+            let phase = parse_phase(value).ok_or_else(|| QGraphError::InvalidPhase {
+                vertex: name.clone(),
+                value: value.to_string(),
+            })?;
+            let kind = match field("type")?.unwrap_or("Z") {
+                "Z" => VertexKind::Spider(SpiderType::Z, phase),
+                "X" => VertexKind::Spider(SpiderType::X, phase),
+                "hadamard" => VertexKind::Hadamard,
+                kind => {
+                    return Err(QGraphError::UnknownVertexType {
+                        vertex: name.clone(),
+                        kind: kind.to_string(),
+                    });
+                }
+            };
+            vertices.push(Vertex {
+                name: name.clone(),
+                kind,
+                coord: coordinates(attributes, &path)?,
+                input: None,
+                output: None,
+            });
+        }
+
+        let mut lookup = HashMap::new();
+        for (i, vertex) in vertices.iter().enumerate() {
+            if lookup.insert(vertex.name.as_str(), i).is_some() {
+                return Err(QGraphError::DuplicateVertex(vertex.name.clone()));
+            }
+        }
+        let mut edges = Vec::new();
+        for (name, attributes) in section("undir_edges")? {
+            let end = |key: &str| -> Result<usize, QGraphError> {
+                let vertex = attributes
+                    .get(key)
+                    .and_then(Json::as_str)
+                    .ok_or_else(|| invalid(&format!("undir_edges.{}.{}", name, key), "a string"))?;
+                lookup
+                    .get(vertex)
+                    .copied()
+                    .ok_or_else(|| QGraphError::UnknownVertex(vertex.to_string()))
+            };
+            edges.push((end("src")?, end("tgt")?));
+        }
+
+        let mut graph = ZXGraph::new();
+        let mut positions = Positions::new();
+        let mut nodes: Vec<Option<NodeIndex>> = vec![None; vertices.len()];
+        // Inputs first and outputs next, so that node order is boundary order
+        let mut order: Vec<usize> = (0..vertices.len()).collect();
+        order.sort_by_key(|&i| match (vertices[i].input, vertices[i].output) {
+            (Some(k), _) => (0, k),
+            (None, Some(k)) => (1, k),
+            (None, None) => (2, 0),
+        });
+        let mut seen = HashSet::new();
+        for i in order {
+            let vertex = &vertices[i];
+            let VertexKind::Spider(spider_type, phase) = &vertex.kind else {
+                continue;
+            };
+            let node = graph.add_node(spider_type.clone(), phase.clone());
+            for (is_input, index) in [(true, vertex.input), (false, vertex.output)] {
+                let Some(index) = index else { continue };
+                if !seen.insert((is_input, index)) {
+                    return Err(QGraphError::DuplicateBoundary {
+                        vertex: vertex.name.clone(),
+                        index,
+                    });
+                }
+                if is_input {
+                    graph.set_as_input(node);
+                } else {
+                    graph.set_as_output(node);
+                }
+            }
+            if let Some(coord) = vertex.coord {
+                positions.insert(node, coord);
+            }
+            nodes[i] = Some(node);
+        }
+
+        let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); vertices.len()];
+        for &(a, b) in &edges {
+            neighbors[a].push(b);
+            neighbors[b].push(a);
+        }
+        for (i, vertex) in vertices.iter().enumerate() {
+            if nodes[i].is_none() && neighbors[i].len() != 2 {
+                return Err(QGraphError::HadamardDegree {
+                    vertex: vertex.name.clone(),
+                    degree: neighbors[i].len(),
+                });
+            }
+        }
+
+        let mut walked = vec![false; vertices.len()];
+        for &(a, b) in &edges {
+            let (start, first) = match (nodes[a], nodes[b]) {
+                (Some(na), Some(nb)) => {
+                    graph.add_edge(na, nb, EdgeType::Regular);
+                    continue;
+                }
+                (Some(_), None) => (a, b),
+                (None, Some(_)) => (b, a),
+                (None, None) => continue,
+            };
+            if walked[first] {
+                continue;
+            }
+            // Follow the chain of Hadamard vertices to the spider at its other end
+            let (mut previous, mut current, mut hadamards) = (start, first, 0);
+            while nodes[current].is_none() {
+                walked[current] = true;
+                hadamards += 1;
+                let [x, y] = neighbors[current][..] else {
+                    unreachable!("Hadamard vertices have two neighbours")
+                };
+                let next = if x == previous { y } else { x };
+                (previous, current) = (current, next);
+            }
+            let edge_type = if hadamards % 2 == 1 {
+                EdgeType::Hadamard
+            } else {
+                EdgeType::Regular
+            };
+            graph.add_edge(nodes[start].unwrap(), nodes[current].unwrap(), edge_type);
+        }
+        // Hadamard vertices only reachable from each other form a loop with no spider
+        if let Some(i) = (0..vertices.len()).find(|&i| nodes[i].is_none() && !walked[i]) {
+            return Err(QGraphError::HadamardDegree {
+                vertex: vertices[i].name.clone(),
+                degree: 2,
+            });
+        }
+
+        if let Some(scalar) = file.get("scalar") {
+            graph.scalar = parse_scalar(scalar)?;
+        }
+        Ok((graph, positions))
+    }
+}
+
+struct Vertex {
+    name: String,
+    kind: VertexKind,
+    coord: Option<(f64, f64)>,
+    input: Option<usize>,
+    output: Option<usize>,
+}
+
+enum VertexKind {
+    Spider(SpiderType, Phase),
+    Hadamard,
+}
+
+fn invalid(path: &str, expected: &'static str) -> QGraphError {
+    QGraphError::InvalidField {
+        path: path.to_string(),
+        expected,
+    }
+}
+
+/// The `annotation.coord` of a vertex, if it has one.
+fn coordinates(attributes: &Json, path: &str) -> Result<Option<(f64, f64)>, QGraphError> {
+    let Some(coord) = attributes.get("annotation").and_then(|a| a.get("coord")) else {
+        return Ok(None);
+    };
+    match coord.as_array() {
+        Some([x, y]) => match (x.as_f64(), y.as_f64()) {
+            (Some(x), Some(y)) => Ok(Some((x, y))),
+            _ => Err(invalid(
+                &format!("{}.annotation.coord", path),
+                "a pair of numbers",
+            )),
+        },
+        _ => Err(invalid(
+            &format!("{}.annotation.coord", path),
+            "a pair of numbers",
+        )),
+    }
+}
+
+/// A phase as PyZX writes it, such as `3\pi/4`, or an empty string for zero. `None` for
+/// phases with variables.
+fn phase_value(phase: &Phase) -> Option<String> {
+    match (phase.as_rational(), phase.as_float()) {
+        (Some((0, _)), _) => Some(String::new()),
+        (Some((numerator, denominator)), _) => {
+            let numerator = match numerator {
+                1 => String::new(),
+                n => n.to_string(),
+            };
+            let denominator = match denominator {
+                1 => String::new(),
+                d => format!("/{}", d),
+            };
+            Some(format!("{}\\pi{}", numerator, denominator))
+        }
+        (_, Some(radians)) => Some(format!("{}\\pi", radians / PI)),
+        (None, None) => None,
+    }
+}
+
+/// Reads a phase in the forms PyZX accepts: a multiple of π such as `3\pi/4`, `-π/2` or
+/// `pi`, or a bare fraction or decimal counting half-turns, such as `3/4` or `0.25`.
+fn parse_phase(value: &str) -> Option<Phase> {
+    let value: String = value
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '*')
+        .collect();
+    let Some(position) = ["\\pi", "π", "pi"]
+        .iter()
+        .find_map(|pi| value.find(pi).map(|at| (at, pi.len())))
+    else {
+        return match value.as_str() {
+            "" => Some(Phase::ZERO),
+            _ => half_turns(&value),
+        };
+    };
+    let (at, length) = position;
+    let rest = value[..at].to_string() + &value[at + length..];
+    let rest = match rest.as_str() {
+        "" => "1".to_string(),
+        "-" => "-1".to_string(),
+        r if r.starts_with('/') => format!("1{}", r),
+        r if r.starts_with("-/") => format!("-1{}", &r[1..]),
+        r => r.to_string(),
+    };
+    half_turns(&rest)
+}
+
+/// A phase of `value` half-turns, given as an integer, a fraction or a decimal.
+fn half_turns(value: &str) -> Option<Phase> {
+    match value.split_once('/') {
+        Some((numerator, denominator)) => {
+            let numerator: i64 = numerator.parse().ok()?;
+            let denominator: i64 = denominator.parse().ok()?;
+            Phase::try_new(numerator, denominator)
+        }
+        None => match value.parse::<i64>() {
+            Ok(numerator) => Some(Phase::new(numerator, 1)),
+            Err(_) => {
+                let number: f64 = value.parse().ok()?;
+                number.is_finite().then(|| Phase::from_radians(number * PI))
+            }
+        },
+    }
+}
+
+/// The scalar as PyZX's `Scalar.to_json` writes it: `power2` counts factors of √2 and
+/// `phase` half-turns, with anything else in `floatfactor`.
+fn scalar_json(scalar: &Scalar) -> Json {
+    let phase = match Phase::new(scalar.phase as i64, 4).as_rational() {
+        Some((numerator, 1)) => numerator.to_string(),
+        Some((numerator, denominator)) => format!("{}/{}", numerator, denominator),
+        None => unreachable!("scalar phases are multiples of π/4"),
+    };
+    let mut members = vec![
+        ("power2", Json::Number(scalar.sqrt2_power as f64)),
+        ("phase", Json::String(phase)),
+    ];
+    let value = scalar.value;
+    if scalar.is_zero() {
+        members.push(("is_zero", Json::Bool(true)));
+    } else if value.im != 0.0 {
+        // Python's complex() reads this form back
+        let sign = if value.im < 0.0 { "-" } else { "+" };
+        let factor = format!("({}{}{}j)", value.re, sign, value.im.abs());
+        members.push(("floatfactor", Json::String(factor)));
+    } else if value.re != 1.0 {
+        members.push(("floatfactor", Json::Number(value.re)));
+    }
+    Json::object(members)
+}
+
+/// Reads a scalar written by `scalar_json` or PyZX, either as an object or as a string
+/// holding one.
+fn parse_scalar(scalar: &Json) -> Result<Scalar, QGraphError> {
+    let fail = || QGraphError::InvalidScalar(scalar.compact());
+    let parsed;
+    let scalar = match scalar {
+        Json::String(text) => {
+            parsed = Json::parse(text).map_err(|_| fail())?;
+            &parsed
+        }
+        _ => scalar,
+    };
+    if scalar.as_object().is_none() {
+        return Err(fail());
+    }
+
+    let mut result = Scalar::one();
+    if let Some(power) = scalar.get("power2") {
+        let power = power
+            .as_f64()
+            .filter(|p| p.fract() == 0.0)
+            .ok_or_else(fail)?;
+        result.multiply_sqrt2_power(power as i32);
+    }
+    if let Some(phase) = scalar.get("phase") {
+        let phase = match phase {
+            Json::Number(number) => half_turns(&number.to_string()),
+            Json::String(text) => half_turns(text),
+            _ => None,
+        };
+        result.multiply_phase(phase.ok_or_else(fail)?);
+    }
+    match scalar.get("floatfactor") {
+        None => {}
+        Some(Json::Number(number)) => result.multiply_complex(Complex::new(*number, 0.0)),
+        Some(Json::String(text)) => result.multiply_complex(parse_complex(text).ok_or_else(fail)?),
+        Some(_) => return Err(fail()),
+    }
+    if scalar.get("is_zero") == Some(&Json::Bool(true)) {
+        result.multiply_complex(Complex::new(0.0, 0.0));
+    }
+    Ok(result)
+}
+
+/// Reads a complex number as Python writes one, such as `(0.5-1j)`, `2j` or `1.5`.
+fn parse_complex(text: &str) -> Option<Complex> {
+    let text = text.trim().trim_start_matches('(').trim_end_matches(')');
+    let Some(imaginary) = text.strip_suffix('j') else {
+        return text.parse().ok().map(|re| Complex::new(re, 0.0));
+    };
+    // The sign between the parts, skipping a leading sign and exponent signs
+    let split = imaginary
+        .char_indices()
+        .skip(1)
+        .filter(|&(i, c)| {
+            matches!(c, '+' | '-') && !matches!(imaginary.as_bytes()[i - 1], b'e' | b'E')
+        })
+        .map(|(i, _)| i)
+        .last();
+    let (re, im) = match split {
+        Some(i) => (imaginary[..i].parse().ok()?, &imaginary[i..]),
+        None => (0.0, imaginary),
+    };
+    let im = match im {
+        "" | "+" => 1.0,
+        "-" => -1.0,
+        im => im.parse().ok()?,
+    };
+    Some(Complex::new(re, im))
+}
@@ -0,0 +1,571 @@
+use super::graph::*;
+use crate::qcore::phase::Phase;
+
+impl ZXGraph {
+    /// Removes phase-free Z and X spiders with two legs, joining their neighbours directly.
+    /// Two Hadamard legs cancel into a regular edge. Regular self-loops on spiders are
+    /// dropped as well. Returns the number of rewrites performed.
+    pub fn remove_identities(&mut self) -> usize {
+        self.apply_until_fixpoint(Self::try_remove_identity)
+    }
+
+    /// Removes Hadamard self-loops, which add π to a spider, and pairs of parallel
+    /// Hadamard edges between spiders of the same colour. Returns the number of rewrites
+    /// performed.
+    pub fn cancel_hadamard_edges(&mut self) -> usize {
+        self.apply_until_fixpoint(Self::try_cancel_hadamard)
+    }
+
+    /// Turns every X spider into a Z spider with Hadamard legs. Returns the number of
+    /// spiders changed.
+    pub fn color_change(&mut self) -> usize {
+        self.apply_until_fixpoint(|graph, node| {
+            let is_x = matches!(graph.node_data(node), Some((SpiderType::X, _)));
+            if is_x {
+                graph.change_color(node);
+            }
+            is_x
+        })
+    }
+
+    /// Swaps the colour of a spider and toggles the type of each of its edges.
+    pub fn change_color(&mut self, node: NodeIndex) {
+        let Some(data) = self.nodes[node.0].as_mut() else {
+            return;
+        };
+        data.spider_type = match data.spider_type {
+            SpiderType::Z => SpiderType::X,
+            SpiderType::X => SpiderType::Z,
+            SpiderType::Boundary => return,
+        };
+
+        for (edge_idx, other, _) in self.incident_edges(node) {
+            if other != node
+                && let Some(edge) = self.edges[edge_idx.0].as_mut()
+            {
+                edge.edge_type = toggle(&edge.edge_type);
+            }
+        }
+    }
+
+    /// Pushes two-legged π spiders through a neighbouring spider of the other colour,
+    /// when every copy that appears on the far side can be fused into an existing spider.
+    /// Returns the number of π spiders removed.
+    pub fn pi_commute(&mut self) -> usize {
+        self.apply_until_fixpoint(Self::try_pi_commute)
+    }
+
+    /// Copies one-legged Pauli spiders (computational or Hadamard basis states) through
+    /// a phase-carrying spider of the other colour. Returns the number of states copied.
+    pub fn copy_states(&mut self) -> usize {
+        self.apply_until_fixpoint(Self::try_copy_state)
+    }
+
+    /// Applies the bialgebra rule to phase-free Z and X spiders joined by a single
+    /// regular edge whose other neighbours have the opposite colour, replacing the pair
+    /// by a complete bipartite connection between those neighbours. Returns the number of
+    /// pairs removed.
+    pub fn bialgebra(&mut self) -> usize {
+        self.apply_until_fixpoint(Self::try_bialgebra)
+    }
+
+    /// Removes pairs of parallel regular edges between a Z and an X spider. Returns the
+    /// number of pairs removed.
+    pub fn hopf(&mut self) -> usize {
+        self.apply_until_fixpoint(Self::try_hopf)
+    }
+
+    /// Sweeps `rule` over every node until a full sweep changes nothing.
+    pub(crate) fn apply_until_fixpoint(
+        &mut self,
+        mut rule: impl FnMut(&mut Self, NodeIndex) -> bool,
+    ) -> usize {
+        let mut count = 0;
+
+        loop {
+            let mut fired = false;
+            for i in 0..self.nodes.len() {
+                if self.nodes[i].is_some() && rule(self, NodeIndex(i)) {
+                    count += 1;
+                    fired = true;
+                }
+            }
+
+            if !fired {
+                break;
+            }
+        }
+
+        count
+    }
+
+    /// Every edge at `node` as `(edge, other endpoint, type)`. A self-loop is listed once,
+    /// with `node` as its other endpoint.
+    pub(crate) fn incident_edges(&self, node: NodeIndex) -> Vec<(EdgeIndex, NodeIndex, EdgeType)> {
+        let Some(data) = &self.nodes[node.0] else {
+            return Vec::new();
+        };
+
+        let mut incident: Vec<(EdgeIndex, NodeIndex, EdgeType)> = data
+            .edges
+            .iter()
+            .filter_map(|&edge_idx| {
+                let edge = self.edges[edge_idx.0].as_ref()?;
+                let other = if edge.endpoints.0 == node {
+                    edge.endpoints.1
+                } else {
+                    edge.endpoints.0
+                };
+                Some((edge_idx, other, edge.edge_type.clone()))
+            })
+            .collect();
+        incident.sort_by_key(|(edge_idx, _, _)| edge_idx.0);
+        incident
+    }
+
+    /// Colour and phase of a Z or X spider without boolean parameters.
+    pub(crate) fn plain_spider(&self, node: NodeIndex) -> Option<(SpiderType, Phase)> {
+        let data = self.nodes[node.0].as_ref()?;
+        match data.spider_type {
+            SpiderType::Boundary => None,
+            _ if !data.params.is_empty() => None,
+            _ => Some((data.spider_type.clone(), data.phase.clone())),
+        }
+    }
+
+    pub(crate) fn spider_type(&self, node: NodeIndex) -> Option<SpiderType> {
+        self.node_data(node)
+            .map(|(spider_type, _)| spider_type.clone())
+    }
+
+    pub(crate) fn add_phase(&mut self, node: NodeIndex, phase: Phase) {
+        if let Some(data) = self.nodes[node.0].as_mut() {
+            data.phase += phase;
+        }
+    }
+
+    fn try_remove_identity(&mut self, node: NodeIndex) -> bool {
+        let Some((_, phase)) = self.plain_spider(node) else {
+            return false;
+        };
+        let incident = self.incident_edges(node);
+
+        if let Some((edge_idx, _, _)) = incident
+            .iter()
+            .find(|(_, other, edge_type)| *other == node && *edge_type == EdgeType::Regular)
+        {
+            self.remove_edge(*edge_idx);
+            return true;
+        }
+
+        if !phase.is_zero() || incident.len() != 2 {
+            return false;
+        }
+        let (_, node1, type1) = incident[0].clone();
+        let (_, node2, type2) = incident[1].clone();
+        if node1 == node || node2 == node {
+            return false;
+        }
+
+        self.remove_node(node);
+        let edge_type = if type1 == type2 {
+            EdgeType::Regular
+        } else {
+            EdgeType::Hadamard
+        };
+        if node1 != node2 || edge_type == EdgeType::Hadamard {
+            self.add_edge(node1, node2, edge_type);
+        }
+        true
+    }
+
+    fn try_cancel_hadamard(&mut self, node: NodeIndex) -> bool {
+        let Some(spider_type) = self.spider_type(node) else {
+            return false;
+        };
+        if spider_type == SpiderType::Boundary {
+            return false;
+        }
+        let incident = self.incident_edges(node);
+
+        if let Some((edge_idx, _, _)) = incident
+            .iter()
+            .find(|(_, other, edge_type)| *other == node && *edge_type == EdgeType::Hadamard)
+        {
+            // A Hadamard self-loop adds π to the spider and a factor of 1/√2
+            self.remove_edge(*edge_idx);
+            self.add_phase(node, Phase::PI);
+            self.scalar.multiply_sqrt2_power(-1);
+            return true;
+        }
+
+        for (i, (edge_idx, other, edge_type)) in incident.iter().enumerate() {
+            if *edge_type != EdgeType::Hadamard
+                || self.spider_type(*other) != Some(spider_type.clone())
+            {
+                continue;
+            }
+            let parallel = incident[i + 1..]
+                .iter()
+                .find(|(_, o, t)| o == other && *t == EdgeType::Hadamard);
+            if let Some((parallel_idx, _, _)) = parallel {
+                self.remove_edge(*edge_idx);
+                self.remove_edge(*parallel_idx);
+                self.scalar.multiply_sqrt2_power(-2);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn try_pi_commute(&mut self, node: NodeIndex) -> bool {
+        let Some((pi_type, phase)) = self.plain_spider(node) else {
+            return false;
+        };
+        let incident = self.incident_edges(node);
+        if phase != Phase::PI || incident.len() != 2 {
+            return false;
+        }
+
+        for (through, rest) in [(0, 1), (1, 0)] {
+            let (through_edge, target, ref through_type) = incident[through];
+            let (_, far_node, ref far_type) = incident[rest];
+            if *through_type != EdgeType::Regular
+                || target == node
+                || far_node == node
+                || far_node == target
+            {
+                continue;
+            }
+            let Some((target_type, alpha)) = self.plain_spider(target) else {
+                continue;
+            };
+            // The rewrite contributes e^(iα) to the scalar, which cannot hold a parameter
+            if target_type == pi_type || alpha.is_symbolic() {
+                continue;
+            }
+
+            // Each copy of the π spider must be absorbed by the spider on the other leg
+            let mut legs = Vec::new();
+            for (edge_idx, other, edge_type) in self.incident_edges(target) {
+                if edge_idx == through_edge {
+                    continue;
+                }
+                let absorbed = other != target
+                    && match (self.spider_type(other), edge_type) {
+                        (Some(t), EdgeType::Regular) => t == pi_type,
+                        (Some(t), EdgeType::Hadamard) => t == target_type,
+                        (None, _) => false,
+                    };
+                if !absorbed {
+                    legs.clear();
+                    break;
+                }
+                legs.push(other);
+            }
+            if legs.is_empty() {
+                continue;
+            }
+
+            let far_type = far_type.clone();
+            self.remove_node(node);
+            self.add_edge(target, far_node, far_type);
+            for leg in legs {
+                self.add_phase(leg, Phase::PI);
+            }
+            self.add_phase(target, alpha.clone() * -2);
+            self.scalar.multiply_phase(alpha);
+            return true;
+        }
+
+        false
+    }
+
+    fn try_copy_state(&mut self, node: NodeIndex) -> bool {
+        let Some((state_type, phase)) = self.plain_spider(node) else {
+            return false;
+        };
+        let incident = self.incident_edges(node);
+        if !phase.is_pauli() {
+            return false;
+        }
+        let bit = phase == Phase::PI;
+        if incident.len() != 1 || incident[0].1 == node {
+            return false;
+        }
+
+        let (_, target, ref edge_type) = incident[0];
+        // Seen through a Hadamard edge, the state has the other colour
+        let state_type = if *edge_type == EdgeType::Hadamard {
+            other_color(&state_type)
+        } else {
+            state_type
+        };
+        let Some((target_type, alpha)) = self.plain_spider(target) else {
+            return false;
+        };
+        let target_edges = self.incident_edges(target);
+        if target_type == state_type
+            || target_edges.iter().any(|(_, other, _)| *other == target)
+            || (bit && alpha.is_symbolic())
+        {
+            return false;
+        }
+
+        let legs: Vec<(NodeIndex, EdgeType)> = target_edges
+            .into_iter()
+            .filter(|(_, other, _)| *other != node)
+            .map(|(_, other, edge_type)| (other, edge_type))
+            .collect();
+        let degree = legs.len() as i32 + 1;
+
+        self.remove_node(node);
+        self.remove_node(target);
+        for (other, edge_type) in legs {
+            let copy = self.add_node(state_type.clone(), phase.clone());
+            self.add_edge(copy, other, edge_type);
+        }
+
+        self.scalar.multiply_sqrt2_power(2 - degree);
+        if bit {
+            self.scalar.multiply_phase(alpha);
+        }
+        true
+    }
+
+    fn try_bialgebra(&mut self, node: NodeIndex) -> bool {
+        if !self.is_phase_free(node, SpiderType::Z) {
+            return false;
+        }
+        let z_edges = self.incident_edges(node);
+
+        for (_, x_node, edge_type) in &z_edges {
+            if *edge_type != EdgeType::Regular || !self.is_phase_free(*x_node, SpiderType::X) {
+                continue;
+            }
+            let x_edges = self.incident_edges(*x_node);
+
+            let Some(x_neighbors) = self.bialgebra_side(&z_edges, node, *x_node, SpiderType::X)
+            else {
+                continue;
+            };
+            let Some(z_neighbors) = self.bialgebra_side(&x_edges, *x_node, node, SpiderType::Z)
+            else {
+                continue;
+            };
+
+            let (n, m) = (x_neighbors.len() as i32, z_neighbors.len() as i32);
+            self.remove_node(node);
+            self.remove_node(*x_node);
+            for &a in &x_neighbors {
+                for &b in &z_neighbors {
+                    self.add_edge(a, b, EdgeType::Regular);
+                }
+            }
+            self.scalar.multiply_sqrt2_power((n - 1) * (m - 1));
+            return true;
+        }
+
+        false
+    }
+
+    fn is_phase_free(&self, node: NodeIndex, spider_type: SpiderType) -> bool {
+        matches!(self.plain_spider(node), Some((t, phase)) if t == spider_type && phase.is_zero())
+    }
+
+    /// Neighbours of `node` other than `partner` for the bialgebra rule: each must be a
+    /// distinct spider of `neighbor_type` behind a regular edge, and `partner` must be
+    /// joined to `node` by exactly one edge.
+    fn bialgebra_side(
+        &self,
+        edges: &[(EdgeIndex, NodeIndex, EdgeType)],
+        node: NodeIndex,
+        partner: NodeIndex,
+        neighbor_type: SpiderType,
+    ) -> Option<Vec<NodeIndex>> {
+        let mut neighbors = Vec::new();
+        let mut partner_edges = 0;
+
+        for (_, other, edge_type) in edges {
+            if *other == partner {
+                partner_edges += 1;
+                continue;
+            }
+            if *other == node
+                || *edge_type != EdgeType::Regular
+                || self.spider_type(*other) != Some(neighbor_type.clone())
+                || neighbors.contains(other)
+            {
+                return None;
+            }
+            neighbors.push(*other);
+        }
+
+        (partner_edges == 1).then_some(neighbors)
+    }
+
+    fn try_hopf(&mut self, node: NodeIndex) -> bool {
+        let Some(spider_type) = self.spider_type(node) else {
+            return false;
+        };
+        if spider_type == SpiderType::Boundary {
+            return false;
+        }
+        let opposite = other_color(&spider_type);
+        let incident = self.incident_edges(node);
+
+        for (i, (edge_idx, other, edge_type)) in incident.iter().enumerate() {
+            if *edge_type != EdgeType::Regular || self.spider_type(*other) != Some(opposite.clone())
+            {
+                continue;
+            }
+            let parallel = incident[i + 1..]
+                .iter()
+                .find(|(_, o, t)| o == other && *t == EdgeType::Regular);
+            if let Some((parallel_idx, _, _)) = parallel {
+                self.remove_edge(*edge_idx);
+                self.remove_edge(*parallel_idx);
+                self.scalar.multiply_sqrt2_power(-2);
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+/// The edge rewrites of local complementation and pivoting on spiders joined by Hadamard
+/// edges, with their √2 factors. `ZXGraph` and the simulator's reduced graph supply the
+/// edges and the scalar; the phases are left to the caller.
+pub(crate) trait HadamardEdges {
+    type Node: Copy + PartialEq;
+
+    fn hadamard_neighbors(&self, node: Self::Node) -> Vec<Self::Node>;
+
+    /// Toggles the Hadamard edge between `a` and `b`, returning whether it was added.
+    fn toggle_hadamard_edge(&mut self, a: Self::Node, b: Self::Node) -> bool;
+
+    /// Removes `node` with its edges.
+    fn remove_spider(&mut self, node: Self::Node);
+
+    fn multiply_sqrt2_power(&mut self, power: i32);
+
+    /// Multiplies every pair in `a` x `b` into the diagram as a factor of (-1)^(x_a x_b).
+    /// An added edge needs √2 to cancel its own 1/√2, a removed one leaves 1/√2 behind.
+    fn toggle_hadamard_edges(&mut self, a: &[Self::Node], b: &[Self::Node]) {
+        for &x in a {
+            for &y in b {
+                if x == y {
+                    continue;
+                }
+                let power = if self.toggle_hadamard_edge(x, y) {
+                    1
+                } else {
+                    -1
+                };
+                self.multiply_sqrt2_power(power);
+            }
+        }
+    }
+
+    /// Removes `node` and complements the edges among its n neighbours, which are returned.
+    /// Summing out the spider leaves √2^(1−n); a ±π/2 spider also gives its neighbours ∓π/2
+    /// and the scalar e^(±iπ/4), which the caller adds.
+    fn complement_neighborhood(&mut self, node: Self::Node) -> Vec<Self::Node> {
+        let neighbors = self.hadamard_neighbors(node);
+        self.remove_spider(node);
+        for (i, &a) in neighbors.iter().enumerate() {
+            self.toggle_hadamard_edges(&[a], &neighbors[i + 1..]);
+        }
+        self.multiply_sqrt2_power(1 - neighbors.len() as i32);
+        neighbors
+    }
+
+    /// Removes the adjacent spiders `u` and `v` and complements the edges between the
+    /// neighbours only `u` has, only `v` has and both share, which are returned in that
+    /// order. The caller adds v's phase to the first, u's to the second, both plus π to the
+    /// shared ones, and −1 when both were π.
+    fn pivot_neighborhoods(&mut self, u: Self::Node, v: Self::Node) -> [Vec<Self::Node>; 3] {
+        let neighbors_u = self.hadamard_neighbors(u);
+        let neighbors_v = self.hadamard_neighbors(v);
+        let only_u: Vec<Self::Node> = neighbors_u
+            .iter()
+            .copied()
+            .filter(|w| *w != v && !neighbors_v.contains(w))
+            .collect();
+        let only_v: Vec<Self::Node> = neighbors_v
+            .iter()
+            .copied()
+            .filter(|w| *w != u && !neighbors_u.contains(w))
+            .collect();
+        let shared: Vec<Self::Node> = neighbors_u
+            .iter()
+            .copied()
+            .filter(|w| neighbors_v.contains(w))
+            .collect();
+
+        self.remove_spider(u);
+        self.remove_spider(v);
+        self.toggle_hadamard_edges(&only_u, &only_v);
+        self.toggle_hadamard_edges(&only_u, &shared);
+        self.toggle_hadamard_edges(&only_v, &shared);
+
+        let (a, b, c) = (
+            only_u.len() as i32,
+            only_v.len() as i32,
+            shared.len() as i32,
+        );
+        self.multiply_sqrt2_power(1 - a - b - 2 * c);
+        [only_u, only_v, shared]
+    }
+}
+
+impl HadamardEdges for ZXGraph {
+    type Node = NodeIndex;
+
+    fn hadamard_neighbors(&self, node: NodeIndex) -> Vec<NodeIndex> {
+        self.neighbors(node)
+    }
+
+    fn toggle_hadamard_edge(&mut self, a: NodeIndex, b: NodeIndex) -> bool {
+        let existing = self
+            .incident_edges(a)
+            .into_iter()
+            .find(|(_, other, edge_type)| *other == b && *edge_type == EdgeType::Hadamard);
+
+        match existing {
+            Some((edge_idx, _, _)) => {
+                self.remove_edge(edge_idx);
+                false
+            }
+            None => {
+                self.add_edge(a, b, EdgeType::Hadamard);
+                true
+            }
+        }
+    }
+
+    fn remove_spider(&mut self, node: NodeIndex) {
+        self.remove_node(node);
+    }
+
+    fn multiply_sqrt2_power(&mut self, power: i32) {
+        self.scalar.multiply_sqrt2_power(power);
+    }
+}
+
+pub(crate) fn toggle(edge_type: &EdgeType) -> EdgeType {
+    match edge_type {
+        EdgeType::Regular => EdgeType::Hadamard,
+        EdgeType::Hadamard => EdgeType::Regular,
+    }
+}
+
+fn other_color(spider_type: &SpiderType) -> SpiderType {
+    match spider_type {
+        SpiderType::Z => SpiderType::X,
+        SpiderType::X => SpiderType::Z,
+        SpiderType::Boundary => SpiderType::Boundary,
+    }
+}
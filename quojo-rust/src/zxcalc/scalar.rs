@@ -0,0 +1,97 @@
+use crate::linalg::vector::Complex;
+use crate::qcore::phase::Phase;
+use std::f64::consts::{FRAC_PI_4, SQRT_2};
+use std::ops;
+
+/// A global factor √2^sqrt2_power · e^(i·π·phase/4) · value.
+///
+/// Rewrites mostly contribute powers of √2 and multiples of π/4, which are kept exact.
+/// Anything else, such as 1 + e^(iα) for a non-Clifford α, goes into `value`.
+#[derive(Debug, Clone, Copy)]
+pub struct Scalar {
+    pub sqrt2_power: i32,
+    /// Phase in units of π/4, in 0..8.
+    pub phase: u8,
+    pub value: Complex,
+}
+
+impl Scalar {
+    pub fn one() -> Self {
+        Scalar {
+            sqrt2_power: 0,
+            phase: 0,
+            value: Complex::new(1.0, 0.0),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.value.re == 0.0 && self.value.im == 0.0
+    }
+
+    pub fn multiply_sqrt2_power(&mut self, power: i32) {
+        self.sqrt2_power += power;
+    }
+
+    /// Multiplies by e^(i·phase), exactly when the phase is a multiple of π/4.
+    pub fn multiply_phase(&mut self, phase: impl Into<Phase>) {
+        let phase = phase.into();
+        match eighth_turns(&phase) {
+            Some(k) => self.phase = (self.phase + k) % 8,
+            None => self.value = self.value * Complex::from_phase(phase.to_radians()),
+        }
+    }
+
+    /// Multiplies by 1 + e^(i·phase), the value of a spider without edges.
+    pub fn multiply_one_plus_phase(&mut self, phase: impl Into<Phase>) {
+        let phase = phase.into();
+        match eighth_turns(&phase) {
+            Some(0) => self.sqrt2_power += 2,
+            Some(2) => {
+                self.sqrt2_power += 1;
+                self.phase = (self.phase + 1) % 8;
+            }
+            Some(4) => self.value = Complex::new(0.0, 0.0),
+            Some(6) => {
+                self.sqrt2_power += 1;
+                self.phase = (self.phase + 7) % 8;
+            }
+            _ => self.value = self.value * (Complex::from_phase(phase.to_radians()) + 1.0),
+        }
+    }
+
+    pub fn multiply_complex(&mut self, value: Complex) {
+        self.value = self.value * value;
+    }
+
+    pub fn to_complex(&self) -> Complex {
+        let half = self.sqrt2_power.div_euclid(2);
+        let magnitude = if self.sqrt2_power % 2 == 0 {
+            2f64.powi(half)
+        } else {
+            2f64.powi(half) * SQRT_2
+        };
+        self.value * Complex::from_phase(self.phase as f64 * FRAC_PI_4) * magnitude
+    }
+}
+
+impl ops::Mul for Scalar {
+    type Output = Scalar;
+
+    fn mul(self, rhs: Scalar) -> Scalar {
+        Scalar {
+            sqrt2_power: self.sqrt2_power + rhs.sqrt2_power,
+            phase: (self.phase + rhs.phase) % 8,
+            value: self.value * rhs.value,
+        }
+    }
+}
+
+/// Returns k if the phase is k·π/4 for some k in 0..8.
+fn eighth_turns(phase: &Phase) -> Option<u8> {
+    match phase.as_rational() {
+        Some((numerator, denominator)) if 4 % denominator == 0 => {
+            Some((numerator * 4 / denominator) as u8)
+        }
+        _ => None,
+    }
+}
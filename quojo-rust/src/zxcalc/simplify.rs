@@ -0,0 +1,382 @@
+use super::graph::*;
+use super::rules::{HadamardEdges, toggle};
+use crate::qcore::phase::Phase;
+use std::collections::HashMap;
+
+impl ZXGraph {
+    /// Rewrites the diagram into graph-like form: only Z spiders, joined to each other by
+    /// single Hadamard edges, with every boundary attached to its own spider.
+    pub fn to_graph_like(&mut self) {
+        self.color_change();
+        while self.fuse_spiders() + self.cancel_hadamard_edges() > 0 {}
+
+        for i in 0..self.edges.len() {
+            let regular_loop = matches!(
+                &self.edges[i],
+                Some(edge) if edge.endpoints.0 == edge.endpoints.1 && edge.edge_type == EdgeType::Regular
+            );
+            if regular_loop {
+                self.remove_edge(EdgeIndex(i));
+            }
+        }
+
+        self.separate_boundaries();
+    }
+
+    /// Checks the invariants established by `to_graph_like`.
+    pub fn is_graph_like(&self) -> bool {
+        let mut boundary_spiders = Vec::new();
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            let Some(node) = node else {
+                continue;
+            };
+            let idx = NodeIndex(i);
+            let incident = self.incident_edges(idx);
+
+            match node.spider_type {
+                SpiderType::X => return false,
+                SpiderType::Boundary => {
+                    if incident.len() != 1 || self.spider_type(incident[0].1) != Some(SpiderType::Z)
+                    {
+                        return false;
+                    }
+                    boundary_spiders.push(incident[0].1);
+                }
+                SpiderType::Z => {
+                    let mut seen = Vec::new();
+                    for (_, other, edge_type) in incident {
+                        if self.spider_type(other) == Some(SpiderType::Boundary) {
+                            continue;
+                        }
+                        if other == idx || edge_type != EdgeType::Hadamard || seen.contains(&other)
+                        {
+                            return false;
+                        }
+                        seen.push(other);
+                    }
+                }
+            }
+        }
+
+        let count = boundary_spiders.len();
+        boundary_spiders.sort_by_key(|n| n.0);
+        boundary_spiders.dedup();
+        boundary_spiders.len() == count
+    }
+
+    /// Removes interior spiders with phase ±π/2 by complementing the edges among their
+    /// neighbours. Expects a graph-like diagram. Returns the number of spiders removed.
+    pub fn local_complement(&mut self) -> usize {
+        self.apply_until_fixpoint(Self::try_local_complement)
+    }
+
+    /// Removes pairs of adjacent interior spiders with Pauli phases. Expects a graph-like
+    /// diagram. Returns the number of pairs removed.
+    pub fn pivot(&mut self) -> usize {
+        self.apply_until_fixpoint(Self::try_pivot)
+    }
+
+    /// Pivots an interior Pauli spider with a Pauli neighbour on the boundary, moving the
+    /// boundary onto a new spider first. Returns the number of pivots performed.
+    pub fn pivot_boundary(&mut self) -> usize {
+        self.apply_until_fixpoint(Self::try_pivot_boundary)
+    }
+
+    /// Pivots an interior Pauli spider with a non-Clifford neighbour, moving the
+    /// neighbour's phase onto a new phase gadget first. Returns the number of pivots
+    /// performed.
+    pub fn pivot_gadget(&mut self) -> usize {
+        self.apply_until_fixpoint(Self::try_pivot_gadget)
+    }
+
+    /// Brings the diagram into graph-like form and removes as many Clifford spiders as
+    /// possible with identity removal, fusion, local complementation and pivoting.
+    /// Returns the total number of rewrites performed.
+    pub fn clifford_simp(&mut self) -> usize {
+        self.to_graph_like();
+        let mut count = 0;
+
+        loop {
+            let fired = self.apply_until_fixpoint(Self::try_remove_interior_identity)
+                + self.cancel_hadamard_edges()
+                + self.apply_until_fixpoint(Self::try_remove_isolated)
+                + self.local_complement()
+                + self.pivot()
+                + self.pivot_boundary();
+            if fired == 0 {
+                break;
+            }
+            count += fired;
+        }
+
+        count
+    }
+
+    /// Runs `clifford_simp` and gadget pivots until neither applies. The remaining
+    /// interior spiders are non-Clifford or parts of phase gadgets. Returns the total
+    /// number of rewrites performed.
+    pub fn full_reduce(&mut self) -> usize {
+        let mut count = self.clifford_simp();
+
+        loop {
+            let gadgets = self.pivot_gadget();
+            if gadgets == 0 {
+                break;
+            }
+            count += gadgets + self.clifford_simp();
+        }
+
+        count
+    }
+
+    /// Gives every boundary its own Z spider, inserting identity spiders where two
+    /// boundaries share a spider or are joined directly.
+    fn separate_boundaries(&mut self) {
+        let mut boundaries: Vec<NodeIndex> = self
+            .input_nodes
+            .iter()
+            .chain(&self.output_nodes)
+            .copied()
+            .collect();
+        boundaries.sort_by_key(|n| n.0);
+        let mut owner: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+
+        for boundary in boundaries {
+            if self.spider_type(boundary) != Some(SpiderType::Boundary) {
+                continue;
+            }
+            let Some((edge_idx, other, edge_type)) =
+                self.incident_edges(boundary).into_iter().next()
+            else {
+                continue;
+            };
+
+            if self.spider_type(other) == Some(SpiderType::Boundary) {
+                // b –t– b' becomes b – w1 –H– w2 –(H·t)– b'
+                self.remove_edge(edge_idx);
+                let w1 = self.add_node(SpiderType::Z, Phase::ZERO);
+                let w2 = self.add_node(SpiderType::Z, Phase::ZERO);
+                self.add_edge(boundary, w1, EdgeType::Regular);
+                self.add_edge(w1, w2, EdgeType::Hadamard);
+                self.add_edge(w2, other, toggle(&edge_type));
+                owner.insert(w1, boundary);
+                owner.insert(w2, other);
+            } else if owner.get(&other).is_some_and(|&b| b != boundary) {
+                // v –t– b becomes v –H– w –(H·t)– b
+                self.remove_edge(edge_idx);
+                let w = self.add_node(SpiderType::Z, Phase::ZERO);
+                self.add_edge(other, w, EdgeType::Hadamard);
+                self.add_edge(w, boundary, toggle(&edge_type));
+                owner.insert(w, boundary);
+            } else {
+                owner.insert(other, boundary);
+            }
+        }
+    }
+
+    /// Phase of a Z spider without parameters whose edges all go through single
+    /// Hadamard edges to other Z spiders.
+    fn interior_phase(&self, node: NodeIndex) -> Option<Phase> {
+        let (SpiderType::Z, phase) = self.plain_spider(node)? else {
+            return None;
+        };
+
+        let mut seen = Vec::new();
+        for (_, other, edge_type) in self.incident_edges(node) {
+            if other == node
+                || edge_type != EdgeType::Hadamard
+                || self.spider_type(other) != Some(SpiderType::Z)
+                || seen.contains(&other)
+            {
+                return None;
+            }
+            seen.push(other);
+        }
+
+        Some(phase)
+    }
+
+    pub(crate) fn is_interior_pauli(&self, node: NodeIndex) -> bool {
+        self.interior_phase(node)
+            .is_some_and(|phase| phase.is_pauli())
+    }
+
+    fn try_local_complement(&mut self, node: NodeIndex) -> bool {
+        let Some(phase) = self.interior_phase(node) else {
+            return false;
+        };
+        if !phase.is_proper_clifford() {
+            return false;
+        }
+        let sign = if phase == Phase::new(1, 2) { 1 } else { -1 };
+
+        for a in self.complement_neighborhood(node) {
+            self.add_phase(a, -phase.clone());
+        }
+        self.scalar.multiply_phase(Phase::new(sign, 4));
+        true
+    }
+
+    fn try_pivot(&mut self, node: NodeIndex) -> bool {
+        if !self.is_interior_pauli(node) {
+            return false;
+        }
+        let partner = self
+            .neighbors(node)
+            .into_iter()
+            .find(|&other| self.is_interior_pauli(other));
+
+        match partner {
+            Some(partner) => {
+                self.apply_pivot(node, partner);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn try_pivot_boundary(&mut self, node: NodeIndex) -> bool {
+        if !self.is_interior_pauli(node) {
+            return false;
+        }
+
+        for partner in self.neighbors(node) {
+            let Some((SpiderType::Z, phase)) = self.plain_spider(partner) else {
+                continue;
+            };
+            if !phase.is_pauli() {
+                continue;
+            }
+
+            let incident = self.incident_edges(partner);
+            let boundary_edges: Vec<_> = incident
+                .iter()
+                .filter(|(_, other, _)| self.spider_type(*other) == Some(SpiderType::Boundary))
+                .cloned()
+                .collect();
+            let [(edge_idx, boundary, edge_type)] = boundary_edges.as_slice() else {
+                continue;
+            };
+            let interior = incident.iter().all(|(_, other, t)| {
+                other == boundary
+                    || (*other != partner
+                        && *t == EdgeType::Hadamard
+                        && self.spider_type(*other) == Some(SpiderType::Z))
+            });
+            if !interior {
+                continue;
+            }
+
+            // Move the boundary onto a new identity spider so the partner is interior
+            let (edge_idx, boundary, edge_type) = (*edge_idx, *boundary, edge_type.clone());
+            self.remove_edge(edge_idx);
+            let spider = self.add_node(SpiderType::Z, Phase::ZERO);
+            self.add_edge(partner, spider, EdgeType::Hadamard);
+            self.add_edge(spider, boundary, toggle(&edge_type));
+
+            self.apply_pivot(node, partner);
+            return true;
+        }
+
+        false
+    }
+
+    fn try_pivot_gadget(&mut self, node: NodeIndex) -> bool {
+        if !self.is_interior_pauli(node) || self.is_gadget_part(node) {
+            return false;
+        }
+
+        for partner in self.neighbors(node) {
+            let Some(phase) = self.interior_phase(partner) else {
+                continue;
+            };
+            if phase.is_clifford() || self.is_gadget_part(partner) {
+                continue;
+            }
+
+            // Move the partner's phase onto a gadget: v(α) = v(0) –H– axis(0) –H– leaf(α)
+            let axis = self.add_node(SpiderType::Z, Phase::ZERO);
+            let leaf = self.add_node(SpiderType::Z, phase.clone());
+            self.add_edge(partner, axis, EdgeType::Hadamard);
+            self.add_edge(axis, leaf, EdgeType::Hadamard);
+            self.add_phase(partner, -phase);
+
+            self.apply_pivot(node, partner);
+            return true;
+        }
+
+        false
+    }
+
+    /// Removes two adjacent interior Pauli spiders, complementing the edges between their
+    /// three neighbourhoods.
+    pub(crate) fn apply_pivot(&mut self, u: NodeIndex, v: NodeIndex) {
+        let (phase_u, phase_v) = (
+            self.node_data(u).map_or(Phase::ZERO, |d| d.1.clone()),
+            self.node_data(v).map_or(Phase::ZERO, |d| d.1.clone()),
+        );
+        let [only_u, only_v, shared] = self.pivot_neighborhoods(u, v);
+
+        for &a in &only_u {
+            self.add_phase(a, phase_v.clone());
+        }
+        for &b in &only_v {
+            self.add_phase(b, phase_u.clone());
+        }
+        for &c in &shared {
+            self.add_phase(c, phase_u.clone() + phase_v.clone() + Phase::PI);
+        }
+
+        if phase_u == Phase::PI && phase_v == Phase::PI {
+            self.scalar.multiply_phase(Phase::PI);
+        }
+    }
+
+    /// Removes a phase-free spider between two Hadamard edges and fuses its neighbours,
+    /// unless both of them sit on a boundary.
+    fn try_remove_interior_identity(&mut self, node: NodeIndex) -> bool {
+        let Some(phase) = self.interior_phase(node) else {
+            return false;
+        };
+        let neighbors = self.neighbors(node);
+        if !phase.is_zero() || neighbors.len() != 2 {
+            return false;
+        }
+        if neighbors.iter().all(|&n| self.has_boundary_neighbor(n)) {
+            return false;
+        }
+
+        self.remove_node(node);
+        let edge_idx = self.add_edge(neighbors[0], neighbors[1], EdgeType::Regular);
+        self.fuse_spider_pair(neighbors[0], neighbors[1], edge_idx);
+        true
+    }
+
+    /// Whether the spider is a phase gadget leaf (a single edge) or an axis (next to a
+    /// leaf). Gadget pivots leave these alone, otherwise they could undo each other.
+    fn is_gadget_part(&self, node: NodeIndex) -> bool {
+        let neighbors = self.neighbors(node);
+        neighbors.len() == 1 || neighbors.iter().any(|&n| self.neighbors(n).len() == 1)
+    }
+
+    fn has_boundary_neighbor(&self, node: NodeIndex) -> bool {
+        self.neighbors(node)
+            .iter()
+            .any(|&n| self.spider_type(n) == Some(SpiderType::Boundary))
+    }
+
+    /// A spider without edges evaluates to 1 + e^(iα). Symbolic ones stay in the diagram.
+    fn try_remove_isolated(&mut self, node: NodeIndex) -> bool {
+        let Some((_, phase)) = self.plain_spider(node) else {
+            return false;
+        };
+        if !self.neighbors(node).is_empty() || phase.is_symbolic() {
+            return false;
+        }
+
+        self.remove_node(node);
+        self.scalar.multiply_one_plus_phase(phase);
+        true
+    }
+}
@@ -0,0 +1,561 @@
+use super::graph::*;
+use super::parametric::{Factor, ParametricScalar, Parity, Term};
+use super::rules::HadamardEdges;
+use crate::error::QuojoError;
+use crate::linalg::vector::Complex;
+use crate::qcore::phase::Phase;
+use std::collections::{HashMap, HashSet};
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+impl ZXGraph {
+    /// Computes ⟨output_bits| D |input_bits⟩ for the diagram D.
+    ///
+    /// Inputs and outputs are ordered by node index. A `Boundary` node is replaced by the
+    /// computational basis state given by its bit; any other input or output spider keeps
+    /// one open wire, which is plugged with that state. Spider parameters, if any, are taken
+    /// to be 0.
+    ///
+    /// Every non-Clifford spider that survives the Clifford rewrites is cut into two terms,
+    /// so the cost grows as 2^t in the number t of such spiders, for instance T gates that
+    /// do not cancel or fuse. A few dozen of them are already out of reach.
+    pub fn amplitude(&self, input_bits: &[bool], output_bits: &[bool]) -> Complex {
+        self.try_amplitude(input_bits, output_bits)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `amplitude`, but returns an error when the number of bits does not match the
+    /// inputs or outputs, or a phase has unbound variables.
+    pub fn try_amplitude(
+        &self,
+        input_bits: &[bool],
+        output_bits: &[bool],
+    ) -> Result<Complex, QuojoError> {
+        self.check_boundary_bits(input_bits, output_bits.len())?;
+
+        let states = input_bits
+            .iter()
+            .chain(output_bits)
+            .map(|&bit| (bit, Parity::new()))
+            .collect();
+        let assignment = vec![false; self.num_parameters()];
+
+        let mut total = Complex::new(0.0, 0.0);
+        self.plugged(states)
+            .decompose(&mut |term| total = total + term.evaluate(&assignment));
+        Ok(total)
+    }
+
+    /// Simplifies ⟨b| D |input_bits⟩ once, leaving the output bits b as parameters.
+    ///
+    /// Output j becomes parameter `self.num_parameters() + j`, after any parameters that
+    /// already appear on spiders. The result can be evaluated for every output bitstring
+    /// without rewriting the diagram again.
+    pub fn compile_amplitudes(&self, input_bits: &[bool]) -> ParametricScalar {
+        self.try_compile_amplitudes(input_bits)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `compile_amplitudes`, but returns an error when the number of bits does not
+    /// match the inputs, or a phase has unbound variables.
+    pub fn try_compile_amplitudes(
+        &self,
+        input_bits: &[bool],
+    ) -> Result<ParametricScalar, QuojoError> {
+        let output_count = self.output_nodes.len();
+        self.check_boundary_bits(input_bits, output_count)?;
+
+        let offset = self.num_parameters();
+        let states = input_bits
+            .iter()
+            .map(|&bit| (bit, Parity::new()))
+            .chain((0..output_count).map(|j| (false, Parity::single(offset + j))))
+            .collect();
+
+        let mut terms = Vec::new();
+        self.plugged(states).decompose(&mut |term| terms.push(term));
+
+        Ok(ParametricScalar {
+            num_params: offset + output_count,
+            terms,
+        })
+    }
+
+    /// Computes the value of a diagram with no inputs or outputs. Spider parameters, if
+    /// any, are taken to be 0.
+    pub fn scalar_value(&self) -> Complex {
+        self.try_scalar_value()
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `scalar_value`, but returns an error for a diagram with inputs or outputs, or
+    /// with unbound variables.
+    pub fn try_scalar_value(&self) -> Result<Complex, QuojoError> {
+        self.try_compile_scalar()?
+            .try_evaluate(&vec![false; self.num_parameters()])
+    }
+
+    /// Simplifies a diagram with no inputs or outputs into a sum of terms over the
+    /// parameters carried by its spiders.
+    pub fn compile_scalar(&self) -> ParametricScalar {
+        self.try_compile_scalar()
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `compile_scalar`, but returns an error for a diagram with inputs or outputs, or
+    /// with unbound variables.
+    pub fn try_compile_scalar(&self) -> Result<ParametricScalar, QuojoError> {
+        if !self.input_nodes.is_empty() || !self.output_nodes.is_empty() {
+            return Err(QuojoError::OpenDiagram {
+                inputs: self.input_nodes.len(),
+                outputs: self.output_nodes.len(),
+            });
+        }
+        self.check_bound()?;
+
+        let mut terms = Vec::new();
+        ReducedGraph::from_graph(self).decompose(&mut |term| terms.push(term));
+
+        Ok(ParametricScalar {
+            num_params: self.num_parameters(),
+            terms,
+        })
+    }
+
+    /// Matrix of the diagram, indexed as `matrix[output][input]`. Bit i of each index is
+    /// the i-th boundary in node-index order, and spider parameters are taken to be 0.
+    pub fn to_matrix(&self) -> Vec<Vec<Complex>> {
+        let (input_count, output_count) = (self.input_nodes.len(), self.output_nodes.len());
+        let offset = self.num_parameters();
+        let mut matrix = vec![vec![Complex::new(0.0, 0.0); 1 << input_count]; 1 << output_count];
+
+        for input in 0..1usize << input_count {
+            let compiled = self.compile_amplitudes(&index_bits(input, input_count));
+            for (output, row) in matrix.iter_mut().enumerate() {
+                let mut assignment = vec![false; offset];
+                assignment.extend(index_bits(output, output_count));
+                row[input] = compiled.evaluate(&assignment);
+            }
+        }
+
+        matrix
+    }
+
+    pub fn ordered_inputs(&self) -> Vec<NodeIndex> {
+        let mut inputs: Vec<NodeIndex> = self.input_nodes.iter().copied().collect();
+        inputs.sort_by_key(|n| n.0);
+        inputs
+    }
+
+    pub fn ordered_outputs(&self) -> Vec<NodeIndex> {
+        let mut outputs: Vec<NodeIndex> = self.output_nodes.iter().copied().collect();
+        outputs.sort_by_key(|n| n.0);
+        outputs
+    }
+
+    fn check_boundary_bits(
+        &self,
+        input_bits: &[bool],
+        output_count: usize,
+    ) -> Result<(), QuojoError> {
+        if input_bits.len() != self.input_nodes.len() {
+            return Err(QuojoError::CountMismatch {
+                kind: "input bits".to_string(),
+                expected: self.input_nodes.len(),
+                found: input_bits.len(),
+            });
+        }
+        if output_count != self.output_nodes.len() {
+            return Err(QuojoError::CountMismatch {
+                kind: "output bits".to_string(),
+                expected: self.output_nodes.len(),
+                found: output_count,
+            });
+        }
+        self.check_bound()
+    }
+
+    fn check_bound(&self) -> Result<(), QuojoError> {
+        let variables = self.variables();
+        if variables.is_empty() {
+            Ok(())
+        } else {
+            Err(QuojoError::UnboundVariables(
+                variables.into_iter().collect(),
+            ))
+        }
+    }
+
+    /// Plugs the inputs and then the outputs with the basis states |bit ⊕ parity⟩.
+    fn plugged(&self, states: Vec<(bool, Parity)>) -> ReducedGraph {
+        let mut plugged = self.clone();
+        let boundary = self
+            .ordered_inputs()
+            .into_iter()
+            .chain(self.ordered_outputs());
+
+        for (node, (bit, parity)) in boundary.zip(states) {
+            // |0⟩ and |1⟩ are X spiders with phase 0 and π, up to a factor of 1/√2
+            let phase = if bit { Phase::PI } else { Phase::ZERO };
+            match plugged.nodes[node.0].as_mut() {
+                Some(boundary) if boundary.spider_type == SpiderType::Boundary => {
+                    boundary.spider_type = SpiderType::X;
+                    boundary.phase = phase;
+                    boundary.params = parity;
+                }
+                _ => {
+                    let state = plugged.add_parametric_node(SpiderType::X, phase, parity);
+                    plugged.add_edge(node, state, EdgeType::Regular);
+                }
+            }
+        }
+
+        let boundary_count = (plugged.input_nodes.len() + plugged.output_nodes.len()) as i32;
+        plugged.input_nodes.clear();
+        plugged.output_nodes.clear();
+
+        let mut reduced = ReducedGraph::from_graph(&plugged);
+        reduced.scalar.sqrt2_power -= boundary_count;
+        reduced
+    }
+}
+
+/// A closed graph-like diagram: only Z spiders, joined by at most one Hadamard edge each.
+///
+/// This is the working form for strong simulation. Clifford spiders are removed with
+/// local complementation and pivoting, and whatever non-Clifford spiders remain are cut
+/// into a sum of two stabilizer terms each. None of these choices depend on the boolean
+/// parameters, so a parametric diagram is decomposed exactly once.
+#[derive(Debug, Clone)]
+struct ReducedGraph {
+    phases: Vec<Phase>,
+    parities: Vec<Parity>,
+    adjacency: Vec<HashSet<usize>>,
+    alive: Vec<bool>,
+    scalar: Term,
+}
+
+impl HadamardEdges for ReducedGraph {
+    type Node = usize;
+
+    fn hadamard_neighbors(&self, v: usize) -> Vec<usize> {
+        self.adjacency[v].iter().copied().collect()
+    }
+
+    fn toggle_hadamard_edge(&mut self, a: usize, b: usize) -> bool {
+        if self.adjacency[a].remove(&b) {
+            self.adjacency[b].remove(&a);
+            false
+        } else {
+            self.adjacency[a].insert(b);
+            self.adjacency[b].insert(a);
+            true
+        }
+    }
+
+    fn remove_spider(&mut self, v: usize) {
+        for w in std::mem::take(&mut self.adjacency[v]) {
+            self.adjacency[w].remove(&v);
+        }
+        self.alive[v] = false;
+    }
+
+    fn multiply_sqrt2_power(&mut self, power: i32) {
+        self.scalar.sqrt2_power += power;
+    }
+}
+
+impl ReducedGraph {
+    fn from_graph(graph: &ZXGraph) -> Self {
+        let mut index_map = vec![None; graph.nodes.len()];
+        let mut spiders = Vec::new();
+
+        for (i, node) in graph.nodes.iter().enumerate() {
+            if let Some(node) = node {
+                if node.spider_type == SpiderType::Boundary {
+                    panic!("Boundary node {} must be plugged before reduction", i);
+                }
+                if node.phase.is_symbolic() {
+                    panic!(
+                        "Spider {} has symbolic phase {}; bind its variables first",
+                        i, node.phase
+                    );
+                }
+                index_map[i] = Some(spiders.len());
+                spiders.push(node);
+            }
+        }
+
+        // Colour change every X spider, then fuse along the remaining regular edges
+        let mut parent: Vec<usize> = (0..spiders.len()).collect();
+        let mut hadamard_edges = Vec::new();
+
+        for edge in graph.edges.iter().flatten() {
+            let a = index_map[edge.endpoints.0.0].expect("edge to removed node");
+            let b = index_map[edge.endpoints.1.0].expect("edge to removed node");
+            let hadamard = (edge.edge_type == EdgeType::Hadamard)
+                ^ (spiders[a].spider_type == SpiderType::X)
+                ^ (spiders[b].spider_type == SpiderType::X);
+
+            if hadamard {
+                hadamard_edges.push((a, b));
+            } else if a != b {
+                let (root_a, root_b) = (find_root(&mut parent, a), find_root(&mut parent, b));
+                if root_a != root_b {
+                    parent[root_b] = root_a;
+                }
+            }
+        }
+
+        let mut reduced = ReducedGraph {
+            phases: vec![Phase::ZERO; spiders.len()],
+            parities: vec![Parity::new(); spiders.len()],
+            adjacency: vec![HashSet::new(); spiders.len()],
+            alive: vec![false; spiders.len()],
+            scalar: Term::one(),
+        };
+        reduced.scalar.sqrt2_power = graph.scalar.sqrt2_power;
+        reduced.scalar.value =
+            graph.scalar.value * Complex::from_phase(graph.scalar.phase as f64 * FRAC_PI_4);
+
+        for (i, spider) in spiders.iter().enumerate() {
+            let root = find_root(&mut parent, i);
+            reduced.alive[root] = true;
+            reduced.phases[root] += spider.phase.clone();
+            reduced.parities[root].xor_assign(&spider.params);
+        }
+
+        let mut multiplicity: HashMap<(usize, usize), usize> = HashMap::new();
+        for (a, b) in hadamard_edges {
+            let (root_a, root_b) = (find_root(&mut parent, a), find_root(&mut parent, b));
+            if root_a == root_b {
+                // Both ends were fused into one spider, so the edge became a self-loop, worth
+                // π on the root and 1/√2
+                reduced.phases[root_a] += Phase::PI;
+                reduced.scalar.sqrt2_power -= 1;
+            } else {
+                *multiplicity
+                    .entry((root_a.min(root_b), root_a.max(root_b)))
+                    .or_default() += 1;
+            }
+        }
+
+        for ((a, b), count) in multiplicity {
+            // Edges between the same two roots cancel in pairs, each pair leaving 1/2
+            reduced.scalar.sqrt2_power -= 2 * (count / 2) as i32;
+            if count % 2 == 1 {
+                reduced.adjacency[a].insert(b);
+                reduced.adjacency[b].insert(a);
+            }
+        }
+
+        reduced
+    }
+
+    /// Emits the diagram as a sum of terms, one per leaf of the cutting tree.
+    fn decompose(mut self, sink: &mut dyn FnMut(Term)) {
+        self.simplify();
+
+        if self.scalar.is_zero() {
+            return;
+        }
+
+        match self.find_non_clifford() {
+            Some(v) => {
+                let mut zero_branch = self.clone();
+                zero_branch.cut(v, false);
+                self.cut(v, true);
+                zero_branch.decompose(sink);
+                self.decompose(sink);
+            }
+            None => sink(self.scalar),
+        }
+    }
+
+    /// Applies Clifford rewrites until none match.
+    fn simplify(&mut self) {
+        loop {
+            if self.scalar.is_zero() {
+                return;
+            }
+
+            let applied = self.remove_isolated()
+                || self.remove_identity()
+                || self.local_complement()
+                || self.pivot();
+
+            if !applied {
+                return;
+            }
+        }
+    }
+
+    fn vertices(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.alive.len()).filter(|&v| self.alive[v])
+    }
+
+    fn add_phase(&mut self, v: usize, phase: Phase, parity: &Parity) {
+        self.phases[v] += phase;
+        self.parities[v].xor_assign(parity);
+    }
+
+    /// A lone spider evaluates to 1 + e^(iα).
+    fn remove_isolated(&mut self) -> bool {
+        let Some(v) = self.vertices().find(|&v| self.adjacency[v].is_empty()) else {
+            return false;
+        };
+
+        let phase = self.phases[v].clone();
+        if self.parities[v].is_empty() {
+            if phase.is_zero() {
+                self.scalar.sqrt2_power += 2;
+            } else if phase == Phase::PI {
+                self.scalar.value = Complex::new(0.0, 0.0);
+            } else {
+                self.scalar.value =
+                    self.scalar.value + self.scalar.value * Complex::from_phase(phase.to_radians());
+            }
+        } else {
+            let parity = std::mem::take(&mut self.parities[v]);
+            self.scalar.multiply(Factor::OnePlusPhase {
+                phase: phase.to_radians(),
+                parity,
+            });
+        }
+
+        self.alive[v] = false;
+        true
+    }
+
+    /// Removes a phase-free spider of degree two and fuses its neighbours.
+    fn remove_identity(&mut self) -> bool {
+        let candidate = self.vertices().find(|&v| {
+            self.adjacency[v].len() == 2 && self.phases[v].is_zero() && self.parities[v].is_empty()
+        });
+        let Some(v) = candidate else {
+            return false;
+        };
+
+        let mut ends = self.adjacency[v].iter().copied();
+        let (u, w) = (ends.next().unwrap(), ends.next().unwrap());
+        self.remove_spider(v);
+
+        let w_parity = std::mem::take(&mut self.parities[w]);
+        self.add_phase(u, self.phases[w].clone(), &w_parity);
+        let w_neighbors: Vec<usize> = self.adjacency[w].iter().copied().collect();
+        self.remove_spider(w);
+
+        for x in w_neighbors {
+            if x == u {
+                self.add_phase(u, Phase::PI, &Parity::new());
+                self.scalar.sqrt2_power -= 1;
+            } else if !self.toggle_hadamard_edge(u, x) {
+                self.scalar.sqrt2_power -= 2;
+            }
+        }
+
+        true
+    }
+
+    /// Removes a ±π/2 spider by complementing the edges among its neighbours.
+    fn local_complement(&mut self) -> bool {
+        let candidate = self
+            .vertices()
+            .find(|&v| self.phases[v].is_proper_clifford());
+        let Some(v) = candidate else {
+            return false;
+        };
+
+        let phase = self.phases[v].clone();
+        let parity = std::mem::take(&mut self.parities[v]);
+        for a in self.complement_neighborhood(v) {
+            self.add_phase(a, -phase.clone(), &parity);
+        }
+
+        // 1 ± i(-1)^s = √2·e^(±iπ/4)·e^(∓iπ/2·s); flipping the parity flips the sign of ±
+        let sign = if phase == Phase::new(1, 2) { 1.0 } else { -1.0 };
+        self.scalar.multiply_phase(sign * FRAC_PI_4);
+        self.scalar.multiply(Factor::Phase {
+            angle: -sign * FRAC_PI_2,
+            parity,
+        });
+        true
+    }
+
+    /// Removes a pair of adjacent Pauli spiders.
+    fn pivot(&mut self) -> bool {
+        let candidate = self.vertices().find_map(|u| {
+            if !self.phases[u].is_pauli() {
+                return None;
+            }
+            self.adjacency[u]
+                .iter()
+                .find(|&&v| self.phases[v].is_pauli())
+                .map(|&v| (u, v))
+        });
+        let Some((u, v)) = candidate else {
+            return false;
+        };
+
+        let (phase_u, phase_v) = (self.phases[u].clone(), self.phases[v].clone());
+        let parity_u = std::mem::take(&mut self.parities[u]);
+        let parity_v = std::mem::take(&mut self.parities[v]);
+        let [only_u, only_v, shared] = self.pivot_neighborhoods(u, v);
+
+        let parity_uv = parity_u.xor(&parity_v);
+        for &a in &only_u {
+            self.add_phase(a, phase_v.clone(), &parity_v);
+        }
+        for &b in &only_v {
+            self.add_phase(b, phase_u.clone(), &parity_u);
+        }
+        for &c in &shared {
+            self.add_phase(c, phase_u.clone() + phase_v.clone() + Phase::PI, &parity_uv);
+        }
+
+        self.scalar.multiply(Factor::SignProduct {
+            a: phase_u == Phase::PI,
+            p: parity_u,
+            b: phase_v == Phase::PI,
+            q: parity_v,
+        });
+        true
+    }
+
+    fn find_non_clifford(&self) -> Option<usize> {
+        self.vertices()
+            .filter(|&v| !self.phases[v].is_clifford())
+            .max_by_key(|&v| self.adjacency[v].len())
+    }
+
+    /// Replaces spider `v` by one of the two terms of Z(α) = |0...0⟩ + e^(iα)|1...1⟩.
+    ///
+    /// Each cut doubles the number of terms that `decompose` emits.
+    fn cut(&mut self, v: usize, bit: bool) {
+        let degree = self.adjacency[v].len() as i32;
+        let neighbors: Vec<usize> = self.adjacency[v].iter().copied().collect();
+        let parity = std::mem::take(&mut self.parities[v]);
+
+        if bit {
+            self.scalar.multiply_phase(self.phases[v].to_radians());
+            self.scalar.multiply(Factor::Phase { angle: PI, parity });
+            for &w in &neighbors {
+                self.add_phase(w, Phase::PI, &Parity::new());
+            }
+        }
+
+        self.scalar.sqrt2_power -= degree;
+        self.remove_spider(v);
+    }
+}
+
+fn index_bits(index: usize, width: usize) -> Vec<bool> {
+    (0..width).map(|i| (index >> i) & 1 == 1).collect()
+}
+
+fn find_root(parent: &mut [usize], mut x: usize) -> usize {
+    while parent[x] != x {
+        parent[x] = parent[parent[x]];
+        x = parent[x];
+    }
+    x
+}
@@ -0,0 +1,266 @@
+//! SVG drawings of ZX-graphs, laid out like the TikZ ones, for viewing without LaTeX.
+
+use super::dot::{X_COLORS, Z_COLORS};
+use super::graph::*;
+use super::tikz::{
+    TikzConfig, calculate_edge_curve, calculate_output_endpoint, calculate_professional_layout,
+    group_edges_by_endpoints, spider_label,
+};
+use crate::qcore::svg::svg_text;
+use std::fmt::Write;
+
+/// The knobs of `TikzConfig`, with styles given as CSS declarations and lengths in the
+/// same units as TikZ, drawn `scale` pixels to the unit.
+pub struct SvgConfig {
+    pub node_spacing: f64,
+    pub layer_spacing: f64,
+    pub z_style: String,
+    pub x_style: String,
+    pub regular_edge_style: String,
+    pub hadamard_edge_style: String,
+    pub hadamard_box_style: String,
+    /// Font size of phase labels, in pixels.
+    pub font_size: f64,
+    pub scale: f64,
+}
+
+impl Default for SvgConfig {
+    fn default() -> Self {
+        Self {
+            node_spacing: 2.0,
+            layer_spacing: 3.0,
+            z_style: spider_style(Z_COLORS),
+            x_style: spider_style(X_COLORS),
+            regular_edge_style: "stroke:black;stroke-width:1.5".into(),
+            hadamard_edge_style: "stroke:black;stroke-width:1.5".into(),
+            hadamard_box_style: "fill:yellow;stroke:black;stroke-width:1".into(),
+            font_size: 12.0,
+            scale: 40.0,
+        }
+    }
+}
+
+fn spider_style((fill, stroke): (&str, &str)) -> String {
+    format!("fill:{};stroke:{};stroke-width:1.5", fill, stroke)
+}
+
+/// Radius of spiders and boundary dots, and side of Hadamard boxes, in TikZ units.
+const SPIDER_RADIUS: f64 = 0.3;
+const BOUNDARY_RADIUS: f64 = 0.06;
+const HADAMARD_BOX: f64 = 0.3;
+const MARGIN: f64 = 0.6;
+
+pub fn generate_svg(graph: &ZXGraph, config: &SvgConfig) -> String {
+    let layout_config = TikzConfig {
+        node_spacing: config.node_spacing,
+        layer_spacing: config.layer_spacing,
+        ..TikzConfig::default()
+    };
+    let positions = calculate_professional_layout(graph, &layout_config);
+    let output_end = calculate_output_endpoint(&positions, graph, &layout_config);
+    let input_wire_length = config.node_spacing * 1.5;
+
+    // Bounds of the drawing in TikZ coordinates, where y grows upwards
+    let (mut min_x, mut max_x) = (f64::MAX, f64::MIN);
+    let (mut min_y, mut max_y) = (f64::MAX, f64::MIN);
+    for &(x, y) in positions.values() {
+        min_x = min_x.min(x);
+        max_x = max_x.max(x);
+        min_y = min_y.min(y);
+        max_y = max_y.max(y);
+    }
+    if positions.is_empty() {
+        (min_x, max_x, min_y, max_y) = (0.0, 0.0, 0.0, 0.0);
+    }
+    if !graph.input_nodes.is_empty() {
+        min_x -= input_wire_length;
+    }
+    if !graph.output_nodes.is_empty() {
+        max_x = max_x.max(output_end);
+    }
+
+    let scale = config.scale;
+    let point = |(x, y): (f64, f64)| ((x - min_x + MARGIN) * scale, (max_y - y + MARGIN) * scale);
+    let width = (max_x - min_x + 2.0 * MARGIN) * scale;
+    let height = (max_y - min_y + 2.0 * MARGIN) * scale;
+
+    let mut output = String::with_capacity(1024);
+    writeln!(
+        output,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.2}\" height=\"{:.2}\" viewBox=\"0 0 {:.2} {:.2}\">",
+        width, height, width, height
+    )
+    .unwrap();
+    writeln!(
+        output,
+        "<style>.z_node {{ {} }} .x_node {{ {} }} .boundary_node {{ fill:black; }} .regular_edge {{ fill:none; {} }} .hadamard_edge {{ fill:none; {} }} .hadamard_box {{ {} }} .wire {{ stroke:black; stroke-width:1.5; }} text {{ font-family:serif; font-size:{}px; }}</style>",
+        config.z_style,
+        config.x_style,
+        config.regular_edge_style,
+        config.hadamard_edge_style,
+        config.hadamard_box_style,
+        config.font_size
+    )
+    .unwrap();
+    writeln!(
+        output,
+        "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>"
+    )
+    .unwrap();
+
+    for (terminals, is_input) in [(&graph.input_nodes, true), (&graph.output_nodes, false)] {
+        for node in terminals {
+            let Some(&(x, y)) = positions.get(node) else {
+                continue;
+            };
+            let (from, to) = match is_input {
+                true => ((x - input_wire_length, y), (x, y)),
+                false => ((x, y), (output_end, y)),
+            };
+            let ((x1, y1), (x2, y2)) = (point(from), point(to));
+            writeln!(
+                output,
+                "<line class=\"wire\" x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\"/>",
+                x1, y1, x2, y2
+            )
+            .unwrap();
+        }
+    }
+
+    // Edges before nodes, so that spiders cover the ends of their edges
+    let mut edge_groups: Vec<_> = group_edges_by_endpoints(graph).into_iter().collect();
+    edge_groups.sort_by_key(|((src, dst), _)| (src.0, dst.0));
+    for ((src, dst), edges) in edge_groups {
+        let (Some(&src_pos), Some(&dst_pos)) = (positions.get(&src), positions.get(&dst)) else {
+            continue;
+        };
+        if (src_pos.0 - dst_pos.0).abs() < 0.001 && (src_pos.1 - dst_pos.1).abs() < 0.001 {
+            continue;
+        }
+        for edge_type in [EdgeType::Regular, EdgeType::Hadamard] {
+            let count = edges
+                .iter()
+                .filter(|(_, e)| e.edge_type == edge_type)
+                .count();
+            if count > 0 {
+                draw_edge_set(
+                    &mut output,
+                    src_pos,
+                    dst_pos,
+                    count,
+                    edge_type,
+                    config,
+                    &point,
+                );
+            }
+        }
+    }
+
+    let mut nodes: Vec<_> = positions.iter().map(|(&node, &pos)| (node, pos)).collect();
+    nodes.sort_by_key(|(node, _)| node.0);
+    for (node, position) in nodes {
+        let Some(data) = graph.nodes.get(node.0).and_then(|n| n.as_ref()) else {
+            continue;
+        };
+        let (x, y) = point(position);
+        let (class, radius) = match data.spider_type {
+            SpiderType::Z => ("z_node", SPIDER_RADIUS),
+            SpiderType::X => ("x_node", SPIDER_RADIUS),
+            SpiderType::Boundary => ("boundary_node", BOUNDARY_RADIUS),
+        };
+        let (label, characters) = svg_text(&spider_label(data));
+        // Spiders grow to fit their phase, like TikZ nodes do
+        let radius = (radius * scale).max(characters * config.font_size * 0.3 + 3.0);
+        writeln!(
+            output,
+            "<circle class=\"{}\" cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\"/>",
+            class, x, y, radius
+        )
+        .unwrap();
+        if !label.is_empty() {
+            writeln!(
+                output,
+                "<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+                x, y, label
+            )
+            .unwrap();
+        }
+    }
+
+    output.push_str("</svg>\n");
+    output
+}
+
+/// `count` parallel edges of one type between two nodes, fanned out like the TikZ edges.
+/// More than four are drawn as the outermost two with the count between them.
+fn draw_edge_set(
+    output: &mut String,
+    src_pos: (f64, f64),
+    dst_pos: (f64, f64),
+    count: usize,
+    edge_type: EdgeType,
+    config: &SvgConfig,
+    point: &impl Fn((f64, f64)) -> (f64, f64),
+) {
+    let class = match edge_type {
+        EdgeType::Regular => "regular_edge",
+        EdgeType::Hadamard => "hadamard_edge",
+    };
+    let curves: Vec<_> = if count <= 4 {
+        (0..count)
+            .map(|i| calculate_edge_curve(src_pos, dst_pos, i, count, config.node_spacing))
+            .collect()
+    } else {
+        [0, 2]
+            .iter()
+            .map(|&i| calculate_edge_curve(src_pos, dst_pos, i, 3, config.node_spacing * 0.6))
+            .collect()
+    };
+
+    let box_side = HADAMARD_BOX * config.scale;
+    for &((cx1, cy1, cx2, cy2), mid) in &curves {
+        let (x1, y1) = point(src_pos);
+        let (c1x, c1y) = point((cx1, cy1));
+        let (c2x, c2y) = point((cx2, cy2));
+        let (x2, y2) = point(dst_pos);
+        writeln!(
+            output,
+            "<path class=\"{}\" d=\"M {:.2} {:.2} C {:.2} {:.2} {:.2} {:.2} {:.2} {:.2}\"/>",
+            class, x1, y1, c1x, c1y, c2x, c2y, x2, y2
+        )
+        .unwrap();
+        if edge_type == EdgeType::Hadamard {
+            let (mx, my) = point(mid);
+            writeln!(
+                output,
+                "<rect class=\"hadamard_box\" x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\"/>",
+                mx - box_side / 2.0,
+                my - box_side / 2.0,
+                box_side,
+                box_side
+            )
+            .unwrap();
+        }
+    }
+
+    if count > 4 {
+        let (top, bottom) = (point(curves[0].1), point(curves[1].1));
+        let (cx, cy) = ((top.0 + bottom.0) / 2.0, (top.1 + bottom.1) / 2.0);
+        writeln!(
+            output,
+            "<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"middle\" dominant-baseline=\"central\" font-size=\"80%\">{}</text>",
+            cx, cy, count
+        )
+        .unwrap();
+    }
+}
+
+pub fn save_svg_to_file(
+    graph: &ZXGraph,
+    config: &SvgConfig,
+    filepath: &str,
+) -> std::io::Result<()> {
+    std::fs::write(filepath, generate_svg(graph, config))?;
+    println!("ZX-diagram SVG saved to: {}", filepath);
+    Ok(())
+}
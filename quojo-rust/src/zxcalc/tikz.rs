@@ -1,4 +1,6 @@
+use super::dot::format_spider_label;
 use super::graph::*;
+use crate::qcore::phase::Phase;
 use std::collections::{HashMap, VecDeque};
 use std::fs::File;
 use std::io::Write;
@@ -32,7 +34,7 @@ pub fn generate_tikz(graph: &ZXGraph, config: &TikzConfig) -> String {
     let mut output = String::with_capacity(1024);
     
     output.push_str(&format!(
-        "\\begin{{tikzpicture}}[\n    z_node/.style={{{}}},\n    x_node/.style={{{}}},\n    regular_edge/.style={{{}}},\n    hadamard_edge/.style={{{}}},\n    hadamard_box/.style={{{}}},\n    small_hadamard_box/.style={{fill=yellow,draw=black,minimum size=2mm}},\n    edge_count/.style={{text=black,font=\\scriptsize}},\n    input_wire/.style={{thick,black}},\n    output_wire/.style={{thick,black}}]\n",
+        "\\begin{{tikzpicture}}[\n    z_node/.style={{{}}},\n    x_node/.style={{{}}},\n    boundary_node/.style={{circle,fill=black,inner sep=1pt}},\n    regular_edge/.style={{{}}},\n    hadamard_edge/.style={{{}}},\n    hadamard_box/.style={{{}}},\n    small_hadamard_box/.style={{fill=yellow,draw=black,minimum size=2mm}},\n    edge_count/.style={{text=black,font=\\scriptsize}},\n    input_wire/.style={{thick,black}},\n    output_wire/.style={{thick,black}}]\n",
         config.z_style, config.x_style, config.regular_edge_style, 
         config.hadamard_edge_style, config.hadamard_box_style
     ));
@@ -51,7 +53,7 @@ pub fn generate_tikz(graph: &ZXGraph, config: &TikzConfig) -> String {
     output
 }
 
-fn calculate_output_endpoint(
+pub(crate) fn calculate_output_endpoint(
     positions: &HashMap<NodeIndex, (f64, f64)>, 
     graph: &ZXGraph,
     config: &TikzConfig
@@ -112,26 +114,37 @@ fn draw_nodes(graph: &ZXGraph, positions: &HashMap<NodeIndex, (f64, f64)>, outpu
     nodes.sort_by_key(|(idx, _)| idx.0);
     
     for (node_idx, (x, y)) in nodes {
-        if let Some((spider_type, phase)) = graph.node_data(node_idx) {
-            let style = match spider_type {
+        if let Some(node) = graph.nodes.get(node_idx.0).and_then(Option::as_ref) {
+            let style = match node.spider_type {
                 SpiderType::Z => "z_node",
                 SpiderType::X => "x_node",
-            };
-            
-            let phase_text = if phase.abs() < 0.0001 {
-                String::new()
-            } else {
-                format!("{:.2}", phase)
+                SpiderType::Boundary => "boundary_node",
             };
             
             output.push_str(&format!(
                 "\\node[{}] ({}) at ({:.2},{:.2}) {{{}}};\n",
-                style, node_idx.0, x, y, phase_text
+                style, node_idx.0, x, y, spider_label(node)
             ));
         }
     }
 }
 
+/// LaTeX label of a spider, with boolean parameters as `π(b_{0}⊕b_{1})`.
+pub(crate) fn spider_label(node: &Node) -> String {
+    format_spider_label(node, phase_label, |params| {
+        let parity: Vec<String> = params.params().map(|p| format!("b_{{{}}}", p)).collect();
+        format!("$\\pi({})$", parity.join("\\oplus "))
+    })
+}
+
+/// LaTeX label for a phase, as a fraction of π when it has one.
+pub(crate) fn phase_label(phase: &Phase) -> String {
+    match phase.as_float() {
+        Some(radians) => format!("{:.2}", radians),
+        None => format!("${}$", phase.to_latex()),
+    }
+}
+
 fn draw_edges(
     graph: &ZXGraph,
     positions: &HashMap<NodeIndex, (f64, f64)>,
@@ -309,7 +322,7 @@ fn draw_edge_bundle(
     ));
 }
 
-fn calculate_edge_curve(
+pub(crate) fn calculate_edge_curve(
     src_pos: (f64, f64),
     dst_pos: (f64, f64),
     edge_index: usize,
@@ -362,7 +375,7 @@ fn calculate_edge_curve(
     ((cx1, cy1, cx2, cy2), (mid_x, mid_y))
 }
 
-fn calculate_professional_layout(graph: &ZXGraph, config: &TikzConfig) -> HashMap<NodeIndex, (f64, f64)> {
+pub(crate) fn calculate_professional_layout(graph: &ZXGraph, config: &TikzConfig) -> HashMap<NodeIndex, (f64, f64)> {
     let nodes: Vec<NodeIndex> = graph.nodes.iter().enumerate()
         .filter_map(|(i, n)| if n.is_some() { Some(NodeIndex(i)) } else { None })
         .collect();
@@ -729,7 +742,7 @@ fn center_and_normalize_positions(positions: &mut HashMap<NodeIndex, (f64, f64)>
     }
 }
 
-fn group_edges_by_endpoints(graph: &ZXGraph) -> HashMap<(NodeIndex, NodeIndex), Vec<(EdgeIndex, &Edge)>> {
+pub(crate) fn group_edges_by_endpoints(graph: &ZXGraph) -> HashMap<(NodeIndex, NodeIndex), Vec<(EdgeIndex, &Edge)>> {
     let mut edge_groups = HashMap::new();
     
     for (i, edge) in graph.edges.iter().enumerate() {
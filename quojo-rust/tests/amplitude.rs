@@ -0,0 +1,208 @@
+mod common;
+
+use common::*;
+use quojo_rust::linalg::vector::Complex;
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::sim::statevector::StateVector;
+use quojo_rust::zxcalc::graph::{EdgeType, NodeIndex, SpiderType, ZXGraph};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::SQRT_2;
+
+/// A random Clifford+T circuit as `(wire, gate)` pairs, in which about one gate in eight is
+/// a T or T†.
+fn random_clifford_t(rng: &mut StdRng, width: usize, length: usize) -> Vec<(usize, Gate)> {
+    let mut gates = Vec::new();
+    for _ in 0..length {
+        let q = rng.random_range(0..width);
+        let r = (q + rng.random_range(1..width)) % width;
+        let gate = match rng.random_range(0..8) {
+            0 | 1 => Gate::H,
+            2 => Gate::P(Phase::new(1, 2)),
+            3 => Gate::P(Phase::new(-1, 2)),
+            4 if rng.random_bool(0.5) => Gate::P(Phase::new(1, 4)),
+            4 => Gate::P(Phase::new(-1, 4)),
+            5 => Gate::CZ {
+                control: q,
+                target: r,
+            },
+            _ => Gate::CNOT {
+                control: q,
+                target: r,
+            },
+        };
+        gates.push((q, gate));
+    }
+    gates
+}
+
+/// The diagram of `gates`, built spider by spider, with the power of √2 it carries on top of
+/// the circuit: a Z and an X spider joined by a wire are CNOT/√2, and two Z spiders joined
+/// by a Hadamard edge are CZ/√2.
+fn diagram(width: usize, gates: &[(usize, Gate)]) -> (ZXGraph, i32) {
+    let mut graph = ZXGraph::new();
+    let mut wires: Vec<NodeIndex> = (0..width)
+        .map(|_| graph.add_input_node(SpiderType::Z, Phase::ZERO))
+        .collect();
+    let mut sqrt2_power = 0;
+    let mut extend = |graph: &mut ZXGraph, wire: usize, spider_type, phase, edge_type| {
+        let node = graph.add_node(spider_type, phase);
+        graph.add_edge(wires[wire], node, edge_type);
+        wires[wire] = node;
+        node
+    };
+    for (wire, gate) in gates.iter().cloned() {
+        match gate {
+            Gate::H => {
+                extend(
+                    &mut graph,
+                    wire,
+                    SpiderType::Z,
+                    Phase::ZERO,
+                    EdgeType::Hadamard,
+                );
+            }
+            Gate::P(phase) => {
+                extend(&mut graph, wire, SpiderType::Z, phase, EdgeType::Regular);
+            }
+            Gate::CNOT { control, target } => {
+                let c = extend(
+                    &mut graph,
+                    control,
+                    SpiderType::Z,
+                    Phase::ZERO,
+                    EdgeType::Regular,
+                );
+                let t = extend(
+                    &mut graph,
+                    target,
+                    SpiderType::X,
+                    Phase::ZERO,
+                    EdgeType::Regular,
+                );
+                graph.add_edge(c, t, EdgeType::Regular);
+                sqrt2_power -= 1;
+            }
+            Gate::CZ { control, target } => {
+                let c = extend(
+                    &mut graph,
+                    control,
+                    SpiderType::Z,
+                    Phase::ZERO,
+                    EdgeType::Regular,
+                );
+                let t = extend(
+                    &mut graph,
+                    target,
+                    SpiderType::Z,
+                    Phase::ZERO,
+                    EdgeType::Regular,
+                );
+                graph.add_edge(c, t, EdgeType::Hadamard);
+                sqrt2_power -= 1;
+            }
+            _ => unreachable!("random_clifford_t only draws H, P, CNOT and CZ"),
+        }
+    }
+    for wire in wires {
+        let output = graph.add_output_node(SpiderType::Z, Phase::ZERO);
+        graph.add_edge(wire, output, EdgeType::Regular);
+    }
+    (graph, sqrt2_power)
+}
+
+fn bits(index: usize, width: usize) -> Vec<bool> {
+    (0..width).map(|bit| index >> bit & 1 == 1).collect()
+}
+
+/// The diagram with every input and output spider turned into the X spider preparing its
+/// bit, which is √2 times that basis state.
+fn closed(graph: &ZXGraph, input_bits: &[bool], output_bits: &[bool]) -> ZXGraph {
+    let mut closed = graph.clone();
+    let boundary = graph
+        .ordered_inputs()
+        .into_iter()
+        .chain(graph.ordered_outputs());
+    for (node, &bit) in boundary.zip(input_bits.iter().chain(output_bits)) {
+        let spider = closed.nodes[node.0].as_mut().unwrap();
+        spider.spider_type = SpiderType::X;
+        spider.phase = if bit { Phase::PI } else { Phase::ZERO };
+        closed.unset_as_input(node);
+        closed.unset_as_output(node);
+    }
+    closed
+}
+
+#[test]
+fn amplitudes_match_dense_simulation() {
+    let mut rng = StdRng::seed_from_u64(2024);
+    for width in 2..=12 {
+        let gates = random_clifford_t(&mut rng, width, 6 * width);
+        let (graph, sqrt2_power) = diagram(width, &gates);
+        let factor = SQRT_2.powi(sqrt2_power);
+
+        let dimension = 1 << width;
+        let inputs = [0, dimension - 1, rng.random_range(0..dimension)];
+        for input in inputs {
+            let mut state = StateVector::from_basis_state(width, input);
+            for (wire, gate) in &gates {
+                state.apply_gate(*wire, gate);
+            }
+            let amplitudes = state.amplitudes();
+
+            // The likeliest output, so that nonzero amplitudes are always compared
+            let likeliest = (0..dimension)
+                .max_by(|&a, &b| {
+                    amplitudes[a]
+                        .norm_sqr()
+                        .total_cmp(&amplitudes[b].norm_sqr())
+                })
+                .unwrap();
+            let outputs = [0, likeliest, rng.random_range(0..dimension)];
+            for output in outputs {
+                assert_close(
+                    amplitudes[output] * factor,
+                    graph.amplitude(&bits(input, width), &bits(output, width)),
+                    &format!("{} qubits, <{}|U|{}>\n{:?}", width, output, input, gates),
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn closed_diagrams_match_plugged_amplitudes() {
+    let mut rng = StdRng::seed_from_u64(7);
+    for width in [2, 5, 8] {
+        let (graph, _) = diagram(width, &random_clifford_t(&mut rng, width, 5 * width));
+        let (input, output) = (bits(1, width), bits(rng.random_range(0..1 << width), width));
+
+        let amplitude = graph.amplitude(&input, &output);
+        let value = closed(&graph, &input, &output).scalar_value();
+        let plugs = SQRT_2.powi(2 * width as i32);
+        assert_close(amplitude * plugs, value, &format!("{} qubits", width));
+    }
+}
+
+#[test]
+fn scalar_value_of_small_closed_diagrams() {
+    // A lone spider is 1 + e^(iα)
+    let mut graph = ZXGraph::new();
+    graph.add_node(SpiderType::Z, Phase::new(1, 2));
+    assert_close(Complex::new(1.0, 1.0), graph.scalar_value(), "Z(π/2)");
+
+    // A one-legged Z spider is |0⟩ + |1⟩ and a one-legged X spider √2|0⟩
+    let mut graph = ZXGraph::new();
+    let z = graph.add_node(SpiderType::Z, Phase::ZERO);
+    let x = graph.add_node(SpiderType::X, Phase::ZERO);
+    graph.add_edge(z, x, EdgeType::Regular);
+    assert_close(Complex::new(SQRT_2, 0.0), graph.scalar_value(), "Z-X");
+
+    // Through a Hadamard the X spider becomes |0⟩ + |1⟩ too
+    let mut graph = ZXGraph::new();
+    let a = graph.add_node(SpiderType::Z, Phase::ZERO);
+    let b = graph.add_node(SpiderType::X, Phase::ZERO);
+    graph.add_edge(a, b, EdgeType::Hadamard);
+    assert_close(Complex::new(2.0, 0.0), graph.scalar_value(), "Z-H-X");
+}
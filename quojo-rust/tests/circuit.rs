@@ -0,0 +1,103 @@
+mod common;
+
+use common::*;
+use quojo_rust::linalg::vector::Complex;
+use quojo_rust::qcore::circuits::{Circuit, CircuitRepr};
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::tikz::{TikzCircuit, TikzQConfig};
+use quojo_rust::qcore::{self, Targets};
+use quojo_rust::sim::statevector::simulate;
+use quojo_rust::utils::to_zx::CircuitConverter;
+
+/// A GHZ state preparation on however many qubits are asked for.
+fn ghz(num_qubits: usize) -> Circuit {
+    let mut circuit = Circuit::new(num_qubits);
+    circuit.Apply(Gate::H, Targets(&[0]));
+    for q in 1..num_qubits {
+        circuit.ApplyControlled(Gate::X, q - 1, q);
+    }
+    circuit
+}
+
+#[test]
+fn width_is_chosen_at_run_time() {
+    for num_qubits in 1..6 {
+        let circuit = ghz(num_qubits);
+        assert_eq!(circuit.num_qubits(), num_qubits);
+
+        let amplitudes = simulate(&circuit);
+        let half = Complex::new(std::f64::consts::FRAC_1_SQRT_2, 0.0);
+        assert_close(half, amplitudes[0], "simulated |0…0⟩");
+        assert_close(half, amplitudes[(1 << num_qubits) - 1], "simulated |1…1⟩");
+
+        let graph = CircuitConverter::convert(&circuit).unwrap();
+        assert_eq!(graph.input_nodes.len(), num_qubits);
+        let matrix = graph.to_matrix();
+        assert_close(half, matrix[0][0], "ZX |0…0⟩");
+        assert_close(half, matrix[(1 << num_qubits) - 1][0], "ZX |1…1⟩");
+    }
+}
+
+#[test]
+fn qubits_and_registers_can_be_added_later() {
+    let mut circuit = Circuit::new(0);
+    let data = circuit.add_register("data", 2);
+    circuit.Apply(Gate::H, Targets(&[data.start]));
+    let ancilla = circuit.add_register("ancilla", 1);
+    let extra = circuit.add_qubits(2);
+
+    assert_eq!(data, 0..2);
+    assert_eq!(ancilla, 2..3);
+    assert_eq!(extra, 3..5);
+    assert_eq!(circuit.num_qubits(), 5);
+    assert_eq!(circuit.register("ancilla").unwrap().qubits, 2..3);
+    assert!(circuit.register("missing").is_none());
+
+    circuit.ApplyControlled(Gate::X, data.start, ancilla.start);
+    circuit.Apply(Gate::P(Phase::new(1, 2)), Targets(&[extra.end - 1]));
+    assert_eq!(circuit.wires().len(), 5);
+    assert_eq!(circuit.dag.predecessors(1), &[0]);
+
+    let tikz = circuit.to_tikz(&TikzQConfig::default());
+    assert!(tikz.contains("$q_{4}$"));
+    assert_eq!(simulate(&circuit).len(), 32);
+}
+
+#[test]
+#[should_panic(expected = "Register data already exists")]
+fn register_names_are_unique() {
+    let mut circuit = Circuit::new(1);
+    circuit.add_register("data", 1);
+    circuit.add_register("data", 2);
+}
+
+#[test]
+fn fixed_width_wraps_runtime_circuit() {
+    let mut repr = qcore::CircuitRepr::<3>();
+    repr.Apply(Gate::H, Targets(&[0]));
+    repr.ApplyControlled(Gate::X, 0, 2);
+    assert_eq!(repr.num_qubits(), 3);
+
+    let circuit = repr.clone().into_circuit();
+    assert_eq!(circuit.ordered_gates(), repr.ordered_gates());
+    for (&expected, &actual) in simulate(&repr).iter().zip(&simulate(&circuit)) {
+        assert_close(expected, actual, "wrapped amplitude");
+    }
+
+    let rewrapped = CircuitRepr::<3>::from_circuit(circuit);
+    assert_eq!(rewrapped.wires(), repr.wires());
+}
+
+#[test]
+fn extraction_returns_runtime_circuit() {
+    let circuit = ghz(4);
+    let mut graph = CircuitConverter::convert(&circuit).unwrap();
+    let expected = graph.to_matrix();
+    graph.full_reduce();
+
+    let extracted = graph.extract().unwrap();
+    assert_eq!(extracted.num_qubits(), 4);
+    let actual = CircuitConverter::convert(&extracted).unwrap().to_matrix();
+    assert_close_up_to_phase(&expected, &actual);
+}
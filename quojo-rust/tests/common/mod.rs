@@ -0,0 +1,254 @@
+//! Matrix and diagram helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use quojo_rust::linalg::vector::Complex;
+use quojo_rust::qcore::circuits::{Circuit, CircuitRepr};
+use quojo_rust::qcore::gates::{DecomposedGate, Gate, PrimitiveGate};
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::{self, Targets};
+use quojo_rust::sim::statevector::{StateVector, single_qubit_matrix};
+use quojo_rust::utils::to_zx::CircuitConverter;
+use quojo_rust::zxcalc::graph::{SpiderType, ZXGraph};
+use rand::Rng;
+use rand::rngs::StdRng;
+
+pub const TOLERANCE: f64 = 1e-9;
+
+pub fn c(re: f64, im: f64) -> Complex {
+    Complex::new(re, im)
+}
+
+/// A unitary from Gram-Schmidt on pseudo-random complex columns.
+pub fn random_unitary(num_qubits: usize, seed: u64) -> Vec<Vec<Complex>> {
+    let dimension = 1 << num_qubits;
+    let mut state = seed;
+    let mut next = || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+    };
+
+    let mut columns: Vec<Vec<Complex>> = Vec::new();
+    for _ in 0..dimension {
+        let mut column: Vec<Complex> = (0..dimension).map(|_| c(next(), next())).collect();
+        for previous in &columns {
+            let mut overlap = c(0.0, 0.0);
+            for (p, v) in previous.iter().zip(&column) {
+                overlap = overlap + p.conj() * *v;
+            }
+            for (v, p) in column.iter_mut().zip(previous) {
+                *v = *v - overlap * *p;
+            }
+        }
+        let norm = column.iter().map(|v| v.norm_sqr()).sum::<f64>().sqrt();
+        columns.push(column.into_iter().map(|v| v * (1.0 / norm)).collect());
+    }
+
+    (0..dimension)
+        .map(|row| (0..dimension).map(|col| columns[col][row]).collect())
+        .collect()
+}
+
+/// The full matrix of `matrix` on `qubits` inside a `width`-qubit register.
+pub fn embed(matrix: &[Vec<Complex>], qubits: &[usize], width: usize) -> Vec<Vec<Complex>> {
+    let local = |index: usize| -> usize {
+        qubits
+            .iter()
+            .enumerate()
+            .map(|(bit, &q)| (index >> q & 1) << bit)
+            .sum()
+    };
+    let mask: usize = qubits.iter().map(|&q| 1 << q).sum();
+
+    (0..1 << width)
+        .map(|output: usize| {
+            (0..1 << width)
+                .map(|input: usize| {
+                    if output & !mask == input & !mask {
+                        matrix[local(output)][local(input)]
+                    } else {
+                        c(0.0, 0.0)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn product(a: &[Vec<Complex>], b: &[Vec<Complex>]) -> Vec<Vec<Complex>> {
+    (0..a.len())
+        .map(|i| {
+            (0..b[0].len())
+                .map(|j| (0..b.len()).fold(c(0.0, 0.0), |sum, k| sum + a[i][k] * b[k][j]))
+                .collect()
+        })
+        .collect()
+}
+
+/// `high ⊗ low`, where `low` acts on the low bits of the index.
+pub fn kron(high: &[Vec<Complex>], low: &[Vec<Complex>]) -> Vec<Vec<Complex>> {
+    let n = low.len();
+    (0..high.len() * n)
+        .map(|row| {
+            (0..high.len() * n)
+                .map(|col| high[row / n][col / n] * low[row % n][col % n])
+                .collect()
+        })
+        .collect()
+}
+
+/// Checks that two numbers agree within `TOLERANCE`; `context` says which ones failed.
+pub fn assert_close(expected: Complex, actual: Complex, context: &str) {
+    assert!(
+        (expected.re - actual.re).abs() < TOLERANCE && (expected.im - actual.im).abs() < TOLERANCE,
+        "{}: expected {:?}, got {:?}",
+        context,
+        expected,
+        actual
+    );
+}
+
+pub fn assert_matrices_close(expected: &[Vec<Complex>], actual: &[Vec<Complex>]) {
+    for (i, (expected_row, actual_row)) in expected.iter().zip(actual).enumerate() {
+        for (j, (e, a)) in expected_row.iter().zip(actual_row).enumerate() {
+            assert!(
+                (e.re - a.re).abs() < TOLERANCE && (e.im - a.im).abs() < TOLERANCE,
+                "entry ({}, {}): expected {:?}, got {:?}",
+                i,
+                j,
+                e,
+                a
+            );
+        }
+    }
+}
+
+/// Checks that `graph` still has the matrix `expected`, taken before a rewrite.
+pub fn assert_same_matrix(expected: &[Vec<Complex>], graph: &ZXGraph) {
+    assert_matrices_close(expected, &graph.to_matrix());
+}
+
+/// The number of spiders in `graph`, not counting boundaries.
+pub fn spider_count(graph: &ZXGraph) -> usize {
+    graph
+        .nodes
+        .iter()
+        .flatten()
+        .filter(|node| node.spider_type != SpiderType::Boundary)
+        .count()
+}
+
+/// Like `assert_matrices_close`, after removing the global phase between the matrices. Any
+/// nonzero factor is removed, so this also checks that they are proportional.
+pub fn assert_close_up_to_phase(expected: &[Vec<Complex>], actual: &[Vec<Complex>]) {
+    let (row, col) = (0..expected.len())
+        .flat_map(|row| (0..expected.len()).map(move |col| (row, col)))
+        .max_by(|&(r1, c1), &(r2, c2)| expected[r1][c1].abs().total_cmp(&expected[r2][c2].abs()))
+        .unwrap();
+    let ratio =
+        actual[row][col] * expected[row][col].conj() * (1.0 / expected[row][col].norm_sqr());
+    assert!(ratio.abs() > TOLERANCE, "matrix is zero");
+    let rephased: Vec<Vec<Complex>> = expected
+        .iter()
+        .map(|values| values.iter().map(|&v| v * ratio).collect())
+        .collect();
+    assert_matrices_close(&rephased, actual);
+}
+
+pub fn simulated_matrix(circuit: &Circuit) -> Vec<Vec<Complex>> {
+    let width = circuit.num_qubits();
+    let columns: Vec<Vec<Complex>> = (0..1 << width)
+        .map(|input| {
+            let mut state = StateVector::from_basis_state(width, input);
+            state.run(circuit);
+            state.amplitudes()
+        })
+        .collect();
+    (0..1 << width)
+        .map(|row| (0..1 << width).map(|col| columns[col][row]).collect())
+        .collect()
+}
+
+/// Checks that the ZX diagram of `circuit` has the matrix the simulator gives it.
+pub fn assert_zx_matches_simulation(circuit: &Circuit) {
+    let graph = CircuitConverter::convert(circuit).unwrap();
+    assert_matrices_close(&simulated_matrix(circuit), &graph.to_matrix());
+}
+
+/// The unitary of a decomposition, reading Z on several qubits as a multi-controlled Z.
+pub fn decomposed_matrix(primitives: &[DecomposedGate], width: usize) -> Vec<Vec<Complex>> {
+    let columns: Vec<Vec<Complex>> = (0..1 << width)
+        .map(|input| {
+            let mut state = StateVector::from_basis_state(width, input);
+            for primitive in primitives {
+                let gate = match &primitive.gate {
+                    PrimitiveGate::X => Gate::X,
+                    PrimitiveGate::Z => Gate::Z,
+                    PrimitiveGate::H => Gate::H,
+                    PrimitiveGate::P(phase) => Gate::P(phase.clone()),
+                    PrimitiveGate::Connect => continue,
+                };
+                let (target, controls) = primitive.qubits.split_last().unwrap();
+                state.apply_controlled_matrix(controls, *target, single_qubit_matrix(&gate));
+            }
+            state.amplitudes()
+        })
+        .collect();
+    (0..1 << width)
+        .map(|row| (0..1 << width).map(|col| columns[col][row]).collect())
+        .collect()
+}
+
+/// The matrix of `gate` on its targets, applied only where `controls` are |1⟩ and
+/// `open_controls` are |0⟩.
+pub fn controlled_matrix(
+    gate: &Gate,
+    targets: &[usize],
+    controls: &[usize],
+    open_controls: &[usize],
+    width: usize,
+) -> Vec<Vec<Complex>> {
+    let mut uncontrolled = Circuit::new(width);
+    uncontrolled.Apply(gate.clone(), Targets(targets));
+    let matrix = simulated_matrix(&uncontrolled);
+
+    let fires = |index: usize| {
+        controls.iter().all(|&q| index >> q & 1 == 1)
+            && open_controls.iter().all(|&q| index >> q & 1 == 0)
+    };
+    (0..1 << width)
+        .map(|output| {
+            (0..1 << width)
+                .map(|input| match (fires(input), output == input) {
+                    (true, _) => matrix[output][input],
+                    (false, true) => c(1.0, 0.0),
+                    (false, false) => c(0.0, 0.0),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Thirty random H, X, phase, CZ and CNOT gates on four qubits, with phases that are
+/// multiples of `phase_step`, and SWAPs too when `swaps` is set.
+pub fn random_circuit(rng: &mut StdRng, phase_step: Phase, swaps: bool) -> CircuitRepr<4> {
+    let mut circuit = qcore::CircuitRepr::<4>();
+    let kinds = if swaps { 7 } else { 6 };
+    for _ in 0..30 {
+        let q = rng.random_range(0..4);
+        let r = (q + rng.random_range(1..4)) % 4;
+        match rng.random_range(0..kinds) {
+            0 => circuit.Apply(Gate::H, Targets(&[q])),
+            1 => circuit.Apply(Gate::X, Targets(&[q])),
+            2 => circuit.Apply(
+                Gate::P(phase_step.clone() * rng.random_range(1..8)),
+                Targets(&[q]),
+            ),
+            3 => circuit.ApplyControlled(Gate::Z, q, r),
+            4 if swaps => circuit.ApplySwap(q, r),
+            _ => circuit.ApplyControlled(Gate::X, q, r),
+        }
+    }
+    circuit
+}
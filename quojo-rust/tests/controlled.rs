@@ -0,0 +1,269 @@
+mod common;
+
+use common::*;
+use quojo_rust::error::QuojoError;
+use quojo_rust::linalg::vector::Complex;
+use quojo_rust::qcore::circuits::Circuit;
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::tikz::{TikzCircuit, TikzQConfig};
+use quojo_rust::qcore::{self, Controls, Targets};
+use quojo_rust::utils::synthesis::synthesize_controlled_gate;
+use quojo_rust::utils::to_zx::CircuitConverter;
+use std::collections::HashMap;
+
+/// Gate, targets, controls, open controls and width.
+type Case = (Gate, Vec<usize>, Vec<usize>, Vec<usize>, usize);
+
+fn cases() -> Vec<Case> {
+    let unitary = Gate::unitary("U", &[3, 1], random_unitary(2, 21)).unwrap();
+    vec![
+        (Gate::H, vec![2], vec![0], vec![], 3),
+        (Gate::Y, vec![1], vec![2, 0], vec![], 3),
+        (Gate::P(Phase::new(1, 3)), vec![3], vec![0, 1, 2], vec![], 4),
+        (Gate::P(Phase::new(3, 4)), vec![0], vec![1], vec![2], 3),
+        (Gate::H, vec![2, 3], vec![1], vec![0], 4),
+        (Gate::X, vec![3], vec![0, 2], vec![1], 4),
+        (Gate::X, vec![0], vec![1, 2, 3], vec![], 4),
+        (Gate::Z, vec![1], vec![], vec![0, 2], 3),
+        (
+            Gate::SWAP {
+                qubit1: 0,
+                qubit2: 3,
+            },
+            vec![0, 3],
+            vec![1, 2],
+            vec![],
+            4,
+        ),
+        (unitary, vec![3, 1], vec![0], vec![2], 4),
+    ]
+}
+
+fn build(
+    gate: &Gate,
+    targets: &[usize],
+    controls: &[usize],
+    open: &[usize],
+    width: usize,
+) -> Circuit {
+    let mut circuit = Circuit::new(width);
+    circuit.apply_controlled(
+        gate.clone(),
+        Controls(controls).with_open(open),
+        Targets(targets),
+    );
+    circuit
+}
+
+#[test]
+fn simulation_matches_controlled_matrix() {
+    for (gate, targets, controls, open, width) in cases() {
+        let circuit = build(&gate, &targets, &controls, &open, width);
+        assert_matrices_close(
+            &controlled_matrix(&gate, &targets, &controls, &open, width),
+            &simulated_matrix(&circuit),
+        );
+    }
+}
+
+#[test]
+fn synthesis_reproduces_controlled_gates() {
+    for (gate, targets, controls, open, width) in cases() {
+        let circuit = build(&gate, &targets, &controls, &open, width);
+        let mut synthesized = Circuit::new(width);
+        let mut phase = 0.0;
+        for instruction in circuit.dag.iter() {
+            let synthesis =
+                synthesize_controlled_gate(&[], instruction.qubits[0], &instruction.gate);
+            for instruction in synthesis.instructions {
+                assert!(
+                    matches!(
+                        instruction.gate,
+                        Gate::H
+                            | Gate::P(_)
+                            | Gate::X
+                            | Gate::CNOT { .. }
+                            | Gate::CZ { .. }
+                            | Gate::Toffoli { .. }
+                    ),
+                    "{} is not a ZX primitive",
+                    instruction.gate
+                );
+                synthesized.dag.push(instruction);
+            }
+            phase += synthesis.phase;
+        }
+
+        let phase = Complex::from_phase(phase);
+        let actual: Vec<Vec<Complex>> = simulated_matrix(&synthesized)
+            .into_iter()
+            .map(|row| row.into_iter().map(|v| v * phase).collect())
+            .collect();
+        assert_matrices_close(&simulated_matrix(&circuit), &actual);
+    }
+}
+
+#[test]
+fn zx_conversion_matches_simulation() {
+    let cases = [
+        (Gate::Z, vec![2], vec![0, 1], vec![], 3),
+        (Gate::P(Phase::new(1, 2)), vec![0], vec![1], vec![], 2),
+        (Gate::H, vec![1], vec![], vec![0], 2),
+        (Gate::X, vec![3], vec![0, 1, 2], vec![], 4),
+        (
+            Gate::SWAP {
+                qubit1: 0,
+                qubit2: 2,
+            },
+            vec![0, 2],
+            vec![],
+            vec![1],
+            3,
+        ),
+    ];
+    for (gate, targets, controls, open, width) in cases {
+        let mut circuit = build(&gate, &targets, &controls, &open, width);
+        circuit.Apply(Gate::H, Targets(&[0]));
+        assert_zx_matches_simulation(&circuit);
+    }
+}
+
+#[test]
+fn named_gates_are_kept_where_they_fit() {
+    let mut circuit = Circuit::new(4);
+    circuit.apply_controlled(Gate::X, Controls(&[0]), Targets(&[1]));
+    circuit.apply_controlled(Gate::X, Controls(&[0, 1]), Targets(&[2]));
+    circuit.apply_controlled(Gate::Z, Controls(&[3]), Targets(&[0]));
+    circuit.apply_controlled(
+        Gate::SWAP {
+            qubit1: 1,
+            qubit2: 2,
+        },
+        Controls(&[0]),
+        Targets(&[1, 2]),
+    );
+    circuit.apply_controlled(
+        Gate::CNOT {
+            control: 1,
+            target: 3,
+        },
+        Controls(&[2]),
+        Targets(&[1, 3]),
+    );
+    circuit.apply_controlled(Gate::X, Controls(&[0]).with_open(&[1]), Targets(&[2]));
+    circuit.apply_controlled(Gate::H, Controls(&[]), Targets(&[3]));
+
+    let gates: Vec<String> = circuit
+        .ordered_gates()
+        .iter()
+        .map(|(_, gate)| gate.to_string())
+        .collect();
+    assert_eq!(
+        gates,
+        [
+            "CNOT(0,1)",
+            "Toffoli(0,1,2)",
+            "CZ(3,0)",
+            "Fredkin(0,1,2)",
+            "Toffoli(2,1,3)",
+            "C(0,!1)X(2)",
+            "H"
+        ]
+    );
+
+    // Nested controls are merged into one gate
+    let mut nested = Circuit::new(4);
+    let inner = circuit.dag.instructions()[5].gate.clone();
+    nested.apply_controlled(inner, Controls(&[3]), Targets(&[0, 1, 2]));
+    assert_eq!(nested.ordered_gates()[0].1.to_string(), "C(3,0,!1)X(2)");
+    assert_eq!(nested.dag.instructions()[0].qubits, vec![3, 0, 1, 2]);
+}
+
+#[test]
+fn controlled_builders_reject_bad_qubits() {
+    let mut circuit = Circuit::new(3);
+    let error = circuit
+        .try_apply_controlled(Gate::H, Controls(&[0]).with_open(&[2]), Targets(&[2]))
+        .unwrap_err();
+    assert!(matches!(error, QuojoError::DuplicateQubit { qubit: 2, .. }));
+    assert_eq!(
+        error.to_string(),
+        "Gate C(0,!2)H(2) uses qubit 2 more than once"
+    );
+
+    assert!(matches!(
+        circuit.try_apply_controlled(Gate::H, Controls(&[0]), Targets(&[1, 3])),
+        Err(QuojoError::QubitOutOfRange { qubit: 3, .. })
+    ));
+    assert!(matches!(
+        circuit.try_apply_controlled(Gate::Y, Controls(&[0]), Targets(&[1, 1])),
+        Err(QuojoError::DuplicateQubit { qubit: 1, .. })
+    ));
+    let swap = Gate::SWAP {
+        qubit1: 1,
+        qubit2: 2,
+    };
+    assert_eq!(
+        circuit.try_apply_controlled(swap.clone(), Controls(&[0]), Targets(&[1])),
+        Err(QuojoError::ArityMismatch {
+            gate: swap,
+            expected: 2,
+            found: 1
+        })
+    );
+    assert!(circuit.dag.is_empty());
+}
+
+#[test]
+fn controlled_phase_stays_symbolic() {
+    let mut circuit = Circuit::new(3);
+    circuit.Apply(Gate::H, Targets(&[0, 1, 2]));
+    circuit.apply_controlled(
+        Gate::P(Phase::variable("theta")),
+        Controls(&[0, 1]),
+        Targets(&[2]),
+    );
+    assert_eq!(
+        circuit.variables().into_iter().collect::<Vec<_>>(),
+        ["theta"]
+    );
+
+    let values = HashMap::from([("theta".to_string(), std::f64::consts::FRAC_PI_4)]);
+    let bound = circuit.bind(&values);
+    let mut expected = Circuit::new(3);
+    expected.Apply(Gate::H, Targets(&[0, 1, 2]));
+    expected.apply_controlled(Gate::P(Phase::new(1, 4)), Controls(&[0, 1]), Targets(&[2]));
+    assert_matrices_close(&simulated_matrix(&expected), &simulated_matrix(&bound));
+
+    // The symbolic phase survives conversion and binds to the same diagram
+    let graph = CircuitConverter::convert(&circuit).unwrap();
+    assert!(!graph.variables().is_empty());
+    assert_matrices_close(
+        &simulated_matrix(&expected),
+        &graph.bind(&values).to_matrix(),
+    );
+}
+
+#[test]
+fn tikz_draws_controls_and_targets() {
+    let mut circuit = qcore::CircuitRepr::<4>();
+    circuit.apply_controlled(Gate::H, Controls(&[0]).with_open(&[3]), Targets(&[1]));
+    circuit.apply_controlled(Gate::X, Controls(&[0, 2]), Targets(&[3]));
+
+    let tikz = circuit.to_tikz(&TikzQConfig::default());
+    assert!(
+        tikz.contains(
+            "draw=black, fill=white, minimum size=0.15cm, inner sep=0pt] at (1.20,-1.80)"
+        )
+    );
+    assert!(tikz.contains("at (1.20,-0.60) {$H$}"));
+    assert!(tikz.contains("\\draw[thick] (1.20,-1.80) -- (1.20,-0.60);"));
+    // The Toffoli spans the controlled H, so it moves to the next column
+    assert_eq!(
+        tikz.matches("fill=black, minimum size=0.15cm, inner sep=0pt] at (2.40")
+            .count(),
+        2
+    );
+    assert!(tikz.contains("at (2.40,-1.80) {$\\oplus$}"));
+}
@@ -0,0 +1,144 @@
+use quojo_rust::qcore::circuits::CircuitRepr;
+use quojo_rust::qcore::dag::{CircuitDag, Instruction};
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::tikz::{TikzQConfig, generate_tikz_circuit};
+use quojo_rust::qcore::{self, Targets};
+
+/// CNOTs in both directions interleaved with single-qubit gates on three wires.
+fn interleaved_circuit() -> CircuitRepr<3> {
+    let mut circuit = qcore::CircuitRepr::<3>();
+    circuit.ApplyControlled(Gate::X, 0, 1); // 0
+    circuit.Apply(Gate::H, Targets(&[2])); // 1
+    circuit.ApplyControlled(Gate::X, 1, 0); // 2
+    circuit.Apply(Gate::P(Phase::new(1, 4)), Targets(&[1])); // 3
+    circuit.ApplyControlled(Gate::X, 2, 1); // 4
+    circuit.ApplyControlled(Gate::X, 0, 1); // 5
+    circuit.Apply(Gate::X, Targets(&[0])); // 6
+    circuit
+}
+
+#[test]
+fn keeps_the_order_gates_were_applied_in() {
+    let circuit = interleaved_circuit();
+    let gates: Vec<Gate> = circuit
+        .ordered_gates()
+        .into_iter()
+        .map(|(_, g)| g)
+        .collect();
+    assert_eq!(
+        gates,
+        vec![
+            Gate::CNOT {
+                control: 0,
+                target: 1
+            },
+            Gate::H,
+            Gate::CNOT {
+                control: 1,
+                target: 0
+            },
+            Gate::P(Phase::new(1, 4)),
+            Gate::CNOT {
+                control: 2,
+                target: 1
+            },
+            Gate::CNOT {
+                control: 0,
+                target: 1
+            },
+            Gate::X,
+        ]
+    );
+    assert_eq!(circuit.dag.instructions()[3].qubits, vec![1]);
+}
+
+#[test]
+fn dependencies_follow_shared_qubits() {
+    let dag = interleaved_circuit().into_circuit().dag;
+
+    assert_eq!(dag.predecessors(0), &[] as &[usize]);
+    assert_eq!(dag.successors(0), &[2]);
+    assert_eq!(dag.predecessors(4), &[1, 3]);
+    assert_eq!(dag.successors(4), &[5]);
+    assert_eq!(dag.predecessors(5), &[2, 4]);
+    assert_eq!(dag.successors(5), &[6]);
+    assert_eq!(dag.successors(6), &[] as &[usize]);
+
+    assert_eq!(
+        dag.layers(),
+        vec![vec![0, 1], vec![2], vec![3], vec![4], vec![5], vec![6]]
+    );
+    assert_eq!(dag.depth(), 6);
+
+    // Every instruction comes after its predecessors in iteration order
+    for (index, _) in dag.iter().enumerate() {
+        assert!(dag.predecessors(index).iter().all(|&p| p < index));
+    }
+}
+
+#[test]
+fn converts_to_and_from_wires() {
+    let circuit = interleaved_circuit();
+    let wires = circuit.wires();
+    assert_eq!(wires[0].len(), 4);
+    assert_eq!(wires[1].len(), 5);
+    assert_eq!(
+        wires[2],
+        vec![
+            Gate::H,
+            Gate::CNOT {
+                control: 2,
+                target: 1
+            }
+        ]
+    );
+
+    let rebuilt = CircuitRepr::from_wires(&wires);
+    assert_eq!(rebuilt.wires(), wires);
+    // Independent gates on different wires may come back in another order
+    assert_eq!(rebuilt.dag.depth(), circuit.dag.depth());
+}
+
+#[test]
+fn layers_run_disjoint_gates_in_parallel() {
+    let mut dag = CircuitDag::new(4);
+    for q in 0..4 {
+        dag.push(Instruction::new(Gate::H, q));
+    }
+    dag.push(Instruction::new(
+        Gate::CZ {
+            control: 0,
+            target: 1,
+        },
+        0,
+    ));
+    dag.push(Instruction::new(
+        Gate::CZ {
+            control: 2,
+            target: 3,
+        },
+        2,
+    ));
+    dag.push(Instruction::new(
+        Gate::SWAP {
+            qubit1: 1,
+            qubit2: 2,
+        },
+        1,
+    ));
+
+    assert_eq!(dag.layers(), vec![vec![0, 1, 2, 3], vec![4, 5], vec![6]]);
+    assert_eq!(dag.predecessors(6), &[4, 5]);
+}
+
+#[test]
+fn tikz_draws_repeated_cnots() {
+    let mut circuit = qcore::CircuitRepr::<2>();
+    circuit.ApplyControlled(Gate::X, 0, 1);
+    circuit.Apply(Gate::H, Targets(&[0]));
+    circuit.ApplyControlled(Gate::X, 0, 1);
+
+    let tikz = generate_tikz_circuit(&circuit, &TikzQConfig::default());
+    assert_eq!(tikz.matches("\\oplus").count(), 2);
+}
@@ -0,0 +1,219 @@
+mod common;
+
+use common::*;
+use quojo_rust::qcore::circuits::Circuit;
+use quojo_rust::qcore::gates::{DecomposedGate, Gate, GateDecomposition, PrimitiveGate};
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::{Controls, Targets};
+
+fn t_count(primitives: &[DecomposedGate]) -> usize {
+    primitives
+        .iter()
+        .filter(|primitive| matches!(&primitive.gate, PrimitiveGate::P(phase) if matches!(phase.as_rational(), Some((_, 4)))))
+        .count()
+}
+
+fn assert_decomposes(circuit: &Circuit) -> Vec<DecomposedGate> {
+    let gate = &circuit.dag.instructions()[0].gate;
+    let primitives = gate.decompose();
+    assert_matrices_close(
+        &simulated_matrix(circuit),
+        &decomposed_matrix(&primitives, circuit.num_qubits()),
+    );
+    primitives
+}
+
+#[test]
+fn toffoli_and_fredkin_use_seven_t_gates() {
+    for (control1, control2, target) in [(0, 1, 2), (2, 0, 1), (1, 2, 0)] {
+        let mut circuit = Circuit::new(3);
+        let toffoli = Gate::Toffoli {
+            control1,
+            control2,
+            target,
+        };
+        circuit.Apply(toffoli, Targets(&[control1, control2, target]));
+        let primitives = assert_decomposes(&circuit);
+        assert_eq!(t_count(&primitives), 7);
+        assert_eq!(
+            primitives.last().unwrap().gate,
+            PrimitiveGate::Connect,
+            "the gate's qubits are grouped last"
+        );
+    }
+
+    for (control, target1, target2) in [(0, 1, 2), (2, 0, 1), (1, 3, 0)] {
+        let mut circuit = Circuit::new(4);
+        let fredkin = Gate::Fredkin {
+            control,
+            target1,
+            target2,
+        };
+        circuit.Apply(fredkin, Targets(&[control, target1, target2]));
+        assert_eq!(t_count(&assert_decomposes(&circuit)), 7);
+    }
+}
+
+#[test]
+fn two_qubit_decompositions_match_their_gates() {
+    for gate in [
+        Gate::CNOT {
+            control: 1,
+            target: 0,
+        },
+        Gate::CZ {
+            control: 0,
+            target: 1,
+        },
+        Gate::SWAP {
+            qubit1: 0,
+            qubit2: 1,
+        },
+    ] {
+        let mut circuit = Circuit::new(2);
+        circuit.Apply(gate, Targets(&[0, 1]));
+        assert_decomposes(&circuit);
+    }
+}
+
+#[test]
+fn multi_controlled_x_needs_no_ancillas() {
+    for (controls, open, target, width) in [
+        (vec![0, 1, 2], vec![], 3, 4),
+        (vec![3, 1], vec![0], 2, 4),
+        (vec![4, 0, 2, 1], vec![], 3, 5),
+        (vec![], vec![1, 2], 0, 3),
+    ] {
+        let mut circuit = Circuit::new(width);
+        circuit.apply_controlled(
+            Gate::X,
+            Controls(&controls).with_open(&open),
+            Targets(&[target]),
+        );
+        let primitives = assert_decomposes(&circuit);
+
+        // Every qubit of the decomposition belongs to the gate
+        let gate_qubits = &circuit.dag.instructions()[0].qubits;
+        assert!(
+            primitives
+                .iter()
+                .all(|primitive| primitive.qubits.iter().all(|q| gate_qubits.contains(q)))
+        );
+        // n controls take 2^(n+1) − 1 phases of π/2^n
+        let n = controls.len() + open.len();
+        let phases = primitives
+            .iter()
+            .filter(|primitive| matches!(primitive.gate, PrimitiveGate::P(_)))
+            .count();
+        assert_eq!(phases, (1 << (n + 1)) - 1);
+    }
+}
+
+#[test]
+fn multi_controlled_phases_decompose() {
+    for (gate, controls, width) in [
+        (Gate::Z, vec![2, 0, 1], 4),
+        (Gate::P(Phase::new(2, 3)), vec![0, 1], 3),
+        (Gate::P(Phase::from(0.3)), vec![3, 0, 2], 4),
+    ] {
+        let mut circuit = Circuit::new(width);
+        let target = (0..width).find(|q| !controls.contains(q)).unwrap();
+        circuit.apply_controlled(gate, Controls(&controls), Targets(&[target]));
+        assert_decomposes(&circuit);
+    }
+}
+
+#[test]
+fn every_controlled_gate_decomposes() {
+    // Beyond controlled X, Z and P, gates are lowered through synthesis
+    let unitary = Gate::unitary("U", &[2, 0], random_unitary(2, 5)).unwrap();
+    let u3 = Gate::U3 {
+        theta: Phase::from(0.8),
+        phi: Phase::new(1, 3),
+        lambda: Phase::from(-1.1),
+    };
+    let swap = Gate::SWAP {
+        qubit1: 0,
+        qubit2: 2,
+    };
+    for (gate, targets, controls, open, width) in [
+        (Gate::H, vec![1], vec![0], vec![], 2),
+        (Gate::Y, vec![0], vec![1, 2], vec![], 3),
+        (Gate::Rx(Phase::from(0.7)), vec![2], vec![0], vec![1], 3),
+        (Gate::Ry(Phase::new(2, 5)), vec![1], vec![2, 0], vec![], 3),
+        (Gate::Rz(Phase::from(-2.3)), vec![0], vec![1, 2], vec![], 3),
+        (u3, vec![0], vec![2, 1], vec![], 3),
+        (swap, vec![0, 2], vec![1], vec![3], 4),
+        (unitary, vec![2, 0], vec![1], vec![], 3),
+    ] {
+        let mut circuit = Circuit::new(width);
+        circuit.apply_controlled(
+            gate,
+            Controls(&controls).with_open(&open),
+            Targets(&targets),
+        );
+        assert!(matches!(
+            circuit.dag.instructions()[0].gate,
+            Gate::Controlled(_)
+        ));
+        let primitives = circuit.dag.instructions()[0].gate.decompose();
+        assert_eq!(primitives.last().unwrap().gate, PrimitiveGate::Connect);
+        assert_close_up_to_phase(
+            &simulated_matrix(&circuit),
+            &decomposed_matrix(&primitives, width),
+        );
+    }
+}
+
+#[test]
+fn y_and_unitaries_decompose_up_to_global_phase() {
+    let unitary = Gate::unitary("U", &[1, 0], random_unitary(2, 9)).unwrap();
+    for (gate, targets) in [(Gate::Y, vec![1]), (unitary, vec![1, 0])] {
+        let mut circuit = Circuit::new(2);
+        circuit.Apply(gate.clone(), Targets(&targets));
+        let primitives = gate.decompose();
+        let primitives: Vec<DecomposedGate> = match gate {
+            // Single-qubit gates decompose onto qubit 0
+            Gate::Y => primitives
+                .into_iter()
+                .map(|primitive| DecomposedGate {
+                    qubits: vec![1],
+                    ..primitive
+                })
+                .collect(),
+            _ => primitives,
+        };
+        assert!(!primitives.is_empty());
+        assert_close_up_to_phase(
+            &simulated_matrix(&circuit),
+            &decomposed_matrix(&primitives, 2),
+        );
+    }
+    assert!(Gate::Reset.decompose().is_empty());
+}
+
+#[test]
+fn rotations_decompose_up_to_global_phase() {
+    for gate in [
+        Gate::S,
+        Gate::Tdg,
+        Gate::SX,
+        Gate::Rx(Phase::from(0.9)),
+        Gate::Ry(Phase::new(-1, 3)),
+        Gate::Rz(Phase::new(5, 4)),
+        Gate::U3 {
+            theta: Phase::from(1.3),
+            phi: Phase::new(1, 5),
+            lambda: Phase::from(-0.4),
+        },
+    ] {
+        let mut circuit = Circuit::new(1);
+        circuit.Apply(gate.clone(), Targets(&[0]));
+        let primitives = gate.decompose();
+        assert_close_up_to_phase(
+            &simulated_matrix(&circuit),
+            &decomposed_matrix(&primitives, 1),
+        );
+    }
+    assert!(Gate::GlobalPhase(Phase::PI).decompose().is_empty());
+}
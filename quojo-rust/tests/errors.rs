@@ -0,0 +1,337 @@
+use quojo_rust::error::QuojoError;
+use quojo_rust::qcore::circuits::{Circuit, CircuitRepr};
+use quojo_rust::qcore::dag::Instruction;
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::{self, Controls, Targets};
+use quojo_rust::sim::statevector::StateVector;
+use quojo_rust::utils::to_zx::CircuitConverter;
+use quojo_rust::zxcalc::extract::ExtractionError;
+use quojo_rust::zxcalc::graph::{EdgeType, SpiderType, ZXGraph};
+
+#[test]
+fn try_apply_rejects_bad_qubits() {
+    let mut circuit = Circuit::new(2);
+    assert_eq!(
+        circuit.try_apply(Gate::H, Targets(&[0, 2])),
+        Err(QuojoError::QubitOutOfRange {
+            gate: Gate::H,
+            qubit: 2,
+            width: 2
+        })
+    );
+    assert_eq!(
+        circuit.try_apply(Gate::X, Targets(&[1, 0, 1])),
+        Err(QuojoError::DuplicateQubit {
+            gate: Gate::X,
+            qubit: 1
+        })
+    );
+    let cz = Gate::CZ {
+        control: 1,
+        target: 1,
+    };
+    assert_eq!(
+        circuit.try_apply(cz.clone(), Targets(&[1, 1])),
+        Err(QuojoError::DuplicateQubit { gate: cz, qubit: 1 })
+    );
+    // Nothing is added when any target is rejected
+    assert!(circuit.dag.is_empty());
+
+    circuit
+        .try_apply(Gate::P(Phase::new(1, 4)), Targets(&[0, 1]))
+        .unwrap();
+    assert_eq!(circuit.dag.len(), 2);
+}
+
+#[test]
+fn try_apply_checks_arity() {
+    let mut circuit = Circuit::new(3);
+    assert_eq!(
+        circuit.try_apply(Gate::H, Targets(&[])),
+        Err(QuojoError::ArityMismatch {
+            gate: Gate::H,
+            expected: 1,
+            found: 0
+        })
+    );
+
+    let toffoli = Gate::Toffoli {
+        control1: 0,
+        control2: 1,
+        target: 2,
+    };
+    assert_eq!(
+        circuit.try_apply(toffoli.clone(), Targets(&[0])),
+        Err(QuojoError::ArityMismatch {
+            gate: toffoli.clone(),
+            expected: 3,
+            found: 1
+        })
+    );
+    circuit.try_apply(toffoli, Targets(&[0, 1, 2])).unwrap();
+    assert_eq!(circuit.dag.instructions()[0].qubits, vec![0, 1, 2]);
+}
+
+#[test]
+fn try_apply_checks_named_targets() {
+    let mut circuit = Circuit::new(3);
+    let cnot = Gate::CNOT {
+        control: 0,
+        target: 1,
+    };
+    assert_eq!(
+        circuit.try_apply(cnot.clone(), Targets(&[2, 0])),
+        Err(QuojoError::TargetMismatch {
+            gate: cnot.clone(),
+            found: vec![2, 0]
+        })
+    );
+    assert_eq!(
+        circuit.try_apply_controlled(cnot.clone(), Controls(&[2]), Targets(&[0, 2])),
+        Err(QuojoError::TargetMismatch {
+            gate: cnot.clone(),
+            found: vec![0, 2]
+        })
+    );
+    assert_eq!(
+        circuit
+            .try_apply(cnot.clone(), Targets(&[2, 0]))
+            .unwrap_err()
+            .to_string(),
+        "Gate CNOT(0,1) acts on qubits [0, 1] but was given [2, 0]"
+    );
+    assert!(circuit.dag.is_empty());
+
+    circuit.try_apply(cnot.clone(), Targets(&[1, 0])).unwrap();
+    circuit
+        .try_apply_controlled(cnot, Controls(&[2]), Targets(&[0, 1]))
+        .unwrap();
+    assert_eq!(circuit.dag.len(), 2);
+}
+
+#[test]
+fn try_apply_controlled_and_swap() {
+    let mut circuit = qcore::CircuitRepr::<2>();
+    assert_eq!(
+        circuit.try_apply_controlled(Gate::H, Controls(&[0]), Targets(&[])),
+        Err(QuojoError::ArityMismatch {
+            gate: Gate::H,
+            expected: 1,
+            found: 0
+        })
+    );
+    assert_eq!(
+        circuit.try_apply_controlled(Gate::X, Controls(&[1]), Targets(&[1])),
+        Err(QuojoError::DuplicateQubit {
+            gate: Gate::CNOT {
+                control: 1,
+                target: 1
+            },
+            qubit: 1
+        })
+    );
+    assert_eq!(
+        circuit.try_apply_swap(0, 5),
+        Err(QuojoError::QubitOutOfRange {
+            gate: Gate::SWAP {
+                qubit1: 0,
+                qubit2: 5
+            },
+            qubit: 5,
+            width: 2
+        })
+    );
+    assert!(circuit.dag.is_empty());
+
+    circuit
+        .try_apply_controlled(Gate::Z, Controls(&[0]), Targets(&[1]))
+        .unwrap();
+    circuit.try_apply_swap(1, 0).unwrap();
+    assert_eq!(circuit.dag.len(), 2);
+    assert_eq!(circuit.validate(), Ok(()));
+}
+
+#[test]
+#[should_panic(expected = "Gate CNOT(0,0) uses qubit 0 more than once")]
+fn apply_controlled_panics_on_equal_qubits() {
+    let mut circuit = qcore::CircuitRepr::<2>();
+    circuit.ApplyControlled(Gate::X, 0, 0);
+}
+
+#[test]
+fn registers_and_widths() {
+    let mut circuit = Circuit::new(0);
+    circuit.try_add_register("data", 2).unwrap();
+    assert_eq!(
+        circuit.try_add_register("data", 1),
+        Err(QuojoError::DuplicateRegister("data".to_string()))
+    );
+    assert_eq!(circuit.num_qubits(), 2);
+
+    assert_eq!(
+        CircuitRepr::<3>::try_from_circuit(circuit.clone()).err(),
+        Some(QuojoError::WidthMismatch {
+            expected: 3,
+            found: 2
+        })
+    );
+    assert!(CircuitRepr::<2>::try_from_circuit(circuit).is_ok());
+}
+
+#[test]
+fn errors_are_shared_across_modules() {
+    // Instructions pushed past the builder are caught by `validate` and by conversion
+    let mut circuit = Circuit::new(2);
+    let gate = Gate::CNOT {
+        control: 2,
+        target: 0,
+    };
+    circuit.dag.push(Instruction::new(gate.clone(), 2));
+    let expected = QuojoError::QubitOutOfRange {
+        gate,
+        qubit: 2,
+        width: 2,
+    };
+    assert_eq!(circuit.validate(), Err(expected.clone()));
+    assert_eq!(CircuitConverter::convert(&circuit).err(), Some(expected));
+
+    // Extraction errors convert with `?`
+    fn extract(graph: &ZXGraph) -> Result<Circuit, QuojoError> {
+        Ok(graph.extract()?)
+    }
+    let mut graph = ZXGraph::new();
+    graph.add_input_node(SpiderType::Boundary, Phase::ZERO);
+    let error = extract(&graph).unwrap_err();
+    assert_eq!(
+        error,
+        QuojoError::Extraction(ExtractionError::WidthMismatch {
+            inputs: 1,
+            outputs: 0,
+            width: 1
+        })
+    );
+    assert_eq!(
+        error.to_string(),
+        "Diagram has 1 inputs and 0 outputs but the circuit width is 1"
+    );
+}
+
+#[test]
+fn state_vector_try_methods() {
+    assert_eq!(
+        StateVector::try_from_basis_state(2, 4).err(),
+        Some(QuojoError::BasisStateOutOfRange {
+            index: 4,
+            num_qubits: 2
+        })
+    );
+    let mut state = StateVector::try_from_basis_state(2, 3).unwrap();
+    assert!(state.try_amplitude(4).is_err());
+    assert_eq!(state.try_amplitude(3).unwrap().re, 1.0);
+
+    let toffoli = Gate::Toffoli {
+        control1: 0,
+        control2: 1,
+        target: 2,
+    };
+    assert_eq!(
+        state.try_apply_gate(0, &toffoli),
+        Err(QuojoError::QubitOutOfRange {
+            gate: toffoli,
+            qubit: 2,
+            width: 2
+        })
+    );
+    assert_eq!(
+        state.try_apply_controlled_gate(&[0], 0, &Gate::X),
+        Err(QuojoError::DuplicateQubit {
+            gate: Gate::X,
+            qubit: 0
+        })
+    );
+    let measure = Gate::Measure { qubit: 0, clbit: 0 };
+    assert_eq!(
+        state.try_apply_gate(0, &measure),
+        Err(QuojoError::NonUnitaryGate(measure))
+    );
+    assert_eq!(
+        state.try_apply_gate(0, &Gate::Rz(Phase::variable("theta"))),
+        Err(QuojoError::UnboundVariables(vec!["theta".to_string()]))
+    );
+
+    // A wider circuit, or one bad gate, leaves the state as it was
+    assert_eq!(
+        state.try_run(&Circuit::new(3)),
+        Err(QuojoError::WidthMismatch {
+            expected: 2,
+            found: 3
+        })
+    );
+    let mut circuit = Circuit::new(2);
+    circuit.add_clbits(1);
+    circuit.Apply(Gate::X, Targets(&[0]));
+    circuit.Measure(1, 0);
+    assert!(state.try_run(&circuit).is_err());
+    assert_eq!(state.try_amplitude(3).unwrap().re, 1.0);
+
+    state.try_apply_controlled_gate(&[1], 0, &Gate::X).unwrap();
+    assert_eq!(state.amplitude(2).re, 1.0);
+}
+
+#[test]
+#[should_panic(expected = "Basis state 8 does not exist on 3 qubits")]
+fn state_vector_panics_on_missing_basis_state() {
+    StateVector::new(3).amplitude(8);
+}
+
+#[test]
+fn zx_simulation_try_methods() {
+    let mut graph = ZXGraph::new();
+    let input = graph.add_input_node(SpiderType::Boundary, Phase::ZERO);
+    let output = graph.add_output_node(SpiderType::Boundary, Phase::ZERO);
+    graph.add_edge(input, output, EdgeType::Regular);
+
+    let expected = QuojoError::CountMismatch {
+        kind: "input bits".to_string(),
+        expected: 1,
+        found: 2,
+    };
+    assert_eq!(expected.to_string(), "Expected 1 input bits but got 2");
+    assert_eq!(graph.try_amplitude(&[true, false], &[true]), Err(expected));
+    assert_eq!(
+        graph.try_amplitude(&[true], &[]),
+        Err(QuojoError::CountMismatch {
+            kind: "output bits".to_string(),
+            expected: 1,
+            found: 0
+        })
+    );
+    assert!(graph.try_compile_amplitudes(&[]).is_err());
+    assert!((graph.try_amplitude(&[true], &[true]).unwrap().re - 1.0).abs() < 1e-9);
+
+    let open = QuojoError::OpenDiagram {
+        inputs: 1,
+        outputs: 1,
+    };
+    assert_eq!(graph.try_compile_scalar().err(), Some(open.clone()));
+    assert_eq!(graph.try_scalar_value(), Err(open));
+
+    let compiled = graph.try_compile_amplitudes(&[false]).unwrap();
+    assert_eq!(
+        compiled.try_evaluate(&[]),
+        Err(QuojoError::CountMismatch {
+            kind: "parameter values".to_string(),
+            expected: 1,
+            found: 0
+        })
+    );
+    assert!((compiled.try_evaluate(&[false]).unwrap().re - 1.0).abs() < 1e-9);
+
+    let mut symbolic = ZXGraph::new();
+    symbolic.add_node(SpiderType::Z, Phase::variable("theta"));
+    assert_eq!(
+        symbolic.try_scalar_value(),
+        Err(QuojoError::UnboundVariables(vec!["theta".to_string()]))
+    );
+}
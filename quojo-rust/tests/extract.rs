@@ -0,0 +1,113 @@
+mod common;
+
+use common::*;
+use quojo_rust::linalg::vector::Complex;
+use quojo_rust::qcore::circuits::CircuitRepr;
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::{self, Targets};
+use quojo_rust::utils::to_zx::CircuitConverter;
+use quojo_rust::zxcalc::extract::ExtractionError;
+use quojo_rust::zxcalc::graph::{EdgeType, SpiderType, ZXGraph};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+fn only_extraction_gates<const WIDTH: usize>(circuit: &CircuitRepr<WIDTH>) -> bool {
+    circuit.ordered_gates().iter().all(|(_, gate)| {
+        matches!(
+            gate,
+            Gate::CNOT { .. } | Gate::CZ { .. } | Gate::H | Gate::P(_)
+        )
+    })
+}
+
+fn check_round_trip<const WIDTH: usize>(graph: &ZXGraph, expected: &[Vec<Complex>]) {
+    let extracted = graph.extract_circuit::<WIDTH>().unwrap();
+    assert!(only_extraction_gates(&extracted));
+    let actual = CircuitConverter::convert(&extracted).unwrap().to_matrix();
+    assert_close_up_to_phase(expected, &actual);
+}
+
+#[test]
+fn extracts_unsimplified_circuit() {
+    let mut circuit = qcore::CircuitRepr::<3>();
+    circuit.Apply(Gate::H, Targets(&[0]));
+    circuit.ApplyControlled(Gate::X, 0, 1);
+    circuit.Apply(Gate::P(Phase::from(0.3)), Targets(&[1]));
+    circuit.ApplySwap(1, 2);
+    circuit.ApplyControlled(Gate::Z, 2, 0);
+    circuit.Apply(Gate::Y, Targets(&[2]));
+
+    let graph = CircuitConverter::convert(&circuit).unwrap();
+    check_round_trip::<3>(&graph, &graph.to_matrix());
+}
+
+#[test]
+fn extracts_permutations() {
+    let mut circuit = qcore::CircuitRepr::<3>();
+    circuit.ApplySwap(0, 1);
+    circuit.ApplySwap(1, 2);
+    circuit.Apply(Gate::H, Targets(&[1]));
+
+    let mut graph = CircuitConverter::convert(&circuit).unwrap();
+    let expected = graph.to_matrix();
+    graph.clifford_simp();
+    check_round_trip::<3>(&graph, &expected);
+}
+
+#[test]
+fn extracts_after_clifford_simp() {
+    let mut rng = StdRng::seed_from_u64(3);
+    for _ in 0..20 {
+        let circuit = random_circuit(&mut rng, Phase::new(1, 2), true);
+        let mut graph = CircuitConverter::convert(&circuit).unwrap();
+        let expected = graph.to_matrix();
+
+        graph.clifford_simp();
+        check_round_trip::<4>(&graph, &expected);
+    }
+}
+
+#[test]
+fn extracts_after_full_reduce() {
+    let mut rng = StdRng::seed_from_u64(5);
+    for _ in 0..20 {
+        let circuit = random_circuit(&mut rng, Phase::new(1, 4), true);
+        let mut graph = CircuitConverter::convert(&circuit).unwrap();
+        let expected = graph.to_matrix();
+
+        graph.full_reduce();
+        check_round_trip::<4>(&graph, &expected);
+    }
+}
+
+#[test]
+fn extraction_errors() {
+    let circuit = qcore::CircuitRepr::<2>();
+    let graph = CircuitConverter::convert(&circuit).unwrap();
+    assert_eq!(
+        graph.extract_circuit::<3>().unwrap_err(),
+        ExtractionError::WidthMismatch {
+            inputs: 2,
+            outputs: 2,
+            width: 3
+        }
+    );
+
+    // A Z spider joining both inputs to a single output is not unitary
+    let mut graph = ZXGraph::new();
+    let z = graph.add_node(SpiderType::Z, 0.0);
+    for _ in 0..2 {
+        let input = graph.add_input_node(SpiderType::Boundary, 0.0);
+        graph.add_edge(input, z, EdgeType::Regular);
+    }
+    let a = graph.add_output_node(SpiderType::Boundary, 0.0);
+    let b = graph.add_output_node(SpiderType::Boundary, 0.0);
+    graph.add_edge(z, a, EdgeType::Regular);
+    let x = graph.add_node(SpiderType::X, 0.0);
+    graph.add_edge(x, b, EdgeType::Regular);
+    assert_eq!(
+        graph.extract_circuit::<2>().unwrap_err(),
+        ExtractionError::NoGflow
+    );
+}
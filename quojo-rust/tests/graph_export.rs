@@ -0,0 +1,133 @@
+use quojo_rust::qcore::circuits::Circuit;
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::{Controls, Targets};
+use quojo_rust::utils::to_zx::CircuitConverter;
+use quojo_rust::zxcalc::graph::{EdgeType, NodeIndex, SpiderType, ZXGraph};
+use quojo_rust::zxcalc::parametric::Parity;
+
+/// Two inputs added out of qubit order, a Hadamard edge and spiders of both colours.
+fn small_graph() -> (ZXGraph, [NodeIndex; 5]) {
+    let mut graph = ZXGraph::new();
+    let input0 = graph.add_input_node(SpiderType::Boundary, Phase::ZERO);
+    let z = graph.add_node(SpiderType::Z, Phase::new(1, 2));
+    let x = graph.add_node(SpiderType::X, Phase::ZERO);
+    let input1 = graph.add_input_node(SpiderType::Boundary, Phase::ZERO);
+    let output = graph.add_output_node(SpiderType::Boundary, Phase::ZERO);
+    graph.add_edge(input0, z, EdgeType::Regular);
+    graph.add_edge(input1, x, EdgeType::Regular);
+    graph.add_edge(z, x, EdgeType::Hadamard);
+    graph.add_edge(x, output, EdgeType::Regular);
+    (graph, [input0, z, x, input1, output])
+}
+
+#[test]
+fn dot_colours_spiders_and_edges() {
+    let (graph, [input0, z, x, input1, output]) = small_graph();
+    let dot = graph.to_dot();
+    assert!(dot.starts_with("graph zx {\n  rankdir=LR;\n"));
+    assert!(dot.ends_with("}\n"));
+
+    let line = |node: NodeIndex| {
+        let prefix = format!("  n{} [", node.0);
+        dot.lines()
+            .find(|l| l.starts_with(&prefix))
+            .unwrap()
+            .to_string()
+    };
+    assert!(line(z).contains("fillcolor=\"#ccffcc\""));
+    assert!(line(z).contains("label=\"π/2\""));
+    assert!(line(x).contains("fillcolor=\"#ffcccc\""));
+    assert!(line(x).contains("label=\"\""));
+    assert!(line(input1).contains("xlabel=\"in 1\""));
+    assert!(line(output).contains("xlabel=\"out 0\""));
+
+    assert!(dot.contains(&format!(
+        "  n{} -- n{} [color=\"#1f5fd0\", style=dashed];",
+        z.0, x.0
+    )));
+    assert!(dot.contains(&format!("  n{} -- n{};", x.0, output.0)));
+    assert!(dot.contains(&format!(
+        "  {{ rank=source; n{}; n{}; }}",
+        input0.0, input1.0
+    )));
+    assert!(dot.contains(&format!("  n{} -- n{} [style=invis];", input0.0, input1.0)));
+    assert!(dot.contains(&format!("  {{ rank=sink; n{}; }}", output.0)));
+}
+
+#[test]
+fn dot_labels_parameters_and_symbols() {
+    let mut graph = ZXGraph::new();
+    graph.add_parametric_node(
+        SpiderType::Z,
+        Phase::new(1, 4),
+        Parity::from_params(&[0, 2]),
+    );
+    graph.add_node(SpiderType::X, Phase::variable("theta") * 2i64);
+    graph.add_node(SpiderType::Z, Phase::from(0.3));
+
+    let dot = graph.to_dot();
+    assert!(dot.contains("label=\"π/4+π(b0⊕b2)\""));
+    assert!(dot.contains("label=\"2·theta\""));
+    assert!(dot.contains("label=\"0.30\""));
+    assert!(!dot.contains("rank="));
+}
+
+#[test]
+fn graphml_records_types_and_positions() {
+    let (graph, [input0, z, _, input1, output]) = small_graph();
+    let graphml = graph.to_graphml();
+    assert!(graphml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<graphml"));
+    assert!(graphml.ends_with("</graphml>\n"));
+    assert_eq!(graphml.matches("<node ").count(), 5);
+    assert_eq!(graphml.matches("<edge ").count(), 4);
+    assert_eq!(
+        graphml
+            .matches("<data key=\"edge_type\">hadamard</data>")
+            .count(),
+        1
+    );
+
+    let node = |index: NodeIndex| {
+        let start = graphml
+            .find(&format!("<node id=\"n{}\">", index.0))
+            .unwrap();
+        let end = start + graphml[start..].find("</node>").unwrap();
+        graphml[start..end].to_string()
+    };
+    let data = |index: NodeIndex, key: &str| {
+        let block = node(index);
+        let open = format!("<data key=\"{}\">", key);
+        let start = block.find(&open)? + open.len();
+        let end = start + block[start..].find("</data>").unwrap();
+        Some(block[start..end].to_string())
+    };
+    assert_eq!(data(z, "type").as_deref(), Some("Z"));
+    assert_eq!(data(z, "phase").as_deref(), Some("π/2"));
+    assert_eq!(data(input1, "input").as_deref(), Some("1"));
+    assert_eq!(data(output, "output").as_deref(), Some("0"));
+    assert_eq!(data(z, "input"), None);
+
+    let x = |index: NodeIndex| data(index, "x").unwrap().parse::<f64>().unwrap();
+    assert!(x(input0) < x(z) && x(z) < x(output));
+    assert_eq!(x(input0), x(input1));
+}
+
+#[test]
+fn exports_converted_circuits() {
+    let mut circuit = Circuit::new(3);
+    circuit.Apply(Gate::H, Targets(&[0]));
+    circuit.apply_controlled(Gate::X, Controls(&[0]), Targets(&[1]));
+    circuit.Apply(Gate::T, Targets(&[2]));
+    let mut graph = CircuitConverter::convert(&circuit).unwrap();
+    graph.full_reduce();
+    let nodes = graph.nodes.iter().flatten().count();
+    let edges = graph.edges.iter().flatten().count();
+
+    let dot = graph.to_dot();
+    assert_eq!(dot.matches(" [shape=").count(), nodes);
+    assert_eq!(dot.matches(" -- ").count(), edges + 2 + 2);
+    let graphml = graph.to_graphml();
+    assert_eq!(graphml.matches("<node ").count(), nodes);
+    assert_eq!(graphml.matches("<edge ").count(), edges);
+}
@@ -0,0 +1,407 @@
+mod common;
+
+use common::*;
+use quojo_rust::linalg::vector::Complex;
+use quojo_rust::qcore::circuits::Circuit;
+use quojo_rust::qcore::gates::{Gate, GateDecomposition, PrimitiveGate};
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::tikz::{TikzCircuit, TikzQConfig};
+use quojo_rust::qcore::{Controls, Targets};
+use quojo_rust::sim::statevector::{single_qubit_matrix, two_qubit_matrix};
+use quojo_rust::utils::synthesis::synthesize_controlled_gate;
+use quojo_rust::utils::to_zx::CircuitConverter;
+use std::collections::HashMap;
+use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_2};
+
+/// Every two-qubit gate on `first` and `second`.
+fn family(first: usize, second: usize) -> Vec<Gate> {
+    let (control, target) = (first, second);
+    let (qubit1, qubit2) = (first, second);
+    vec![
+        Gate::CP {
+            control,
+            target,
+            phase: Phase::new(2, 3),
+        },
+        Gate::CRx {
+            control,
+            target,
+            theta: Phase::new(1, 2),
+        },
+        Gate::CRy {
+            control,
+            target,
+            theta: Phase::from(-0.7),
+        },
+        Gate::CRz {
+            control,
+            target,
+            theta: Phase::new(5, 4),
+        },
+        Gate::RXX {
+            qubit1,
+            qubit2,
+            theta: Phase::new(1, 3),
+        },
+        Gate::RYY {
+            qubit1,
+            qubit2,
+            theta: Phase::from(1.1),
+        },
+        Gate::RZZ {
+            qubit1,
+            qubit2,
+            theta: Phase::new(-1, 4),
+        },
+        Gate::ISWAP { qubit1, qubit2 },
+        Gate::SqrtISWAP { qubit1, qubit2 },
+        Gate::ECR { qubit1, qubit2 },
+    ]
+}
+
+fn single(gate: Gate) -> Vec<Vec<Complex>> {
+    single_qubit_matrix(&gate)
+        .iter()
+        .map(|row| row.to_vec())
+        .collect()
+}
+
+#[test]
+fn matrices_follow_standard_definitions() {
+    let (zero, one) = (c(0.0, 0.0), c(1.0, 0.0));
+    let (h, i_h) = (c(FRAC_1_SQRT_2, 0.0), c(0.0, FRAC_1_SQRT_2));
+    let (qubit1, qubit2) = (0, 1);
+
+    let iswap = two_qubit_matrix(&Gate::ISWAP { qubit1, qubit2 });
+    let sqrt_iswap = two_qubit_matrix(&Gate::SqrtISWAP { qubit1, qubit2 });
+    assert_matrices_close(&iswap, &product(&sqrt_iswap, &sqrt_iswap));
+    assert_matrices_close(
+        &[
+            vec![one, zero, zero, zero],
+            vec![zero, zero, c(0.0, 1.0), zero],
+            vec![zero, c(0.0, 1.0), zero, zero],
+            vec![zero, zero, zero, one],
+        ],
+        &iswap,
+    );
+    // The matrix Qiskit gives for ECR on (q0, q1)
+    assert_matrices_close(
+        &[
+            vec![zero, h, zero, i_h],
+            vec![h, zero, i_h * -1.0, zero],
+            vec![zero, i_h, zero, h],
+            vec![i_h * -1.0, zero, h, zero],
+        ],
+        &two_qubit_matrix(&Gate::ECR { qubit1, qubit2 }),
+    );
+
+    // The Ising couplings at π are −i times the Pauli products
+    for (gate, pauli) in [
+        (
+            Gate::RXX {
+                qubit1,
+                qubit2,
+                theta: Phase::PI,
+            },
+            Gate::X,
+        ),
+        (
+            Gate::RYY {
+                qubit1,
+                qubit2,
+                theta: Phase::PI,
+            },
+            Gate::Y,
+        ),
+        (
+            Gate::RZZ {
+                qubit1,
+                qubit2,
+                theta: Phase::PI,
+            },
+            Gate::Z,
+        ),
+    ] {
+        let expected: Vec<Vec<Complex>> = kron(&single(pauli.clone()), &single(pauli))
+            .into_iter()
+            .map(|row| row.into_iter().map(|v| v * c(0.0, -1.0)).collect())
+            .collect();
+        assert_matrices_close(&expected, &two_qubit_matrix(&gate));
+    }
+
+    // Controlled gates apply their base gate where the control is |1⟩
+    for gate in family(0, 1).into_iter().take(4) {
+        let base = single(gate.controlled_base());
+        let mut expected = vec![vec![zero; 4]; 4];
+        expected[0][0] = one;
+        expected[2][2] = one;
+        for (row, col) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+            expected[1 + 2 * row][1 + 2 * col] = base[row][col];
+        }
+        assert_matrices_close(&expected, &two_qubit_matrix(&gate));
+    }
+}
+
+#[test]
+fn simulation_applies_matrix_to_named_qubits() {
+    for (first, second, width) in [(0, 1, 2), (1, 0, 2), (2, 0, 3), (1, 3, 4)] {
+        for gate in family(first, second) {
+            let mut circuit = Circuit::new(width);
+            circuit.Apply(gate.clone(), Targets(&[first, second]));
+            assert_matrices_close(
+                &embed(&two_qubit_matrix(&gate), &[first, second], width),
+                &simulated_matrix(&circuit),
+            );
+        }
+    }
+}
+
+#[test]
+fn zx_conversion_matches_simulation() {
+    for (first, second) in [(0, 1), (2, 0)] {
+        for gate in family(first, second) {
+            let mut circuit = Circuit::new(3);
+            circuit.Apply(Gate::H, Targets(&[0, 1, 2]));
+            circuit.Apply(gate.clone(), Targets(&[first, second]));
+            circuit.ApplyControlled(Gate::X, 1, 2);
+            assert_zx_matches_simulation(&circuit);
+        }
+    }
+}
+
+#[test]
+fn rzz_is_a_phase_gadget() {
+    let mut circuit = Circuit::new(2);
+    let rzz = Gate::RZZ {
+        qubit1: 0,
+        qubit2: 1,
+        theta: Phase::new(1, 4),
+    };
+    circuit.Apply(rzz, Targets(&[0, 1]));
+
+    // A Z-spider on each wire, the parity X-spider and the phase-carrying head
+    let graph = CircuitConverter::convert(&circuit).unwrap();
+    let spiders: Vec<_> = graph.nodes.iter().flatten().collect();
+    assert_eq!(spiders.len(), 4 + 4);
+    assert_eq!(
+        spiders
+            .iter()
+            .filter(|node| node.phase == Phase::new(1, 4))
+            .count(),
+        1
+    );
+    assert_matrices_close(&simulated_matrix(&circuit), &graph.to_matrix());
+}
+
+#[test]
+fn decompositions_match_their_gates() {
+    for (first, second, width) in [(0, 1, 2), (1, 0, 2), (2, 0, 3)] {
+        for gate in family(first, second) {
+            let mut circuit = Circuit::new(width);
+            circuit.Apply(gate.clone(), Targets(&[first, second]));
+            let primitives = gate.decompose();
+            assert_eq!(primitives.last().unwrap().gate, PrimitiveGate::Connect);
+
+            let actual = decomposed_matrix(&primitives, width);
+            match gate {
+                Gate::CP { .. }
+                | Gate::CRx { .. }
+                | Gate::CRy { .. }
+                | Gate::CRz { .. }
+                | Gate::ISWAP { .. } => assert_matrices_close(&simulated_matrix(&circuit), &actual),
+                _ => assert_close_up_to_phase(&simulated_matrix(&circuit), &actual),
+            }
+
+            // CNOT-based: the only two-qubit primitive is CZ
+            assert!(primitives.iter().all(|primitive| {
+                primitive.qubits.len() == 1
+                    || matches!(primitive.gate, PrimitiveGate::Z | PrimitiveGate::Connect)
+            }));
+        }
+    }
+}
+
+#[test]
+fn controlled_interactions_match_simulation() {
+    for gate in family(1, 3) {
+        let mut circuit = Circuit::new(4);
+        circuit.apply_controlled(
+            gate.clone(),
+            Controls(&[0]).with_open(&[2]),
+            Targets(&[1, 3]),
+        );
+        assert_matrices_close(
+            &controlled_matrix(&gate, &[1, 3], &[0], &[2], 4),
+            &simulated_matrix(&circuit),
+        );
+
+        let synthesis = synthesize_controlled_gate(&[], 0, &circuit.dag.instructions()[0].gate);
+        let mut synthesized = Circuit::new(4);
+        for instruction in synthesis.instructions {
+            synthesized.dag.push(instruction);
+        }
+        let phase = Complex::from_phase(synthesis.phase);
+        let actual: Vec<Vec<Complex>> = simulated_matrix(&synthesized)
+            .into_iter()
+            .map(|row| row.into_iter().map(|v| v * phase).collect())
+            .collect();
+        assert_matrices_close(&simulated_matrix(&circuit), &actual);
+    }
+}
+
+#[test]
+fn single_controls_use_named_gates() {
+    let mut circuit = Circuit::new(3);
+    circuit.apply_controlled(Gate::P(Phase::new(1, 2)), Controls(&[0]), Targets(&[1]));
+    circuit.apply_controlled(Gate::Ry(Phase::new(1, 4)), Controls(&[2]), Targets(&[0]));
+    circuit.apply_controlled(
+        Gate::Rz(Phase::PI),
+        Controls(&[1]).with_open(&[0]),
+        Targets(&[2]),
+    );
+    let crx = Gate::CRx {
+        control: 1,
+        target: 2,
+        theta: Phase::new(1, 3),
+    };
+    circuit.apply_controlled(crx, Controls(&[0]), Targets(&[1, 2]));
+
+    let gates: Vec<String> = circuit
+        .ordered_gates()
+        .iter()
+        .map(|(_, gate)| gate.to_string())
+        .collect();
+    assert_eq!(
+        gates,
+        [
+            "CP(0,1,π/2)",
+            "CRy(2,0,π/4)",
+            "C(1,!0)Rz(π)(2)",
+            "C(0,1)Rx(π/3)(2)"
+        ]
+    );
+    let names: Vec<String> = family(0, 1)[4..]
+        .iter()
+        .map(|gate| gate.to_string())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "RXX(0,1,π/3)",
+            "RYY(0,1,1.1)",
+            "RZZ(0,1,7π/4)",
+            "iSWAP(0,1)",
+            "√iSWAP(0,1)",
+            "ECR(0,1)"
+        ]
+    );
+}
+
+#[test]
+fn symbolic_couplings_bind_consistently() {
+    let mut circuit = Circuit::new(2);
+    circuit.Apply(Gate::H, Targets(&[0, 1]));
+    for gate in [
+        Gate::RZZ {
+            qubit1: 0,
+            qubit2: 1,
+            theta: Phase::variable("theta"),
+        },
+        Gate::CRy {
+            control: 1,
+            target: 0,
+            theta: Phase::variable("theta"),
+        },
+        Gate::CP {
+            control: 0,
+            target: 1,
+            phase: Phase::variable("phi"),
+        },
+    ] {
+        circuit.Apply(gate, Targets(&[0, 1]));
+    }
+    assert_eq!(circuit.variables().len(), 2);
+
+    let graph = CircuitConverter::convert(&circuit).unwrap();
+    for (theta, phi) in [(0.4, -1.2), (2.5, 0.3)] {
+        let values = HashMap::from([("theta".to_string(), theta), ("phi".to_string(), phi)]);
+        let bound = circuit.bind(&values);
+        assert!(bound.variables().is_empty());
+        assert_matrices_close(&simulated_matrix(&bound), &graph.bind(&values).to_matrix());
+    }
+}
+
+#[test]
+fn controlled_rotations_bind_consistently_past_pi() {
+    let theta = Phase::variable("theta");
+    let gates = [
+        Gate::CRx {
+            control: 0,
+            target: 1,
+            theta: theta.clone(),
+        },
+        Gate::CRy {
+            control: 1,
+            target: 0,
+            theta: theta.clone(),
+        },
+        Gate::CRz {
+            control: 0,
+            target: 1,
+            theta: theta.clone(),
+        },
+        Gate::RZZ {
+            qubit1: 0,
+            qubit2: 1,
+            theta: theta.clone(),
+        },
+    ];
+    for gate in gates {
+        let mut circuit = Circuit::new(2);
+        circuit.Apply(Gate::H, Targets(&[0, 1]));
+        circuit.Apply(gate, Targets(&[0, 1]));
+        let graph = CircuitConverter::convert(&circuit).unwrap();
+        for value in [3.0 * FRAC_PI_2, 5.9, -4.0] {
+            let values = HashMap::from([("theta".to_string(), value)]);
+            assert_matrices_close(
+                &simulated_matrix(&circuit.bind(&values)),
+                &graph.bind(&values).to_matrix(),
+            );
+        }
+    }
+}
+
+#[test]
+fn tikz_draws_controls_and_boxes() {
+    let mut circuit = Circuit::new(3);
+    circuit.Apply(
+        Gate::CRz {
+            control: 2,
+            target: 0,
+            theta: Phase::new(1, 2),
+        },
+        Targets(&[2, 0]),
+    );
+    circuit.Apply(
+        Gate::RZZ {
+            qubit1: 2,
+            qubit2: 1,
+            theta: Phase::new(1, 4),
+        },
+        Targets(&[2, 1]),
+    );
+    circuit.Apply(
+        Gate::ISWAP {
+            qubit1: 0,
+            qubit2: 1,
+        },
+        Targets(&[0, 1]),
+    );
+
+    let tikz = circuit.to_tikz(&TikzQConfig::default());
+    assert!(tikz.contains("at (1.20,-0.00) {$R_z(\\frac{\\pi}{2})$}"));
+    assert!(tikz.contains("\\draw[thick] (1.20,-1.20) -- (1.20,-0.00);"));
+    assert!(tikz.contains("minimum height=1.10cm"));
+    assert!(tikz.contains("{$R_{ZZ}(\\frac{\\pi}{4})$}"));
+    assert!(tikz.contains("{$i\\mathrm{SWAP}$}"));
+}
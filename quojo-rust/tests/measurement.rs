@@ -0,0 +1,263 @@
+use quojo_rust::error::QuojoError;
+use quojo_rust::qcore::circuits::Circuit;
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::tikz::{TikzCircuit, TikzQConfig};
+use quojo_rust::qcore::{Condition, Controls, Targets};
+use quojo_rust::sim::sampler::{Sampler, bitstring};
+use quojo_rust::sim::statevector::simulate;
+use quojo_rust::utils::to_zx::CircuitConverter;
+
+fn bell_pair() -> Circuit {
+    let mut circuit = Circuit::new(2);
+    circuit.add_clbits(2);
+    circuit.Apply(Gate::H, Targets(&[0]));
+    circuit.Apply(
+        Gate::CNOT {
+            control: 0,
+            target: 1,
+        },
+        Targets(&[0, 1]),
+    );
+    circuit
+}
+
+#[test]
+fn registers_share_names() {
+    let mut circuit = Circuit::new(0);
+    assert_eq!(circuit.add_register("q", 2), 0..2);
+    assert_eq!(circuit.add_classical_register("c", 3), 0..3);
+    assert_eq!(circuit.add_classical_register("d", 1), 3..4);
+    assert_eq!(circuit.num_clbits(), 4);
+    assert_eq!(circuit.classical_register("d").unwrap().clbits, 3..4);
+    assert_eq!(
+        circuit.try_add_classical_register("q", 1),
+        Err(QuojoError::DuplicateRegister("q".to_string()))
+    );
+    assert_eq!(
+        circuit.try_add_register("c", 1),
+        Err(QuojoError::DuplicateRegister("c".to_string()))
+    );
+}
+
+#[test]
+fn builders_check_classical_bits() {
+    let mut circuit = Circuit::new(2);
+    circuit.add_clbits(1);
+    assert_eq!(
+        circuit.try_measure(1, 1),
+        Err(QuojoError::ClbitOutOfRange {
+            gate: Gate::Measure { qubit: 1, clbit: 1 },
+            clbit: 1,
+            num_clbits: 1,
+        })
+    );
+    assert!(matches!(
+        circuit.try_reset(2),
+        Err(QuojoError::QubitOutOfRange { qubit: 2, .. })
+    ));
+    assert!(matches!(
+        circuit.try_apply_conditional(Gate::X, Condition(&[0, 3], 1), Targets(&[0, 1])),
+        Err(QuojoError::ClbitOutOfRange { clbit: 3, .. })
+    ));
+    assert_eq!(
+        circuit.try_apply_controlled(Gate::Reset, Controls(&[0]), Targets(&[1])),
+        Err(QuojoError::UnsupportedControl(Gate::Reset))
+    );
+    assert!(circuit.dag.is_empty());
+
+    circuit.Measure(0, 0);
+    circuit.Reset(0);
+    circuit.apply_conditional(Gate::X, Condition(&[0], 1), Targets(&[0, 1]));
+    assert_eq!(circuit.dag.len(), 4);
+    assert!(circuit.validate().is_ok());
+    assert!(!circuit.is_unitary());
+}
+
+#[test]
+fn display_names_classical_bits() {
+    let mut circuit = Circuit::new(4);
+    circuit.add_clbits(2);
+    circuit.Measure(0, 1);
+    circuit.Reset(2);
+    circuit.apply_conditional(Gate::X, Condition(&[0, 1], 2), Targets(&[3]));
+    circuit.apply_conditional(
+        Gate::CZ {
+            control: 0,
+            target: 1,
+        },
+        Condition(&[1], 1),
+        Targets(&[0, 1]),
+    );
+    let gates: Vec<String> = circuit.dag.iter().map(|i| i.gate.to_string()).collect();
+    assert_eq!(
+        gates,
+        [
+            "Measure(0,c1)",
+            "Reset",
+            "If(c0,c1=2)X(3)",
+            "If(c1=1)CZ(0,1)"
+        ]
+    );
+}
+
+#[test]
+fn classical_bits_order_the_dag() {
+    let mut circuit = Circuit::new(2);
+    circuit.add_clbits(1);
+    circuit.Measure(0, 0);
+    circuit.Apply(Gate::H, Targets(&[1]));
+    circuit.apply_conditional(Gate::X, Condition(&[0], 1), Targets(&[1]));
+    circuit.Measure(1, 0);
+
+    assert_eq!(circuit.dag.predecessors(2), &[0, 1]);
+    assert_eq!(circuit.dag.predecessors(3), &[2]);
+    assert_eq!(circuit.dag.depth(), 3);
+}
+
+#[test]
+fn condition_compares_all_bits() {
+    let condition = Condition(&[2, 0], 1);
+    assert!(condition.holds(&[false, true, true]));
+    assert!(!condition.holds(&[true, false, true]));
+    assert!(!Condition(&[0], 2).holds(&[false]));
+    assert!(Condition(&[], 0).holds(&[]));
+}
+
+#[test]
+fn bell_pairs_agree() {
+    let mut circuit = bell_pair();
+    circuit.Measure(0, 0);
+    circuit.Measure(1, 1);
+
+    let counts = Sampler::seeded(7).sample(&circuit, 2000);
+    assert_eq!(counts.keys().collect::<Vec<_>>(), ["00", "11"]);
+    assert!((counts["00"] as i64 - 1000).abs() < 150);
+    assert_eq!(counts.values().sum::<usize>(), 2000);
+}
+
+#[test]
+fn shortcut_matches_trajectories() {
+    let mut circuit = Circuit::new(3);
+    circuit.add_clbits(3);
+    circuit.Apply(Gate::Ry(Phase::new(1, 3)), Targets(&[0]));
+    circuit.Apply(Gate::H, Targets(&[2]));
+    circuit.Measure(0, 2);
+    circuit.Measure(2, 0);
+    let shots = 4000;
+    let direct = Sampler::seeded(1).sample(&circuit, shots);
+
+    // A reset on an unused qubit forces one trajectory per shot
+    circuit.Reset(1);
+    let trajectories = Sampler::seeded(2).sample(&circuit, shots);
+
+    // P(qubit 0 = 1) = sin²(π/6) = 1/4 and qubit 2 is uniform
+    for (key, expected) in [
+        ("000", 0.375),
+        ("001", 0.375),
+        ("100", 0.125),
+        ("101", 0.125),
+    ] {
+        for counts in [&direct, &trajectories] {
+            let frequency = counts.get(key).copied().unwrap_or(0) as f64 / shots as f64;
+            assert!((frequency - expected).abs() < 0.03, "{key}: {frequency}");
+        }
+    }
+}
+
+#[test]
+fn reset_returns_to_zero() {
+    let mut circuit = Circuit::new(2);
+    circuit.add_clbits(2);
+    circuit.Apply(Gate::H, Targets(&[0]));
+    circuit.Apply(
+        Gate::CNOT {
+            control: 0,
+            target: 1,
+        },
+        Targets(&[0, 1]),
+    );
+    circuit.Reset(0);
+    circuit.Measure(0, 0);
+    circuit.Measure(1, 1);
+
+    let counts = Sampler::seeded(3).sample(&circuit, 500);
+    assert!(counts.keys().all(|key| key.ends_with('0')));
+    assert!(counts.contains_key("10") && counts.contains_key("00"));
+}
+
+#[test]
+fn teleportation_corrects_outcomes() {
+    let mut circuit = Circuit::new(3);
+    circuit.add_classical_register("m", 2);
+    circuit.Apply(Gate::Ry(Phase::new(1, 3)), Targets(&[0]));
+    circuit.Apply(Gate::H, Targets(&[1]));
+    circuit.Apply(
+        Gate::CNOT {
+            control: 1,
+            target: 2,
+        },
+        Targets(&[1, 2]),
+    );
+    circuit.Apply(
+        Gate::CNOT {
+            control: 0,
+            target: 1,
+        },
+        Targets(&[0, 1]),
+    );
+    circuit.Apply(Gate::H, Targets(&[0]));
+    circuit.Measure(0, 0);
+    circuit.Measure(1, 1);
+    circuit.apply_conditional(Gate::X, Condition(&[1], 1), Targets(&[2]));
+    circuit.apply_conditional(Gate::Z, Condition(&[0], 1), Targets(&[2]));
+
+    let mut sampler = Sampler::seeded(11);
+    let mut outcomes = Vec::new();
+    for _ in 0..40 {
+        let (state, clbits) = sampler.run(&circuit);
+        assert!((state.probability_of_one(2) - 0.25).abs() < 1e-9);
+        assert!((state.norm_squared() - 1.0).abs() < 1e-9);
+        outcomes.push(bitstring(&clbits));
+    }
+    outcomes.sort();
+    outcomes.dedup();
+    assert_eq!(outcomes, ["00", "01", "10", "11"]);
+}
+
+#[test]
+fn conversion_rejects_measurement() {
+    let mut circuit = bell_pair();
+    circuit.Measure(1, 0);
+    assert_eq!(
+        CircuitConverter::convert(&circuit).err(),
+        Some(QuojoError::UnsupportedGate(Gate::Measure {
+            qubit: 1,
+            clbit: 0
+        }))
+    );
+}
+
+#[test]
+#[should_panic(expected = "is not unitary")]
+fn statevector_rejects_measurement() {
+    let mut circuit = bell_pair();
+    circuit.Measure(1, 0);
+    simulate(&circuit);
+}
+
+#[test]
+fn tikz_draws_meters_and_classical_wires() {
+    let mut circuit = Circuit::new(2);
+    circuit.add_clbits(1);
+    circuit.Measure(0, 0);
+    circuit.apply_conditional(Gate::X, Condition(&[0], 1), Targets(&[1]));
+
+    let tikz = circuit.to_tikz(&TikzQConfig::default());
+    assert!(tikz.contains("\\draw[double] (0,-1.20) -- (4.10,-1.20);"));
+    assert!(tikz.contains("{$c_{0}$}"));
+    assert!(tikz.contains("\\draw[double] (1.20,-0.00) -- (1.20,-1.20);"));
+    assert!(tikz.contains("arc (180:0:0.15)"));
+    assert!(tikz.contains("\\draw[double] (2.40,-1.20) -- (2.40,-0.60);"));
+    assert!(tikz.contains("at (2.40,-0.60) {$\\oplus$}"));
+}
@@ -0,0 +1,199 @@
+mod common;
+
+use common::*;
+use quojo_rust::linalg::vector::Complex;
+use quojo_rust::zxcalc::graph::{EdgeType, SpiderType, ZXGraph};
+use quojo_rust::zxcalc::parametric::{Factor, Parity};
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI, SQRT_2};
+
+fn bits(index: usize, width: usize) -> Vec<bool> {
+    (0..width).map(|bit| index >> bit & 1 == 1).collect()
+}
+
+/// Three wires of Hadamards, T and T† spiders, joined by a CNOT-like Z–X pair and a CZ-like
+/// Hadamard edge.
+fn clifford_t_diagram() -> ZXGraph {
+    let mut graph = ZXGraph::new();
+    let inputs: Vec<_> = (0..3)
+        .map(|_| graph.add_input_node(SpiderType::Z, 0.0))
+        .collect();
+    let a = graph.add_node(SpiderType::Z, FRAC_PI_4);
+    let b = graph.add_node(SpiderType::Z, 0.0);
+    let c = graph.add_node(SpiderType::X, 0.0);
+    let d = graph.add_node(SpiderType::Z, -FRAC_PI_4);
+    let e = graph.add_node(SpiderType::Z, FRAC_PI_2);
+    graph.add_edge(inputs[0], a, EdgeType::Hadamard);
+    graph.add_edge(inputs[1], b, EdgeType::Hadamard);
+    graph.add_edge(inputs[2], c, EdgeType::Regular);
+    graph.add_edge(a, c, EdgeType::Regular);
+    graph.add_edge(a, b, EdgeType::Hadamard);
+    graph.add_edge(c, d, EdgeType::Regular);
+    graph.add_edge(b, e, EdgeType::Hadamard);
+    for wire in [a, e, d] {
+        let output = graph.add_output_node(SpiderType::Z, 0.0);
+        graph.add_edge(wire, output, EdgeType::Regular);
+    }
+    graph
+}
+
+#[test]
+fn compiled_amplitudes_cover_every_output() {
+    let graph = clifford_t_diagram();
+    let width = 3;
+
+    for input in [0b000, 0b101, 0b111] {
+        let compiled = graph.compile_amplitudes(&bits(input, width));
+        assert_eq!(compiled.num_params, width);
+
+        let mut norm = 0.0;
+        for output in 0..1 << width {
+            let expected = graph.amplitude(&bits(input, width), &bits(output, width));
+            let value = compiled.evaluate(&bits(output, width));
+            assert_close(expected, value, &format!("<{}|D|{}>", output, input));
+            norm += value.norm_sqr();
+        }
+        assert!(norm > TOLERANCE, "every amplitude of |{}⟩ is zero", input);
+    }
+}
+
+/// input — Z(π/4 + π·b0) —H— X(π·(b0 ⊕ b1)) — output, with the parameters either left
+/// free or fixed to `fixed`.
+fn parametric_wire(fixed: Option<[bool; 2]>) -> ZXGraph {
+    let mut graph = ZXGraph::new();
+    let input = graph.add_input_node(SpiderType::Z, 0.0);
+    let (z, x) = match fixed {
+        None => (
+            graph.add_parametric_node(SpiderType::Z, FRAC_PI_4, Parity::single(0)),
+            graph.add_parametric_node(SpiderType::X, 0.0, Parity::from_params(&[0, 1])),
+        ),
+        Some([b0, b1]) => {
+            let pi = |bit: bool| if bit { PI } else { 0.0 };
+            (
+                graph.add_node(SpiderType::Z, FRAC_PI_4 + pi(b0)),
+                graph.add_node(SpiderType::X, pi(b0 ^ b1)),
+            )
+        }
+    };
+    let output = graph.add_output_node(SpiderType::Z, 0.0);
+    graph.add_edge(input, z, EdgeType::Regular);
+    graph.add_edge(z, x, EdgeType::Hadamard);
+    graph.add_edge(x, output, EdgeType::Regular);
+    graph
+}
+
+#[test]
+fn parametric_spiders_evaluate_every_assignment() {
+    let graph = parametric_wire(None);
+    assert_eq!(graph.num_parameters(), 2);
+
+    for input in [false, true] {
+        // Outputs are numbered after the spider parameters
+        let compiled = graph.compile_amplitudes(&[input]);
+        assert_eq!(compiled.num_params, 3);
+        for assignment in 0..4 {
+            let [b0, b1] = [assignment & 1 == 1, assignment & 2 == 2];
+            let fixed = parametric_wire(Some([b0, b1]));
+            for output in [false, true] {
+                assert_close(
+                    fixed.amplitude(&[input], &[output]),
+                    compiled.evaluate(&[b0, b1, output]),
+                    &format!("b0={} b1={} <{}|D|{}>", b0, b1, output, input),
+                );
+            }
+        }
+    }
+
+    // `amplitude` takes every parameter to be 0
+    assert_close(
+        parametric_wire(Some([false, false])).amplitude(&[true], &[false]),
+        graph.amplitude(&[true], &[false]),
+        "amplitude",
+    );
+}
+
+#[test]
+fn compiled_scalars_depend_on_parameters() {
+    // A lone Z(π·(b0 ⊕ b1)) is 1 + (-1)^(b0 ⊕ b1)
+    let mut graph = ZXGraph::new();
+    graph.add_parametric_node(SpiderType::Z, 0.0, Parity::from_params(&[0, 1]));
+    let compiled = graph.compile_scalar();
+    for assignment in 0..4 {
+        let values = bits(assignment, 2);
+        let expected = if values[0] ^ values[1] { 0.0 } else { 2.0 };
+        assert_close(
+            Complex::new(expected, 0.0),
+            compiled.evaluate(&values),
+            &format!("{:?}", values),
+        );
+    }
+
+    let mut graph = ZXGraph::new();
+    let z = graph.add_parametric_node(SpiderType::Z, FRAC_PI_2, Parity::single(0));
+    let x = graph.add_parametric_node(SpiderType::X, 0.0, Parity::single(1));
+    graph.add_edge(z, x, EdgeType::Regular);
+    let compiled = graph.compile_scalar();
+    for assignment in 0..4 {
+        let values = bits(assignment, 2);
+        // ⟨0| + e^(iα)⟨1| against the X spider's √2|b1⟩
+        let alpha = FRAC_PI_2 + if values[0] { PI } else { 0.0 };
+        let expected = if values[1] {
+            Complex::from_phase(alpha) * SQRT_2
+        } else {
+            Complex::new(SQRT_2, 0.0)
+        };
+        assert_close(
+            expected,
+            compiled.evaluate(&values),
+            &format!("{:?}", values),
+        );
+    }
+    assert_close(
+        compiled.evaluate(&[false, false]),
+        graph.scalar_value(),
+        "scalar_value",
+    );
+}
+
+#[test]
+fn parities_and_factors() {
+    let parity = Parity::from_params(&[0, 2, 2, 3]);
+    assert_eq!(parity.to_string(), "b0⊕b3");
+    assert_eq!(parity.param_count(), 4);
+    assert_eq!(parity.xor(&Parity::single(3)), Parity::single(0));
+    assert!(parity.evaluate(&[true, false, false, false]));
+    assert!(!parity.evaluate(&[true, false, false, true]));
+    assert!(Parity::new().is_empty());
+
+    let on = [true, true];
+    let off = [false, true];
+    let phase = Factor::Phase {
+        angle: FRAC_PI_4,
+        parity: Parity::single(0),
+    };
+    assert_close(Complex::from_phase(FRAC_PI_4), phase.evaluate(&on), "phase");
+    assert_close(Complex::new(1.0, 0.0), phase.evaluate(&off), "phase");
+
+    let one_plus = Factor::OnePlusPhase {
+        phase: FRAC_PI_2,
+        parity: Parity::single(0),
+    };
+    assert_close(Complex::new(1.0, -1.0), one_plus.evaluate(&on), "1 + e^iα");
+    assert_close(Complex::new(1.0, 1.0), one_plus.evaluate(&off), "1 + e^iα");
+
+    let sign = Factor::SignProduct {
+        a: false,
+        p: Parity::single(0),
+        b: true,
+        q: Parity::new(),
+    };
+    assert_close(Complex::new(-1.0, 0.0), sign.evaluate(&on), "sign");
+    assert_close(Complex::new(1.0, 0.0), sign.evaluate(&off), "sign");
+}
+
+#[test]
+#[should_panic(expected = "Expected 3 parameter values but got 2")]
+fn evaluate_rejects_short_assignments() {
+    parametric_wire(None)
+        .compile_amplitudes(&[false])
+        .evaluate(&[false, true]);
+}
@@ -0,0 +1,124 @@
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::zxcalc::graph::{EdgeType, SpiderType, ZXGraph};
+use std::f64::consts::{FRAC_PI_4, PI};
+
+#[test]
+fn arithmetic_is_exact_mod_two_pi() {
+    assert_eq!(Phase::PI + Phase::PI, Phase::ZERO);
+    assert_eq!(Phase::new(3, 4) + Phase::new(5, 4), Phase::ZERO);
+    assert_eq!(Phase::new(1, 3) - Phase::new(1, 2), Phase::new(11, 6));
+    assert_eq!(-Phase::new(1, 4), Phase::new(7, 4));
+    assert_eq!(Phase::new(3, 4) * -2, Phase::new(1, 2));
+    assert_eq!(Phase::new(-2, -4), Phase::new(1, 2));
+    assert_eq!(Phase::new(9, 2), Phase::new(1, 2));
+    assert_eq!(Phase::PI / 4, Phase::new(1, 4));
+    assert_eq!(Phase::new(-1, 2) / 3, Phase::new(1, 2));
+    // Halving picks the representative in (−π, π]
+    assert_eq!(Phase::new(-1, 2).half(), Phase::new(-1, 4));
+    assert_eq!(Phase::PI.half(), Phase::new(1, 2));
+    assert_eq!(Phase::new(2, 3).half(), Phase::new(1, 3));
+    assert_eq!(Phase::from(-0.5).half(), Phase::from(-0.25));
+}
+
+#[test]
+fn radians_snap_to_rational_multiples() {
+    assert_eq!(Phase::from(PI / 4.0), Phase::new(1, 4));
+    assert_eq!(Phase::from(-PI / 2.0), Phase::new(3, 2));
+    assert_eq!(Phase::from(2.0 * PI / 3.0), Phase::new(2, 3));
+    assert_eq!(Phase::from(4.0 * PI), Phase::ZERO);
+
+    let float = Phase::from(0.3);
+    assert_eq!(float.as_float(), Some(0.3));
+    assert_eq!(float.as_rational(), None);
+    assert!((float.to_radians() - 0.3).abs() < 1e-12);
+    assert_eq!(float.clone() - float.clone(), Phase::ZERO);
+    assert!(((float + Phase::PI).to_radians() - (0.3 + PI)).abs() < 1e-12);
+}
+
+#[test]
+fn clifford_queries() {
+    let cases = [
+        (Phase::ZERO, true, true, false),
+        (Phase::PI, true, true, false),
+        (Phase::new(1, 2), false, true, true),
+        (Phase::new(3, 2), false, true, true),
+        (Phase::new(1, 4), false, false, false),
+        (Phase::from(0.3), false, false, false),
+    ];
+    for (phase, pauli, clifford, proper) in cases {
+        assert_eq!(phase.is_pauli(), pauli, "{}", phase);
+        assert_eq!(phase.is_clifford(), clifford, "{}", phase);
+        assert_eq!(phase.is_proper_clifford(), proper, "{}", phase);
+    }
+}
+
+#[test]
+fn rationals_are_kept_in_lowest_terms() {
+    assert_eq!(Phase::new(2, 4).as_rational(), Some((1, 2)));
+    assert_eq!(Phase::new(5, 2).as_rational(), Some((1, 2)));
+    assert_eq!(Phase::new(-1, 1).as_rational(), Some((1, 1)));
+    assert_eq!(Phase::new(4, -2).as_rational(), Some((0, 1)));
+    assert_eq!(
+        (Phase::new(3, 4) + Phase::new(3, 4)).as_rational(),
+        Some((3, 2))
+    );
+    assert!(Phase::new(0, 3).is_zero());
+    assert!(Phase::new(2, 2).is_pauli());
+    assert!(Phase::new(2, 4).is_proper_clifford());
+    assert!(!Phase::new(2, 8).is_clifford());
+
+    assert_eq!(Phase::try_new(1, 0), None);
+    assert_eq!(Phase::try_new(6, 4), Some(Phase::new(3, 2)));
+}
+
+#[test]
+#[should_panic(expected = "Phase denominator must be non-zero")]
+fn zero_denominator_panics() {
+    Phase::new(1, 0);
+}
+
+#[test]
+fn float_phases_equal_rationals_at_the_same_angle() {
+    // Too fine for an i64 denominator, so it falls back to radians
+    let tiny = Phase::new(1, 3 << 60) - Phase::new(1, 5 << 59);
+    assert!(tiny.as_float().is_some());
+    assert_eq!(tiny, Phase::ZERO);
+    assert_eq!(Phase::ZERO, tiny);
+    assert_eq!(Phase::from(0.3) + Phase::from(0.3), Phase::from(0.6));
+    assert_eq!(Phase::from(0.3), Phase::from(0.3 + 2.0 * PI));
+    assert_ne!(Phase::from(0.3), Phase::from(0.3001));
+    assert_ne!(Phase::from(0.3), Phase::variable("theta"));
+}
+
+#[test]
+#[should_panic(expected = "Phase π cannot be divided by zero")]
+fn division_by_zero_panics() {
+    let _ = Phase::PI / 0;
+}
+
+#[test]
+fn display_as_fraction_of_pi() {
+    assert_eq!(Phase::ZERO.to_string(), "0");
+    assert_eq!(Phase::PI.to_string(), "π");
+    assert_eq!(Phase::new(1, 4).to_string(), "π/4");
+    assert_eq!(Phase::new(3, 2).to_string(), "3π/2");
+}
+
+#[test]
+fn fused_phases_stay_exact() {
+    let mut graph = ZXGraph::new();
+    let input = graph.add_input_node(SpiderType::Boundary, Phase::ZERO);
+    let output = graph.add_output_node(SpiderType::Boundary, Phase::ZERO);
+    let mut previous = input;
+    for _ in 0..8 {
+        let spider = graph.add_node(SpiderType::Z, FRAC_PI_4);
+        graph.add_edge(previous, spider, EdgeType::Regular);
+        previous = spider;
+    }
+    graph.add_edge(previous, output, EdgeType::Regular);
+
+    assert_eq!(graph.fuse_spiders(), 7);
+    let spider = graph.neighbors(input)[0];
+    assert_eq!(graph.node_data(spider).unwrap().1, &Phase::ZERO);
+    assert_eq!(graph.remove_identities(), 1);
+}
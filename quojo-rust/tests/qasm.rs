@@ -0,0 +1,376 @@
+mod common;
+
+use common::*;
+use quojo_rust::error::QuojoError;
+use quojo_rust::qcore::gates::{Condition, ConditionalGate, Gate};
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::sim::sampler::Sampler;
+use quojo_rust::utils::qasm::{QasmError, QasmErrorKind, parse_qasm2};
+
+const HEADER: &str = "OPENQASM 2.0;\ninclude \"qelib1.inc\";\n";
+
+fn gates(source: &str) -> Vec<Gate> {
+    let circuit = parse_qasm2(source).unwrap();
+    circuit.dag.iter().map(|i| i.gate.clone()).collect()
+}
+
+fn error(source: &str) -> (usize, usize, QasmErrorKind) {
+    let QasmError { line, column, kind } = parse_qasm2(source).unwrap_err();
+    (line, column, kind)
+}
+
+#[test]
+fn reads_registers_and_measurements() {
+    let source = format!(
+        "{HEADER}// Bell pair\nqreg q[2];\ncreg c[2];\nh q[0];\ncx q[0], q[1];\nbarrier q;\nmeasure q -> c;\n"
+    );
+    let circuit = parse_qasm2(&source).unwrap();
+    assert_eq!(circuit.num_qubits(), 2);
+    assert_eq!(circuit.num_clbits(), 2);
+    assert_eq!(circuit.register("q").unwrap().qubits, 0..2);
+    assert_eq!(circuit.classical_register("c").unwrap().clbits, 0..2);
+    let gates: Vec<String> = circuit.dag.iter().map(|i| i.gate.to_string()).collect();
+    assert_eq!(gates, ["H", "CNOT(0,1)", "Measure(0,c0)", "Measure(1,c1)"]);
+
+    let counts = Sampler::seeded(5).sample(&circuit, 200);
+    assert_eq!(counts.keys().collect::<Vec<_>>(), ["00", "11"]);
+}
+
+#[test]
+fn maps_qelib1_to_gate_variants() {
+    let source = format!(
+        "{HEADER}qreg a[2];\nqreg b[2];\nu1(pi/4) a[1];\ncp(-pi/2) a[0], b[1];\nrz(3*pi/4) b[0];\nu2(0, pi) a[0];\nccx a[0], a[1], b[0];\ncswap b[0], a[0], a[1];\nrzz(pi/8) a[1], b[1];\n"
+    );
+    assert_eq!(
+        gates(&source),
+        [
+            Gate::P(Phase::new(1, 4)),
+            Gate::CP {
+                control: 0,
+                target: 3,
+                phase: Phase::new(3, 2)
+            },
+            Gate::Rz(Phase::new(3, 4)),
+            Gate::U3 {
+                theta: Phase::new(1, 2),
+                phi: Phase::ZERO,
+                lambda: Phase::PI
+            },
+            Gate::Toffoli {
+                control1: 0,
+                control2: 1,
+                target: 2
+            },
+            Gate::Fredkin {
+                control: 2,
+                target1: 0,
+                target2: 1
+            },
+            Gate::RZZ {
+                qubit1: 1,
+                qubit2: 3,
+                theta: Phase::new(1, 8)
+            },
+        ]
+    );
+}
+
+/// Each qelib1.inc gate against its definition in qelib1.inc, up to a global phase.
+#[test]
+fn qelib1_gates_match_their_definitions() {
+    let definitions = [
+        ("x", "", "a", "u3(pi,0,pi) a;"),
+        ("y", "", "a", "u3(pi,pi/2,pi/2) a;"),
+        ("sxdg", "", "a", "s a; h a; s a;"),
+        ("rx(0.7)", "(theta)", "a", "u3(theta,-pi/2,pi/2) a;"),
+        ("cz", "", "a,b", "h b; cx a,b; h b;"),
+        ("cy", "", "a,b", "sdg b; cx a,b; s b;"),
+        ("swap", "", "a,b", "cx a,b; cx b,a; cx a,b;"),
+        (
+            "ch",
+            "",
+            "a,b",
+            "h b; sdg b; cx a,b; h b; t b; cx a,b; t b; h b; s b; x b; s a;",
+        ),
+        (
+            "crx(0.9)",
+            "(l)",
+            "a,b",
+            "u1(pi/2) b; cx a,b; u3(-l/2,0,0) b; cx a,b; u3(l/2,-pi/2,0) b;",
+        ),
+        (
+            "cry(-1.3)",
+            "(l)",
+            "a,b",
+            "ry(l/2) b; cx a,b; ry(-l/2) b; cx a,b;",
+        ),
+        (
+            "crz(2.1)",
+            "(l)",
+            "a,b",
+            "rz(l/2) b; cx a,b; rz(-l/2) b; cx a,b;",
+        ),
+        (
+            "cu1(0.4)",
+            "(l)",
+            "a,b",
+            "u1(l/2) a; cx a,b; u1(-l/2) b; cx a,b; u1(l/2) b;",
+        ),
+        (
+            "cu3(0.3,1.1,-0.6)",
+            "(theta,phi,l)",
+            "c,t",
+            "u1((l+phi)/2) c; u1((l-phi)/2) t; cx c,t; u3(-theta/2,0,-(phi+l)/2) t; cx c,t; u3(theta/2,phi,0) t;",
+        ),
+        (
+            "cu(0.3,1.1,-0.6,0.25)",
+            "(theta,phi,l,gamma)",
+            "c,t",
+            "p(gamma) c; p((l+phi)/2) c; p((l-phi)/2) t; cx c,t; u(-theta/2,0,-(phi+l)/2) t; cx c,t; u(theta/2,phi,0) t;",
+        ),
+        ("rzz(0.8)", "(theta)", "a,b", "cx a,b; u1(theta) b; cx a,b;"),
+        (
+            "ccx",
+            "",
+            "a,b,c",
+            "h c; cx b,c; tdg c; cx a,c; t c; cx b,c; tdg c; cx a,c; t b; t c; h c; cx a,b; t a; tdg b; cx a,b;",
+        ),
+        ("cswap", "", "a,b,c", "cx c,b; ccx a,b,c; cx c,b;"),
+    ];
+
+    for (call, parameters, qubits, body) in definitions {
+        let width = qubits.split(',').count();
+        let targets: Vec<String> = (0..width).map(|i| format!("q[{}]", i)).collect();
+        let arguments = call.find('(').map_or("", |i| &call[i..]);
+        let program = |statement: &str| {
+            format!(
+                "{HEADER}qreg q[{width}];\ngate reference{parameters} {qubits} {{ {body} }}\n{statement} {};\n",
+                targets.join(",")
+            )
+        };
+        let native = parse_qasm2(&program(call)).unwrap();
+        let defined = parse_qasm2(&program(&format!("reference{}", arguments))).unwrap();
+        assert_close_up_to_phase(&simulated_matrix(&defined), &simulated_matrix(&native));
+    }
+}
+
+#[test]
+fn relative_phase_toffolis_permute_like_toffolis() {
+    for (gate, width) in [("rccx", 3), ("rc3x", 4)] {
+        let targets: Vec<String> = (0..width).map(|i| format!("q[{}]", i)).collect();
+        let source = format!("{HEADER}qreg q[{width}];\n{gate} {};\n", targets.join(","));
+        let matrix = simulated_matrix(&parse_qasm2(&source).unwrap());
+        let all_controls = (1 << (width - 1)) - 1;
+        for (output, row) in matrix.iter().enumerate() {
+            for (input, entry) in row.iter().enumerate() {
+                let flipped = if input & all_controls == all_controls {
+                    input ^ 1 << (width - 1)
+                } else {
+                    input
+                };
+                let expected = if output == flipped { 1.0 } else { 0.0 };
+                assert!((entry.abs() - expected).abs() < TOLERANCE, "{gate}");
+            }
+        }
+    }
+}
+
+#[test]
+fn inlines_gate_definitions() {
+    let source = format!(
+        "{HEADER}gate rot(a, b) x, y {{\n  rz(a/2) x;\n  barrier x, y;\n  CX x, y;\n  ry(-b) y;\n}}\ngate twice(t) p, q {{ rot(t, 2*t) q, p; rot(t, 2*t) p, q; }}\nqreg r[2];\ntwice(pi/2) r[0], r[1];\n"
+    );
+    let rz = Gate::Rz(Phase::new(1, 4));
+    let ry = Gate::Ry(Phase::PI);
+    assert_eq!(
+        gates(&source),
+        [
+            rz.clone(),
+            Gate::CNOT {
+                control: 1,
+                target: 0
+            },
+            ry.clone(),
+            rz,
+            Gate::CNOT {
+                control: 0,
+                target: 1
+            },
+            ry,
+        ]
+    );
+}
+
+#[test]
+fn broadcasts_registers() {
+    let source = format!(
+        "{HEADER}qreg a[3];\nqreg b[3];\ncreg c[3];\nh a;\ncx a, b[1];\nreset b;\nmeasure b -> c;\n"
+    );
+    let circuit = parse_qasm2(&source).unwrap();
+    let gates: Vec<String> = circuit.dag.iter().map(|i| i.gate.to_string()).collect();
+    assert_eq!(gates[..3], ["H", "H", "H"]);
+    assert_eq!(gates[3..6], ["CNOT(0,4)", "CNOT(1,4)", "CNOT(2,4)"]);
+    assert_eq!(gates[6..9], ["Reset", "Reset", "Reset"]);
+    assert_eq!(gates[9], "Measure(3,c0)");
+}
+
+#[test]
+fn reads_conditions() {
+    let source = format!(
+        "{HEADER}qreg q[2];\ncreg m[1];\ncreg c[2];\nmeasure q[0] -> m[0];\nif (c == 2) x q[1];\nif (m == 1) measure q[1] -> c[0];\n"
+    );
+    let circuit = parse_qasm2(&source).unwrap();
+    let conditional = |condition, gate, target| {
+        Gate::Conditional(Box::new(ConditionalGate {
+            condition,
+            gate,
+            target,
+        }))
+    };
+    let gates: Vec<Gate> = circuit.dag.iter().map(|i| i.gate.clone()).collect();
+    assert_eq!(
+        gates[1..],
+        [
+            conditional(
+                Condition {
+                    clbits: vec![1, 2],
+                    value: 2
+                },
+                Gate::X,
+                1
+            ),
+            conditional(
+                Condition {
+                    clbits: vec![0],
+                    value: 1
+                },
+                Gate::Measure { qubit: 1, clbit: 1 },
+                1
+            ),
+        ]
+    );
+}
+
+#[test]
+fn reports_positions() {
+    let program = |body: &str| format!("{HEADER}qreg q[2];\ncreg c[2];\n{body}");
+
+    assert_eq!(
+        error(&program("h q[0];\nfoo q[1];\n")),
+        (6, 1, QasmErrorKind::UnknownGate("foo".to_string()))
+    );
+    assert_eq!(
+        error(&program("x q[0]\nx q[1];")),
+        (
+            6,
+            1,
+            QasmErrorKind::UnexpectedToken {
+                expected: "`;`".to_string(),
+                found: "`x`".to_string()
+            }
+        )
+    );
+    assert_eq!(
+        error(&program("  cx q[0], q[2];")),
+        (
+            5,
+            14,
+            QasmErrorKind::IndexOutOfRange {
+                register: "q".to_string(),
+                index: 2,
+                size: 2
+            }
+        )
+    );
+    assert_eq!(
+        error(&program("cx q[1], q[1];")),
+        (
+            5,
+            10,
+            QasmErrorKind::RepeatedQubit {
+                gate: "cx".to_string(),
+                qubit: "q[1]".to_string()
+            }
+        )
+    );
+    assert_eq!(
+        error(&program("rx q[0];")),
+        (
+            5,
+            1,
+            QasmErrorKind::ParameterCount {
+                gate: "rx".to_string(),
+                expected: 1,
+                found: 0
+            }
+        )
+    );
+    assert_eq!(
+        error(&program("qreg r[3];\ncx q, r;")),
+        (
+            6,
+            7,
+            QasmErrorKind::RegisterSizeMismatch {
+                expected: 2,
+                found: 3
+            }
+        )
+    );
+    assert_eq!(
+        error(&program("rz(theta) q[0];")),
+        (5, 4, QasmErrorKind::UnknownParameter("theta".to_string()))
+    );
+    assert_eq!(
+        error(&program("measure q -> d;")),
+        (5, 14, QasmErrorKind::UnknownRegister("d".to_string()))
+    );
+    assert_eq!(
+        error(&program("creg q[1];")),
+        (5, 6, QasmErrorKind::DuplicateDefinition("q".to_string()))
+    );
+    assert_eq!(
+        error(&program("gate g a { h b; }")),
+        (5, 14, QasmErrorKind::UnknownArgument("b".to_string()))
+    );
+    assert_eq!(
+        error(&program("opaque magic a;\nmagic q[0];")),
+        (6, 1, QasmErrorKind::OpaqueGate("magic".to_string()))
+    );
+    assert_eq!(
+        error(&program("h q[0]; # comment")),
+        (5, 9, QasmErrorKind::UnexpectedCharacter('#'))
+    );
+    assert_eq!(
+        error("OPENQASM 3.0;\n"),
+        (1, 10, QasmErrorKind::UnsupportedVersion("3".to_string()))
+    );
+    assert_eq!(
+        error("OPENQASM 2.0;\ninclude \"stdgates.inc\";"),
+        (
+            2,
+            9,
+            QasmErrorKind::UnsupportedInclude("stdgates.inc".to_string())
+        )
+    );
+}
+
+#[test]
+fn gates_need_qelib1() {
+    assert_eq!(
+        error("OPENQASM 2.0;\nqreg q[1];\nh q[0];"),
+        (3, 1, QasmErrorKind::UnknownGate("h".to_string()))
+    );
+    let gates = gates("OPENQASM 2.0;\nqreg q[2];\nU(pi, 0, pi) q[1];\nCX q[1], q[0];");
+    assert_eq!(gates.len(), 2);
+}
+
+#[test]
+fn errors_convert_and_display() {
+    let error = parse_qasm2(&format!("{HEADER}qreg q[1];\nh q[0], q[0];")).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "line 4, column 1: gate h acts on 1 qubits but was given 2"
+    );
+    let error: QuojoError = error.into();
+    assert!(matches!(error, QuojoError::Qasm(QasmError { line: 4, .. })));
+}
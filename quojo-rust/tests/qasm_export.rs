@@ -0,0 +1,318 @@
+mod common;
+
+use common::*;
+use quojo_rust::error::QuojoError;
+use quojo_rust::qcore::circuits::Circuit;
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::{Condition, Controls, Targets};
+use quojo_rust::sim::sampler::Sampler;
+use quojo_rust::utils::qasm::{QasmCircuit, parse_qasm2};
+
+/// One of every unitary gate variant, with controlled gates both named in qelib1.inc and not.
+fn every_gate() -> Circuit {
+    let mut circuit = Circuit::new(4);
+    for gate in [
+        Gate::X,
+        Gate::Y,
+        Gate::Z,
+        Gate::H,
+        Gate::S,
+        Gate::Sdg,
+        Gate::T,
+        Gate::Tdg,
+        Gate::SX,
+    ] {
+        circuit.Apply(gate, Targets(&[1]));
+    }
+    circuit.Apply(Gate::P(Phase::new(1, 3)), Targets(&[0]));
+    circuit.Apply(Gate::Rx(Phase::new(3, 4)), Targets(&[2]));
+    circuit.Apply(Gate::Ry(Phase::from(0.3)), Targets(&[3]));
+    circuit.Apply(Gate::Rz(Phase::new(-1, 8)), Targets(&[0]));
+    circuit.Apply(
+        Gate::U3 {
+            theta: Phase::new(1, 5),
+            phi: Phase::from(1.1),
+            lambda: Phase::new(7, 4),
+        },
+        Targets(&[2]),
+    );
+    circuit.Apply(Gate::GlobalPhase(Phase::new(1, 7)), Targets(&[0]));
+    let pairs = [
+        Gate::CNOT {
+            control: 0,
+            target: 3,
+        },
+        Gate::CZ {
+            control: 1,
+            target: 2,
+        },
+        Gate::CP {
+            control: 2,
+            target: 0,
+            phase: Phase::new(1, 6),
+        },
+        Gate::CRx {
+            control: 3,
+            target: 1,
+            theta: Phase::from(0.7),
+        },
+        Gate::CRy {
+            control: 0,
+            target: 2,
+            theta: Phase::new(2, 3),
+        },
+        Gate::CRz {
+            control: 1,
+            target: 3,
+            theta: Phase::new(-1, 4),
+        },
+        Gate::SWAP {
+            qubit1: 0,
+            qubit2: 2,
+        },
+        Gate::RXX {
+            qubit1: 1,
+            qubit2: 0,
+            theta: Phase::new(1, 3),
+        },
+        Gate::RYY {
+            qubit1: 2,
+            qubit2: 3,
+            theta: Phase::from(0.4),
+        },
+        Gate::RZZ {
+            qubit1: 3,
+            qubit2: 0,
+            theta: Phase::new(5, 6),
+        },
+        Gate::ISWAP {
+            qubit1: 1,
+            qubit2: 2,
+        },
+        Gate::SqrtISWAP {
+            qubit1: 0,
+            qubit2: 1,
+        },
+        Gate::ECR {
+            qubit1: 3,
+            qubit2: 2,
+        },
+    ];
+    for gate in pairs {
+        let qubits = gate.involved_qubits(0);
+        circuit.Apply(gate, Targets(&qubits));
+    }
+    circuit.Apply(
+        Gate::Toffoli {
+            control1: 0,
+            control2: 1,
+            target: 3,
+        },
+        Targets(&[0, 1, 3]),
+    );
+    circuit.Apply(
+        Gate::Fredkin {
+            control: 2,
+            target1: 3,
+            target2: 0,
+        },
+        Targets(&[2, 3, 0]),
+    );
+    circuit.Apply(
+        Gate::unitary("random", &[3, 1], random_unitary(2, 11)).unwrap(),
+        Targets(&[3, 1]),
+    );
+
+    circuit.apply_controlled(Gate::Y, Controls(&[0]), Targets(&[1]));
+    circuit.apply_controlled(Gate::H, Controls(&[2]).with_open(&[3]), Targets(&[1]));
+    circuit.apply_controlled(Gate::SX, Controls(&[0, 1, 2]), Targets(&[3]));
+    circuit.apply_controlled(Gate::X, Controls(&[0, 2, 3]), Targets(&[1]));
+    circuit.apply_controlled(Gate::S, Controls(&[3]).with_open(&[0, 1]), Targets(&[2]));
+    circuit.apply_controlled(Gate::Rx(Phase::new(1, 5)), Controls(&[1, 2]), Targets(&[0]));
+    circuit.apply_controlled(
+        Gate::SWAP {
+            qubit1: 1,
+            qubit2: 3,
+        },
+        Controls(&[2]).with_open(&[0]),
+        Targets(&[1, 3]),
+    );
+    circuit.apply_controlled(
+        Gate::RZZ {
+            qubit1: 0,
+            qubit2: 1,
+            theta: Phase::new(1, 3),
+        },
+        Controls(&[2]),
+        Targets(&[0, 1]),
+    );
+    circuit
+}
+
+#[test]
+fn qasm2_round_trips_every_gate() {
+    let circuit = every_gate();
+    let source = circuit.to_qasm2().unwrap();
+    let read = parse_qasm2(&source).unwrap();
+    assert_eq!(read.num_qubits(), 4);
+    assert_matrices_close(&simulated_matrix(&circuit), &simulated_matrix(&read));
+}
+
+#[test]
+fn definitions_match_the_gates() {
+    for gate in [
+        Gate::ECR {
+            qubit1: 0,
+            qubit2: 1,
+        },
+        Gate::ISWAP {
+            qubit1: 1,
+            qubit2: 0,
+        },
+        Gate::SqrtISWAP {
+            qubit1: 0,
+            qubit2: 1,
+        },
+        Gate::RYY {
+            qubit1: 1,
+            qubit2: 0,
+            theta: Phase::new(2, 5),
+        },
+    ] {
+        let mut circuit = Circuit::new(2);
+        let qubits = gate.involved_qubits(0);
+        circuit.Apply(gate, Targets(&qubits));
+        let read = parse_qasm2(&circuit.to_qasm2().unwrap()).unwrap();
+        assert_matrices_close(&simulated_matrix(&circuit), &simulated_matrix(&read));
+    }
+}
+
+#[test]
+fn writes_angles_as_fractions_of_pi() {
+    let mut circuit = Circuit::new(1);
+    for phase in [
+        Phase::PI,
+        Phase::new(1, 4),
+        Phase::new(3, 4),
+        Phase::new(-1, 2),
+        Phase::new(5, 4),
+        Phase::ZERO,
+        Phase::from(0.25),
+    ] {
+        circuit.Apply(Gate::Rz(phase), Targets(&[0]));
+    }
+    let source = circuit.to_qasm2().unwrap();
+    let lines: Vec<&str> = source.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "OPENQASM 2.0;",
+            "include \"qelib1.inc\";",
+            "qreg q[1];",
+            "rz(pi) q[0];",
+            "rz(pi/4) q[0];",
+            "rz(3*pi/4) q[0];",
+            "rz(-pi/2) q[0];",
+            "rz(-3*pi/4) q[0];",
+            "rz(0) q[0];",
+            "rz(0.25) q[0];",
+        ]
+    );
+}
+
+#[test]
+fn keeps_registers_and_measurements() {
+    let mut circuit = Circuit::new(0);
+    let a = circuit.add_register("a", 2);
+    circuit.add_qubits(1);
+    let results = circuit.add_classical_register("results", 2);
+    circuit.add_classical_register("flag", 1);
+    circuit.Apply(Gate::H, Targets(&[a.start]));
+    circuit.ApplyControlled(Gate::X, a.start, 2);
+    circuit.Measure(0, results.start);
+    circuit.Measure(2, results.start + 1);
+    circuit.Reset(1);
+    circuit.apply_conditional(Gate::X, Condition(&[0, 1], 3), Targets(&[1]));
+    circuit.Measure(1, 2);
+
+    let source = circuit.to_qasm2().unwrap();
+    assert!(source.contains("qreg a[2];\nqreg q[1];\ncreg results[2];\ncreg flag[1];\n"));
+    assert!(source.contains("measure q[0] -> results[1];"));
+    assert!(source.contains("if (results == 3) x a[1];"));
+
+    let read = parse_qasm2(&source).unwrap();
+    assert_eq!(read.register("a").unwrap().qubits, 0..2);
+    assert_eq!(read.classical_register("flag").unwrap().clbits, 2..3);
+    assert_eq!(
+        Sampler::seeded(3).sample(&read, 100),
+        Sampler::seeded(3).sample(&circuit, 100)
+    );
+}
+
+#[test]
+fn qasm2_rejects_what_it_cannot_express() {
+    let mut circuit = Circuit::new(1);
+    let theta = Phase::variable("theta");
+    circuit.Apply(Gate::Rx(theta.clone()), Targets(&[0]));
+    assert!(matches!(
+        circuit.to_qasm2(),
+        Err(QuojoError::UnsupportedExport {
+            gate: Gate::Rx(_),
+            ..
+        })
+    ));
+
+    let mut circuit = Circuit::new(1);
+    circuit.add_clbits(2);
+    circuit.apply_conditional(Gate::X, Condition(&[1], 1), Targets(&[0]));
+    let error = circuit.to_qasm2().unwrap_err();
+    assert!(error.to_string().contains("OpenQASM 2.0"));
+}
+
+#[test]
+fn qasm3_uses_modifiers_and_inputs() {
+    let mut circuit = Circuit::new(4);
+    circuit.add_clbits(2);
+    circuit.Apply(
+        Gate::P(Phase::variable("theta") * 2i64 + Phase::new(1, 2)),
+        Targets(&[0]),
+    );
+    circuit.apply_controlled(Gate::H, Controls(&[0, 1]).with_open(&[2]), Targets(&[3]));
+    circuit.apply_controlled(Gate::Rx(Phase::new(1, 2)), Controls(&[2]), Targets(&[0]));
+    circuit.Apply(
+        Gate::RZZ {
+            qubit1: 0,
+            qubit2: 2,
+            theta: Phase::PI,
+        },
+        Targets(&[0, 2]),
+    );
+    circuit.Apply(Gate::GlobalPhase(Phase::new(1, 3)), Targets(&[0]));
+    circuit.Measure(1, 0);
+    circuit.apply_conditional(Gate::Z, Condition(&[0, 1], 1), Targets(&[2]));
+    circuit.apply_conditional(Gate::Z, Condition(&[1], 1), Targets(&[2]));
+    circuit.apply_conditional(Gate::Z, Condition(&[1], 2), Targets(&[2]));
+
+    let source = circuit.to_qasm3();
+    let lines: Vec<&str> = source.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "OPENQASM 3.0;",
+            "include \"stdgates.inc\";",
+            "input float[64] theta;",
+            "gate rzz(theta) a, b { cx a, b; rz(theta) b; cx a, b; }",
+            "qubit[4] q;",
+            "bit[2] c;",
+            "p(2*theta + pi/2) q[0];",
+            "ctrl(2) @ negctrl @ h q[0], q[1], q[2], q[3];",
+            "crx(pi/2) q[2], q[0];",
+            "rzz(pi) q[0], q[2];",
+            "gphase(pi/3);",
+            "c[0] = measure q[1];",
+            "if (c == 1) z q[2];",
+            "if (c[1] == 1) z q[2];",
+        ]
+    );
+}
@@ -0,0 +1,224 @@
+mod common;
+
+use common::*;
+use quojo_rust::error::QuojoError;
+use quojo_rust::qcore::circuits::Circuit;
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::{Controls, Targets};
+use quojo_rust::utils::to_zx::CircuitConverter;
+use quojo_rust::zxcalc::graph::{EdgeType, NodeIndex, SpiderType, ZXGraph};
+use quojo_rust::zxcalc::qgraph::{Positions, QGraphError};
+
+fn sample_graph() -> ZXGraph {
+    let mut circuit = Circuit::new(3);
+    circuit.Apply(Gate::H, Targets(&[0, 2]));
+    circuit.Apply(Gate::T, Targets(&[1]));
+    circuit.ApplyControlled(Gate::X, 0, 1);
+    circuit.Apply(Gate::Rz(Phase::from(0.3)), Targets(&[2]));
+    circuit.apply_controlled(Gate::Z, Controls(&[1]), Targets(&[2]));
+    circuit.Apply(Gate::Rx(Phase::new(3, 4)), Targets(&[0]));
+    let mut graph = CircuitConverter::convert(&circuit).unwrap();
+    graph.full_reduce();
+    graph
+}
+
+fn edge_types(graph: &ZXGraph) -> (usize, usize) {
+    let edges: Vec<&EdgeType> = graph.edges.iter().flatten().map(|e| &e.edge_type).collect();
+    let hadamard = edges.iter().filter(|&&t| *t == EdgeType::Hadamard).count();
+    (edges.len() - hadamard, hadamard)
+}
+
+#[test]
+fn round_trips_diagrams() {
+    let graph = sample_graph();
+    let source = graph.to_qgraph().unwrap();
+    assert!(source.contains("\"is_edge\": \"true\""));
+
+    let (read, positions) = ZXGraph::from_qgraph(&source).unwrap();
+    assert_eq!(edge_types(&read), edge_types(&graph));
+    assert_eq!(
+        positions.len(),
+        read.nodes.iter().flatten().count(),
+        "every vertex keeps its coordinates"
+    );
+    assert_matrices_close(&graph.to_matrix(), &read.to_matrix());
+    let spiders = |g: &ZXGraph| g.nodes.iter().flatten().count();
+    assert_eq!(spiders(&read), spiders(&graph));
+}
+
+#[test]
+fn keeps_boundary_order_and_positions() {
+    let mut graph = ZXGraph::new();
+    let spider = graph.add_node(SpiderType::X, Phase::new(1, 2));
+    let second = graph.add_input_node(SpiderType::Boundary, Phase::ZERO);
+    let first = graph.add_input_node(SpiderType::Boundary, Phase::ZERO);
+    let output = graph.add_output_node(SpiderType::Boundary, Phase::ZERO);
+    graph.add_edge(second, spider, EdgeType::Regular);
+    graph.add_edge(first, spider, EdgeType::Hadamard);
+    graph.add_edge(spider, output, EdgeType::Regular);
+
+    let positions: Positions = [(spider, (1.0, -0.5)), (output, (2.5, 0.0))].into();
+    let source = graph.to_qgraph_with_positions(&positions).unwrap();
+    let (read, read_positions) = ZXGraph::from_qgraph(&source).unwrap();
+
+    // Inputs are numbered by node index, so `second` is input 0 in both graphs
+    let inputs = read.ordered_inputs();
+    assert_eq!(inputs.len(), 2);
+    let neighbor_edge = |input: NodeIndex| {
+        let edge = *read.nodes[input.0]
+            .as_ref()
+            .unwrap()
+            .edges
+            .iter()
+            .next()
+            .unwrap();
+        read.edge_data(edge).unwrap().clone()
+    };
+    assert_eq!(neighbor_edge(inputs[0]), EdgeType::Regular);
+    assert_eq!(neighbor_edge(inputs[1]), EdgeType::Hadamard);
+    assert_matrices_close(&graph.to_matrix(), &read.to_matrix());
+
+    let spider = read
+        .nodes
+        .iter()
+        .position(|n| n.as_ref().is_some_and(|n| n.spider_type == SpiderType::X))
+        .unwrap();
+    assert_eq!(read_positions[&NodeIndex(spider)], (1.0, -0.5));
+    assert_eq!(read_positions[&read.ordered_outputs()[0]], (2.5, 0.0));
+    assert_eq!(
+        read.node_data(NodeIndex(spider)).unwrap().1,
+        &Phase::new(1, 2)
+    );
+}
+
+#[test]
+fn reads_pyzx_files() {
+    let source = r#"{
+        "wire_vertices": {
+            "out": {"annotation": {"boundary": true, "coord": [4, 0], "output": 0}},
+            "in1": {"annotation": {"boundary": true, "coord": [0, -1], "input": 1}},
+            "in0": {"annotation": {"boundary": true, "coord": [0, 0], "input": 0}}
+        },
+        "node_vertices": {
+            "z": {"annotation": {"coord": [1, 0]}, "data": {"type": "Z", "value": "3\\pi/4"}},
+            "x": {"annotation": {"coord": [2, -1]}, "data": {"type": "X", "value": "-π/2"}},
+            "plain": {"annotation": {"coord": [3, 0]}, "data": {"value": "1/4"}},
+            "h0": {"annotation": {"coord": [1.5, -0.5]}, "data": {"type": "hadamard", "is_edge": "true"}},
+            "h1": {"annotation": {"coord": [2.5, -0.5]}, "data": {"type": "hadamard", "is_edge": true}},
+            "h2": {"annotation": {"coord": [2.7, -0.5]}, "data": {"type": "hadamard", "is_edge": "true"}}
+        },
+        "undir_edges": {
+            "e0": {"src": "in0", "tgt": "z"},
+            "e1": {"src": "in1", "tgt": "x"},
+            "e2": {"src": "z", "tgt": "h0"},
+            "e3": {"src": "h0", "tgt": "x"},
+            "e4": {"src": "x", "tgt": "h1"},
+            "e5": {"src": "h1", "tgt": "h2"},
+            "e6": {"src": "h2", "tgt": "plain"},
+            "e7": {"src": "plain", "tgt": "out"}
+        },
+        "scalar": "{\"power2\": -2, \"phase\": \"1/2\"}"
+    }"#;
+    let (graph, positions) = ZXGraph::from_qgraph(source).unwrap();
+
+    let [in0, in1] = graph.ordered_inputs()[..] else {
+        panic!("expected two inputs")
+    };
+    assert_eq!(positions[&in0], (0.0, 0.0));
+    assert_eq!(positions[&in1], (0.0, -1.0));
+    let phases: Vec<(SpiderType, Phase)> = graph
+        .nodes
+        .iter()
+        .flatten()
+        .filter(|n| n.spider_type != SpiderType::Boundary)
+        .map(|n| (n.spider_type.clone(), n.phase.clone()))
+        .collect();
+    assert_eq!(
+        phases,
+        [
+            (SpiderType::Z, Phase::new(3, 4)),
+            (SpiderType::X, Phase::new(3, 2)),
+            (SpiderType::Z, Phase::new(1, 4)),
+        ]
+    );
+    // h0 alone is a Hadamard edge; h1 and h2 cancel
+    assert_eq!(edge_types(&graph), (4, 1));
+    assert_eq!((graph.scalar.sqrt2_power, graph.scalar.phase), (-2, 2));
+}
+
+#[test]
+fn writes_pyzx_phases() {
+    let mut graph = ZXGraph::new();
+    for phase in [Phase::PI, Phase::new(3, 4), Phase::new(1, 8), Phase::ZERO] {
+        graph.add_node(SpiderType::Z, phase);
+    }
+    let source = graph.to_qgraph().unwrap();
+    assert!(source.contains("\"value\": \"\\\\pi\""));
+    assert!(source.contains("\"value\": \"3\\\\pi/4\""));
+    assert!(source.contains("\"value\": \"\\\\pi/8\""));
+    assert_eq!(source.matches("\"value\"").count(), 3);
+}
+
+#[test]
+fn reports_invalid_files() {
+    let error = |source: &str| ZXGraph::from_qgraph(source).unwrap_err();
+    assert!(matches!(
+        error("{\n  \"wire_vertices\": {\n    \"b0\" 1\n}"),
+        QGraphError::Json {
+            line: 3,
+            column: 10,
+            ..
+        }
+    ));
+    assert_eq!(
+        error(r#"{"undir_edges": {"e0": {"src": "a", "tgt": "b"}}}"#),
+        QGraphError::UnknownVertex("a".to_string())
+    );
+    assert_eq!(
+        error(r#"{"node_vertices": {"v": {"data": {"type": "W"}}}}"#),
+        QGraphError::UnknownVertexType {
+            vertex: "v".to_string(),
+            kind: "W".to_string()
+        }
+    );
+    assert_eq!(
+        error(r#"{"node_vertices": {"v": {"data": {"value": "a+1"}}}}"#),
+        QGraphError::InvalidPhase {
+            vertex: "v".to_string(),
+            value: "a+1".to_string()
+        }
+    );
+    assert_eq!(
+        error(r#"{"node_vertices": {"h": {"data": {"type": "hadamard"}}}}"#),
+        QGraphError::HadamardDegree {
+            vertex: "h".to_string(),
+            degree: 0
+        }
+    );
+    assert_eq!(
+        error(r#"{"node_vertices": {"v": {"annotation": {"coord": [1]}}}}"#),
+        QGraphError::InvalidField {
+            path: "node_vertices.v.annotation.coord".to_string(),
+            expected: "a pair of numbers"
+        }
+    );
+    assert!(matches!(
+        error(
+            r#"{"wire_vertices": {"a": {"annotation": {"input": 0}}, "b": {"annotation": {"input": 0}}}}"#
+        ),
+        QGraphError::DuplicateBoundary { index: 0, .. }
+    ));
+}
+
+#[test]
+fn rejects_what_qgraph_cannot_hold() {
+    let mut graph = ZXGraph::new();
+    let node = graph.add_node(SpiderType::Z, Phase::variable("alpha"));
+    assert_eq!(graph.to_qgraph(), Err(QGraphError::SymbolicPhase(node)));
+
+    let mut graph = ZXGraph::new();
+    let node = graph.add_input_node(SpiderType::X, Phase::ZERO);
+    let error: QuojoError = graph.to_qgraph().unwrap_err().into();
+    assert!(matches!(error, QuojoError::QGraph(QGraphError::NonBoundaryTerminal(n)) if n == node));
+}
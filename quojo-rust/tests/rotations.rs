@@ -0,0 +1,222 @@
+mod common;
+
+use common::*;
+use quojo_rust::linalg::vector::Complex;
+use quojo_rust::qcore::circuits::Circuit;
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::tikz::{TikzCircuit, TikzQConfig};
+use quojo_rust::qcore::{Controls, Targets};
+use quojo_rust::sim::statevector::single_qubit_matrix;
+use quojo_rust::utils::to_zx::CircuitConverter;
+use std::collections::HashMap;
+use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_2, FRAC_PI_4};
+
+fn matrix(gate: Gate) -> Vec<Vec<Complex>> {
+    single_qubit_matrix(&gate)
+        .iter()
+        .map(|row| row.to_vec())
+        .collect()
+}
+
+fn family() -> Vec<Gate> {
+    vec![
+        Gate::S,
+        Gate::Sdg,
+        Gate::T,
+        Gate::Tdg,
+        Gate::SX,
+        Gate::Rx(Phase::new(1, 3)),
+        Gate::Ry(Phase::new(-1, 4)),
+        Gate::Rz(Phase::new(-1, 2)),
+        Gate::Rz(Phase::from(2.0)),
+        Gate::U3 {
+            theta: Phase::new(1, 2),
+            phi: Phase::new(1, 4),
+            lambda: Phase::new(-1, 2),
+        },
+        Gate::GlobalPhase(Phase::new(3, 4)),
+    ]
+}
+
+#[test]
+fn matrices_follow_standard_definitions() {
+    let (zero, one) = (c(0.0, 0.0), c(1.0, 0.0));
+    let e = |radians: f64| Complex::from_phase(radians);
+
+    assert_matrices_close(
+        &matrix(Gate::Z),
+        &product(&matrix(Gate::S), &matrix(Gate::S)),
+    );
+    assert_matrices_close(
+        &matrix(Gate::S),
+        &product(&matrix(Gate::T), &matrix(Gate::T)),
+    );
+    assert_matrices_close(
+        &matrix(Gate::X),
+        &product(&matrix(Gate::SX), &matrix(Gate::SX)),
+    );
+    let identity = [vec![one, zero], vec![zero, one]];
+    assert_matrices_close(&identity, &product(&matrix(Gate::S), &matrix(Gate::Sdg)));
+    assert_matrices_close(&identity, &product(&matrix(Gate::T), &matrix(Gate::Tdg)));
+
+    // Negative angles are halved as negative angles
+    assert_matrices_close(
+        &[vec![e(FRAC_PI_4), zero], vec![zero, e(-FRAC_PI_4)]],
+        &matrix(Gate::Rz(Phase::new(-1, 2))),
+    );
+    let (cos, sin) = (FRAC_1_SQRT_2, FRAC_1_SQRT_2);
+    assert_matrices_close(
+        &[
+            vec![c(cos, 0.0), c(0.0, -sin)],
+            vec![c(0.0, -sin), c(cos, 0.0)],
+        ],
+        &matrix(Gate::Rx(Phase::new(1, 2))),
+    );
+    assert_matrices_close(
+        &[
+            vec![c(cos, 0.0), c(sin, 0.0)],
+            vec![c(-sin, 0.0), c(cos, 0.0)],
+        ],
+        &matrix(Gate::Ry(Phase::new(-1, 2))),
+    );
+    assert_matrices_close(
+        &[vec![e(0.75), zero], vec![zero, e(0.75)]],
+        &matrix(Gate::GlobalPhase(Phase::from(0.75))),
+    );
+
+    // U3 covers the other rotations
+    let theta = Phase::from(0.7);
+    let u3 = |phi: Phase, lambda: Phase| {
+        matrix(Gate::U3 {
+            theta: theta.clone(),
+            phi,
+            lambda,
+        })
+    };
+    assert_matrices_close(
+        &matrix(Gate::Ry(theta.clone())),
+        &u3(Phase::ZERO, Phase::ZERO),
+    );
+    assert_matrices_close(
+        &matrix(Gate::Rx(theta.clone())),
+        &u3(Phase::new(-1, 2), Phase::new(1, 2)),
+    );
+    let rz = matrix(Gate::Rz(Phase::from(0.4)));
+    let p = matrix(Gate::P(Phase::from(0.4)));
+    assert_matrices_close(
+        &p,
+        &[vec![rz[0][0] * e(0.2), zero], vec![zero, rz[1][1] * e(0.2)]],
+    );
+    assert_eq!(rz[0][1], zero);
+}
+
+#[test]
+fn zx_conversion_matches_simulation() {
+    for gate in family() {
+        let mut circuit = Circuit::new(2);
+        circuit.Apply(Gate::H, Targets(&[0, 1]));
+        circuit.Apply(gate.clone(), Targets(&[1]));
+        circuit.ApplyControlled(Gate::X, 1, 0);
+        assert_zx_matches_simulation(&circuit);
+    }
+}
+
+#[test]
+fn controlled_rotations_match_simulation() {
+    for gate in family() {
+        let mut circuit = Circuit::new(3);
+        circuit.apply_controlled(gate.clone(), Controls(&[0]).with_open(&[2]), Targets(&[1]));
+        assert_matrices_close(
+            &controlled_matrix(&gate, &[1], &[0], &[2], 3),
+            &simulated_matrix(&circuit),
+        );
+
+        circuit.Apply(Gate::H, Targets(&[0, 1, 2]));
+        assert_zx_matches_simulation(&circuit);
+    }
+}
+
+#[test]
+fn symbolic_rotations_bind_consistently() {
+    let theta = Phase::variable("theta");
+    let mut circuit = Circuit::new(1);
+    circuit.Apply(Gate::H, Targets(&[0]));
+    circuit.Apply(Gate::Rz(theta.clone()), Targets(&[0]));
+    circuit.Apply(
+        Gate::U3 {
+            theta: theta.clone(),
+            phi: Phase::new(1, 4),
+            lambda: theta * 2.0,
+        },
+        Targets(&[0]),
+    );
+    circuit.Apply(Gate::GlobalPhase(Phase::variable("phi")), Targets(&[0]));
+    assert_eq!(circuit.variables().len(), 2);
+
+    let graph = CircuitConverter::convert(&circuit).unwrap();
+    for (theta, phi) in [(0.3, 1.1), (-0.5, 2.0)] {
+        let values = HashMap::from([("theta".to_string(), theta), ("phi".to_string(), phi)]);
+        let bound = circuit.bind(&values);
+        assert!(bound.variables().is_empty());
+        assert_matrices_close(&simulated_matrix(&bound), &graph.bind(&values).to_matrix());
+    }
+}
+
+#[test]
+fn binding_commutes_with_conversion_past_pi() {
+    // θ/2 depends on which representative of θ is halved, so each rotation is checked alone
+    let theta = Phase::variable("theta");
+    let gates = [
+        Gate::Rx(theta.clone()),
+        Gate::Ry(theta.clone()),
+        Gate::Rz(theta.clone()),
+        Gate::U3 {
+            theta: theta.clone(),
+            phi: Phase::new(1, 4),
+            lambda: Phase::ZERO,
+        },
+    ];
+    for gate in gates {
+        let mut circuit = Circuit::new(1);
+        circuit.Apply(gate, Targets(&[0]));
+        let graph = CircuitConverter::convert(&circuit).unwrap();
+        for value in [3.0 * FRAC_PI_2, 5.9, 8.0, -4.0] {
+            let values = HashMap::from([("theta".to_string(), value)]);
+            assert_matrices_close(
+                &simulated_matrix(&circuit.bind(&values)),
+                &graph.bind(&values).to_matrix(),
+            );
+        }
+    }
+}
+
+#[test]
+fn display_and_tikz_labels() {
+    let names: Vec<String> = family().iter().map(|gate| gate.to_string()).collect();
+    assert_eq!(
+        names,
+        [
+            "S",
+            "S†",
+            "T",
+            "T†",
+            "√X",
+            "Rx(π/3)",
+            "Ry(7π/4)",
+            "Rz(3π/2)",
+            "Rz(2)",
+            "U3(π/2,π/4,3π/2)",
+            "GlobalPhase(3π/4)"
+        ]
+    );
+
+    let mut circuit = Circuit::new(2);
+    circuit.Apply(Gate::Sdg, Targets(&[0]));
+    circuit.Apply(Gate::Rx(Phase::new(1, 2)), Targets(&[1]));
+    circuit.apply_controlled(Gate::SX, Controls(&[0]), Targets(&[1]));
+    let tikz = circuit.to_tikz(&TikzQConfig::default());
+    assert!(tikz.contains("at (1.20,-0.00) {$S^\\dagger$}"));
+    assert!(tikz.contains("at (1.20,-0.60) {$R_x(\\frac{\\pi}{2})$}"));
+    assert!(tikz.contains("at (2.40,-0.60) {$\\sqrt{X}$}"));
+}
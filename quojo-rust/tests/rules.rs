@@ -0,0 +1,225 @@
+mod common;
+
+use common::*;
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::{self, Targets};
+use quojo_rust::utils::to_zx::CircuitConverter;
+use quojo_rust::zxcalc::graph::{EdgeType, NodeIndex, SpiderType, ZXGraph};
+use std::f64::consts::PI;
+
+/// Applies `pass` and checks that it fired `expected` times without changing the map.
+fn check_pass(graph: &mut ZXGraph, pass: fn(&mut ZXGraph) -> usize, expected: usize) {
+    let before = graph.to_matrix();
+    assert_eq!(pass(graph), expected);
+    assert_same_matrix(&before, graph);
+}
+
+fn boundaries(
+    graph: &mut ZXGraph,
+    inputs: usize,
+    outputs: usize,
+) -> (Vec<NodeIndex>, Vec<NodeIndex>) {
+    let inputs = (0..inputs)
+        .map(|_| graph.add_input_node(SpiderType::Boundary, 0.0))
+        .collect();
+    let outputs = (0..outputs)
+        .map(|_| graph.add_output_node(SpiderType::Boundary, 0.0))
+        .collect();
+    (inputs, outputs)
+}
+
+#[test]
+fn remove_identities_joins_neighbours() {
+    let mut graph = ZXGraph::new();
+    let (inputs, outputs) = boundaries(&mut graph, 1, 1);
+    let z = graph.add_node(SpiderType::Z, 0.0);
+    let x = graph.add_node(SpiderType::X, 0.0);
+    let p = graph.add_node(SpiderType::Z, 0.4);
+    graph.add_edge(inputs[0], z, EdgeType::Hadamard);
+    graph.add_edge(z, x, EdgeType::Hadamard);
+    graph.add_edge(x, p, EdgeType::Regular);
+    graph.add_edge(p, p, EdgeType::Regular);
+    graph.add_edge(p, outputs[0], EdgeType::Regular);
+
+    check_pass(&mut graph, ZXGraph::remove_identities, 3);
+    assert_eq!(spider_count(&graph), 1);
+}
+
+#[test]
+fn cancel_hadamard_edges_removes_pairs_and_loops() {
+    let mut graph = ZXGraph::new();
+    let (inputs, outputs) = boundaries(&mut graph, 1, 1);
+    let a = graph.add_node(SpiderType::Z, 0.3);
+    let b = graph.add_node(SpiderType::Z, 1.1);
+    graph.add_edge(inputs[0], a, EdgeType::Regular);
+    graph.add_edge(a, b, EdgeType::Hadamard);
+    graph.add_edge(a, b, EdgeType::Hadamard);
+    graph.add_edge(a, b, EdgeType::Hadamard);
+    graph.add_edge(b, b, EdgeType::Hadamard);
+    graph.add_edge(b, outputs[0], EdgeType::Regular);
+
+    check_pass(&mut graph, ZXGraph::cancel_hadamard_edges, 2);
+    assert_eq!(graph.neighbors(a).len(), 2);
+}
+
+#[test]
+fn fusion_keeps_parallel_hadamard_edges() {
+    let mut graph = ZXGraph::new();
+    let (inputs, outputs) = boundaries(&mut graph, 1, 1);
+    let a = graph.add_node(SpiderType::Z, 0.3);
+    let b = graph.add_node(SpiderType::Z, 0.9);
+    graph.add_edge(inputs[0], a, EdgeType::Regular);
+    graph.add_edge(a, b, EdgeType::Regular);
+    graph.add_edge(a, b, EdgeType::Hadamard);
+    graph.add_edge(b, outputs[0], EdgeType::Regular);
+
+    check_pass(&mut graph, ZXGraph::fuse_spiders, 1);
+    check_pass(&mut graph, ZXGraph::cancel_hadamard_edges, 1);
+}
+
+#[test]
+fn color_change_removes_x_spiders() {
+    let mut circuit = qcore::CircuitRepr::<2>();
+    circuit.Apply(Gate::H, Targets(&[0]));
+    circuit.ApplyControlled(Gate::X, 0, 1);
+    circuit.Apply(Gate::X, Targets(&[1]));
+    let mut graph = CircuitConverter::convert(&circuit).unwrap();
+
+    check_pass(&mut graph, ZXGraph::color_change, 2);
+    assert!(
+        graph
+            .nodes
+            .iter()
+            .flatten()
+            .all(|node| node.spider_type != SpiderType::X)
+    );
+}
+
+#[test]
+fn pi_commute_pushes_pi_through_spider() {
+    let mut graph = ZXGraph::new();
+    let (inputs, outputs) = boundaries(&mut graph, 1, 2);
+    let pi = graph.add_node(SpiderType::X, PI);
+    let z = graph.add_node(SpiderType::Z, 0.7);
+    let x1 = graph.add_node(SpiderType::X, 0.2);
+    let z2 = graph.add_node(SpiderType::Z, 1.3);
+    graph.add_edge(inputs[0], pi, EdgeType::Hadamard);
+    graph.add_edge(pi, z, EdgeType::Regular);
+    graph.add_edge(z, x1, EdgeType::Regular);
+    graph.add_edge(z, z2, EdgeType::Hadamard);
+    graph.add_edge(x1, outputs[0], EdgeType::Regular);
+    graph.add_edge(z2, outputs[1], EdgeType::Regular);
+
+    check_pass(&mut graph, ZXGraph::pi_commute, 1);
+    assert_eq!(spider_count(&graph), 3);
+}
+
+#[test]
+fn copy_states_copies_through_spider() {
+    let mut graph = ZXGraph::new();
+    let (_, outputs) = boundaries(&mut graph, 0, 3);
+    let state = graph.add_node(SpiderType::Z, PI);
+    let z = graph.add_node(SpiderType::Z, 0.4);
+    graph.add_edge(state, z, EdgeType::Hadamard);
+    for (i, &output) in outputs.iter().enumerate() {
+        let edge_type = if i == 1 {
+            EdgeType::Hadamard
+        } else {
+            EdgeType::Regular
+        };
+        graph.add_edge(z, output, edge_type);
+    }
+
+    check_pass(&mut graph, ZXGraph::copy_states, 1);
+    assert_eq!(spider_count(&graph), 3);
+
+    let mut closed = ZXGraph::new();
+    let state = closed.add_node(SpiderType::X, PI);
+    let effect = closed.add_node(SpiderType::Z, 0.6);
+    closed.add_edge(state, effect, EdgeType::Regular);
+    let before = closed.scalar_value();
+    assert_eq!(closed.copy_states(), 1);
+    assert_eq!(spider_count(&closed), 0);
+    assert!((closed.scalar.to_complex().re - before.re).abs() < TOLERANCE);
+    assert!((closed.scalar.to_complex().im - before.im).abs() < TOLERANCE);
+}
+
+#[test]
+fn bialgebra_connects_neighbours() {
+    for (n, m) in [(1, 1), (2, 1), (2, 3), (3, 2)] {
+        let mut graph = ZXGraph::new();
+        let (inputs, outputs) = boundaries(&mut graph, n, m);
+        let z = graph.add_node(SpiderType::Z, 0.0);
+        let x = graph.add_node(SpiderType::X, 0.0);
+        graph.add_edge(z, x, EdgeType::Regular);
+
+        for (i, &input) in inputs.iter().enumerate() {
+            let a = graph.add_node(SpiderType::X, 0.3 * (i + 1) as f64);
+            graph.add_edge(input, a, EdgeType::Regular);
+            graph.add_edge(a, z, EdgeType::Regular);
+        }
+        for (j, &output) in outputs.iter().enumerate() {
+            let b = graph.add_node(SpiderType::Z, 0.5 * (j + 1) as f64);
+            graph.add_edge(x, b, EdgeType::Regular);
+            graph.add_edge(b, output, EdgeType::Hadamard);
+        }
+
+        check_pass(&mut graph, ZXGraph::bialgebra, 1);
+        assert_eq!(spider_count(&graph), n + m);
+    }
+}
+
+#[test]
+fn hopf_removes_parallel_edges() {
+    let mut graph = ZXGraph::new();
+    let (inputs, outputs) = boundaries(&mut graph, 1, 1);
+    let z = graph.add_node(SpiderType::Z, 0.3);
+    let x = graph.add_node(SpiderType::X, 0.8);
+    graph.add_edge(inputs[0], z, EdgeType::Regular);
+    for _ in 0..5 {
+        graph.add_edge(z, x, EdgeType::Regular);
+    }
+    graph.add_edge(x, outputs[0], EdgeType::Regular);
+
+    check_pass(&mut graph, ZXGraph::hopf, 2);
+    assert_eq!(graph.neighbors(z).len(), 2);
+}
+
+#[test]
+fn passes_preserve_converted_circuits() {
+    let mut circuit = qcore::CircuitRepr::<3>();
+    circuit.Apply(Gate::H, Targets(&[0, 1]));
+    circuit.ApplyControlled(Gate::X, 0, 2);
+    circuit.Apply(Gate::X, Targets(&[1]));
+    circuit.ApplyControlled(Gate::X, 1, 2);
+    circuit.Apply(Gate::P(Phase::new(1, 4)), Targets(&[2]));
+    circuit.ApplyControlled(Gate::X, 0, 2);
+    circuit.ApplyControlled(Gate::Z, 1, 0);
+    circuit.Apply(Gate::Y, Targets(&[0]));
+    circuit.ApplyControlled(Gate::X, 0, 1);
+    circuit.ApplyControlled(Gate::X, 0, 1);
+    circuit.Apply(Gate::H, Targets(&[2]));
+
+    let mut graph = CircuitConverter::convert(&circuit).unwrap();
+    let expected = graph.to_matrix();
+    let passes: [fn(&mut ZXGraph) -> usize; 7] = [
+        ZXGraph::fuse_spiders,
+        ZXGraph::remove_identities,
+        ZXGraph::cancel_hadamard_edges,
+        ZXGraph::hopf,
+        ZXGraph::bialgebra,
+        ZXGraph::copy_states,
+        ZXGraph::pi_commute,
+    ];
+
+    while passes.iter().map(|pass| pass(&mut graph)).sum::<usize>() > 0 {
+        assert_same_matrix(&expected, &graph);
+    }
+    assert_same_matrix(&expected, &graph);
+
+    graph.color_change();
+    graph.fuse_spiders();
+    graph.cancel_hadamard_edges();
+    assert_same_matrix(&expected, &graph);
+}
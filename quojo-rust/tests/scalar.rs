@@ -0,0 +1,94 @@
+mod common;
+
+use common::*;
+use quojo_rust::linalg::vector::Complex;
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::{self, Targets};
+use quojo_rust::utils::to_zx::CircuitConverter;
+use quojo_rust::zxcalc::graph::{EdgeType, SpiderType, ZXGraph};
+use quojo_rust::zxcalc::scalar::Scalar;
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI, SQRT_2};
+
+#[test]
+fn scalar_keeps_clifford_factors_exact() {
+    let mut scalar = Scalar::one();
+    scalar.multiply_sqrt2_power(3);
+    scalar.multiply_phase(3.0 * FRAC_PI_4);
+    scalar.multiply_phase(-PI);
+    scalar.multiply_one_plus_phase(FRAC_PI_2);
+
+    assert_eq!(scalar.sqrt2_power, 4);
+    assert_eq!(scalar.phase, 0);
+    assert_eq!((scalar.value.re, scalar.value.im), (1.0, 0.0));
+    assert_close(
+        Complex::new(4.0, 0.0),
+        scalar.to_complex(),
+        "clifford factors",
+    );
+
+    scalar.multiply_phase(0.3);
+    scalar.multiply_one_plus_phase(0.5);
+    let expected = Complex::from_phase(0.3) * (Complex::from_phase(0.5) + 1.0) * 4.0;
+    assert_close(expected, scalar.to_complex(), "general phases");
+
+    let product = scalar * scalar;
+    assert_eq!(product.sqrt2_power, 8);
+    assert_close(expected * expected, product.to_complex(), "product");
+
+    scalar.multiply_one_plus_phase(PI);
+    assert!(scalar.is_zero());
+}
+
+#[test]
+fn conversion_tracks_scalar_exactly() {
+    let mut circuit = qcore::CircuitRepr::<2>();
+    circuit.ApplyControlled(Gate::X, 0, 1);
+    circuit.ApplyControlled(Gate::Z, 1, 0);
+    circuit.Apply(Gate::Y, Targets(&[0]));
+
+    let graph = CircuitConverter::convert(&circuit).unwrap();
+    assert_eq!(graph.scalar.sqrt2_power, 2);
+    assert_eq!(graph.scalar.phase, 2);
+    assert_close(
+        Complex::new(0.0, 2.0),
+        graph.scalar.to_complex(),
+        "converted scalar",
+    );
+}
+
+#[test]
+fn clifford_simp_keeps_scalar_exact() {
+    let mut circuit = qcore::CircuitRepr::<3>();
+    circuit.Apply(Gate::H, Targets(&[0, 1, 2]));
+    circuit.ApplyControlled(Gate::X, 0, 1);
+    circuit.Apply(Gate::P(Phase::new(1, 2)), Targets(&[1]));
+    circuit.ApplyControlled(Gate::Z, 1, 2);
+    circuit.Apply(Gate::H, Targets(&[1]));
+    circuit.ApplyControlled(Gate::X, 2, 0);
+    circuit.Apply(Gate::P(Phase::new(3, 2)), Targets(&[0]));
+    circuit.Apply(Gate::H, Targets(&[0, 2]));
+
+    let mut graph = CircuitConverter::convert(&circuit).unwrap();
+    let expected = graph.to_matrix();
+    graph.clifford_simp();
+
+    assert_eq!((graph.scalar.value.re, graph.scalar.value.im), (1.0, 0.0));
+    assert_same_matrix(&expected, &graph);
+}
+
+#[test]
+fn amplitudes_include_scalar() {
+    let mut graph = ZXGraph::new();
+    let input = graph.add_input_node(SpiderType::Boundary, 0.0);
+    let output = graph.add_output_node(SpiderType::Boundary, 0.0);
+    graph.add_edge(input, output, EdgeType::Regular);
+    graph.scalar.multiply_sqrt2_power(-1);
+    graph.scalar.multiply_phase(FRAC_PI_4);
+
+    let matrix = graph.to_matrix();
+    let factor = Complex::from_phase(FRAC_PI_4) * (1.0 / SQRT_2);
+    assert_close(factor, matrix[0][0], "entry (0, 0)");
+    assert_close(factor, matrix[1][1], "entry (1, 1)");
+    assert_close(Complex::new(0.0, 0.0), matrix[0][1], "entry (0, 1)");
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use common::*;
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::{self, Targets};
+use quojo_rust::utils::to_zx::CircuitConverter;
+use quojo_rust::zxcalc::graph::{EdgeType, NodeIndex, SpiderType, ZXGraph};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+/// A graph-like diagram: `phases` are interior spiders, each spider in `outer` sits on
+/// its own input or output, and `edges` are Hadamard edges between spiders.
+fn graph_like(
+    phases: &[f64],
+    outer: &[f64],
+    edges: &[(usize, usize)],
+) -> (ZXGraph, Vec<NodeIndex>) {
+    let mut graph = ZXGraph::new();
+    let mut spiders = Vec::new();
+    for &phase in phases.iter().chain(outer) {
+        spiders.push(graph.add_node(SpiderType::Z, phase));
+    }
+    for (i, &spider) in spiders[phases.len()..].iter().enumerate() {
+        let boundary = if i % 2 == 0 {
+            graph.add_input_node(SpiderType::Boundary, 0.0)
+        } else {
+            graph.add_output_node(SpiderType::Boundary, 0.0)
+        };
+        graph.add_edge(boundary, spider, EdgeType::Regular);
+    }
+    for &(a, b) in edges {
+        graph.add_edge(spiders[a], spiders[b], EdgeType::Hadamard);
+    }
+    (graph, spiders)
+}
+
+#[test]
+fn to_graph_like_preserves_circuits() {
+    let mut circuit = qcore::CircuitRepr::<3>();
+    circuit.Apply(Gate::H, Targets(&[0]));
+    circuit.ApplyControlled(Gate::X, 0, 1);
+    circuit.Apply(Gate::X, Targets(&[1]));
+    circuit.ApplySwap(0, 1);
+    circuit.ApplyControlled(Gate::X, 1, 0);
+    circuit.ApplyControlled(Gate::Z, 0, 1);
+
+    let mut graph = CircuitConverter::convert(&circuit).unwrap();
+    let expected = graph.to_matrix();
+    assert!(!graph.is_graph_like());
+    graph.to_graph_like();
+    assert!(graph.is_graph_like());
+    assert_same_matrix(&expected, &graph);
+}
+
+#[test]
+fn local_complement_removes_proper_clifford_spiders() {
+    for phase in [FRAC_PI_2, 3.0 * FRAC_PI_2] {
+        let (mut graph, _) = graph_like(
+            &[phase],
+            &[0.3, FRAC_PI_4, PI, 1.2],
+            &[(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (3, 4)],
+        );
+        let expected = graph.to_matrix();
+        assert_eq!(graph.local_complement(), 1);
+        assert_eq!(spider_count(&graph), 4);
+        assert!(graph.is_graph_like());
+        assert_same_matrix(&expected, &graph);
+    }
+}
+
+#[test]
+fn pivot_removes_pauli_pairs() {
+    for (phase_u, phase_v) in [(0.0, 0.0), (PI, 0.0), (PI, PI)] {
+        let (mut graph, _) = graph_like(
+            &[phase_u, phase_v],
+            &[0.4, 1.1, 2.0, 0.7],
+            &[(0, 1), (0, 2), (0, 4), (1, 3), (1, 4), (1, 5), (2, 3)],
+        );
+        let expected = graph.to_matrix();
+        assert_eq!(graph.pivot(), 1);
+        assert_eq!(spider_count(&graph), 4);
+        assert!(graph.is_graph_like());
+        assert_same_matrix(&expected, &graph);
+    }
+}
+
+#[test]
+fn pivot_boundary_moves_boundary_first() {
+    let (mut graph, _) = graph_like(&[PI], &[0.0, 0.9, 1.3], &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+    let expected = graph.to_matrix();
+    assert_eq!(graph.pivot(), 0);
+    assert_eq!(graph.pivot_boundary(), 1);
+    assert!(graph.is_graph_like());
+    assert_same_matrix(&expected, &graph);
+}
+
+#[test]
+fn pivot_gadget_creates_phase_gadget() {
+    let (mut graph, spiders) = graph_like(
+        &[0.0, 0.3],
+        &[0.9, 1.3, 0.2],
+        &[(0, 1), (0, 2), (1, 3), (1, 4), (2, 3)],
+    );
+    let expected = graph.to_matrix();
+    assert_eq!(graph.pivot(), 0);
+    assert_eq!(graph.pivot_gadget(), 1);
+    assert!(graph.nodes[spiders[0].0].is_none());
+    assert!(graph.is_graph_like());
+    assert_same_matrix(&expected, &graph);
+}
+
+#[test]
+fn clifford_simp_preserves_clifford_circuits() {
+    let mut rng = StdRng::seed_from_u64(7);
+    for _ in 0..20 {
+        let circuit = random_circuit(&mut rng, Phase::new(1, 2), false);
+        let mut graph = CircuitConverter::convert(&circuit).unwrap();
+        let expected = graph.to_matrix();
+        let before = spider_count(&graph);
+
+        graph.clifford_simp();
+        assert!(graph.is_graph_like());
+        assert!(spider_count(&graph) <= before);
+        assert_same_matrix(&expected, &graph);
+    }
+}
+
+#[test]
+fn full_reduce_preserves_clifford_t_circuits() {
+    let mut rng = StdRng::seed_from_u64(11);
+    for _ in 0..20 {
+        let circuit = random_circuit(&mut rng, Phase::new(1, 4), false);
+        let mut graph = CircuitConverter::convert(&circuit).unwrap();
+        let expected = graph.to_matrix();
+
+        graph.full_reduce();
+        assert!(graph.is_graph_like());
+        assert_same_matrix(&expected, &graph);
+    }
+}
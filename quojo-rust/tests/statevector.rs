@@ -0,0 +1,613 @@
+mod common;
+
+use common::*;
+use quojo_rust::linalg::vector::Complex;
+use quojo_rust::qcore::Targets;
+use quojo_rust::qcore::circuits::Circuit;
+use quojo_rust::qcore::gates::{ControlledGate, Gate};
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::sim::statevector::StateVector;
+use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_2, FRAC_PI_4};
+
+type Matrix = Vec<Vec<Complex>>;
+
+fn identity(dimension: usize) -> Matrix {
+    (0..dimension)
+        .map(|row| {
+            (0..dimension)
+                .map(|col| c(if row == col { 1.0 } else { 0.0 }, 0.0))
+                .collect()
+        })
+        .collect()
+}
+
+fn scaled_sum(terms: &[(Complex, &Matrix)]) -> Matrix {
+    let n = terms[0].1.len();
+    (0..n)
+        .map(|row| {
+            (0..n)
+                .map(|col| {
+                    terms
+                        .iter()
+                        .fold(c(0.0, 0.0), |sum, &(k, m)| sum + k * m[row][col])
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn pauli(name: char) -> Matrix {
+    let (o, l, i) = (c(0.0, 0.0), c(1.0, 0.0), c(0.0, 1.0));
+    match name {
+        'X' => vec![vec![o, l], vec![l, o]],
+        'Y' => vec![vec![o, c(0.0, -1.0)], vec![i, o]],
+        _ => vec![vec![l, o], vec![o, c(-1.0, 0.0)]],
+    }
+}
+
+/// e^(-iθ/2·P) for a Pauli product `p` with P² = I.
+fn rotation(p: &Matrix, theta: f64) -> Matrix {
+    let (sin, cos) = (theta / 2.0).sin_cos();
+    scaled_sum(&[(c(cos, 0.0), &identity(p.len())), (c(0.0, -sin), p)])
+}
+
+fn diagonal(entries: &[Complex]) -> Matrix {
+    let mut matrix = identity(entries.len());
+    for (k, &entry) in entries.iter().enumerate() {
+        matrix[k][k] = entry;
+    }
+    matrix
+}
+
+/// `u` on bit 1 of the index, applied when bit 0 is set.
+fn controlled(u: &Matrix) -> Matrix {
+    let mut matrix = identity(4);
+    for row in 0..2 {
+        for col in 0..2 {
+            matrix[1 + 2 * row][1 + 2 * col] = u[row][col];
+        }
+    }
+    matrix
+}
+
+/// The permutation matrix sending basis state `k` to `image(k)`.
+fn permutation(dimension: usize, image: impl Fn(usize) -> usize) -> Matrix {
+    let mut matrix = vec![vec![c(0.0, 0.0); dimension]; dimension];
+    for k in 0..dimension {
+        matrix[image(k)][k] = c(1.0, 0.0);
+    }
+    matrix
+}
+
+/// The qubits a gate acts on and its matrix over them, written out from the textbook
+/// definitions rather than taken from the simulator. Bit i of an index is `qubits[i]`.
+fn reference(gate: &Gate, wire: usize) -> (Vec<usize>, Matrix) {
+    let e = Complex::from_phase;
+    let single = |matrix: Matrix| (vec![wire], matrix);
+    match gate {
+        Gate::X => single(pauli('X')),
+        Gate::Y => single(pauli('Y')),
+        Gate::Z => single(pauli('Z')),
+        Gate::H => single(vec![
+            vec![c(FRAC_1_SQRT_2, 0.0), c(FRAC_1_SQRT_2, 0.0)],
+            vec![c(FRAC_1_SQRT_2, 0.0), c(-FRAC_1_SQRT_2, 0.0)],
+        ]),
+        Gate::P(phase) => single(diagonal(&[c(1.0, 0.0), e(phase.to_radians())])),
+        Gate::S => single(diagonal(&[c(1.0, 0.0), c(0.0, 1.0)])),
+        Gate::Sdg => single(diagonal(&[c(1.0, 0.0), c(0.0, -1.0)])),
+        Gate::T => single(diagonal(&[c(1.0, 0.0), e(FRAC_PI_4)])),
+        Gate::Tdg => single(diagonal(&[c(1.0, 0.0), e(-FRAC_PI_4)])),
+        Gate::SX => single(vec![
+            vec![c(0.5, 0.5), c(0.5, -0.5)],
+            vec![c(0.5, -0.5), c(0.5, 0.5)],
+        ]),
+        Gate::Rx(theta) => single(rotation(&pauli('X'), theta.to_radians())),
+        Gate::Ry(theta) => single(rotation(&pauli('Y'), theta.to_radians())),
+        Gate::Rz(theta) => single(rotation(&pauli('Z'), theta.to_radians())),
+        Gate::U3 { theta, phi, lambda } => {
+            let (sin, cos) = (theta.to_radians() / 2.0).sin_cos();
+            let (phi, lambda) = (phi.to_radians(), lambda.to_radians());
+            single(vec![
+                vec![c(cos, 0.0), e(lambda) * -sin],
+                vec![e(phi) * sin, e(phi + lambda) * cos],
+            ])
+        }
+        Gate::GlobalPhase(phase) => {
+            let phase = e(phase.to_radians());
+            single(diagonal(&[phase, phase]))
+        }
+        Gate::CNOT { control, target } => (vec![*control, *target], controlled(&pauli('X'))),
+        Gate::CZ { control, target } => (vec![*control, *target], controlled(&pauli('Z'))),
+        Gate::CP {
+            control,
+            target,
+            phase,
+        } => (
+            vec![*control, *target],
+            controlled(&diagonal(&[c(1.0, 0.0), e(phase.to_radians())])),
+        ),
+        Gate::CRx {
+            control,
+            target,
+            theta,
+        }
+        | Gate::CRy {
+            control,
+            target,
+            theta,
+        }
+        | Gate::CRz {
+            control,
+            target,
+            theta,
+        } => {
+            let axis = match gate {
+                Gate::CRx { .. } => 'X',
+                Gate::CRy { .. } => 'Y',
+                _ => 'Z',
+            };
+            let u = rotation(&pauli(axis), theta.to_radians());
+            (vec![*control, *target], controlled(&u))
+        }
+        Gate::SWAP { qubit1, qubit2 } => (
+            vec![*qubit1, *qubit2],
+            permutation(4, |k| (k >> 1) | (k & 1) << 1),
+        ),
+        Gate::RXX {
+            qubit1,
+            qubit2,
+            theta,
+        }
+        | Gate::RYY {
+            qubit1,
+            qubit2,
+            theta,
+        }
+        | Gate::RZZ {
+            qubit1,
+            qubit2,
+            theta,
+        } => {
+            let axis = match gate {
+                Gate::RXX { .. } => 'X',
+                Gate::RYY { .. } => 'Y',
+                _ => 'Z',
+            };
+            let p = kron(&pauli(axis), &pauli(axis));
+            (vec![*qubit1, *qubit2], rotation(&p, theta.to_radians()))
+        }
+        Gate::ISWAP { qubit1, qubit2 } | Gate::SqrtISWAP { qubit1, qubit2 } => {
+            // |01⟩ and |10⟩ mix by i·sin(θ), with θ = π/2 for iSWAP and π/4 for √iSWAP
+            let angle = match gate {
+                Gate::ISWAP { .. } => FRAC_PI_2,
+                _ => FRAC_PI_4,
+            };
+            let mut matrix = identity(4);
+            matrix[1][1] = c(angle.cos(), 0.0);
+            matrix[2][2] = c(angle.cos(), 0.0);
+            matrix[1][2] = c(0.0, angle.sin());
+            matrix[2][1] = c(0.0, angle.sin());
+            (vec![*qubit1, *qubit2], matrix)
+        }
+        Gate::ECR { qubit1, qubit2 } => {
+            // (X⊗I − Y⊗X)/√2 with qubit1 on the left, so on bit 0 of the index
+            let h = c(FRAC_1_SQRT_2, 0.0);
+            let matrix = scaled_sum(&[
+                (h, &kron(&identity(2), &pauli('X'))),
+                (h * -1.0, &kron(&pauli('X'), &pauli('Y'))),
+            ]);
+            (vec![*qubit1, *qubit2], matrix)
+        }
+        Gate::Toffoli {
+            control1,
+            control2,
+            target,
+        } => (
+            vec![*control1, *control2, *target],
+            permutation(8, |k| if k & 3 == 3 { k ^ 4 } else { k }),
+        ),
+        Gate::Fredkin {
+            control,
+            target1,
+            target2,
+        } => (
+            vec![*control, *target1, *target2],
+            permutation(8, |k| match k & 1 {
+                1 => 1 | (k >> 2 & 1) << 1 | (k >> 1 & 1) << 2,
+                _ => k,
+            }),
+        ),
+        Gate::Unitary(unitary) => (unitary.qubits.clone(), unitary.matrix().to_vec()),
+        Gate::Controlled(controlled) => {
+            let (targets, u) = reference(&controlled.gate, controlled.target);
+            let qubits = [
+                controlled.controls.as_slice(),
+                &controlled.open_controls,
+                &targets,
+            ]
+            .concat();
+            let (closed, open) = (controlled.controls.len(), controlled.open_controls.len());
+            let fires = |k: usize| {
+                k & ((1 << closed) - 1) == (1 << closed) - 1 && k >> closed & ((1 << open) - 1) == 0
+            };
+            let shift = closed + open;
+            let mut matrix = identity(1 << qubits.len());
+            for row in 0..1 << qubits.len() {
+                for col in 0..1 << qubits.len() {
+                    if fires(col) && row & ((1 << shift) - 1) == col & ((1 << shift) - 1) {
+                        matrix[row][col] = u[row >> shift][col >> shift];
+                    }
+                }
+            }
+            (qubits, matrix)
+        }
+        Gate::Measure { .. } | Gate::Reset | Gate::Conditional(_) => {
+            panic!("{} is not unitary", gate)
+        }
+    }
+}
+
+/// The matrix the simulator applies for `gate` on `wire`, column by column.
+fn simulated(gate: &Gate, wire: usize, width: usize) -> Matrix {
+    let columns: Vec<Vec<Complex>> = (0..1 << width)
+        .map(|input| {
+            let mut state = StateVector::from_basis_state(width, input);
+            state.apply_gate(wire, gate);
+            state.amplitudes()
+        })
+        .collect();
+    (0..1 << width)
+        .map(|row| (0..1 << width).map(|col| columns[col][row]).collect())
+        .collect()
+}
+
+fn every_gate() -> Vec<(Gate, usize)> {
+    let theta = Phase::new(2, 5);
+    let unitary = Gate::unitary("U", &[2, 0], random_unitary(2, 7)).unwrap();
+    let controlled = Gate::Controlled(Box::new(ControlledGate {
+        controls: vec![3],
+        open_controls: vec![0],
+        gate: Gate::Ry(Phase::new(1, 3)),
+        target: 2,
+    }));
+    vec![
+        (Gate::X, 1),
+        (Gate::Y, 2),
+        (Gate::Z, 0),
+        (Gate::H, 3),
+        (Gate::P(Phase::new(1, 3)), 1),
+        (Gate::P(Phase::from(0.7)), 3),
+        (Gate::S, 0),
+        (Gate::Sdg, 1),
+        (Gate::T, 2),
+        (Gate::Tdg, 3),
+        (Gate::SX, 0),
+        (Gate::Rx(theta.clone()), 1),
+        (Gate::Ry(theta.clone()), 2),
+        (Gate::Rz(theta.clone()), 3),
+        (
+            Gate::U3 {
+                theta: Phase::new(1, 3),
+                phi: Phase::new(1, 5),
+                lambda: Phase::new(-3, 4),
+            },
+            0,
+        ),
+        (Gate::GlobalPhase(Phase::new(3, 4)), 2),
+        (
+            Gate::CNOT {
+                control: 3,
+                target: 0,
+            },
+            3,
+        ),
+        (
+            Gate::CZ {
+                control: 1,
+                target: 2,
+            },
+            1,
+        ),
+        (
+            Gate::CP {
+                control: 0,
+                target: 3,
+                phase: Phase::new(1, 3),
+            },
+            0,
+        ),
+        (
+            Gate::CRx {
+                control: 2,
+                target: 1,
+                theta: theta.clone(),
+            },
+            2,
+        ),
+        (
+            Gate::CRy {
+                control: 0,
+                target: 2,
+                theta: theta.clone(),
+            },
+            0,
+        ),
+        (
+            Gate::CRz {
+                control: 3,
+                target: 1,
+                theta: theta.clone(),
+            },
+            3,
+        ),
+        (
+            Gate::SWAP {
+                qubit1: 0,
+                qubit2: 3,
+            },
+            0,
+        ),
+        (
+            Gate::RXX {
+                qubit1: 1,
+                qubit2: 3,
+                theta: theta.clone(),
+            },
+            1,
+        ),
+        (
+            Gate::RYY {
+                qubit1: 2,
+                qubit2: 0,
+                theta: theta.clone(),
+            },
+            2,
+        ),
+        (
+            Gate::RZZ {
+                qubit1: 0,
+                qubit2: 1,
+                theta: theta.clone(),
+            },
+            0,
+        ),
+        (
+            Gate::ISWAP {
+                qubit1: 3,
+                qubit2: 1,
+            },
+            3,
+        ),
+        (
+            Gate::SqrtISWAP {
+                qubit1: 0,
+                qubit2: 2,
+            },
+            0,
+        ),
+        (
+            Gate::ECR {
+                qubit1: 1,
+                qubit2: 0,
+            },
+            1,
+        ),
+        (
+            Gate::Toffoli {
+                control1: 3,
+                control2: 0,
+                target: 2,
+            },
+            3,
+        ),
+        (
+            Gate::Fredkin {
+                control: 2,
+                target1: 0,
+                target2: 3,
+            },
+            2,
+        ),
+        (unitary, 2),
+        (controlled, 3),
+    ]
+}
+
+#[test]
+fn every_gate_matches_its_matrix() {
+    let width = 4;
+    for (gate, wire) in every_gate() {
+        let (qubits, matrix) = reference(&gate, wire);
+        let expected = embed(&matrix, &qubits, width);
+        let actual = simulated(&gate, wire, width);
+        for (row, (e, a)) in expected.iter().zip(&actual).enumerate() {
+            for (col, (e, a)) in e.iter().zip(a).enumerate() {
+                assert!(
+                    (*e - *a).abs() < TOLERANCE,
+                    "{} entry ({}, {}): expected {:?}, got {:?}",
+                    gate,
+                    row,
+                    col,
+                    e,
+                    a
+                );
+            }
+        }
+    }
+}
+
+/// Applies the reference matrix of every gate of `circuit` to `state` by dense
+/// matrix-vector products.
+fn reference_run(circuit: &Circuit, state: &[Complex]) -> Vec<Complex> {
+    let width = circuit.num_qubits();
+    circuit
+        .ordered_gates()
+        .iter()
+        .fold(state.to_vec(), |state, (wire, gate)| {
+            let (qubits, matrix) = reference(gate, *wire);
+            embed(&matrix, &qubits, width)
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .zip(&state)
+                        .fold(c(0.0, 0.0), |sum, (&m, &v)| sum + m * v)
+                })
+                .collect()
+        })
+}
+
+fn apply(circuit: &mut Circuit, gate: Gate, wire: usize) {
+    let qubits = gate.involved_qubits(wire);
+    circuit.Apply(gate, Targets(&qubits));
+}
+
+fn assert_amplitudes_close(expected: &[Complex], actual: &[Complex]) {
+    for (index, (e, a)) in expected.iter().zip(actual).enumerate() {
+        assert!(
+            (*e - *a).abs() < TOLERANCE,
+            "amplitude {}: expected {:?}, got {:?}",
+            index,
+            e,
+            a
+        );
+    }
+}
+
+#[test]
+fn kernels_agree_inside_and_across_blocks() {
+    // A block holds 16 amplitudes, so qubits 0-3 are lanes and 4-6 select blocks
+    let width = 7;
+    let mut circuit = Circuit::new(width);
+    for qubit in 0..width {
+        apply(&mut circuit, Gate::H, qubit);
+        apply(&mut circuit, Gate::T, qubit);
+        apply(
+            &mut circuit,
+            Gate::Ry(Phase::new(qubit as i64 + 1, 7)),
+            qubit,
+        );
+    }
+    let cnot = |control, target| Gate::CNOT { control, target };
+    let crz = |control, target| Gate::CRz {
+        control,
+        target,
+        theta: Phase::new(3, 5),
+    };
+    for (gate, wire) in [
+        // Lane target, lane and block controls
+        (cnot(2, 0), 2),
+        (cnot(5, 3), 5),
+        (crz(6, 1), 6),
+        // Block target, lane and block controls
+        (cnot(0, 4), 0),
+        (cnot(6, 5), 6),
+        (crz(3, 6), 3),
+        (
+            Gate::Toffoli {
+                control1: 1,
+                control2: 5,
+                target: 3,
+            },
+            1,
+        ),
+        (
+            Gate::Toffoli {
+                control1: 4,
+                control2: 2,
+                target: 6,
+            },
+            4,
+        ),
+        (
+            Gate::Fredkin {
+                control: 5,
+                target1: 0,
+                target2: 6,
+            },
+            5,
+        ),
+        (
+            Gate::SWAP {
+                qubit1: 2,
+                qubit2: 4,
+            },
+            2,
+        ),
+        (
+            Gate::CP {
+                control: 1,
+                target: 5,
+                phase: Phase::new(1, 3),
+            },
+            1,
+        ),
+        (
+            Gate::RXX {
+                qubit1: 3,
+                qubit2: 5,
+                theta: Phase::new(1, 4),
+            },
+            3,
+        ),
+        (
+            Gate::Controlled(Box::new(ControlledGate {
+                controls: vec![0, 6],
+                open_controls: vec![4],
+                gate: Gate::H,
+                target: 2,
+            })),
+            0,
+        ),
+        (
+            Gate::Controlled(Box::new(ControlledGate {
+                controls: vec![3],
+                open_controls: vec![1],
+                gate: Gate::SX,
+                target: 5,
+            })),
+            3,
+        ),
+    ] {
+        apply(&mut circuit, gate, wire);
+    }
+
+    let mut state = StateVector::new(width);
+    state.run(&circuit);
+    let mut initial = vec![c(0.0, 0.0); 1 << width];
+    initial[0] = c(1.0, 0.0);
+    assert_amplitudes_close(&reference_run(&circuit, &initial), &state.amplitudes());
+    assert!((state.norm_squared() - 1.0).abs() < TOLERANCE);
+}
+
+#[test]
+fn narrow_states_fit_in_one_block() {
+    // Fewer qubits than a block holds: the unused lanes must stay zero
+    for width in 1..=3 {
+        let mut circuit = Circuit::new(width);
+        for qubit in 0..width {
+            apply(&mut circuit, Gate::H, qubit);
+            apply(
+                &mut circuit,
+                Gate::P(Phase::new(1, qubit as i64 + 2)),
+                qubit,
+            );
+        }
+        if width > 1 {
+            apply(
+                &mut circuit,
+                Gate::CNOT {
+                    control: 0,
+                    target: width - 1,
+                },
+                0,
+            );
+        }
+        let mut state = StateVector::from_basis_state(width, (1 << width) - 1);
+        state.run(&circuit);
+        let mut initial = vec![c(0.0, 0.0); 1 << width];
+        initial[(1 << width) - 1] = c(1.0, 0.0);
+        assert_eq!(state.amplitudes().len(), 1 << width);
+        assert_amplitudes_close(&reference_run(&circuit, &initial), &state.amplitudes());
+        assert!((state.norm_squared() - 1.0).abs() < TOLERANCE);
+    }
+}
@@ -0,0 +1,169 @@
+use quojo_rust::qcore::circuits::Circuit;
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::svg::{SvgCircuit, SvgQConfig};
+use quojo_rust::qcore::{Condition, Targets};
+use quojo_rust::zxcalc::graph::{EdgeType, SpiderType, ZXGraph};
+use quojo_rust::zxcalc::parametric::Parity;
+use quojo_rust::zxcalc::svg::{SvgConfig, generate_svg};
+
+/// Whether every element opened in `svg` is closed again, in order.
+fn is_balanced(svg: &str) -> bool {
+    let mut open = Vec::new();
+    for tag in svg.split('<').skip(1) {
+        let tag = tag.split('>').next().unwrap();
+        let name = |t: &str| t.split_whitespace().next().unwrap_or("").to_string();
+        if let Some(closing) = tag.strip_prefix('/') {
+            if open.pop() != Some(name(closing)) {
+                return false;
+            }
+        } else if !tag.ends_with('/') {
+            open.push(name(tag));
+        }
+    }
+    open.is_empty()
+}
+
+fn count(svg: &str, pattern: &str) -> usize {
+    svg.matches(pattern).count()
+}
+
+#[test]
+fn circuit_draws_wires_gates_and_labels() {
+    let mut circuit = Circuit::new(3);
+    circuit.Apply(Gate::H, Targets(&[0]));
+    circuit.Apply(
+        Gate::CNOT {
+            control: 0,
+            target: 2,
+        },
+        Targets(&[0, 2]),
+    );
+    circuit.Apply(Gate::P(Phase::new(3, 4)), Targets(&[1]));
+    circuit.Apply(Gate::Sdg, Targets(&[1]));
+
+    let svg = circuit.to_svg(&SvgQConfig::default());
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.ends_with("</svg>\n"));
+    assert!(is_balanced(&svg));
+
+    assert_eq!(count(&svg, "<line class=\"wire\""), 3);
+    assert_eq!(count(&svg, "<line class=\"connection\""), 1);
+    for qubit in 0..3 {
+        assert!(svg.contains(&format!(
+            ">q<tspan baseline-shift=\"sub\" font-size=\"70%\">{}</tspan>",
+            qubit
+        )));
+    }
+    assert!(svg.contains(">H</text>"));
+    assert!(svg.contains(">P(3π/4)</text>"));
+    assert!(svg.contains(">S<tspan baseline-shift=\"super\" font-size=\"70%\">†</tspan></text>"));
+    // The control dot and the ⊕ target
+    assert_eq!(count(&svg, "<circle class=\"dot\""), 1);
+    assert_eq!(count(&svg, "<circle class=\"gate\""), 1);
+}
+
+#[test]
+fn circuit_uses_config_styles_and_spacing() {
+    let mut circuit = Circuit::new(2);
+    circuit.Apply(Gate::H, Targets(&[0]));
+    let config = SvgQConfig {
+        wire_style: "stroke:navy;stroke-width:2".to_string(),
+        show_labels: false,
+        scale: 10.0,
+        ..SvgQConfig::default()
+    };
+
+    let svg = circuit.to_svg(&config);
+    assert!(svg.contains(".wire { stroke:navy;stroke-width:2 }"));
+    assert!(!svg.contains("<tspan"));
+    // Two time steps of 1.2 plus 0.5 across, one wire gap of 0.6 down, and margins of 0.4
+    assert!(svg.contains("width=\"37.00\" height=\"14.00\""));
+}
+
+#[test]
+fn circuit_draws_classical_wires_double() {
+    let mut circuit = Circuit::new(1);
+    circuit.add_clbits(1);
+    circuit.Apply(Gate::H, Targets(&[0]));
+    circuit.Measure(0, 0);
+    circuit.apply_conditional(Gate::X, Condition(&[0], 1), Targets(&[0]));
+    circuit.Reset(0);
+
+    let svg = circuit.to_svg(&SvgQConfig::default());
+    assert!(is_balanced(&svg));
+    assert_eq!(count(&svg, "<line class=\"wire\""), 3);
+    assert!(svg.contains("<path class=\"mark\" d=\"M"));
+    assert!(svg.contains(">c<tspan"));
+    assert!(svg.contains(">|0⟩</text>"));
+    assert_eq!(count(&svg, "<line class=\"connection\""), 4);
+}
+
+#[test]
+fn zx_draws_spiders_and_hadamard_boxes() {
+    let mut graph = ZXGraph::new();
+    let input = graph.add_input_node(SpiderType::Boundary, Phase::ZERO);
+    let z = graph.add_node(SpiderType::Z, Phase::new(1, 2));
+    let x = graph.add_node(SpiderType::X, Phase::ZERO);
+    let output = graph.add_output_node(SpiderType::Boundary, Phase::ZERO);
+    graph.add_edge(input, z, EdgeType::Regular);
+    graph.add_edge(z, x, EdgeType::Hadamard);
+    graph.add_edge(x, output, EdgeType::Regular);
+
+    let config = SvgConfig {
+        z_style: "fill:lime".to_string(),
+        ..SvgConfig::default()
+    };
+    let svg = generate_svg(&graph, &config);
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(is_balanced(&svg));
+    assert!(svg.contains(".z_node { fill:lime }"));
+
+    assert_eq!(count(&svg, "<circle class=\"z_node\""), 1);
+    assert_eq!(count(&svg, "<circle class=\"x_node\""), 1);
+    assert_eq!(count(&svg, "<circle class=\"boundary_node\""), 2);
+    assert_eq!(count(&svg, "<line class=\"wire\""), 2);
+    assert_eq!(count(&svg, "<path class=\"regular_edge\""), 2);
+    assert_eq!(count(&svg, "<path class=\"hadamard_edge\""), 1);
+    assert_eq!(count(&svg, "<rect class=\"hadamard_box\""), 1);
+    assert!(svg.contains(">π/2</text>"));
+}
+
+#[test]
+fn zx_bundles_many_parallel_edges() {
+    let mut graph = ZXGraph::new();
+    let z = graph.add_node(SpiderType::Z, Phase::ZERO);
+    let x = graph.add_node(SpiderType::X, Phase::ZERO);
+    for _ in 0..6 {
+        graph.add_edge(z, x, EdgeType::Regular);
+    }
+
+    let svg = generate_svg(&graph, &SvgConfig::default());
+    assert!(is_balanced(&svg));
+    assert_eq!(count(&svg, "<path class=\"regular_edge\""), 2);
+    assert!(svg.contains(">6</text>"));
+}
+
+#[test]
+fn zx_labels_parametric_and_float_phases() {
+    let mut graph = ZXGraph::new();
+    let z = graph.add_parametric_node(
+        SpiderType::Z,
+        Phase::new(1, 4),
+        Parity::from_params(&[0, 1]),
+    );
+    let x = graph.add_node(SpiderType::X, Phase::from(0.3));
+    graph.add_edge(z, x, EdgeType::Regular);
+
+    let svg = generate_svg(&graph, &SvgConfig::default());
+    assert!(is_balanced(&svg));
+    assert!(!svg.contains('$'));
+    assert!(svg.contains(">0.30</text>"));
+    let sub = |bit: usize| {
+        format!(
+            "<tspan baseline-shift=\"sub\" font-size=\"70%\">{}</tspan>",
+            bit
+        )
+    };
+    assert!(svg.contains(&format!(">π/4+π(b{}⊕ b{})</text>", sub(0), sub(1))));
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use common::*;
+use quojo_rust::qcore::circuits::CircuitRepr;
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::{self, Targets};
+use quojo_rust::utils::to_zx::CircuitConverter;
+use std::collections::{BTreeSet, HashMap};
+
+fn values(pairs: &[(&str, f64)]) -> HashMap<String, f64> {
+    pairs
+        .iter()
+        .map(|&(name, value)| (name.to_string(), value))
+        .collect()
+}
+
+/// One QAOA layer on a triangle: e^(-iγ ZZ) on every edge, then e^(-iβ X) on every qubit,
+/// up to global phase.
+fn qaoa_layer(circuit: &mut CircuitRepr<3>, gamma: &str, beta: &str) {
+    for (a, b) in [(0, 1), (1, 2), (0, 2)] {
+        circuit.ApplyControlled(Gate::X, a, b);
+        circuit.Apply(Gate::P(Phase::variable(gamma) * 2.0), Targets(&[b]));
+        circuit.ApplyControlled(Gate::X, a, b);
+    }
+    circuit.Apply(Gate::H, Targets(&[0, 1, 2]));
+    circuit.Apply(
+        Gate::P(Phase::variable(beta) * 2.0 + Phase::PI),
+        Targets(&[0, 1, 2]),
+    );
+    circuit.Apply(Gate::H, Targets(&[0, 1, 2]));
+}
+
+fn qaoa_circuit() -> CircuitRepr<3> {
+    let mut circuit = qcore::CircuitRepr::<3>();
+    circuit.Apply(Gate::H, Targets(&[0, 1, 2]));
+    qaoa_layer(&mut circuit, "gamma0", "beta0");
+    qaoa_layer(&mut circuit, "gamma1", "beta1");
+    circuit
+}
+
+#[test]
+fn expressions_combine_linearly() {
+    let theta = Phase::variable("theta");
+    let gamma = Phase::variable("gamma");
+
+    let sum = theta.clone() * 2 + gamma.clone() + Phase::new(1, 4);
+    assert_eq!(sum.to_string(), "gamma + 2·theta + π/4");
+    assert_eq!(
+        sum.variables(),
+        BTreeSet::from(["gamma".to_string(), "theta".to_string()])
+    );
+    assert!(!sum.is_zero() && !sum.is_clifford());
+
+    // Cancelled variables collapse back to an exact constant
+    let difference = sum.clone() - theta.clone() * 2 - gamma;
+    assert_eq!(difference, Phase::new(1, 4));
+    assert_eq!((-theta.clone()).to_string(), "-theta");
+    assert_eq!((theta.clone() * 0.5 - theta).to_string(), "-0.5·theta");
+
+    // Partial binding keeps the remaining variables
+    let partial = sum.bind(&values(&[("gamma", std::f64::consts::FRAC_PI_4)]));
+    assert_eq!(partial, Phase::variable("theta") * 2 + Phase::new(1, 2));
+    let full = partial.bind(&values(&[("theta", 0.25)]));
+    assert!((full.to_radians() - (0.5 + std::f64::consts::FRAC_PI_2)).abs() < TOLERANCE);
+}
+
+#[test]
+fn circuits_bind_variables() {
+    let circuit = qaoa_circuit();
+    let names = ["beta0", "beta1", "gamma0", "gamma1"];
+    assert_eq!(
+        circuit.variables(),
+        names.iter().map(|n| n.to_string()).collect()
+    );
+
+    let bound = circuit.bind(&values(&[("gamma0", 0.1), ("beta0", 0.2)]));
+    assert_eq!(
+        bound.variables(),
+        BTreeSet::from(["beta1".to_string(), "gamma1".to_string()])
+    );
+    assert!(bound.wires()[1].contains(&Gate::P(Phase::from(0.2))));
+}
+
+#[test]
+fn symbolic_graphs_survive_rewriting() {
+    let circuit = qaoa_circuit();
+    let mut graph = CircuitConverter::convert(&circuit).unwrap();
+    assert_eq!(graph.variables(), circuit.variables());
+
+    graph.full_reduce();
+    assert_eq!(graph.variables(), circuit.variables());
+
+    for assignment in [
+        [
+            ("gamma0", 0.3),
+            ("beta0", 1.1),
+            ("gamma1", -0.7),
+            ("beta1", 0.45),
+        ],
+        [
+            ("gamma0", 0.0),
+            ("beta0", 0.0),
+            ("gamma1", 0.0),
+            ("beta1", 0.0),
+        ],
+        [
+            ("gamma0", 2.0),
+            ("beta0", -0.1),
+            ("gamma1", 0.25),
+            ("beta1", 3.0),
+        ],
+    ] {
+        let values = values(&assignment);
+        let expected = CircuitConverter::convert(&circuit.bind(&values))
+            .unwrap()
+            .to_matrix();
+        assert_matrices_close(&expected, &graph.bind(&values).to_matrix());
+    }
+}
+
+#[test]
+fn symbolic_graphs_extract() {
+    let circuit = qaoa_circuit();
+    let mut graph = CircuitConverter::convert(&circuit).unwrap();
+    graph.full_reduce();
+    let extracted = graph.extract_circuit::<3>().unwrap();
+    assert_eq!(extracted.variables(), circuit.variables());
+
+    let values = values(&[
+        ("gamma0", 0.3),
+        ("beta0", 1.1),
+        ("gamma1", -0.7),
+        ("beta1", 0.45),
+    ]);
+    let expected = CircuitConverter::convert(&circuit.bind(&values))
+        .unwrap()
+        .to_matrix();
+    let actual = CircuitConverter::convert(&extracted.bind(&values))
+        .unwrap()
+        .to_matrix();
+    let ratio = actual[0][0] * expected[0][0].conj() * (1.0 / expected[0][0].norm_sqr());
+    assert!((ratio.norm_sqr() - 1.0).abs() < TOLERANCE);
+    assert_close_up_to_phase(&expected, &actual);
+}
+
+#[test]
+#[should_panic(expected = "bind its variables first")]
+fn simulating_unbound_graph_panics() {
+    let mut circuit = qcore::CircuitRepr::<1>();
+    circuit.Apply(Gate::P(Phase::variable("theta")), Targets(&[0]));
+    CircuitConverter::convert(&circuit).unwrap().to_matrix();
+}
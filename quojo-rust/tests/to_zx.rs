@@ -0,0 +1,184 @@
+mod common;
+
+use common::*;
+use quojo_rust::qcore::circuits::CircuitRepr;
+use quojo_rust::qcore::dag::Instruction;
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::phase::Phase;
+use quojo_rust::qcore::{self, Targets};
+use quojo_rust::utils::to_zx::{CircuitConverter, ConversionError};
+use quojo_rust::zxcalc::graph::SpiderType;
+
+fn push_gate<const WIDTH: usize>(circuit: &mut CircuitRepr<WIDTH>, gate: Gate, qubits: &[usize]) {
+    circuit.dag.push(Instruction {
+        gate,
+        qubits: qubits.to_vec(),
+    });
+}
+
+#[test]
+fn single_qubit_gates_match_unitary() {
+    for gate in [
+        Gate::X,
+        Gate::Y,
+        Gate::Z,
+        Gate::H,
+        Gate::P(Phase::from(0.3)),
+        Gate::P(Phase::new(-1, 4)),
+    ] {
+        let mut circuit = qcore::CircuitRepr::<1>();
+        circuit.Apply(gate, Targets(&[0]));
+        assert_zx_matches_simulation(&circuit);
+    }
+}
+
+#[test]
+fn two_qubit_gates_match_unitary() {
+    let gates = [
+        Gate::CNOT {
+            control: 0,
+            target: 1,
+        },
+        Gate::CNOT {
+            control: 1,
+            target: 0,
+        },
+        Gate::CZ {
+            control: 0,
+            target: 1,
+        },
+        Gate::SWAP {
+            qubit1: 0,
+            qubit2: 1,
+        },
+    ];
+
+    for gate in gates {
+        let mut circuit = qcore::CircuitRepr::<2>();
+        circuit.Apply(Gate::H, Targets(&[0]));
+        circuit.Apply(Gate::P(Phase::from(0.7)), Targets(&[1]));
+        let qubits = gate.involved_qubits(0);
+        push_gate(&mut circuit, gate, &qubits);
+        circuit.Apply(Gate::Y, Targets(&[1]));
+        assert_zx_matches_simulation(&circuit);
+    }
+}
+
+#[test]
+fn three_qubit_gates_match_unitary() {
+    let gates = [
+        Gate::Toffoli {
+            control1: 0,
+            control2: 1,
+            target: 2,
+        },
+        Gate::Toffoli {
+            control1: 2,
+            control2: 0,
+            target: 1,
+        },
+        Gate::Fredkin {
+            control: 0,
+            target1: 1,
+            target2: 2,
+        },
+        Gate::Fredkin {
+            control: 2,
+            target1: 0,
+            target2: 1,
+        },
+    ];
+
+    for gate in gates {
+        let mut circuit = qcore::CircuitRepr::<3>();
+        circuit.Apply(Gate::H, Targets(&[0, 1, 2]));
+        circuit.Apply(Gate::P(Phase::from(0.2)), Targets(&[2]));
+        let qubits = gate.involved_qubits(0);
+        push_gate(&mut circuit, gate, &qubits);
+        circuit.Apply(Gate::H, Targets(&[1]));
+        assert_zx_matches_simulation(&circuit);
+    }
+}
+
+#[test]
+fn mixed_circuit_matches_unitary() {
+    let mut circuit = qcore::CircuitRepr::<3>();
+    circuit.Apply(Gate::H, Targets(&[0, 2]));
+    circuit.ApplyControlled(Gate::X, 0, 1);
+    circuit.Apply(Gate::P(Phase::new(1, 4)), Targets(&[1]));
+    circuit.ApplySwap(1, 2);
+    circuit.ApplyControlled(Gate::Z, 2, 0);
+    circuit.Apply(Gate::H, Targets(&[0]));
+    circuit.Apply(Gate::H, Targets(&[0]));
+    push_gate(
+        &mut circuit,
+        Gate::Toffoli {
+            control1: 1,
+            control2: 2,
+            target: 0,
+        },
+        &[0, 1, 2],
+    );
+    circuit.Apply(Gate::Y, Targets(&[2]));
+    push_gate(
+        &mut circuit,
+        Gate::Fredkin {
+            control: 1,
+            target1: 0,
+            target2: 2,
+        },
+        &[0, 1, 2],
+    );
+    circuit.Apply(Gate::X, Targets(&[0]));
+    assert_zx_matches_simulation(&circuit);
+}
+
+#[test]
+fn invalid_gates_return_errors() {
+    let mut circuit = qcore::CircuitRepr::<2>();
+    let gate = Gate::CNOT {
+        control: 0,
+        target: 3,
+    };
+    circuit.dag.push(Instruction::new(gate.clone(), 0));
+    assert_eq!(
+        CircuitConverter::convert(&circuit).err(),
+        Some(ConversionError::QubitOutOfRange {
+            gate,
+            qubit: 3,
+            width: 2
+        })
+    );
+
+    let mut circuit = qcore::CircuitRepr::<2>();
+    let gate = Gate::CZ {
+        control: 1,
+        target: 1,
+    };
+    circuit.dag.push(Instruction::new(gate.clone(), 1));
+    assert_eq!(
+        CircuitConverter::convert(&circuit).err(),
+        Some(ConversionError::DuplicateQubit { gate, qubit: 1 })
+    );
+}
+
+#[test]
+fn fusion_keeps_boundaries() {
+    let mut circuit = qcore::CircuitRepr::<2>();
+    circuit.Apply(Gate::X, Targets(&[0]));
+    circuit.Apply(Gate::P(Phase::from(0.4)), Targets(&[0, 1]));
+    circuit.ApplyControlled(Gate::X, 0, 1);
+    circuit.Apply(Gate::Z, Targets(&[0]));
+    circuit.ApplyControlled(Gate::X, 0, 1);
+
+    let mut graph = CircuitConverter::convert(&circuit).unwrap();
+    let expected = graph.to_matrix();
+    assert!(graph.fuse_spiders() > 0);
+
+    for &node in graph.input_nodes.iter().chain(&graph.output_nodes) {
+        assert_eq!(graph.node_data(node).unwrap().0, &SpiderType::Boundary);
+        assert_eq!(graph.neighbors(node).len(), 1);
+    }
+
+    assert_same_matrix(&expected, &graph);
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use common::*;
+use quojo_rust::error::QuojoError;
+use quojo_rust::linalg::vector::Complex;
+use quojo_rust::qcore::circuits::Circuit;
+use quojo_rust::qcore::gates::Gate;
+use quojo_rust::qcore::tikz::{TikzQConfig, generate_tikz_circuit};
+use quojo_rust::qcore::{self, Targets};
+use quojo_rust::utils::synthesis::synthesize_unitary;
+use quojo_rust::utils::to_zx::CircuitConverter;
+use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_4};
+
+#[test]
+fn construction_checks_shape_and_unitarity() {
+    let h = vec![
+        vec![c(FRAC_1_SQRT_2, 0.0), c(FRAC_1_SQRT_2, 0.0)],
+        vec![c(FRAC_1_SQRT_2, 0.0), c(-FRAC_1_SQRT_2, 0.0)],
+    ];
+    let gate = Gate::unitary("U", &[3], h.clone()).unwrap();
+    assert_eq!(gate.to_string(), "U(3)");
+    assert_eq!(gate.involved_qubits(0), vec![3]);
+
+    assert_eq!(
+        Gate::unitary("U", &[0, 1], h.clone()),
+        Err(QuojoError::MatrixShape {
+            label: "U".to_string(),
+            qubits: 2,
+            rows: 2,
+            columns: 2
+        })
+    );
+    assert!(matches!(
+        Gate::unitary("U", &[], vec![vec![c(1.0, 0.0)]]),
+        Err(QuojoError::MatrixShape { qubits: 0, .. })
+    ));
+    let ragged = vec![h[0].clone(), vec![c(1.0, 0.0)]];
+    assert!(matches!(
+        Gate::unitary("U", &[0], ragged),
+        Err(QuojoError::MatrixShape { columns: 1, .. })
+    ));
+
+    let mut skewed = h;
+    skewed[0][0] = c(0.8, 0.0);
+    match Gate::unitary("bad", &[0], skewed) {
+        Err(QuojoError::NotUnitary { label, deviation }) => {
+            assert_eq!(label, "bad");
+            assert!(deviation > 0.05);
+        }
+        other => panic!("expected NotUnitary, got {:?}", other),
+    }
+}
+
+#[test]
+fn simulation_applies_matrix_to_listed_qubits() {
+    for (qubits, width) in [
+        (vec![1], 2),
+        (vec![2, 0], 3),
+        (vec![0, 3, 1], 4),
+        (vec![5, 4], 6),
+    ] {
+        let matrix = random_unitary(qubits.len(), 7 + width as u64);
+        let mut circuit = Circuit::new(width);
+        circuit
+            .try_apply(
+                Gate::unitary("U", &qubits, matrix.clone()).unwrap(),
+                Targets(&qubits),
+            )
+            .unwrap();
+        assert_matrices_close(&embed(&matrix, &qubits, width), &simulated_matrix(&circuit));
+    }
+}
+
+#[test]
+fn synthesis_reproduces_random_unitaries() {
+    for (qubits, width, seed) in [
+        (vec![0], 1, 1),
+        (vec![1, 0], 2, 2),
+        (vec![0, 1], 3, 3),
+        (vec![2, 0, 1], 3, 4),
+        (vec![1, 3, 0, 2], 4, 5),
+    ] {
+        let matrix = random_unitary(qubits.len(), seed);
+        let synthesis = synthesize_unitary(&matrix, &qubits);
+        let mut circuit = Circuit::new(width);
+        for instruction in synthesis.instructions {
+            circuit.dag.push(instruction);
+        }
+
+        let phase = Complex::from_phase(synthesis.phase);
+        let actual: Vec<Vec<Complex>> = simulated_matrix(&circuit)
+            .into_iter()
+            .map(|row| row.into_iter().map(|v| v * phase).collect())
+            .collect();
+        assert_matrices_close(&embed(&matrix, &qubits, width), &actual);
+    }
+}
+
+#[test]
+fn zx_conversion_matches_matrix() {
+    let t = Complex::from_phase(FRAC_PI_4);
+    let (zero, one, h) = (c(0.0, 0.0), c(1.0, 0.0), c(FRAC_1_SQRT_2, 0.0));
+    // An entangling Clifford+T unitary and a doubly controlled T
+    let entangler = vec![
+        vec![h, t * h, zero, zero],
+        vec![zero, zero, h, t * h * -1.0],
+        vec![zero, zero, h, t * h],
+        vec![h, t * h * -1.0, zero, zero],
+    ];
+    let mut controlled_t = vec![vec![zero; 8]; 8];
+    for (i, row) in controlled_t.iter_mut().enumerate() {
+        row[i] = if i == 7 { t } else { one };
+    }
+
+    for (matrix, qubits, width) in [
+        (random_unitary(1, 11), vec![1], 2),
+        (entangler, vec![1, 0], 2),
+        (controlled_t, vec![0, 2, 1], 3),
+    ] {
+        let mut circuit = Circuit::new(width);
+        circuit.Apply(Gate::H, Targets(&[0]));
+        circuit
+            .try_apply(
+                Gate::unitary("U", &qubits, matrix.clone()).unwrap(),
+                Targets(&qubits),
+            )
+            .unwrap();
+
+        let graph = CircuitConverter::convert(&circuit).unwrap();
+        assert_matrices_close(&simulated_matrix(&circuit), &graph.to_matrix());
+    }
+}
+
+#[test]
+fn tikz_draws_labelled_box() {
+    let mut circuit = qcore::CircuitRepr::<4>();
+    let gate = Gate::unitary("V", &[3, 1], random_unitary(2, 9)).unwrap();
+    circuit.Apply(gate, Targets(&[3, 1]));
+    circuit.Apply(Gate::H, Targets(&[2, 0]));
+
+    let tikz = generate_tikz_circuit(&circuit, &TikzQConfig::default());
+    assert_eq!(tikz.matches("{$V$}").count(), 1);
+    assert!(tikz.contains("minimum height=1.70cm"));
+    // H on qubit 2 would sit inside the box, so it moves to the next column
+    assert!(tikz.contains("at (2.40,-1.20) {$H$}"));
+    assert!(tikz.contains("at (1.20,-0.00) {$H$}"));
+}